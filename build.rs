@@ -0,0 +1,18 @@
+use std::env;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rustc-check-cfg=cfg(primary_selection)");
+
+    // Selecting text sets the primary selection with the `primary-selection`
+    // feature, but only Linux and the BSDs have one. `cfg(primary_selection)`
+    // is set where both are true, so the condition is written only here.
+    let unix = env::var_os("CARGO_CFG_UNIX").is_some();
+    let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+    if env::var_os("CARGO_FEATURE_PRIMARY_SELECTION").is_some()
+        && unix
+        && !matches!(target_os.as_str(), "macos" | "ios" | "android")
+    {
+        println!("cargo:rustc-cfg=primary_selection");
+    }
+}
@@ -5,8 +5,8 @@ fn main() {
         console.push_line("This demo echoes each line of input.");
 
         while let Ok(event) = console.next_event() {
-            if let console_thingy::ConsoleEvent::Input = event {
-                console.push_line(console.input());
+            if let console_thingy::ConsoleEvent::Input(input) = event {
+                console.push_line(input);
                 console.clear_input();
                 console.reset_scroll();
             }
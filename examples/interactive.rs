@@ -1,16 +1,18 @@
-use console_thingy::{Config, Console, ConsoleEvent};
+use console_thingy::{Config, Console, ConsoleEvent, Level, Overlay};
 
 fn main() {
     Config::default().run(|console: Console| {
         console.push_line(
             "This demo echoes each line of input and has a few slash commands (e.g., /exit)",
         );
+        console.set_placeholder("Type a message, / for commands…");
 
         let mut secure_input = false;
         while let Ok(event) = console.next_event() {
-            let input = console.input();
             match event {
                 ConsoleEvent::InputBufferChanged => {
+                    let input = console.input();
+                    console.set_input_decoration(format!("{}/280", input.chars().count()));
                     if !secure_input {
                         if let Some(command) = input.strip_prefix('/') {
                             if "quit".starts_with(command) {
@@ -19,6 +21,8 @@ fn main() {
                                 console.set_suggestion(&"exit"[command.len()..]);
                             } else if "clear".starts_with(command) {
                                 console.set_suggestion(&"clear"[command.len()..]);
+                            } else if "about".starts_with(command) {
+                                console.set_suggestion(&"about"[command.len()..]);
                             } else if "secure".starts_with(command) {
                                 console.set_suggestion(&"secure"[command.len()..]);
                             } else {
@@ -27,15 +31,24 @@ fn main() {
                         }
                     }
                 }
-                ConsoleEvent::Input => {
+                ConsoleEvent::Input(input) => {
                     if secure_input {
                         secure_input = false;
-                        console.push_line(format!("Your 'secure' input: {}", *input));
+                        console.push_line(format!("Your 'secure' input: {input}"));
                         console.clear_secure();
                     } else if let Some(command) = input.strip_prefix('/') {
                         match command {
                             "e" | "q" | "exit" | "quit" => break,
                             "clear" => console.clear_scrollback(),
+                            "about" => console.show_overlay(
+                                Overlay::text("A demo of console-thingy.\nPress Escape to close.")
+                                    .with_title("About"),
+                            ),
+                            command if command.starts_with("json ") => {
+                                if let Err(err) = console.push_json(&command[5..]) {
+                                    console.push_line_with_level(Level::Error, err.to_string());
+                                }
+                            }
                             "secure" => {
                                 console.push_line("Demo of 'secure' entry. This simply prints asterisks instead of what the user typed.");
                                 console.set_secure();
@@ -51,6 +64,7 @@ fn main() {
                     console.clear_input();
                     console.reset_scroll();
                 }
+                _ => {}
             }
         }
 
@@ -7,14 +7,10 @@ fn main() {
         console.push_line(
             "This demo echoes each line of input, and also has events come in from a thread.",
         );
-        std::thread::spawn({
-            let console = console.clone();
-            move || background_message_thread(console)
-        });
+        console.spawn("background messages", background_message_thread);
 
         while let Ok(event) = console.next_event() {
-            if let console_thingy::ConsoleEvent::Input = event {
-                let input = String::from(console.input());
+            if let console_thingy::ConsoleEvent::Input(input) = event {
                 match &*input {
                     "exit" | "quit" => {
                         break;
@@ -44,9 +40,7 @@ fn main() {
 
 fn background_message_thread(console: Console) {
     let start = Instant::now();
-    while !console.should_shutdown() {
-        std::thread::sleep(Duration::from_secs(1));
-
+    while console.sleep(Duration::from_secs(1)) {
         let words = (start.elapsed().subsec_nanos() % 31) as usize;
         console.push_line(lipsum::lipsum_words(words));
     }
@@ -0,0 +1,42 @@
+use std::process::Command;
+
+use console_thingy::{Config, Console, ConsoleEvent, Process};
+
+fn main() {
+    Config::default().run(|console: Console| {
+        console.push_line("Type a command to run it. While it runs, input is sent to its stdin.");
+
+        let mut running: Option<Process> = None;
+        while let Ok(event) = console.next_event() {
+            match event {
+                ConsoleEvent::Input(input) => {
+                    console.clear_input();
+                    if let Some(process) = &mut running {
+                        process.write_line(&input)?;
+                    } else {
+                        let mut words = input.split_whitespace();
+                        let Some(program) = words.next() else {
+                            continue;
+                        };
+                        let mut command = Command::new(program);
+                        command.args(words);
+                        match console.spawn_process(command) {
+                            Ok(process) => running = Some(process),
+                            Err(err) => console.push_line(format!("error: {err}")),
+                        }
+                    }
+                }
+                ConsoleEvent::ProcessExited { status, .. } => {
+                    running = None;
+                    match status {
+                        Ok(status) => console.push_line(format!("process exited: {status}")),
+                        Err(err) => console.push_line(format!("error: {err}")),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    })
+}
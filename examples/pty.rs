@@ -0,0 +1,41 @@
+use console_thingy::{CommandBuilder, Config, Console, ConsoleEvent, Pty};
+
+fn main() {
+    Config::default().run(|console: Console| {
+        console.push_line("Type a command to run it in a pseudo-terminal (e.g., git log).");
+
+        let mut running: Option<Pty> = None;
+        while let Ok(event) = console.next_event() {
+            match event {
+                ConsoleEvent::Input(input) => {
+                    console.clear_input();
+                    if let Some(pty) = &mut running {
+                        pty.write_line(&input)?;
+                    } else {
+                        let mut words = input.split_whitespace();
+                        let Some(program) = words.next() else {
+                            continue;
+                        };
+                        let mut command = CommandBuilder::new(program);
+                        command.args(words);
+                        command.cwd(std::env::current_dir()?);
+                        match console.spawn_pty(command) {
+                            Ok(pty) => running = Some(pty),
+                            Err(err) => console.push_line(format!("error: {err}")),
+                        }
+                    }
+                }
+                ConsoleEvent::PtyExited { exit_code, .. } => {
+                    running = None;
+                    match exit_code {
+                        Ok(code) => console.push_line(format!("exited with code {code}")),
+                        Err(err) => console.push_line(format!("error: {err}")),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    })
+}
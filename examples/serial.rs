@@ -0,0 +1,31 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use console_thingy::{Config, Console, ConsoleEvent, Newline};
+
+// Usage: cargo run --example serial -- /dev/ttyUSB0
+//
+// The device must already be configured (e.g., using `stty`). Each line of
+// input is sent to the device followed by CRLF.
+fn main() {
+    let path = std::env::args()
+        .nth(1)
+        .expect("expected the path to a serial device");
+
+    Config::default()
+        .incoming_newline(Newline::Auto)
+        .run(move |console: Console| {
+            let mut device = OpenOptions::new().read(true).write(true).open(&path)?;
+            console.push_line(format!("Connected to {path}"));
+            console.attach_reader(device.try_clone()?);
+
+            while let Ok(event) = console.next_event() {
+                if let ConsoleEvent::Input(input) = event {
+                    console.clear_input();
+                    write!(device, "{input}\r\n")?;
+                }
+            }
+
+            Ok(())
+        })
+}
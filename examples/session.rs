@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use console_thingy::{Config, Console, ConsoleEvent};
+
+const SOCKET: &str = "/tmp/console-thingy-session.sock";
+
+// Run with `serve` to start the session in the background, then run without
+// arguments to attach to it. Closing the frontend leaves the session running.
+fn main() {
+    if std::env::args().nth(1).as_deref() == Some("serve") {
+        Config::default().serve_session(SOCKET, |console: Console| {
+            console.push_line("This session counts seconds and echoes input.");
+            console.set_tick(Duration::from_secs(1));
+            let mut seconds = 0;
+            while let Ok(event) = console.next_event() {
+                match event {
+                    ConsoleEvent::Tick => {
+                        seconds += 1;
+                        if seconds % 10 == 0 {
+                            console.push_line(format!("{seconds} seconds elapsed"));
+                        }
+                    }
+                    ConsoleEvent::Input(input) => {
+                        console.push_line(input);
+                        console.clear_input();
+                    }
+                    _ => {}
+                }
+            }
+
+            Ok(())
+        })
+    } else {
+        Config::default().attach(SOCKET)
+    }
+}
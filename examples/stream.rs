@@ -0,0 +1,25 @@
+use std::time::Duration;
+
+use console_thingy::{Config, Console};
+
+fn main() {
+    Config::default().run(|console: Console| {
+        console
+            .push_line("This demo streams a response to each line of input, one word at a time.");
+
+        while let Some(input) = console.read_input() {
+            console.push_line(format!("> {input}"));
+            console.reset_scroll();
+
+            let stream = console.begin_stream();
+            for word in lipsum::lipsum_words(40).split(' ') {
+                stream.write(word);
+                stream.write(" ");
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            stream.finish();
+        }
+
+        Ok(())
+    })
+}
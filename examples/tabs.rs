@@ -0,0 +1,31 @@
+use console_thingy::{Config, Console, ConsoleEvent};
+
+fn main() {
+    Config::default().run(|console: Console| {
+        console.push_line("Type /tab <name> to open a tab. Use Ctrl+Tab to switch tabs.");
+
+        while let Ok(event) = console.next_event() {
+            if let ConsoleEvent::Input(input) = event {
+                console.clear_input();
+                if let Some(name) = input.strip_prefix("/tab ") {
+                    let tab = console.open_tab(name);
+                    std::thread::spawn(move || echo(tab));
+                } else {
+                    console.push_line(input);
+                }
+            }
+        }
+
+        Ok(())
+    })
+}
+
+fn echo(tab: Console) {
+    tab.push_line("Each tab has its own scrollback and input. Type /close to close it.");
+    while let Some(input) = tab.read_input() {
+        if input == "/close" {
+            break;
+        }
+        tab.push_line(input);
+    }
+}
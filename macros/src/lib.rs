@@ -0,0 +1,189 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Expr, Field, Fields, GenericArgument,
+    Lit, Meta, PathArguments, Type,
+};
+
+/// Implements `console_thingy::ConsoleCommand` for an enum, with a command
+/// for each variant. `Option` fields are optional arguments, and `bool`
+/// fields are flags, such as `--dry-run`, that can be given anywhere.
+#[proc_macro_derive(ConsoleCommand)]
+pub fn derive_console_command(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    console_command(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn console_command(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new_spanned(
+            input,
+            "ConsoleCommand can only be derived for enums",
+        ));
+    };
+
+    let mut infos = Vec::new();
+    let mut arms = Vec::new();
+    for variant in &data.variants {
+        let ident = &variant.ident;
+        let name = command_name(&ident.to_string());
+        let help = first_doc_line(&variant.attrs);
+
+        let mut usage = format!("/{name}");
+        let mut flags = Vec::new();
+        let mut parse = |index: usize, field: &Field| {
+            let argument = match &field.ident {
+                Some(field) => field.to_string(),
+                None => format!("arg{}", index + 1),
+            };
+            match kind(&field.ty) {
+                Kind::Required => {
+                    usage.push_str(&format!(" <{argument}>"));
+                    quote!(arguments.next(#argument)?)
+                }
+                Kind::Optional => {
+                    usage.push_str(&format!(" [{argument}]"));
+                    quote!(arguments.optional(#argument)?)
+                }
+                Kind::Flag => {
+                    let argument = argument.replace('_', "-");
+                    usage.push_str(&format!(" [--{argument}]"));
+                    // Flags can be given anywhere, so they're removed before
+                    // the other arguments are parsed.
+                    let flag = format_ident!("flag{}", index);
+                    flags.push(quote!(let #flag = arguments.flag(#argument);));
+                    quote!(#flag)
+                }
+            }
+        };
+        let constructor = match &variant.fields {
+            Fields::Named(fields) => {
+                let fields = fields.named.iter().enumerate().map(|(index, field)| {
+                    let ident = field.ident.as_ref().expect("named field");
+                    let value = parse(index, field);
+                    quote!(#ident: #value)
+                });
+                let fields = fields.collect::<Vec<_>>();
+                quote!(Self::#ident { #(#fields),* })
+            }
+            Fields::Unnamed(fields) => {
+                let fields = fields.unnamed.iter().enumerate();
+                let fields = fields.map(|(index, field)| parse(index, field));
+                let fields = fields.collect::<Vec<_>>();
+                quote!(Self::#ident(#(#fields),*))
+            }
+            Fields::Unit => quote!(Self::#ident),
+        };
+
+        infos.push(quote! {
+            ::console_thingy::CommandInfo {
+                name: #name,
+                usage: #usage,
+                help: #help,
+            }
+        });
+        arms.push(quote!(#name => {
+            #(#flags)*
+            Ok(#constructor)
+        }));
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::console_thingy::ConsoleCommand for #ident #type_generics #where_clause {
+            fn commands() -> &'static [::console_thingy::CommandInfo] {
+                &[#(#infos),*]
+            }
+
+            fn parse_command(
+                name: &str,
+                arguments: &mut ::console_thingy::Arguments,
+            ) -> Result<Self, ::console_thingy::CommandError> {
+                match name {
+                    #(#arms)*
+                    _ => Err(::console_thingy::CommandError::Unknown(name.to_string())),
+                }
+            }
+        }
+    })
+}
+
+// How a field is parsed from the arguments.
+#[derive(Debug, Eq, PartialEq)]
+enum Kind {
+    Required,
+    Optional,
+    Flag,
+}
+
+// Returns how a field of type `ty` is parsed. Types are recognized by name,
+// since macros can't resolve them.
+fn kind(ty: &Type) -> Kind {
+    let Type::Path(path) = ty else {
+        return Kind::Required;
+    };
+    let Some(last) = path.path.segments.last() else {
+        return Kind::Required;
+    };
+    match &last.arguments {
+        PathArguments::None if last.ident == "bool" => Kind::Flag,
+        PathArguments::AngleBracketed(arguments)
+            if last.ident == "Option"
+                && matches!(
+                    arguments.args.first(),
+                    Some(GenericArgument::Type(_)) if arguments.args.len() == 1
+                ) =>
+        {
+            Kind::Optional
+        }
+        _ => Kind::Required,
+    }
+}
+
+// Converts a variant name, such as `JoinChannel`, into a command name, such
+// as `join-channel`.
+fn command_name(variant: &str) -> String {
+    let mut name = String::with_capacity(variant.len());
+    for ch in variant.chars() {
+        if ch.is_uppercase() && !name.is_empty() {
+            name.push('-');
+        }
+        name.extend(ch.to_lowercase());
+    }
+    name
+}
+
+fn first_doc_line(attrs: &[Attribute]) -> String {
+    for attr in attrs {
+        if let Meta::NameValue(meta) = &attr.meta {
+            if !meta.path.is_ident("doc") {
+                continue;
+            }
+            if let Expr::Lit(expr) = &meta.value {
+                if let Lit::Str(doc) = &expr.lit {
+                    return doc.value().trim().to_string();
+                }
+            }
+        }
+    }
+    String::new()
+}
+
+#[test]
+fn field_kinds() {
+    let kind = |ty: &str| kind(&syn::parse_str(ty).unwrap());
+    assert_eq!(kind("String"), Kind::Required);
+    assert_eq!(kind("Option<u16>"), Kind::Optional);
+    assert_eq!(kind("std::option::Option<String>"), Kind::Optional);
+    assert_eq!(kind("bool"), Kind::Flag);
+    assert_eq!(kind("Vec<bool>"), Kind::Required);
+}
+
+#[test]
+fn command_names() {
+    assert_eq!(command_name("Quit"), "quit");
+    assert_eq!(command_name("JoinChannel"), "join-channel");
+}
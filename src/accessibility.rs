@@ -0,0 +1,178 @@
+use std::num::NonZeroU128;
+
+use accesskit::{Live, NodeBuilder, NodeClassSet, NodeId, Role, Tree, TreeUpdate};
+
+// The number of the most recent lines exposed to assistive technologies.
+const MAXIMUM_LINES: usize = 100;
+
+fn node_id(id: u128) -> NodeId {
+    NodeId(NonZeroU128::new(id).expect("node ids start at 1"))
+}
+
+fn window_id() -> NodeId {
+    node_id(1)
+}
+
+fn log_id() -> NodeId {
+    node_id(2)
+}
+
+fn input_id() -> NodeId {
+    node_id(3)
+}
+
+// Lines are identified by their index from the oldest line, so that only
+// newly pushed lines are announced.
+fn line_id(line: usize) -> NodeId {
+    node_id(line as u128 + 4)
+}
+
+/// Describes the scrollback and input line to assistive technologies using
+/// AccessKit. The scrollback is exposed as a live log, so new lines are
+/// announced by screen readers.
+///
+/// The tree is currently only delivered on Linux and the BSDs, using AT-SPI.
+/// Other platforms require access to the native window, which Kludgine does
+/// not provide.
+#[derive(Default)]
+pub struct Accessibility {
+    classes: NodeClassSet,
+    adapter: Option<platform::Adapter>,
+}
+
+impl Accessibility {
+    /// Connects to the platform's accessibility services, if available.
+    pub fn connect() -> Self {
+        let mut accessibility = Self::default();
+        let initial_state = accessibility.tree_update(std::iter::empty(), "", false);
+        accessibility.adapter = platform::connect(initial_state);
+        accessibility
+    }
+
+    /// Delivers the tree described by [`Self::tree_update`].
+    pub fn update<'a>(
+        &mut self,
+        lines: impl DoubleEndedIterator<Item = (usize, &'a str)>,
+        input: &str,
+        secure: bool,
+    ) {
+        if self.adapter.is_none() {
+            return;
+        }
+        let update = self.tree_update(lines, input, secure);
+        if let Some(adapter) = &self.adapter {
+            adapter.update(update);
+        }
+    }
+
+    /// Returns the tree containing `lines`, which are the visible lines
+    /// paired with their index from the oldest line, ordered from oldest to
+    /// newest.
+    pub fn tree_update<'a>(
+        &mut self,
+        lines: impl DoubleEndedIterator<Item = (usize, &'a str)>,
+        input: &str,
+        secure: bool,
+    ) -> TreeUpdate {
+        let mut lines = lines.rev().take(MAXIMUM_LINES).collect::<Vec<_>>();
+        lines.reverse();
+
+        let mut nodes = Vec::with_capacity(lines.len() + 3);
+        let mut window = NodeBuilder::new(Role::Window);
+        window.set_children(vec![log_id(), input_id()]);
+        nodes.push((window_id(), window.build(&mut self.classes)));
+
+        let mut log = NodeBuilder::new(Role::Log);
+        log.set_live(Live::Polite);
+        log.set_children(
+            lines
+                .iter()
+                .map(|&(index, _)| line_id(index))
+                .collect::<Vec<_>>(),
+        );
+        nodes.push((log_id(), log.build(&mut self.classes)));
+
+        let mut input_node = NodeBuilder::new(Role::TextField);
+        if secure {
+            input_node.set_protected();
+            input_node.set_value("*".repeat(input.chars().count()));
+        } else {
+            input_node.set_value(input);
+        }
+        nodes.push((input_id(), input_node.build(&mut self.classes)));
+
+        for (index, text) in lines {
+            let mut line = NodeBuilder::new(Role::StaticText);
+            line.set_name(text);
+            nodes.push((line_id(index), line.build(&mut self.classes)));
+        }
+
+        TreeUpdate {
+            nodes,
+            tree: Some(Tree::new(window_id())),
+            focus: Some(input_id()),
+        }
+    }
+}
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+mod platform {
+    use accesskit::{ActionHandler, ActionRequest, TreeUpdate};
+    pub use accesskit_unix::Adapter;
+
+    struct IgnoreActions;
+
+    impl ActionHandler for IgnoreActions {
+        fn do_action(&self, _request: ActionRequest) {}
+    }
+
+    pub fn connect(initial_state: TreeUpdate) -> Option<Adapter> {
+        Adapter::new(
+            String::from("console-thingy"),
+            String::from("console-thingy"),
+            String::from(env!("CARGO_PKG_VERSION")),
+            move || initial_state,
+            Box::new(IgnoreActions),
+        )
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+)))]
+mod platform {
+    use accesskit::TreeUpdate;
+
+    pub struct Adapter;
+
+    impl Adapter {
+        pub fn update(&self, _update: TreeUpdate) {}
+    }
+
+    pub fn connect(_initial_state: TreeUpdate) -> Option<Adapter> {
+        None
+    }
+}
+
+#[test]
+fn accessibility_tree() {
+    let mut accessibility = Accessibility::default();
+    let update = accessibility.tree_update([(0, "one"), (1, "two")].into_iter(), "pass", true);
+    assert_eq!(update.nodes.len(), 5);
+    let (_, log) = &update.nodes[1];
+    assert_eq!(log.children(), [line_id(0), line_id(1)]);
+    let (_, input) = &update.nodes[2];
+    assert_eq!(input.value(), Some("****"));
+    let (_, line) = &update.nodes[4];
+    assert_eq!(line.name(), Some("two"));
+}
@@ -0,0 +1,170 @@
+use crate::scrollback::Line;
+use crate::style::Span;
+use crate::Color;
+
+// The standard and bright colors used by most terminals.
+const PALETTE: [Color; 16] = [
+    Color::rgb(0, 0, 0),
+    Color::rgb(205, 49, 49),
+    Color::rgb(13, 188, 121),
+    Color::rgb(229, 229, 16),
+    Color::rgb(36, 114, 200),
+    Color::rgb(188, 63, 188),
+    Color::rgb(17, 168, 205),
+    Color::rgb(229, 229, 229),
+    Color::rgb(102, 102, 102),
+    Color::rgb(241, 76, 76),
+    Color::rgb(35, 209, 139),
+    Color::rgb(245, 245, 67),
+    Color::rgb(59, 142, 234),
+    Color::rgb(214, 112, 214),
+    Color::rgb(41, 184, 219),
+    Color::rgb(255, 255, 255),
+];
+
+/// Converts text containing ANSI escape sequences into lines. Foreground
+/// colors are preserved, and all other escape sequences are removed.
+///
+/// The current color carries over from one line to the next, like it does
+/// in a terminal.
+#[derive(Debug, Default)]
+pub struct AnsiParser {
+    color: Option<Color>,
+}
+
+impl AnsiParser {
+    pub fn parse_line(&mut self, source: &str) -> Line {
+        let mut text = String::with_capacity(source.len());
+        let mut spans = Vec::new();
+        let mut span_start = 0;
+        let mut chars = source.chars().peekable();
+        while let Some(ch) = chars.next() {
+            if ch != '\u{1b}' {
+                text.push(ch);
+                continue;
+            }
+
+            match chars.next() {
+                Some('[') => {
+                    let mut parameters = String::new();
+                    let mut command = None;
+                    for ch in chars.by_ref() {
+                        if ('\u{40}'..='\u{7e}').contains(&ch) {
+                            command = Some(ch);
+                            break;
+                        }
+                        parameters.push(ch);
+                    }
+
+                    if command == Some('m') {
+                        let color = self.apply_sgr(&parameters);
+                        if color != self.color {
+                            if let Some(color) = self.color.filter(|_| span_start < text.len()) {
+                                spans.push(Span::new(span_start..text.len(), color));
+                            }
+                            span_start = text.len();
+                            self.color = color;
+                        }
+                    }
+                }
+                Some(']') => {
+                    // Operating system commands end with BEL or ST (ESC \).
+                    while let Some(ch) = chars.next() {
+                        if ch == '\u{7}' || (ch == '\u{1b}' && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(color) = self.color.filter(|_| span_start < text.len()) {
+            spans.push(Span::new(span_start..text.len(), color));
+        }
+
+        let mut line = Line::from(text);
+        line.spans = spans;
+        line
+    }
+
+    fn apply_sgr(&self, parameters: &str) -> Option<Color> {
+        let mut color = self.color;
+        let mut codes = parameters
+            .split(';')
+            .map(|code| code.parse::<u8>().unwrap_or(0));
+        while let Some(code) = codes.next() {
+            color = match code {
+                0 | 39 => None,
+                30..=37 => Some(PALETTE[usize::from(code - 30)]),
+                90..=97 => Some(PALETTE[usize::from(code - 90 + 8)]),
+                38 => extended_color(&mut codes).or(color),
+                // Backgrounds aren't displayed, but their arguments must not
+                // be read as codes of their own.
+                48 => {
+                    extended_color(&mut codes);
+                    color
+                }
+                _ => color,
+            };
+        }
+        color
+    }
+}
+
+// Reads the arguments of an extended color code: `5;n` for an indexed color,
+// or `2;r;g;b`.
+fn extended_color(codes: &mut impl Iterator<Item = u8>) -> Option<Color> {
+    match codes.next() {
+        Some(5) => codes.next().map(indexed_color),
+        Some(2) => match (codes.next(), codes.next(), codes.next()) {
+            (Some(red), Some(green), Some(blue)) => Some(Color::rgb(red, green, blue)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn indexed_color(index: u8) -> Color {
+    match index {
+        0..=15 => PALETTE[usize::from(index)],
+        16..=231 => {
+            let index = index - 16;
+            let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+            Color::rgb(level(index / 36), level(index / 6 % 6), level(index % 6))
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            Color::rgb(gray, gray, gray)
+        }
+    }
+}
+
+#[test]
+fn ansi_tests() {
+    let mut parser = AnsiParser::default();
+    let line =
+        parser.parse_line("plain \u{1b}[31mred\u{1b}[0m \u{1b}]0;title\u{7}\u{1b}[1;32mgreen");
+    assert_eq!(&**line, "plain red green");
+    assert_eq!(
+        line.spans,
+        [Span::new(6..9, PALETTE[1]), Span::new(10..15, PALETTE[2])]
+    );
+
+    // The color continues on the next line until it is reset.
+    let line = parser.parse_line("still green\u{1b}[K");
+    assert_eq!(&**line, "still green");
+    assert_eq!(line.spans, [Span::new(0..11, PALETTE[2])]);
+}
+
+#[test]
+fn background_colors() {
+    let mut parser = AnsiParser::default();
+    // A zero component isn't a reset, and 31 isn't red.
+    let line = parser.parse_line("\u{1b}[32m\u{1b}[48;2;0;0;0mgreen\u{1b}[48;5;31m still");
+    assert_eq!(line.spans, [Span::new(0..11, PALETTE[2])]);
+    let line = parser.parse_line("\u{1b}[48;2;10;200;30;31mred\u{1b}[48;5;0m");
+    assert_eq!(line.spans, [Span::new(0..3, PALETTE[1])]);
+    let line = parser.parse_line("\u{1b}[38;2;0;0;0;48;5;200mblack");
+    assert_eq!(line.spans, [Span::new(0..5, Color::rgb(0, 0, 0))]);
+}
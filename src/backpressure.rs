@@ -0,0 +1,72 @@
+use std::fmt;
+
+/// What happens when a line is pushed while a console's output limit is
+/// reached. See [`Console::limit_output`](crate::Console::limit_output).
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Overflow {
+    /// Wait until the console displays the lines already pushed.
+    Block,
+    /// Discard the line. Once there is room again, a line reporting the
+    /// number of discarded lines is pushed.
+    Drop,
+}
+
+/// Returned by [`Console::try_push_line`](crate::Console::try_push_line)
+/// when the console's output limit has been reached.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Full;
+
+impl fmt::Display for Full {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the console's output limit has been reached")
+    }
+}
+
+impl std::error::Error for Full {}
+
+/// Tracks the lines that have been pushed but not yet displayed.
+#[derive(Debug)]
+pub(crate) struct Limit {
+    pub capacity: usize,
+    pub overflow: Overflow,
+    pub undisplayed: usize,
+    pub dropped: usize,
+}
+
+impl Limit {
+    pub fn new(capacity: usize, overflow: Overflow) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            overflow,
+            undisplayed: 0,
+            dropped: 0,
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.undisplayed >= self.capacity
+    }
+
+    /// Records a line being pushed, returning the notice to push before it
+    /// if any lines were dropped.
+    pub fn pushed(&mut self) -> Option<String> {
+        self.undisplayed += 1;
+        match std::mem::take(&mut self.dropped) {
+            0 => None,
+            1 => Some(String::from("1 line dropped")),
+            dropped => Some(format!("{dropped} lines dropped")),
+        }
+    }
+}
+
+#[test]
+fn limit_notices() {
+    let mut limit = Limit::new(2, Overflow::Drop);
+    assert_eq!(limit.pushed(), None);
+    assert_eq!(limit.pushed(), None);
+    assert!(limit.is_full());
+    limit.dropped = 3;
+    limit.undisplayed = 0;
+    assert_eq!(limit.pushed().as_deref(), Some("3 lines dropped"));
+    assert_eq!(limit.pushed(), None);
+}
@@ -0,0 +1,97 @@
+use std::ops::Range;
+
+use unicode_bidi::{BidiClass, BidiInfo, Direction};
+
+/// Returns true if the first strongly directional character in `text` is
+/// right-to-left.
+pub fn is_rtl(text: &str) -> bool {
+    unicode_bidi::get_base_direction(text) == Direction::Rtl
+}
+
+/// Splits `row`, a range of `text` that does not span multiple paragraphs,
+/// into runs in the order they are displayed. Each run is paired with
+/// whether its characters are displayed right-to-left.
+pub fn visual_runs(text: &str, row: Range<usize>) -> Vec<(Range<usize>, bool)> {
+    let has_rtl = text[row.clone()].chars().any(|ch| {
+        matches!(
+            unicode_bidi::bidi_class(ch),
+            BidiClass::R
+                | BidiClass::AL
+                | BidiClass::AN
+                | BidiClass::RLE
+                | BidiClass::RLO
+                | BidiClass::RLI
+        )
+    });
+    if !has_rtl && !is_rtl(text) {
+        return vec![(row, false)];
+    }
+
+    let info = BidiInfo::new(text, None);
+    let Some(paragraph) = info
+        .paragraphs
+        .iter()
+        .find(|paragraph| paragraph.range.contains(&row.start))
+    else {
+        return vec![(row, false)];
+    };
+    let (levels, runs) = info.visual_runs(paragraph, row);
+    runs.into_iter()
+        .map(|run| {
+            let rtl = levels[run.start].is_rtl();
+            (run, rtl)
+        })
+        .collect()
+}
+
+/// Returns the offset in `text` of the character displayed `column`
+/// characters into `row`, or the end of `row` if it is narrower.
+pub fn offset_at_column(text: &str, row: Range<usize>, column: usize) -> usize {
+    let mut remaining = column;
+    for (run, rtl) in visual_runs(text, row.clone()) {
+        let chars = text[run.clone()].chars().count();
+        if remaining < chars {
+            // Right-to-left runs are displayed last character first.
+            let nth = if rtl {
+                chars - 1 - remaining
+            } else {
+                remaining
+            };
+            let (index, _) = text[run.clone()]
+                .char_indices()
+                .nth(nth)
+                .expect("within the run");
+            return run.start + index;
+        }
+        remaining -= chars;
+    }
+    row.end
+}
+
+#[test]
+fn bidi_runs() {
+    let text = "abc אבג def";
+    assert!(!is_rtl(text));
+    assert_eq!(
+        visual_runs(text, 0..text.len()),
+        [(0..4, false), (4..10, true), (10..14, false)]
+    );
+
+    let text = "אבג abc";
+    assert!(is_rtl(text));
+    assert_eq!(
+        visual_runs(text, 0..text.len()),
+        [(7..10, false), (0..7, true)]
+    );
+}
+
+#[test]
+fn columns_map_to_visual_order() {
+    let text = "abc אבג def";
+    assert_eq!(offset_at_column(text, 0..text.len(), 1), 1);
+    // The first Hebrew letter is displayed last in its run.
+    assert_eq!(offset_at_column(text, 0..text.len(), 6), 4);
+    assert_eq!(offset_at_column(text, 0..text.len(), 4), 8);
+    assert_eq!(offset_at_column(text, 0..text.len(), 8), 11);
+    assert_eq!(offset_at_column(text, 0..text.len(), 20), text.len());
+}
@@ -0,0 +1,49 @@
+use std::fs::File;
+use std::io;
+use std::os::fd::{FromRawFd, RawFd};
+use std::sync::Arc;
+
+use crate::{Level, State, Writer, ERROR_COLOR};
+
+/// Redirects this process's stdout and stderr into `state`'s scrollback, so
+/// that output printed by dependencies is visible in the GUI.
+pub(crate) fn install(state: &Arc<State>) -> io::Result<()> {
+    redirect(
+        libc::STDOUT_FILENO,
+        Writer::new(state, None).interpret_ansi(),
+    )?;
+    let stderr = Writer::new(state, None)
+        .interpret_ansi()
+        .with_color(ERROR_COLOR)
+        .with_level(Level::Error);
+    redirect(libc::STDERR_FILENO, stderr)
+}
+
+fn redirect(target: RawFd, mut writer: Writer) -> io::Result<()> {
+    let mut fds = [0; 2];
+    // SAFETY: `fds` has room for the two descriptors `pipe` returns.
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let [read, write] = fds;
+    // SAFETY: both descriptors were just opened and are owned here. Once
+    // `target` refers to the pipe, the original write descriptor is no
+    // longer needed.
+    let redirected = unsafe { libc::dup2(write, target) };
+    let error = io::Error::last_os_error();
+    let reader = unsafe {
+        libc::close(write);
+        File::from_raw_fd(read)
+    };
+    if redirected < 0 {
+        return Err(error);
+    }
+
+    std::thread::Builder::new()
+        .name(format!("capture fd {target}"))
+        .spawn(move || {
+            let mut reader = reader;
+            let _ = io::copy(&mut reader, &mut writer);
+        })?;
+    Ok(())
+}
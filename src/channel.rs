@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::scrollback::Line;
+use crate::{Color, Console};
+
+/// A handle to a [`Console`] that tags every line it pushes with a channel
+/// name. Channels can be muted or colored at runtime.
+#[derive(Clone)]
+pub struct Channel {
+    console: Console,
+    name: Arc<str>,
+}
+
+impl Channel {
+    pub(crate) fn new(console: Console, name: Arc<str>) -> Self {
+        Self { console, name }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn push_line(&self, line: impl Into<String>) {
+        self.console
+            .push(Line::new(line.into(), Some(self.name.clone())));
+        self.console.state.redraw();
+    }
+
+    pub fn mute(&self) {
+        self.console.mute_channel(&self.name);
+    }
+
+    pub fn unmute(&self) {
+        self.console.unmute_channel(&self.name);
+    }
+
+    pub fn set_color(&self, color: Option<Color>) {
+        self.console.set_channel_color(&self.name, color);
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ChannelSettings {
+    pub muted: bool,
+    pub color: Option<Color>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Channels(HashMap<Arc<str>, ChannelSettings>);
+
+impl Channels {
+    fn settings(&self, line: &Line) -> Option<&ChannelSettings> {
+        line.channel
+            .as_ref()
+            .and_then(|channel| self.0.get(channel))
+    }
+
+    /// Returns false if the line's channel is muted or the line is gagged.
+    pub fn is_visible(&self, line: &Line) -> bool {
+        !line.gagged && self.settings(line).map_or(true, |settings| !settings.muted)
+    }
+
+    pub fn color_of(&self, line: &Line) -> Option<Color> {
+        line.color
+            .or_else(|| self.settings(line).and_then(|settings| settings.color))
+    }
+
+    pub fn update(&mut self, name: &str, update: impl FnOnce(&mut ChannelSettings)) {
+        if let Some(settings) = self.0.get_mut(name) {
+            update(settings);
+        } else {
+            let mut settings = ChannelSettings::default();
+            update(&mut settings);
+            self.0.insert(Arc::from(name), settings);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use crate::scrollback::Line;
+use crate::style::Span;
+use crate::width::Alignment;
+use crate::Color;
+
+const SENDER_COLOR: Color = Color::rgb(160, 160, 160);
+
+/// The percentage of the console's width that messages wrap within.
+const MESSAGE_WIDTH_PERCENT: usize = 70;
+
+/// Who sent a message pushed with
+/// [`Console::push_message`](crate::Console::push_message).
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sender {
+    name: String,
+    local: bool,
+}
+
+impl Sender {
+    /// The user of this console. Their messages are aligned to the right.
+    pub fn local(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            local: true,
+        }
+    }
+
+    /// Anyone else. Their messages are aligned to the left.
+    pub fn remote(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            local: false,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_local(&self) -> bool {
+        self.local
+    }
+}
+
+/// Returns the number of columns a message wraps within in a console that is
+/// `columns` wide.
+pub(crate) fn message_columns(columns: usize) -> usize {
+    (columns.saturating_mul(MESSAGE_WIDTH_PERCENT) / 100).max(1)
+}
+
+/// Returns a line containing the sender's name followed by `text`.
+pub(crate) fn message(sender: Sender, text: &str) -> Line {
+    let mut line = Line::from(format!("{}\n{text}", sender.name));
+    line.spans
+        .push(Span::new(0..sender.name.len(), SENDER_COLOR));
+    line.alignment = Some(if sender.local {
+        Alignment::Right
+    } else {
+        Alignment::Left
+    });
+    line.sender = Some(sender);
+    line
+}
+
+#[test]
+fn message_wrapping() {
+    let mut line = message(Sender::local("ecton"), "hello there, world");
+    line.rewrap(20);
+    assert_eq!(
+        line.rows().collect::<Vec<_>>(),
+        ["ecton", "hello there, ", "world"]
+    );
+    assert_eq!(line.alignment, Some(Alignment::Right));
+}
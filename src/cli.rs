@@ -0,0 +1,88 @@
+use std::any::Any;
+use std::fmt;
+
+use clap::error::ErrorKind;
+
+use crate::command::split;
+use crate::Level;
+
+/// A command parsed from a submitted line by the parser set with
+/// [`Console::set_command_parser`](crate::Console::set_command_parser).
+pub struct ParsedCommand(Box<dyn Any + Send>);
+
+impl fmt::Debug for ParsedCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParsedCommand").finish_non_exhaustive()
+    }
+}
+
+impl ParsedCommand {
+    /// Returns the parsed command, or `self` if it isn't a `T`.
+    pub fn downcast<T: 'static>(self) -> Result<T, Self> {
+        self.0.downcast().map(|command| *command).map_err(Self)
+    }
+
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        self.0.downcast_ref()
+    }
+}
+
+/// Parses a submitted line without its leading slash, or returns the text to
+/// display instead, such as help or an error.
+pub(crate) type CommandParser =
+    Box<dyn Fn(&str) -> Result<ParsedCommand, (Level, String)> + Send + Sync>;
+
+pub(crate) fn parser<P>() -> CommandParser
+where
+    P: clap::Parser + Send + 'static,
+{
+    Box::new(|line| {
+        let arguments = split(line).map_err(|err| (Level::Error, err.to_string()))?;
+        // Commands are typed without a program name, so help shows the slash
+        // in its place, such as "Usage: / connect <HOST>".
+        let command = P::command().no_binary_name(true).bin_name("/");
+        command
+            .try_get_matches_from(arguments)
+            .and_then(|matches| P::from_arg_matches(&matches))
+            .map(|parsed| ParsedCommand(Box::new(parsed)))
+            .map_err(|err| {
+                let level = match err.kind() {
+                    ErrorKind::DisplayHelp
+                    | ErrorKind::DisplayVersion
+                    | ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand => Level::Info,
+                    _ => Level::Error,
+                };
+                (level, err.render().to_string().trim_end().to_string())
+            })
+    })
+}
+
+#[test]
+fn clap_commands() {
+    #[derive(clap::Parser, Debug, Eq, PartialEq)]
+    enum Cli {
+        /// Connects to a server.
+        Connect {
+            host: String,
+            port: Option<u16>,
+        },
+        Quit,
+    }
+
+    let parse = parser::<Cli>();
+    let parsed = parse("connect \"example server\" 6667").unwrap();
+    assert_eq!(
+        parsed.downcast::<Cli>().unwrap(),
+        Cli::Connect {
+            host: String::from("example server"),
+            port: Some(6667)
+        }
+    );
+
+    let (level, help) = parse("help").unwrap_err();
+    assert_eq!(level, Level::Info);
+    assert!(help.contains("Connects to a server"), "{help}");
+    let (level, error) = parse("connect").unwrap_err();
+    assert_eq!(level, Level::Error);
+    assert!(error.contains("<HOST>"), "{error}");
+}
@@ -0,0 +1,78 @@
+use crate::PRIMARY_SELECTION;
+
+/// The system clipboard. It is kept open once used, because on some
+/// platforms copied text is only available while the app that copied it
+/// holds the clipboard.
+#[derive(Default)]
+pub(crate) struct Clipboard(Option<arboard::Clipboard>);
+
+impl Clipboard {
+    pub fn copy(&mut self, text: String) -> Result<(), arboard::Error> {
+        self.open()?.set_text(text)
+    }
+
+    /// Sets the primary selection, which is pasted with the middle mouse
+    /// button. Does nothing unless [`PRIMARY_SELECTION`] is true.
+    pub fn set_primary(&mut self, text: String) -> Result<(), arboard::Error> {
+        if !PRIMARY_SELECTION {
+            return Ok(());
+        }
+        primary::set(self.open()?, text)
+    }
+
+    /// Returns the primary selection, which may have been set by another
+    /// app. Returns None if it is empty, or unless [`PRIMARY_SELECTION`] is
+    /// true.
+    pub fn primary(&mut self) -> Result<Option<String>, arboard::Error> {
+        if !PRIMARY_SELECTION {
+            return Ok(None);
+        }
+        match primary::get(self.open()?) {
+            Ok(text) => Ok(Some(text)),
+            Err(arboard::Error::ContentNotAvailable) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn open(&mut self) -> Result<&mut arboard::Clipboard, arboard::Error> {
+        let clipboard = match self.0.take() {
+            Some(clipboard) => clipboard,
+            None => arboard::Clipboard::new()?,
+        };
+        Ok(self.0.insert(clipboard))
+    }
+}
+
+// The platform's primary selection, which only exists where
+// PRIMARY_SELECTION is true.
+#[cfg(primary_selection)]
+mod primary {
+    use arboard::{Clipboard, Error, GetExtLinux, LinuxClipboardKind, SetExtLinux};
+
+    pub fn set(clipboard: &mut Clipboard, text: String) -> Result<(), Error> {
+        clipboard
+            .set()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text(text)
+    }
+
+    pub fn get(clipboard: &mut Clipboard) -> Result<String, Error> {
+        clipboard
+            .get()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text()
+    }
+}
+
+#[cfg(not(primary_selection))]
+mod primary {
+    use arboard::{Clipboard, Error};
+
+    pub fn set(_clipboard: &mut Clipboard, _text: String) -> Result<(), Error> {
+        Err(Error::ClipboardNotSupported)
+    }
+
+    pub fn get(_clipboard: &mut Clipboard) -> Result<String, Error> {
+        Err(Error::ClipboardNotSupported)
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::VecDeque;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+/// A set of slash commands, such as `/connect example.com 6667`, usually
+/// implemented by deriving it on an enum:
+///
+/// ```ignore
+/// #[derive(ConsoleCommand)]
+/// enum Cmd {
+///     /// Connects to a server.
+///     Connect { host: String, port: Option<u16>, tls: bool },
+///     Quit,
+/// }
+/// ```
+///
+/// Each variant is a command named after the variant in kebab-case, and its
+/// first doc comment line is the command's help. Fields are parsed in order
+/// from the arguments with [`FromStr`]. `Option` fields may be left out at
+/// the end, and `bool` fields are flags, such as `--tls`, that can be given
+/// anywhere. Arguments are separated by whitespace, and can be quoted to
+/// include it.
+///
+/// Submitted inputs are parsed with
+/// [`Console::parse_command`](crate::Console::parse_command).
+pub trait ConsoleCommand: Sized {
+    /// Returns each command, in the order they're listed by `/help`.
+    fn commands() -> &'static [CommandInfo];
+
+    /// Parses the command `name` from its `arguments`.
+    fn parse_command(name: &str, arguments: &mut Arguments) -> Result<Self, CommandError>;
+
+    /// Parses `line`, which starts with a slash.
+    fn parse(line: &str) -> Result<Self, CommandError> {
+        let line = line.strip_prefix('/').ok_or(CommandError::NotACommand)?;
+        let mut arguments = Arguments::new(line)?;
+        let name = arguments.tokens.pop_front().unwrap_or_default();
+        let command = Self::parse_command(&name, &mut arguments)?;
+        match arguments.tokens.pop_front() {
+            Some(extra) => Err(CommandError::Unexpected(extra)),
+            None => Ok(command),
+        }
+    }
+
+    /// Returns the names of the commands starting with `partial`, for
+    /// answering [`ConsoleEvent::TriggerCompletion`](crate::ConsoleEvent::TriggerCompletion)
+    /// when `/` is a completion trigger.
+    fn completions(partial: &str) -> Vec<&'static str> {
+        Self::commands()
+            .iter()
+            .map(|command| command.name)
+            .filter(|name| name.starts_with(partial))
+            .collect()
+    }
+}
+
+/// A command's name and documentation.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct CommandInfo {
+    pub name: &'static str,
+    /// The command followed by its arguments, such as `/connect <host> <port>`.
+    pub usage: &'static str,
+    pub help: &'static str,
+}
+
+/// The arguments following a command's name.
+#[derive(Debug)]
+pub struct Arguments {
+    tokens: VecDeque<String>,
+}
+
+impl Arguments {
+    fn new(line: &str) -> Result<Self, CommandError> {
+        Ok(Self {
+            tokens: split(line)?,
+        })
+    }
+
+    /// Parses the next argument, which is named `argument` in errors.
+    pub fn next<T>(&mut self, argument: &'static str) -> Result<T, CommandError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let value = self
+            .tokens
+            .pop_front()
+            .ok_or(CommandError::Missing { argument })?;
+        value.parse().map_err(|err: T::Err| CommandError::Invalid {
+            argument,
+            reason: err.to_string(),
+            value,
+        })
+    }
+
+    /// Parses the next argument like [`Self::next`], returning None if there
+    /// are no more arguments.
+    pub fn optional<T>(&mut self, argument: &'static str) -> Result<Option<T>, CommandError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        if self.tokens.is_empty() {
+            return Ok(None);
+        }
+        self.next(argument).map(Some)
+    }
+
+    /// Removes the flag `--name` from wherever it is in the arguments,
+    /// returning whether it was given.
+    pub fn flag(&mut self, name: &str) -> bool {
+        let flag = format!("--{name}");
+        let Some(index) = self.tokens.iter().position(|token| *token == flag) else {
+            return false;
+        };
+        self.tokens.remove(index);
+        true
+    }
+}
+
+/// Splits `line` at whitespace outside of quotes.
+pub(crate) fn split(line: &str) -> Result<VecDeque<String>, CommandError> {
+    let mut tokens = VecDeque::new();
+    let mut token = None::<String>;
+    let mut quoted = false;
+    for ch in line.chars() {
+        match ch {
+            '"' => {
+                quoted = !quoted;
+                token.get_or_insert_with(String::new);
+            }
+            ch if ch.is_whitespace() && !quoted => tokens.extend(token.take()),
+            ch => token.get_or_insert_with(String::new).push(ch),
+        }
+    }
+    if quoted {
+        return Err(CommandError::UnclosedQuote);
+    }
+    tokens.extend(token);
+    Ok(tokens)
+}
+
+/// Why a line couldn't be parsed as a command.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum CommandError {
+    /// The line doesn't start with a slash.
+    NotACommand,
+    Unknown(String),
+    Missing {
+        argument: &'static str,
+    },
+    Invalid {
+        argument: &'static str,
+        value: String,
+        reason: String,
+    },
+    /// An argument followed the command's last argument.
+    Unexpected(String),
+    UnclosedQuote,
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::NotACommand => f.write_str("commands start with /"),
+            CommandError::Unknown(name) => write!(f, "unknown command: /{name}"),
+            CommandError::Missing { argument } => write!(f, "missing <{argument}>"),
+            CommandError::Invalid {
+                argument,
+                value,
+                reason,
+            } => write!(f, "invalid <{argument}> {value:?}: {reason}"),
+            CommandError::Unexpected(value) => write!(f, "unexpected argument {value:?}"),
+            CommandError::UnclosedQuote => f.write_str("unclosed quote"),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+#[test]
+fn command_parsing() {
+    #[derive(Debug, Eq, PartialEq)]
+    enum Cmd {
+        Say(String),
+        Wait { seconds: u32 },
+    }
+
+    impl ConsoleCommand for Cmd {
+        fn commands() -> &'static [CommandInfo] {
+            &[
+                CommandInfo {
+                    name: "say",
+                    usage: "/say <message>",
+                    help: "",
+                },
+                CommandInfo {
+                    name: "wait",
+                    usage: "/wait <seconds>",
+                    help: "",
+                },
+            ]
+        }
+
+        fn parse_command(name: &str, arguments: &mut Arguments) -> Result<Self, CommandError> {
+            match name {
+                "say" => Ok(Self::Say(arguments.next("message")?)),
+                "wait" => Ok(Self::Wait {
+                    seconds: arguments.next("seconds")?,
+                }),
+                _ => Err(CommandError::Unknown(name.to_string())),
+            }
+        }
+    }
+
+    assert_eq!(
+        Cmd::parse(r#"/say "hi  there""#),
+        Ok(Cmd::Say(String::from("hi  there")))
+    );
+    assert_eq!(Cmd::parse("/wait 5"), Ok(Cmd::Wait { seconds: 5 }));
+    assert_eq!(
+        Cmd::parse("/wait"),
+        Err(CommandError::Missing {
+            argument: "seconds"
+        })
+    );
+    assert_eq!(
+        Cmd::parse("/wait soon").unwrap_err().to_string(),
+        "invalid <seconds> \"soon\": invalid digit found in string"
+    );
+    assert_eq!(
+        Cmd::parse("/say a b"),
+        Err(CommandError::Unexpected(String::from("b")))
+    );
+    assert_eq!(Cmd::parse("/say \"a"), Err(CommandError::UnclosedQuote));
+    assert_eq!(
+        Cmd::parse("/quit"),
+        Err(CommandError::Unknown(String::from("quit")))
+    );
+    assert_eq!(Cmd::parse("say"), Err(CommandError::NotACommand));
+    assert_eq!(Cmd::completions("w"), ["wait"]);
+}
@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "hot-reload")]
+use std::sync::Weak;
+#[cfg(feature = "hot-reload")]
+use std::time::Duration;
+
+use anyhow::Context;
+use parking_lot::{Mutex, MutexGuard};
+use serde::Deserialize;
+
+use crate::scrollback::Scrollback;
+use crate::shortcut::{self, Shortcut};
+use crate::style::{Color, Style};
+
+#[cfg(feature = "hot-reload")]
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// The file as written, before its colors and shortcuts are parsed.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct RawSettings {
+    font_size: Option<f32>,
+    high_contrast: Option<bool>,
+    leader: Option<String>,
+    theme: RawTheme,
+    keys: HashMap<String, String>,
+    layout: RawLayout,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct RawTheme {
+    colors: HashMap<String, String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct RawLayout {
+    wrap: Option<bool>,
+    alternate_background: Option<String>,
+}
+
+/// The settings loaded from a config file. Settings missing from the file
+/// are left as the app configured them, including settings removed from it
+/// while the console runs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Settings {
+    pub font_size: Option<f32>,
+    pub high_contrast: Option<bool>,
+    // Colors used by the app, and the colors displayed in their place.
+    pub colors: HashMap<Color, Color>,
+    // Shortcuts pressed in turn, and the shortcut they act as.
+    pub keys: HashMap<Vec<Shortcut>, Shortcut>,
+    pub wrap: Option<bool>,
+    pub alternate_background: Option<Color>,
+}
+
+impl Settings {
+    pub fn parse(toml: &str) -> anyhow::Result<Self> {
+        let raw: RawSettings = toml::from_str(toml)?;
+        let colors = raw
+            .theme
+            .colors
+            .iter()
+            .map(|(from, to)| Ok((parse_color(from)?, parse_color(to)?)))
+            .collect::<anyhow::Result<_>>()?;
+        let leader = raw
+            .leader
+            .as_deref()
+            .map(str::parse::<Shortcut>)
+            .transpose()?;
+        let keys = raw
+            .keys
+            .iter()
+            .map(|(from, to)| Ok((shortcut::parse_chord(from, leader)?, to.parse()?)))
+            .collect::<anyhow::Result<_>>()?;
+        let alternate_background = raw
+            .layout
+            .alternate_background
+            .as_deref()
+            .map(parse_color)
+            .transpose()?;
+        Ok(Self {
+            font_size: raw.font_size,
+            high_contrast: raw.high_contrast,
+            colors,
+            keys,
+            wrap: raw.layout.wrap,
+            alternate_background,
+        })
+    }
+
+    pub fn apply_layout(&self, scrollback: &mut Scrollback) {
+        scrollback.no_wrap = self.wrap.map_or(scrollback.app_no_wrap, |wrap| !wrap);
+        if !scrollback.no_wrap {
+            scrollback.view.horizontal_scroll = 0;
+        }
+        scrollback.alternate_background = self
+            .alternate_background
+            .or(scrollback.app_alternate_background);
+    }
+}
+
+// Parses a color written as `#rrggbb`.
+fn parse_color(color: &str) -> anyhow::Result<Color> {
+    let invalid = || anyhow::anyhow!("invalid color {color:?}, expected #rrggbb");
+    let hex = color.strip_prefix('#').ok_or_else(invalid)?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let channel =
+        |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).map_err(|_| invalid());
+    Ok(Color::rgb(channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// A config file loaded with [`Config::from_file`](crate::Config::from_file).
+#[derive(Debug)]
+pub(crate) struct ConfigFile {
+    path: PathBuf,
+    contents: Mutex<String>,
+    settings: Mutex<Settings>,
+    // Incremented each time the settings change, starting at 1.
+    generation: AtomicU64,
+    // Why the file last failed to reload, until it is reported.
+    error: Mutex<Option<anyhow::Error>>,
+}
+
+impl ConfigFile {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("error reading {}", path.display()))?;
+        let settings = Settings::parse(&contents)
+            .with_context(|| format!("error parsing {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            contents: Mutex::new(contents),
+            settings: Mutex::new(settings),
+            generation: AtomicU64::new(1),
+            error: Mutex::default(),
+        })
+    }
+
+    pub fn settings(&self) -> MutexGuard<'_, Settings> {
+        self.settings.lock()
+    }
+
+    /// Replaces the foreground with the theme's color for it, if any.
+    pub fn theme(&self, mut style: Style) -> Style {
+        if let Some(color) = self.settings.lock().colors.get(&style.foreground) {
+            style.foreground = *color;
+        }
+        style
+    }
+
+    pub fn take_error(&self) -> Option<anyhow::Error> {
+        self.error.lock().take()
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Reads the file again, returning true if its settings changed. If the
+    /// file can't be parsed, the previous settings are kept.
+    pub fn reload(&self) -> anyhow::Result<bool> {
+        let contents = fs::read_to_string(&self.path)
+            .with_context(|| format!("error reading {}", self.path.display()))?;
+        let mut previous = self.contents.lock();
+        if *previous == contents {
+            return Ok(false);
+        }
+        // Remember the contents even if they're invalid, so that each error
+        // is only reported once.
+        let settings = Settings::parse(&contents);
+        *previous = contents;
+        drop(previous);
+        let settings =
+            settings.with_context(|| format!("error parsing {}", self.path.display()))?;
+
+        let mut current = self.settings.lock();
+        if *current == settings {
+            return Ok(false);
+        }
+        *current = settings;
+        drop(current);
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        Ok(true)
+    }
+}
+
+/// Reloads the config file whenever it changes, until the console shuts
+/// down.
+#[cfg(feature = "hot-reload")]
+pub(crate) fn watch(shared: Weak<crate::Shared>) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let Some(shared) = shared.upgrade() else {
+            break;
+        };
+        let Some(file) = &shared.config.file else {
+            break;
+        };
+        if shared.should_shutdown() {
+            break;
+        }
+        match file.reload() {
+            Ok(true) => shared.redraw(),
+            Ok(false) => {}
+            // The error is shown in the window once it is redrawn.
+            Err(err) => {
+                *file.error.lock() = Some(err);
+                shared.redraw();
+            }
+        }
+    });
+}
+
+#[test]
+fn loading_config_files() {
+    use crate::shortcut::Key;
+
+    let path = std::env::temp_dir().join(format!("console-thingy-{}.toml", std::process::id()));
+    fs::write(
+        &path,
+        r##"
+font_size = 18.0
+leader = "Ctrl+Space"
+
+[theme.colors]
+"#ff0000" = "#e69f00"
+
+[keys]
+"Ctrl+K" = "Primary+F"
+"Leader W" = "Primary+W"
+
+[layout]
+wrap = false
+alternate_background = "#202020"
+"##,
+    )
+    .unwrap();
+    let file = ConfigFile::load(&path).unwrap();
+    let settings = file.settings().clone();
+    assert_eq!(settings.font_size, Some(18.0));
+    assert_eq!(settings.high_contrast, None);
+    assert_eq!(
+        settings.colors.get(&Color::rgb(255, 0, 0)),
+        Some(&Color::rgb(230, 159, 0))
+    );
+    assert_eq!(
+        settings
+            .keys
+            .get(&vec![Shortcut::new(Key::Char('k')).control()]),
+        Some(&Shortcut::new(Key::Char('f')).primary())
+    );
+    assert_eq!(
+        settings.keys.get(&vec![
+            Shortcut::new(Key::Char(' ')).control(),
+            Shortcut::new(Key::Char('w'))
+        ]),
+        Some(&Shortcut::new(Key::Char('w')).primary())
+    );
+    let mut scrollback = Scrollback::default();
+    settings.apply_layout(&mut scrollback);
+    assert!(scrollback.no_wrap);
+    assert_eq!(
+        scrollback.alternate_background,
+        Some(Color::rgb(32, 32, 32))
+    );
+    // Settings removed from the file restore the app's.
+    scrollback.app_alternate_background = Some(Color::rgb(16, 16, 16));
+    Settings::default().apply_layout(&mut scrollback);
+    assert!(!scrollback.no_wrap);
+    assert_eq!(
+        scrollback.alternate_background,
+        Some(Color::rgb(16, 16, 16))
+    );
+    assert!(!file.reload().unwrap());
+    assert_eq!(file.generation(), 1);
+
+    // Invalid files are reported, and the previous settings are kept.
+    fs::write(
+        &path,
+        "font_size = 12.0\n[theme.colors]\nred = \"#000000\"\n",
+    )
+    .unwrap();
+    assert!(file.reload().is_err());
+    assert!(!file.reload().unwrap());
+    assert_eq!(file.settings().font_size, Some(18.0));
+
+    fs::write(&path, "font_size = 12.0\nhigh_contrast = true\n").unwrap();
+    assert!(file.reload().unwrap());
+    assert_eq!(file.generation(), 2);
+    assert_eq!(file.settings().font_size, Some(12.0));
+    assert_eq!(file.settings().high_contrast, Some(true));
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,430 @@
+use crate::scrollback::Scrollback;
+use crate::selection::{self, Granularity, Selection, TextPosition};
+
+/// A cursor in the scrollback moved with vi-style motions, which selects
+/// text to copy without a mouse.
+#[derive(Debug)]
+pub(crate) struct CopyMode {
+    // Whether the cursor is in the pinned view of a split rather than the
+    // followed one.
+    pinned: bool,
+    cursor: Anchor,
+    // Where the selection started, once `v` or Space is pressed.
+    anchor: Option<Anchor>,
+}
+
+// A position within the line with the id `line`. Unlike a TextPosition, it
+// stays on the same text as lines are inserted or removed before it.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+struct Anchor {
+    line: u64,
+    offset: usize,
+}
+
+impl Anchor {
+    fn new(scrollback: &Scrollback, position: TextPosition) -> Option<Self> {
+        Some(Self {
+            line: scrollback.line(position.line)?.id,
+            offset: position.offset,
+        })
+    }
+
+    fn position(self, scrollback: &Scrollback) -> Option<TextPosition> {
+        let line = scrollback.position_of(self.line)?;
+        // The line may have been updated since.
+        let offset = self.offset.min(text(scrollback, line).len());
+        Some(TextPosition { line, offset })
+    }
+}
+
+/// What a key pressed in copy mode did.
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) enum CopyAction {
+    Moved,
+    Copied(String),
+    Exited,
+    Ignored,
+}
+
+impl CopyMode {
+    /// Starts copy mode in the pinned view if `pinned` is true and the
+    /// output is split, or otherwise in the followed view. The cursor starts
+    /// at the newest line the view shows. Returns None if no lines are
+    /// visible.
+    pub fn begin(scrollback: &mut Scrollback, pinned: bool) -> Option<Self> {
+        let pinned = pinned && scrollback.pinned.is_some();
+        let scroll = scrollback.view_mut(pinned).scroll;
+        let columns = scrollback.wrap_columns();
+        let mut below = 0;
+        let mut shown = None;
+        for (newer, line) in scrollback.events.iter_mut().enumerate() {
+            if !scrollback.channels.is_visible(line) {
+                continue;
+            }
+            line.rewrap(columns);
+            below += line.line_ranges().len();
+            shown = Some(newer);
+            if below > scroll {
+                break;
+            }
+        }
+        let line = scrollback.len() - shown? - 1;
+        let copy_mode = Self {
+            pinned,
+            cursor: Anchor::new(scrollback, TextPosition { line, offset: 0 })?,
+            anchor: None,
+        };
+        copy_mode.show(scrollback, TextPosition { line, offset: 0 }, None);
+        Some(copy_mode)
+    }
+
+    /// Handles `key`, moving the cursor and keeping the view on it within
+    /// `page_rows` rows.
+    pub fn key(&mut self, key: char, scrollback: &mut Scrollback, page_rows: usize) -> CopyAction {
+        let Some(cursor) = self.cursor.position(scrollback) else {
+            // The cursor's line was removed, or the scrollback was cleared.
+            scrollback.clear_selection();
+            return CopyAction::Exited;
+        };
+        let mut anchor = self.anchor.and_then(|anchor| anchor.position(scrollback));
+        let cursor = match key {
+            'h' => horizontal(scrollback, cursor, false),
+            'l' => horizontal(scrollback, cursor, true),
+            'k' => vertical(scrollback, cursor, false),
+            'j' => vertical(scrollback, cursor, true),
+            'w' => next_word(scrollback, cursor),
+            'b' => previous_word(scrollback, cursor),
+            '0' => TextPosition {
+                offset: 0,
+                ..cursor
+            },
+            '$' => TextPosition {
+                offset: last_offset(text(scrollback, cursor.line)),
+                ..cursor
+            },
+            'g' => {
+                while page_in(scrollback) {}
+                first_visible(scrollback, cursor, (0..scrollback.len()).collect())
+            }
+            'G' => first_visible(scrollback, cursor, (0..scrollback.len()).rev().collect()),
+            'v' | ' ' => {
+                anchor = match anchor {
+                    Some(_) => None,
+                    None => Some(cursor),
+                };
+                cursor
+            }
+            'y' | '\r' | '\n' => {
+                let text = scrollback.selected_text().unwrap_or_default();
+                scrollback.clear_selection();
+                return CopyAction::Copied(text);
+            }
+            'q' | '\u{1b}' => {
+                scrollback.clear_selection();
+                return CopyAction::Exited;
+            }
+            _ => return CopyAction::Ignored,
+        };
+        if let Some(moved) = Anchor::new(scrollback, cursor) {
+            self.cursor = moved;
+        }
+        self.anchor = anchor.and_then(|anchor| Anchor::new(scrollback, anchor));
+        self.show(scrollback, cursor, anchor);
+        self.scroll_into_view(scrollback, cursor, page_rows);
+        CopyAction::Moved
+    }
+
+    // Selects from the anchor to the character under the cursor, or just
+    // that character before a selection is started, in the cursor's view.
+    fn show(
+        &self,
+        scrollback: &mut Scrollback,
+        cursor: TextPosition,
+        anchor: Option<TextPosition>,
+    ) {
+        let anchor = anchor.unwrap_or(cursor);
+        let start = anchor.min(cursor);
+        let end = anchor.max(cursor);
+        let end = TextPosition {
+            offset: next_offset(text(scrollback, end.line), end.offset),
+            ..end
+        };
+        scrollback.clear_selection();
+        scrollback.view_mut(self.pinned).selection =
+            Some(Selection::new(start..end, Granularity::Character));
+    }
+
+    fn scroll_into_view(
+        &self,
+        scrollback: &mut Scrollback,
+        cursor: TextPosition,
+        page_rows: usize,
+    ) {
+        let columns = scrollback.wrap_columns();
+        // Spilled lines are paged in before the cursor moves onto them, so
+        // the cursor's line and every newer line are in memory.
+        let newer = scrollback.len() - cursor.line - 1;
+        let mut below = 0;
+        for line in scrollback.events.iter_mut().take(newer) {
+            if scrollback.channels.is_visible(line) {
+                line.rewrap(columns);
+                below += line.line_ranges().len();
+            }
+        }
+        let Some(line) = scrollback.events.get_mut(newer) else {
+            return;
+        };
+        line.rewrap(columns);
+        let rows = line.line_ranges().len();
+        let view = scrollback.view_mut(self.pinned);
+        if below < view.scroll {
+            view.scroll_to(below);
+        } else if below + rows > view.scroll + page_rows {
+            view.scroll_to((below + rows).saturating_sub(page_rows));
+        }
+    }
+}
+
+fn horizontal(scrollback: &Scrollback, cursor: TextPosition, forward: bool) -> TextPosition {
+    let text = text(scrollback, cursor.line);
+    let offset = if forward {
+        let next = next_offset(text, cursor.offset);
+        next.min(last_offset(text))
+    } else {
+        previous_offset(text, cursor.offset)
+    };
+    TextPosition { offset, ..cursor }
+}
+
+// Moves to the next visible line, paging in spilled lines when moving up
+// past the oldest line in memory.
+fn vertical(scrollback: &mut Scrollback, cursor: TextPosition, down: bool) -> TextPosition {
+    let line = loop {
+        let lines: Vec<usize> = if down {
+            (cursor.line + 1..scrollback.len()).collect()
+        } else {
+            (0..cursor.line).rev().collect()
+        };
+        if let Some(line) = lines.into_iter().find(|&line| is_visible(scrollback, line)) {
+            break line;
+        }
+        if down || !page_in(scrollback) {
+            return cursor;
+        }
+    };
+    // Stay in the same column where possible.
+    let column = text(scrollback, cursor.line)[..cursor.offset]
+        .chars()
+        .count();
+    let text = text(scrollback, line);
+    let offset = text
+        .char_indices()
+        .nth(column)
+        .map_or(last_offset(text), |(offset, _)| offset);
+    TextPosition { line, offset }
+}
+
+fn next_word(scrollback: &mut Scrollback, cursor: TextPosition) -> TextPosition {
+    let text = text(scrollback, cursor.line);
+    let mut offset = selection::word_at(text, cursor.offset).end;
+    if text[offset..].starts_with(char::is_whitespace) {
+        offset = selection::word_at(text, offset).end;
+    }
+    if offset < text.len() {
+        return TextPosition { offset, ..cursor };
+    }
+    // Continue at the start of the next line.
+    let next = vertical(scrollback, cursor, true);
+    if next.line == cursor.line {
+        cursor
+    } else {
+        TextPosition { offset: 0, ..next }
+    }
+}
+
+fn previous_word(scrollback: &mut Scrollback, cursor: TextPosition) -> TextPosition {
+    if cursor.offset == 0 {
+        // Continue at the last word of the previous line.
+        let previous = vertical(scrollback, cursor, false);
+        if previous.line == cursor.line {
+            return cursor;
+        }
+        let text = text(scrollback, previous.line);
+        return TextPosition {
+            offset: word_start_before(text, text.len()),
+            ..previous
+        };
+    }
+    TextPosition {
+        offset: word_start_before(text(scrollback, cursor.line), cursor.offset),
+        ..cursor
+    }
+}
+
+fn first_visible(scrollback: &Scrollback, cursor: TextPosition, lines: Vec<usize>) -> TextPosition {
+    lines
+        .into_iter()
+        .find(|&line| is_visible(scrollback, line))
+        .map_or(cursor, |line| TextPosition { line, offset: 0 })
+}
+
+// Moves the newest spilled lines back into memory, returning false if none
+// are left. Like a failure to spill, a failure to read them back is shown
+// in the scrollback.
+#[cfg(feature = "spill")]
+fn page_in(scrollback: &mut Scrollback) -> bool {
+    use crate::scrollback::{Level, Line};
+
+    match scrollback.page_in() {
+        Ok(paged) => paged,
+        Err(err) => {
+            let error = format!("error reading spilled scrollback: {err}");
+            scrollback.push(
+                Line::from(error)
+                    .with_level(Level::Error)
+                    .with_color(Some(crate::ERROR_COLOR)),
+            );
+            false
+        }
+    }
+}
+
+#[cfg(not(feature = "spill"))]
+fn page_in(_scrollback: &mut Scrollback) -> bool {
+    false
+}
+
+fn is_visible(scrollback: &Scrollback, line: usize) -> bool {
+    scrollback
+        .line(line)
+        .is_some_and(|line| scrollback.channels.is_visible(line))
+}
+
+fn text(scrollback: &Scrollback, line: usize) -> &str {
+    scrollback.line(line).map_or("", |line| line.as_str())
+}
+
+fn next_offset(text: &str, offset: usize) -> usize {
+    text[offset..]
+        .chars()
+        .next()
+        .map_or(offset, |ch| offset + ch.len_utf8())
+}
+
+fn previous_offset(text: &str, offset: usize) -> usize {
+    text[..offset]
+        .char_indices()
+        .next_back()
+        .map_or(0, |(offset, _)| offset)
+}
+
+// The offset of the last character, where the cursor stops at the end of a
+// line.
+fn last_offset(text: &str) -> usize {
+    previous_offset(text, text.len())
+}
+
+// Returns the start of the word before `offset`, skipping whitespace.
+fn word_start_before(text: &str, offset: usize) -> usize {
+    let mut start = selection::word_at(text, previous_offset(text, offset)).start;
+    if start > 0 && text[start..].starts_with(char::is_whitespace) {
+        start = selection::word_at(text, previous_offset(text, start)).start;
+    }
+    start
+}
+
+#[test]
+fn copy_mode_motions() {
+    use crate::scrollback::Line;
+
+    let mut scrollback = Scrollback {
+        columns: 80,
+        ..Scrollback::default()
+    };
+    scrollback.push(Line::from(String::from("first line")));
+    scrollback.push(Line::from(String::from("second, line")));
+    let mut copy_mode = CopyMode::begin(&mut scrollback, false).unwrap();
+    assert_eq!(scrollback.selected_text().as_deref(), Some("s"));
+
+    copy_mode.key('w', &mut scrollback, 10);
+    assert_eq!(scrollback.selected_text().as_deref(), Some(","));
+    copy_mode.key('w', &mut scrollback, 10);
+    copy_mode.key('v', &mut scrollback, 10);
+    copy_mode.key('$', &mut scrollback, 10);
+    assert_eq!(scrollback.selected_text().as_deref(), Some("line"));
+    copy_mode.key('k', &mut scrollback, 10);
+    copy_mode.key('b', &mut scrollback, 10);
+    assert_eq!(
+        copy_mode.key('y', &mut scrollback, 10),
+        CopyAction::Copied(String::from("line\nsecond, l"))
+    );
+    assert!(scrollback.view.selection.is_none());
+}
+
+#[test]
+fn copy_mode_anchors() {
+    use crate::scrollback::Line;
+
+    let mut scrollback = Scrollback {
+        columns: 80,
+        ..Scrollback::default()
+    };
+    for line in 0..10 {
+        scrollback.push(Line::from(format!("line {line}")));
+    }
+    scrollback.view.scroll = 4;
+    scrollback.split();
+    // The pinned view keeps its selection and scroll to itself.
+    let mut copy_mode = CopyMode::begin(&mut scrollback, true).unwrap();
+    copy_mode.key('v', &mut scrollback, 3);
+    copy_mode.key('$', &mut scrollback, 3);
+    assert_eq!(scrollback.selected_text().as_deref(), Some("line 5"));
+    assert!(scrollback.view.selection.is_none());
+    for _ in 0..3 {
+        copy_mode.key('k', &mut scrollback, 3);
+    }
+    assert_eq!(scrollback.pinned.as_ref().unwrap().scroll, 5);
+    assert_eq!(scrollback.view.scroll, 0);
+
+    // Inserting older lines shifts positions, but the cursor stays on the
+    // same text.
+    scrollback.insert(9, Line::from(String::from("inserted")));
+    copy_mode.key('j', &mut scrollback, 3);
+    copy_mode.key('0', &mut scrollback, 3);
+    assert_eq!(
+        scrollback.selected_text().as_deref(),
+        Some("line 3\nline 4\nl")
+    );
+
+    scrollback.events.clear();
+    assert_eq!(copy_mode.key('k', &mut scrollback, 3), CopyAction::Exited);
+}
+
+#[cfg(feature = "spill")]
+#[test]
+fn copy_mode_pages_in() {
+    use crate::scrollback::Line;
+    use crate::spill::{SegmentFiles, Spill, SEGMENT_LINES};
+
+    let directory = std::env::temp_dir().join(format!("copy-mode-{}", std::process::id()));
+    let mut scrollback = Scrollback {
+        columns: 80,
+        spill: Some(Spill::new(SegmentFiles::new(&directory).unwrap(), 10)),
+        ..Scrollback::default()
+    };
+    for line in 0..SEGMENT_LINES + 10 {
+        scrollback.push(Line::from(line.to_string()));
+    }
+    let mut copy_mode = CopyMode::begin(&mut scrollback, false).unwrap();
+    for _ in 0..10 {
+        copy_mode.key('k', &mut scrollback, 5);
+    }
+    let cursor = |scrollback: &Scrollback| scrollback.selection().unwrap().range().start;
+    assert_eq!(cursor(&scrollback).line, SEGMENT_LINES - 1);
+    assert_eq!(scrollback.view.scroll, 6);
+    copy_mode.key('g', &mut scrollback, 5);
+    assert_eq!(cursor(&scrollback).line, 0);
+    assert_eq!(scrollback.view.scroll, SEGMENT_LINES + 5);
+    drop(scrollback);
+    std::fs::remove_dir_all(&directory).unwrap();
+}
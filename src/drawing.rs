@@ -0,0 +1,180 @@
+use crate::scrollback::Line;
+use crate::width;
+use crate::wrap::Wrapped;
+
+// The width drawings are drawn at until they are displayed, such as when
+// printed as plain text or written to transcripts.
+const UNDISPLAYED_COLUMNS: usize = 80;
+
+/// A line that is redrawn to span the console's width whenever it is
+/// resized.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Drawing {
+    /// A horizontal rule pushed with
+    /// [`Console::push_rule`](crate::Console::push_rule), with the title
+    /// centered in it unless it is empty.
+    Rule(String),
+    /// Lines surrounded by a border, pushed with
+    /// [`Console::push_boxed`](crate::Console::push_boxed).
+    Boxed(Vec<String>, BoxStyle),
+}
+
+impl Drawing {
+    /// Returns a line containing the drawing, which is redrawn once the
+    /// line is displayed.
+    pub(crate) fn into_line(self) -> Line {
+        let mut line = Line::from(self.draw(UNDISPLAYED_COLUMNS));
+        line.drawing = Some(self);
+        line
+    }
+
+    pub(crate) fn draw(&self, columns: usize) -> String {
+        match self {
+            Drawing::Rule(title) => rule(title, columns),
+            Drawing::Boxed(lines, style) => boxed(lines, *style, columns),
+        }
+    }
+}
+
+/// The characters a box's border is drawn with.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BoxStyle {
+    /// `┌─┐`
+    #[default]
+    Light,
+    /// `┏━┓`
+    Heavy,
+    /// `╔═╗`
+    Double,
+    /// `╭─╮`
+    Rounded,
+    /// `+-+`, for fonts without box-drawing characters.
+    Ascii,
+}
+
+/// The border characters of a [`BoxStyle`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct BoxGlyphs {
+    pub top_left: char,
+    pub top_right: char,
+    pub bottom_left: char,
+    pub bottom_right: char,
+    pub horizontal: char,
+    pub vertical: char,
+}
+
+impl BoxStyle {
+    pub const fn glyphs(self) -> BoxGlyphs {
+        let (corners, horizontal, vertical) = match self {
+            BoxStyle::Light => (['┌', '┐', '└', '┘'], '─', '│'),
+            BoxStyle::Heavy => (['┏', '┓', '┗', '┛'], '━', '┃'),
+            BoxStyle::Double => (['╔', '╗', '╚', '╝'], '═', '║'),
+            BoxStyle::Rounded => (['╭', '╮', '╰', '╯'], '─', '│'),
+            BoxStyle::Ascii => (['+', '+', '+', '+'], '-', '|'),
+        };
+        BoxGlyphs {
+            top_left: corners[0],
+            top_right: corners[1],
+            bottom_left: corners[2],
+            bottom_right: corners[3],
+            horizontal,
+            vertical,
+        }
+    }
+}
+
+// Draws a rule spanning `columns`, such as `──── build #42 ────`. Titles too
+// wide to fit are cut off.
+fn rule(title: &str, columns: usize) -> String {
+    let dash = BoxStyle::Light.glyphs().horizontal;
+    if title.is_empty() {
+        return repeat(dash, columns);
+    }
+    // The title is surrounded by a space on each side, and at least one
+    // dash.
+    let title_columns = width::width(title) + 2;
+    if title_columns + 2 > columns {
+        return width::truncate_to_width(title, columns).into_owned();
+    }
+    let dashes = columns - title_columns;
+    let left = dashes / 2;
+    format!(
+        "{} {title} {}",
+        repeat(dash, left),
+        repeat(dash, dashes - left)
+    )
+}
+
+// Draws `lines` wrapped within a border spanning `columns`, with a column of
+// padding on each side.
+fn boxed(lines: &[String], style: BoxStyle, columns: usize) -> String {
+    let glyphs = style.glyphs();
+    let columns = columns.max(5);
+    let inner = columns - 4;
+    let edge = repeat(glyphs.horizontal, columns - 2);
+    let mut drawn = format!("{}{edge}{}", glyphs.top_left, glyphs.top_right);
+    for line in lines {
+        let mut wrapped = Wrapped::from(line.as_str());
+        for row in wrapped.lines(inner) {
+            let row = row.trim_end();
+            let padding = inner.saturating_sub(width::width(row));
+            drawn.push('\n');
+            drawn.push(glyphs.vertical);
+            drawn.push(' ');
+            drawn.push_str(row);
+            drawn.push_str(&" ".repeat(padding));
+            drawn.push(' ');
+            drawn.push(glyphs.vertical);
+        }
+    }
+    drawn.push('\n');
+    drawn.push(glyphs.bottom_left);
+    drawn.push_str(&edge);
+    drawn.push(glyphs.bottom_right);
+    drawn
+}
+
+fn repeat(ch: char, count: usize) -> String {
+    std::iter::repeat(ch).take(count).collect()
+}
+
+#[test]
+fn drawing_rules() {
+    assert_eq!(rule("", 4), "────");
+    assert_eq!(rule("build #42", 16), "── build #42 ───");
+    assert_eq!(rule("build #42", 6), "build ");
+
+    let mut line = Drawing::Rule(String::from("hi")).into_line();
+    line.rewrap(8);
+    assert_eq!(line.as_str(), "── hi ──");
+    line.rewrap(10);
+    assert_eq!(line.as_str(), "─── hi ───");
+    assert_eq!(line.line_count(10), 1);
+}
+
+#[test]
+fn drawing_boxes() {
+    let lines = vec![String::from("hello world")];
+    assert_eq!(
+        boxed(&lines, BoxStyle::Ascii, 10),
+        "+--------+\n| hello  |\n| world  |\n+--------+"
+    );
+
+    let mut line = Drawing::Boxed(lines, BoxStyle::Rounded).into_line();
+    line.rewrap(15);
+    assert_eq!(
+        line.as_str(),
+        "╭─────────────╮\n│ hello world │\n╰─────────────╯"
+    );
+    assert_eq!(line.line_count(15), 3);
+
+    // Wide characters take two columns of the row.
+    let lines = vec![String::from("日本")];
+    assert_eq!(
+        boxed(&lines, BoxStyle::Ascii, 10),
+        "+--------+\n| 日本   |\n+--------+"
+    );
+    assert_eq!(rule("日本", 10), "── 日本 ──");
+}
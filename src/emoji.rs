@@ -0,0 +1,183 @@
+use crate::Popup;
+
+// The most frequently used emoji, with their short names.
+const EMOJI: &[(&str, &str)] = &[
+    ("😀", "grinning face"),
+    ("😃", "grinning face with big eyes"),
+    ("😄", "grinning face with smiling eyes"),
+    ("😁", "beaming face"),
+    ("😆", "grinning squinting face"),
+    ("😅", "grinning face with sweat"),
+    ("🤣", "rolling on the floor laughing"),
+    ("😂", "face with tears of joy"),
+    ("🙂", "slightly smiling face"),
+    ("🙃", "upside-down face"),
+    ("😉", "winking face"),
+    ("😊", "smiling face with smiling eyes"),
+    ("😇", "smiling face with halo"),
+    ("🥰", "smiling face with hearts"),
+    ("😍", "smiling face with heart-eyes"),
+    ("😘", "face blowing a kiss"),
+    ("😋", "face savoring food"),
+    ("😛", "face with tongue"),
+    ("😜", "winking face with tongue"),
+    ("🤪", "zany face"),
+    ("🤔", "thinking face"),
+    ("🤨", "face with raised eyebrow"),
+    ("😐", "neutral face"),
+    ("😑", "expressionless face"),
+    ("😶", "face without mouth"),
+    ("🙄", "face with rolling eyes"),
+    ("😏", "smirking face"),
+    ("😬", "grimacing face"),
+    ("😌", "relieved face"),
+    ("😔", "pensive face"),
+    ("😴", "sleeping face"),
+    ("😷", "face with medical mask"),
+    ("🤒", "face with thermometer"),
+    ("🤯", "exploding head"),
+    ("🥳", "partying face"),
+    ("😎", "smiling face with sunglasses"),
+    ("🤓", "nerd face"),
+    ("😕", "confused face"),
+    ("😟", "worried face"),
+    ("😮", "face with open mouth"),
+    ("😲", "astonished face"),
+    ("😳", "flushed face"),
+    ("🥺", "pleading face"),
+    ("😢", "crying face"),
+    ("😭", "loudly crying face"),
+    ("😱", "face screaming in fear"),
+    ("😤", "face with steam from nose"),
+    ("😡", "pouting face"),
+    ("😠", "angry face"),
+    ("💀", "skull"),
+    ("💩", "pile of poo"),
+    ("🤖", "robot"),
+    ("👋", "waving hand"),
+    ("👌", "ok hand"),
+    ("✌️", "victory hand"),
+    ("🤞", "crossed fingers"),
+    ("👍", "thumbs up"),
+    ("👎", "thumbs down"),
+    ("👏", "clapping hands"),
+    ("🙌", "raising hands"),
+    ("🙏", "folded hands"),
+    ("💪", "flexed biceps"),
+    ("👀", "eyes"),
+    ("🧠", "brain"),
+    ("❤️", "red heart"),
+    ("💔", "broken heart"),
+    ("💯", "hundred points"),
+    ("💥", "collision"),
+    ("💤", "zzz"),
+    ("🔥", "fire"),
+    ("✨", "sparkles"),
+    ("⭐", "star"),
+    ("🌈", "rainbow"),
+    ("☀️", "sun"),
+    ("🌙", "crescent moon"),
+    ("☕", "hot beverage"),
+    ("🍕", "pizza"),
+    ("🍺", "beer mug"),
+    ("🎉", "party popper"),
+    ("🎂", "birthday cake"),
+    ("🎁", "wrapped gift"),
+    ("🏆", "trophy"),
+    ("🚀", "rocket"),
+    ("⚠️", "warning"),
+    ("✅", "check mark button"),
+    ("❌", "cross mark"),
+    ("❓", "question mark"),
+    ("❗", "exclamation mark"),
+    ("🐛", "bug"),
+    ("🔒", "locked"),
+    ("🔑", "key"),
+    ("💡", "light bulb"),
+    ("📌", "pushpin"),
+    ("📎", "paperclip"),
+    ("🔗", "link"),
+    ("⏰", "alarm clock"),
+    ("🐶", "dog face"),
+    ("🐱", "cat face"),
+    ("🦀", "crab"),
+];
+
+// The number of matches shown at once.
+const VISIBLE_MATCHES: usize = 8;
+
+/// An emoji picker, searching emoji by name as the user types.
+#[derive(Debug, Default)]
+pub(crate) struct EmojiPicker {
+    query: String,
+    selected: usize,
+}
+
+impl EmojiPicker {
+    pub fn push(&mut self, ch: char) {
+        self.query.push(ch);
+        self.selected = 0;
+    }
+
+    pub fn pop(&mut self) {
+        self.query.pop();
+        self.selected = 0;
+    }
+
+    /// Moves the selection by `delta` matches, stopping at either end.
+    pub fn move_selection(&mut self, delta: isize) {
+        let last = self.matches().count().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    /// Returns the selected emoji, if any emoji match the query.
+    pub fn chosen(&self) -> Option<&'static str> {
+        self.matches().nth(self.selected).map(|(emoji, _)| *emoji)
+    }
+
+    pub fn popup(&self) -> Popup {
+        let items = self
+            .matches()
+            .map(|(emoji, name)| format!("{emoji} {name}"))
+            .collect::<Vec<_>>();
+        let popup = if items.is_empty() {
+            Popup::new(["no matches"])
+        } else {
+            Popup::new(items).with_selected(self.selected)
+        };
+        popup.with_title(format!(":{}", self.query))
+    }
+
+    /// The number of rows the picker's popup is limited to.
+    pub fn max_rows(&self) -> usize {
+        // The matches, the border, and the title.
+        VISIBLE_MATCHES + 2
+    }
+
+    // Returns the emoji with a word in their name starting with the query.
+    fn matches(&self) -> impl Iterator<Item = &'static (&'static str, &'static str)> + '_ {
+        let query = self.query.to_lowercase();
+        EMOJI.iter().filter(move |(_, name)| {
+            name.split([' ', '-'])
+                .any(|word| word.starts_with(query.as_str()))
+        })
+    }
+}
+
+#[test]
+fn emoji_search() {
+    let mut picker = EmojiPicker::default();
+    for ch in "thumb".chars() {
+        picker.push(ch);
+    }
+    assert_eq!(picker.chosen(), Some("👍"));
+    picker.move_selection(1);
+    assert_eq!(picker.chosen(), Some("👎"));
+    picker.move_selection(5);
+    assert_eq!(picker.chosen(), Some("👎"));
+
+    picker.pop();
+    picker.push('x');
+    assert_eq!(picker.chosen(), None);
+    assert_eq!(picker.popup().items(), ["no matches"]);
+}
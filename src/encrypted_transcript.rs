@@ -0,0 +1,152 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+use crate::scrollback::Line;
+use crate::transcript::{format_line, RotatingFile, Rotation};
+
+// Written at the start of each encrypted transcript file.
+const MAGIC: &[u8; 8] = b"CTLOG\x00\x00\x01";
+const NONCE_LEN: usize = 12;
+
+/// Options for an encrypted transcript of every pushed line, configured with
+/// [`Config::transcript`](crate::Config::transcript).
+///
+/// Each line is encrypted with ChaCha20-Poly1305 and appended to the file as
+/// its own record, so that a crash loses at most the line being written.
+/// Files are never truncated. Use [`read_transcript`] to decrypt a file.
+#[derive(Clone)]
+pub struct TranscriptOptions {
+    path: PathBuf,
+    key: [u8; 32],
+    rotation: Rotation,
+}
+
+impl TranscriptOptions {
+    /// Returns options that append to `path`, encrypting with `key`.
+    pub fn new(path: impl Into<PathBuf>, key: [u8; 32]) -> Self {
+        Self {
+            path: path.into(),
+            key,
+            rotation: Rotation::Never,
+        }
+    }
+
+    pub fn rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+}
+
+impl fmt::Debug for TranscriptOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TranscriptOptions")
+            .field("path", &self.path)
+            .field("rotation", &self.rotation)
+            .finish_non_exhaustive()
+    }
+}
+
+pub(crate) struct EncryptedTranscript {
+    cipher: ChaCha20Poly1305,
+    file: RotatingFile,
+}
+
+impl EncryptedTranscript {
+    pub fn new(options: TranscriptOptions) -> Self {
+        Self {
+            cipher: ChaCha20Poly1305::new(Key::from_slice(&options.key)),
+            file: RotatingFile::new(options.path, options.rotation),
+        }
+    }
+
+    pub fn push(&mut self, line: &Line) -> io::Result<()> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, format_line(line).as_bytes())
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "encryption failed"))?;
+        let length = u32::try_from(ciphertext.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "line too long"))?;
+
+        // The whole record is written at once, so that it is never
+        // interleaved with a partial record.
+        let mut record = Vec::with_capacity(4 + NONCE_LEN + ciphertext.len());
+        record.extend_from_slice(&length.to_be_bytes());
+        record.extend_from_slice(&nonce);
+        record.extend_from_slice(&ciphertext);
+        self.file.append(MAGIC, &record)
+    }
+}
+
+/// Decrypts a transcript file written using [`TranscriptOptions`], returning
+/// each line in the order it was pushed.
+///
+/// A record cut short by a crash at the end of the file is ignored.
+pub fn read_transcript(path: impl AsRef<Path>, key: [u8; 32]) -> io::Result<Vec<String>> {
+    let mut contents = Vec::new();
+    File::open(path)?.read_to_end(&mut contents)?;
+    let Some(mut records) = contents.strip_prefix(MAGIC.as_slice()) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not an encrypted transcript",
+        ));
+    };
+
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&key));
+    let mut lines = Vec::new();
+    while records.len() >= 4 + NONCE_LEN {
+        let (length, rest) = records.split_at(4);
+        let length = u32::from_be_bytes(length.try_into().expect("4 bytes")) as usize;
+        let (nonce, rest) = rest.split_at(NONCE_LEN);
+        if rest.len() < length {
+            break;
+        }
+        let (ciphertext, rest) = rest.split_at(length);
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "decryption failed"))?;
+        lines.push(
+            String::from_utf8(plaintext)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+        );
+        records = rest;
+    }
+
+    Ok(lines)
+}
+
+#[test]
+fn encrypted_rotation() {
+    use std::fs;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let directory = std::env::temp_dir().join(format!("console-thingy-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    let key = [7; 32];
+    let mut transcript = EncryptedTranscript::new(
+        TranscriptOptions::new(directory.join("session.log"), key).rotation(Rotation::Size(150)),
+    );
+    for number in 0..3 {
+        let mut line = Line::from(format!("line {number}"));
+        line.timestamp = UNIX_EPOCH + Duration::from_millis(1_675_168_496_789);
+        transcript.push(&line).unwrap();
+    }
+
+    let first = read_transcript(directory.join("session.log"), key).unwrap();
+    assert_eq!(
+        first,
+        [
+            "2023-01-31T12:34:56.789Z info line 0",
+            "2023-01-31T12:34:56.789Z info line 1"
+        ]
+    );
+    let second = read_transcript(directory.join("session.1.log"), key).unwrap();
+    assert_eq!(second, ["2023-01-31T12:34:56.789Z info line 2"]);
+    assert!(read_transcript(directory.join("session.log"), [0; 32]).is_err());
+    fs::remove_dir_all(&directory).unwrap();
+}
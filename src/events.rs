@@ -0,0 +1,226 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use crate::{Config, ConsoleEvent, Selector};
+
+/// Creates the queue that delivers a tab's events to its app.
+///
+/// Events are queued in two lanes. Events that respond to the user, such as
+/// submitted input, are always received before ticks, timers, triggers, and
+/// input buffer changes, and are never dropped. The other lane holds at most
+/// [`Config::event_capacity`] events, dropping events that arrive while it
+/// is full.
+pub(crate) fn channel(config: &Config) -> (EventSender, EventReceiver) {
+    let queue = Arc::new(Queue {
+        lanes: Mutex::default(),
+        capacity: config.event_capacity,
+    });
+    // A doorbell is rung for each queued event, so that waiting for an event
+    // can be combined with other channels using a `Selector`.
+    let (sender, doorbell) = flume::unbounded();
+    (
+        EventSender {
+            queue: queue.clone(),
+            doorbell: Arc::new(sender),
+            debounce: config.input_debounce.map(|delay| Debounce {
+                delay,
+                deadline: Arc::default(),
+            }),
+        },
+        EventReceiver { queue, doorbell },
+    )
+}
+
+struct Queue {
+    lanes: Mutex<Lanes>,
+    capacity: Option<usize>,
+}
+
+#[derive(Default)]
+struct Lanes {
+    priority: VecDeque<ConsoleEvent>,
+    normal: VecDeque<ConsoleEvent>,
+}
+
+impl Queue {
+    // Returns false if the event was dropped or coalesced.
+    fn push(&self, event: ConsoleEvent) -> bool {
+        let mut lanes = self.lanes.lock();
+        if is_priority(&event) {
+            lanes.priority.push_back(event);
+            return true;
+        }
+        // The app only needs to know that the input changed since it last
+        // looked, so consecutive changes are delivered once.
+        if matches!(event, ConsoleEvent::InputBufferChanged)
+            && matches!(lanes.normal.back(), Some(ConsoleEvent::InputBufferChanged))
+        {
+            return false;
+        }
+        if self
+            .capacity
+            .is_some_and(|capacity| lanes.normal.len() >= capacity)
+        {
+            return false;
+        }
+        lanes.normal.push_back(event);
+        true
+    }
+
+    fn pop(&self) -> ConsoleEvent {
+        let mut lanes = self.lanes.lock();
+        lanes
+            .priority
+            .pop_front()
+            .or_else(|| lanes.normal.pop_front())
+            .expect("an event is queued for each doorbell")
+    }
+}
+
+fn is_priority(event: &ConsoleEvent) -> bool {
+    !matches!(
+        event,
+        ConsoleEvent::InputBufferChanged
+            | ConsoleEvent::Tick
+            | ConsoleEvent::Timer(_)
+            | ConsoleEvent::Triggered { .. }
+    )
+}
+
+pub(crate) struct EventSender {
+    queue: Arc<Queue>,
+    doorbell: Arc<flume::Sender<()>>,
+    debounce: Option<Debounce>,
+}
+
+// Delays InputBufferChanged until the input has stopped changing for `delay`.
+struct Debounce {
+    delay: Duration,
+    // When the pending change is sent, if one is pending.
+    deadline: Arc<Mutex<Option<Instant>>>,
+}
+
+impl EventSender {
+    pub fn send(&self, event: ConsoleEvent) {
+        match (&event, &self.debounce) {
+            (ConsoleEvent::InputBufferChanged, Some(debounce)) => self.debounce(debounce),
+            _ => ring(&self.queue, &self.doorbell, event),
+        }
+    }
+
+    fn debounce(&self, debounce: &Debounce) {
+        let mut deadline = debounce.deadline.lock();
+        let pending = deadline.replace(Instant::now() + debounce.delay).is_some();
+        if pending {
+            return;
+        }
+        drop(deadline);
+
+        let queue = self.queue.clone();
+        // Once the sender is dropped, the pending change is discarded.
+        let doorbell = Arc::downgrade(&self.doorbell);
+        let deadline = debounce.deadline.clone();
+        std::thread::Builder::new()
+            .name(String::from("input debounce"))
+            .spawn(move || debounced(&queue, &doorbell, &deadline))
+            .expect("error spawning input debounce thread");
+    }
+
+    pub fn len(&self) -> usize {
+        self.doorbell.len()
+    }
+}
+
+fn ring(queue: &Queue, doorbell: &flume::Sender<()>, event: ConsoleEvent) {
+    if queue.push(event) {
+        let _ = doorbell.send(());
+    }
+}
+
+// Waits until the deadline stops moving, then sends the input change.
+fn debounced(queue: &Queue, doorbell: &Weak<flume::Sender<()>>, deadline: &Mutex<Option<Instant>>) {
+    loop {
+        let mut pending = deadline.lock();
+        let now = Instant::now();
+        match *pending {
+            Some(due) if due > now => {
+                drop(pending);
+                std::thread::sleep(due - now);
+            }
+            _ => {
+                *pending = None;
+                break;
+            }
+        }
+    }
+    if let Some(doorbell) = doorbell.upgrade() {
+        ring(queue, &doorbell, ConsoleEvent::InputBufferChanged);
+    }
+}
+
+/// Receives events in priority order. Once the sender is dropped, the queued
+/// events are received before receiving fails.
+#[derive(Clone)]
+pub(crate) struct EventReceiver {
+    queue: Arc<Queue>,
+    doorbell: flume::Receiver<()>,
+}
+
+impl EventReceiver {
+    pub fn recv(&self) -> Result<ConsoleEvent, flume::RecvError> {
+        self.doorbell.recv().map(|()| self.queue.pop())
+    }
+
+    pub fn try_recv(&self) -> Result<ConsoleEvent, flume::TryRecvError> {
+        self.doorbell.try_recv().map(|()| self.queue.pop())
+    }
+
+    pub fn select<'a, T>(
+        &'a self,
+        selector: Selector<'a, T>,
+        mut mapper: impl FnMut(Result<ConsoleEvent, flume::RecvError>) -> T + 'a,
+    ) -> Selector<'a, T> {
+        selector.recv_flume(&self.doorbell, move |rung| {
+            mapper(rung.map(|()| self.queue.pop()))
+        })
+    }
+}
+
+#[test]
+fn event_lanes() {
+    let (sender, receiver) = channel(&Config::default().event_capacity(2));
+    sender.send(ConsoleEvent::InputBufferChanged);
+    sender.send(ConsoleEvent::InputBufferChanged);
+    sender.send(ConsoleEvent::Tick);
+    sender.send(ConsoleEvent::Timer(1));
+    sender.send(ConsoleEvent::Input(String::from("hello")));
+    drop(sender);
+
+    assert!(matches!(receiver.recv(), Ok(ConsoleEvent::Input(input)) if input == "hello"));
+    assert!(matches!(
+        receiver.recv(),
+        Ok(ConsoleEvent::InputBufferChanged)
+    ));
+    assert!(matches!(receiver.recv(), Ok(ConsoleEvent::Tick)));
+    // The timer was dropped because the lane was full.
+    assert!(receiver.recv().is_err());
+}
+
+#[test]
+fn debounced_input_changes() {
+    let config = Config::default().input_change_debounce(Duration::from_millis(20));
+    let (sender, receiver) = channel(&config);
+    for _ in 0..3 {
+        sender.send(ConsoleEvent::InputBufferChanged);
+    }
+    assert!(receiver.try_recv().is_err());
+    assert!(matches!(
+        receiver.recv(),
+        Ok(ConsoleEvent::InputBufferChanged)
+    ));
+    std::thread::sleep(Duration::from_millis(40));
+    assert!(receiver.try_recv().is_err());
+}
@@ -0,0 +1,346 @@
+use std::fmt::Write;
+
+use crate::scrollback::Line;
+use crate::transcript::format_timestamp;
+use crate::wrap::Wrapped;
+use crate::Color;
+
+// The fewest columns text is wrapped within, no matter how long the
+// timestamp, level, and channel before it are.
+const MINIMUM_TEXT_COLUMNS: usize = 20;
+
+/// How [`Console::export_markdown`](crate::Console::export_markdown) formats
+/// lines.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum MarkdownStyle {
+    /// A fenced code block, which keeps the columns aligned.
+    #[default]
+    CodeBlock,
+    /// A blockquote with a paragraph per line, which keeps formatting such
+    /// as links working.
+    Blockquote,
+}
+
+/// Options for exporting a transcript as Markdown.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct MarkdownOptions {
+    pub style: MarkdownStyle,
+    /// The columns rows are wrapped within. Defaults to 80.
+    pub width: usize,
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        Self {
+            style: MarkdownStyle::default(),
+            width: 80,
+        }
+    }
+}
+
+impl MarkdownOptions {
+    pub fn style(mut self, style: MarkdownStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+}
+
+/// Formats `lines`, oldest first, as Markdown. Each line starts with its
+/// timestamp, level, and channel, and its text is wrapped beside them.
+pub(crate) fn markdown(lines: impl IntoIterator<Item = Line>, options: MarkdownOptions) -> String {
+    match options.style {
+        MarkdownStyle::CodeBlock => code_block(lines, options.width),
+        MarkdownStyle::Blockquote => blockquote(lines, options.width),
+    }
+}
+
+fn code_block(lines: impl IntoIterator<Item = Line>, width: usize) -> String {
+    let mut rows = Vec::new();
+    for line in lines {
+        let header = header(&line);
+        // Indented lines keep their indent on every row.
+        let text_indent = line.indent;
+        let indent = header.chars().count() + 1 + text_indent;
+        for (index, row) in wrapped_rows(&line, width.saturating_sub(indent)).enumerate() {
+            rows.push(if index == 0 {
+                format!("{header} {:text_indent$}{row}", "")
+            } else {
+                format!("{:indent$}{row}", "")
+            });
+        }
+    }
+
+    // The fence must be longer than any run of backticks in the text.
+    let longest_run = rows
+        .iter()
+        .flat_map(|row| row.split(|ch| ch != '`'))
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    let mut markdown = format!("{fence}text\n");
+    for row in rows {
+        markdown.push_str(row.trim_end());
+        markdown.push('\n');
+    }
+    markdown.push_str(&fence);
+    markdown.push('\n');
+    markdown
+}
+
+fn blockquote(lines: impl IntoIterator<Item = Line>, width: usize) -> String {
+    let mut markdown = String::new();
+    for line in lines {
+        if !markdown.is_empty() {
+            // An empty quoted row separates paragraphs.
+            markdown.push_str(">\n");
+        }
+        let header = header(&line);
+        // Two columns for "> ". Markdown collapses leading spaces, so
+        // indented rows start with non-breaking ones instead.
+        let columns = width.saturating_sub(header.chars().count() + 3 + line.indent);
+        let indent = "&nbsp;".repeat(line.indent);
+        markdown.push_str("> ");
+        markdown.push_str(&header_markdown(&line));
+        for (source_index, source) in line.split('\n').enumerate() {
+            if source_index > 0 {
+                // A trailing backslash is a hard line break.
+                markdown.push_str("\\\n>");
+            }
+            for row in Wrapped::from(source.to_string()).lines(columns.max(MINIMUM_TEXT_COLUMNS)) {
+                let row = row.trim_end();
+                if !row.is_empty() {
+                    markdown.push(' ');
+                    markdown.push_str(&indent);
+                    markdown.push_str(&escape_markdown(row));
+                    markdown.push_str("\n>");
+                }
+            }
+            // Each row ended a quoted line that the next row continues.
+            if markdown.ends_with("\n>") {
+                markdown.truncate(markdown.len() - 2);
+            }
+        }
+        markdown.push('\n');
+    }
+    markdown
+}
+
+/// Formats `lines`, oldest first with the color of each line's text, as a
+/// standalone HTML page. Colors are passed through `style` and written as
+/// inline CSS. Rows are broken where they would wrap within `columns` only
+/// when the page is too narrow to show them whole.
+pub(crate) fn html(
+    lines: impl IntoIterator<Item = (Line, Color)>,
+    columns: usize,
+    style: impl Fn(Color) -> Color,
+) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html>\n\
+         <html>\n\
+         <head><meta charset=\"utf-8\"><title>Transcript</title></head>\n\
+         <body style=\"margin:0;background:#000\">\n\
+         <div style=\"font-family:monospace;white-space:pre-wrap;padding:8px\">\n",
+    );
+    for (mut line, color) in lines {
+        line.rewrap(columns);
+        let _ = write!(html, "<div title=\"{}\"", escape_html(&header(&line)));
+        if line.indent > 0 {
+            let _ = write!(html, " style=\"padding-left:{}ch\"", line.indent);
+        }
+        html.push('>');
+        let mut end = 0;
+        for row in line.line_ranges().iter().cloned() {
+            if end > 0 {
+                // Where the row wrapped rather than ending at a newline, the
+                // browser is offered a soft break.
+                let gap = &line[end..row.start];
+                html.push_str(&escape_html(gap));
+                if !gap.contains('\n') {
+                    html.push_str("<wbr>");
+                }
+            }
+            end = row.end;
+            for (segment, segment_color) in line.segments(row) {
+                let color = style(segment_color.unwrap_or(color));
+                let _ = write!(
+                    html,
+                    "<span style=\"color:#{:02x}{:02x}{:02x}\">{}</span>",
+                    color.red,
+                    color.green,
+                    color.blue,
+                    escape_html(&line[segment])
+                );
+            }
+        }
+        html.push_str("</div>\n");
+    }
+    html.push_str("</div>\n</body>\n</html>\n");
+    html
+}
+
+// Returns the timestamp, level, and channel shown before a line.
+fn header(line: &Line) -> String {
+    let mut header = format!(
+        "{} {}",
+        format_timestamp(line.timestamp),
+        line.level.as_str()
+    );
+    if let Some(channel) = &line.channel {
+        header.push_str(" [");
+        header.push_str(channel);
+        header.push(']');
+    }
+    header
+}
+
+fn header_markdown(line: &Line) -> String {
+    let mut header = format!(
+        "`{}` **{}**",
+        format_timestamp(line.timestamp),
+        line.level.as_str()
+    );
+    if let Some(channel) = &line.channel {
+        header.push_str(" \\[");
+        header.push_str(&escape_markdown(channel));
+        header.push_str("\\]");
+    }
+    header
+}
+
+// Returns the rows of `line` wrapped within `columns`, keeping its newlines.
+fn wrapped_rows(line: &Line, columns: usize) -> impl Iterator<Item = String> + '_ {
+    let columns = columns.max(MINIMUM_TEXT_COLUMNS);
+    line.split('\n').flat_map(move |source| {
+        let mut wrapped = Wrapped::from(source.to_string());
+        let rows = wrapped
+            .lines(columns)
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        if rows.is_empty() {
+            vec![String::new()]
+        } else {
+            rows
+        }
+    })
+}
+
+// Escapes the characters that Markdown would otherwise interpret.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if matches!(
+            ch,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[test]
+fn markdown_export() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let at = |text: &str, channel: Option<&str>| {
+        let mut line = Line::new(String::from(text), channel.map(Into::into));
+        line.timestamp = UNIX_EPOCH + Duration::from_millis(1_675_168_496_789);
+        line
+    };
+    let lines = [
+        at("connected to the device over serial", Some("serial")),
+        at("a *b*\n```c```", None),
+    ];
+    let mut indented = at("nested", None);
+    indented.indent = 2;
+
+    let options = MarkdownOptions::default().width(60);
+    let indent = " ".repeat(39);
+    assert_eq!(
+        markdown(lines.clone(), options),
+        format!(
+            "````text\n\
+             2023-01-31T12:34:56.789Z info [serial] connected to the\n\
+             {indent}device over serial\n\
+             2023-01-31T12:34:56.789Z info a *b*\n\
+             {}```c```\n\
+             ````\n",
+            &indent[..30]
+        )
+    );
+
+    assert_eq!(
+        markdown([indented.clone()], options),
+        "```text\n2023-01-31T12:34:56.789Z info   nested\n```\n"
+    );
+
+    let options = options.style(MarkdownStyle::Blockquote);
+    assert_eq!(
+        markdown([indented], options),
+        "> `2023-01-31T12:34:56.789Z` **info** &nbsp;&nbsp;nested\n"
+    );
+    assert_eq!(
+        markdown(lines, options),
+        "> `2023-01-31T12:34:56.789Z` **info** \\[serial\\] connected to the\n\
+         > device over serial\n\
+         >\n\
+         > `2023-01-31T12:34:56.789Z` **info** a \\*b\\*\\\n\
+         > \\`\\`\\`c\\`\\`\\`\n"
+    );
+}
+
+#[test]
+fn html_export() {
+    use std::time::UNIX_EPOCH;
+
+    use crate::style::Span;
+
+    let mut line = Line::from(String::from("a <b> wraps\nhere"));
+    line.timestamp = UNIX_EPOCH;
+    line.spans = vec![Span::new(2..5, Color::rgb(255, 0, 0))];
+    let html = html([(line, Color::WHITE)], 8, |color| color);
+    let mut rows = html
+        .lines()
+        .skip_while(|line| !line.starts_with("<div title"));
+    assert_eq!(
+        rows.next().unwrap(),
+        "<div title=\"1970-01-01T00:00:00.000Z info\">\
+         <span style=\"color:#ffffff\">a </span>\
+         <span style=\"color:#ff0000\">&lt;b&gt;</span>\
+         <span style=\"color:#ffffff\"> </span><wbr>\
+         <span style=\"color:#ffffff\">wraps</span>"
+    );
+    assert_eq!(
+        rows.next(),
+        Some("<span style=\"color:#ffffff\">here</span></div>")
+    );
+
+    let mut line = Line::from(String::from("nested"));
+    line.timestamp = UNIX_EPOCH;
+    line.indent = 2;
+    let indented = self::html([(line, Color::WHITE)], 8, |color| color);
+    assert!(indented
+        .contains("<div title=\"1970-01-01T00:00:00.000Z info\" style=\"padding-left:2ch\">"));
+}
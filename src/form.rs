@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use crate::{Draft, Input, InputOptions};
+
+/// Labeled fields filled in one after another using the input line, such as
+/// the steps of a setup wizard. Shown with
+/// [`Console::show_form`](crate::Console::show_form).
+///
+/// Tab and Shift+Tab move between the fields, and Enter submits the form.
+#[derive(Debug, Clone, Default)]
+pub struct Form {
+    fields: Vec<Field>,
+}
+
+#[derive(Debug, Clone)]
+struct Field {
+    label: String,
+    value: String,
+    masked: bool,
+}
+
+impl Form {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn field(self, label: impl Into<String>) -> Self {
+        self.field_with_value(label, "")
+    }
+
+    /// Adds a field that starts out containing `value`.
+    pub fn field_with_value(mut self, label: impl Into<String>, value: impl Into<String>) -> Self {
+        self.fields.push(Field {
+            label: label.into(),
+            value: value.into(),
+            masked: false,
+        });
+        self
+    }
+
+    /// Adds a field whose input is masked, such as a password.
+    pub fn masked_field(mut self, label: impl Into<String>) -> Self {
+        self.fields.push(Field {
+            label: label.into(),
+            value: String::new(),
+            masked: true,
+        });
+        self
+    }
+
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.fields.iter().map(|field| field.label.as_str())
+    }
+}
+
+/// A form being filled in, and the parts of the input it replaced.
+pub(crate) struct ActiveForm {
+    pub id: u64,
+    form: Form,
+    focused: usize,
+    draft: Option<Draft>,
+    placeholder: Option<String>,
+    decoration: Option<String>,
+}
+
+impl ActiveForm {
+    /// Starts filling in `form` using `input`, whose contents are restored
+    /// once the form is finished.
+    pub fn begin(id: u64, form: Form, input: &mut Input) -> Self {
+        let active = Self {
+            id,
+            form,
+            focused: 0,
+            draft: Some(input.take_draft()),
+            placeholder: input.placeholder.take(),
+            decoration: input.decoration.take(),
+        };
+        active.load(input);
+        active
+    }
+
+    pub fn is_last_field(&self) -> bool {
+        self.focused + 1 >= self.form.fields.len()
+    }
+
+    /// Moves to the next field, or the previous one if `forward` is false,
+    /// wrapping around at either end.
+    pub fn focus_next(&mut self, forward: bool, input: &mut Input) {
+        let count = self.form.fields.len();
+        if count < 2 {
+            return;
+        }
+        self.store(input);
+        self.focused = if forward {
+            (self.focused + 1) % count
+        } else {
+            (self.focused + count - 1) % count
+        };
+        self.load(input);
+    }
+
+    /// Restores the input, returning the values of the fields by label.
+    pub fn finish(mut self, input: &mut Input) -> HashMap<String, String> {
+        self.store(input);
+        self.restore(input);
+        self.form
+            .fields
+            .into_iter()
+            .map(|field| (field.label, field.value))
+            .collect()
+    }
+
+    /// Restores the input, discarding the values of the fields.
+    pub fn cancel(mut self, input: &mut Input) {
+        input.clear();
+        self.restore(input);
+    }
+
+    fn restore(&mut self, input: &mut Input) {
+        if let Some(draft) = self.draft.take() {
+            input.restore_draft(draft);
+        }
+        input.placeholder = self.placeholder.take();
+        input.decoration = self.decoration.take();
+    }
+
+    // Saves the input as the focused field's value.
+    fn store(&mut self, input: &mut Input) {
+        if let Some(field) = self.form.fields.get_mut(self.focused) {
+            field.value = input.buffer.to_string();
+        }
+        // Overwrites masked values.
+        input.clear();
+    }
+
+    // Replaces the input with the focused field, labeling it.
+    fn load(&self, input: &mut Input) {
+        let Some(field) = self.form.fields.get(self.focused) else {
+            return;
+        };
+        input.clear();
+        input.options = InputOptions::default().masked(field.masked).history(false);
+        *input.buffer = field.value.clone();
+        input.suggestion.clear();
+        input.template = None;
+        input.placeholder = Some(field.label.clone());
+        input.decoration = Some(format!(
+            "{} {}/{}",
+            field.label,
+            self.focused + 1,
+            self.form.fields.len()
+        ));
+    }
+}
+
+#[test]
+fn filling_in_forms() {
+    let form = Form::new()
+        .field("name")
+        .field_with_value("port", "8080")
+        .masked_field("password");
+    let mut input = Input::default();
+    *input.buffer = String::from("unsent");
+    let mut active = ActiveForm::begin(0, form, &mut input);
+    assert_eq!(input.as_str(), "");
+    input.buffer.push_str("ferris");
+    active.focus_next(true, &mut input);
+    assert_eq!(input.as_str(), "8080");
+    active.focus_next(true, &mut input);
+    assert!(input.options.masked && active.is_last_field());
+    input.buffer.push_str("hunter2");
+    active.focus_next(true, &mut input);
+    assert_eq!(input.as_str(), "ferris");
+
+    let values = active.finish(&mut input);
+    assert_eq!(input.as_str(), "unsent");
+    assert_eq!(values["name"], "ferris");
+    assert_eq!(values["port"], "8080");
+    assert_eq!(values["password"], "hunter2");
+}
@@ -1,10 +1,46 @@
-use std::ops::{Deref, DerefMut};
+use std::borrow::Cow;
+use std::ops::{Deref, DerefMut, Range};
+use std::time::{Duration, Instant};
 
 use kludgine::core::figures::Points;
 use kludgine::prelude::*;
 
+#[cfg(feature = "accessibility")]
+use crate::accessibility::Accessibility;
+use crate::clipboard::Clipboard;
+#[cfg(feature = "emoji")]
+use crate::emoji::EmojiPicker;
+use crate::hud::DebugHud;
+use crate::keymap::{self, Action};
+use crate::layout::{self, Layout, RowPlacement};
+use crate::popup::{self, Popup};
+use crate::scrollback::{Scrollback, Viewport};
+use crate::search::SearchPrompt;
+use crate::selection::{Granularity, TextPosition};
+use crate::shortcut::Chord;
+#[cfg(feature = "spellcheck")]
+use crate::spellcheck::Corrections;
 use crate::wrap::Wrapped;
-use crate::{ConsoleHandle, InputMode};
+use crate::{Config, ConsoleHandle, Key, Overlay, Shortcut, Style, PRIMARY_SELECTION};
+
+// The size text is drawn at before it is zoomed, and that a config file's
+// `font_size` is relative to.
+const FONT_SIZE: f32 = 14.;
+const MULTI_CLICK_DELAY: Duration = Duration::from_millis(500);
+const MARK_COLOR: Color = Color::ORANGE;
+const SEARCH_MATCH_COLOR: Color = Color::DARKGOLDENROD;
+const BELL_COLOR: Color = Color::ORANGERED;
+// How long the window's border flashes when a highlight rings the bell.
+const BELL_FLASH: Duration = Duration::from_millis(150);
+// How often the debug HUD is refreshed when nothing else is redrawn, so that
+// its frame rate falls to the actual rate rather than forcing every frame.
+const HUD_REFRESH: Duration = Duration::from_millis(500);
+#[cfg(feature = "spellcheck")]
+const MISSPELLED_COLOR: Color = Color::RED;
+// The number of columns Left and Right scroll by when lines aren't wrapped.
+const HORIZONTAL_SCROLL_COLUMNS: isize = 8;
+// The fewest rows of output that are split between two views.
+const MINIMUM_SPLIT_ROWS: usize = 5;
 
 #[cfg(feature = "bundled-font")]
 pub fn bundled_font() -> &'static Font {
@@ -14,18 +50,1172 @@ pub fn bundled_font() -> &'static Font {
     BUNDLED_FONT.get_or_init(|| include_font!("../bundled-font/mononoki-Regular.ttf"))
 }
 
+// Converts a key press into a shortcut. Returns None for keys that aren't
+// part of any shortcut.
+fn shortcut_for(key: VirtualKeyCode, scene: &Target) -> Option<Shortcut> {
+    let key = match key {
+        VirtualKeyCode::Plus | VirtualKeyCode::NumpadAdd => Key::Char('+'),
+        VirtualKeyCode::Minus | VirtualKeyCode::NumpadSubtract => Key::Char('-'),
+        VirtualKeyCode::Key0 | VirtualKeyCode::Numpad0 => Key::Char('0'),
+        VirtualKeyCode::Slash => Key::Char('/'),
+        VirtualKeyCode::Space => Key::Char(' '),
+        VirtualKeyCode::Period => Key::Char('.'),
+        VirtualKeyCode::A => Key::Char('a'),
+        VirtualKeyCode::B => Key::Char('b'),
+        VirtualKeyCode::C => Key::Char('c'),
+        VirtualKeyCode::D => Key::Char('d'),
+        VirtualKeyCode::E => Key::Char('e'),
+        VirtualKeyCode::F => Key::Char('f'),
+        VirtualKeyCode::G => Key::Char('g'),
+        VirtualKeyCode::H => Key::Char('h'),
+        VirtualKeyCode::I => Key::Char('i'),
+        VirtualKeyCode::J => Key::Char('j'),
+        VirtualKeyCode::K => Key::Char('k'),
+        VirtualKeyCode::L => Key::Char('l'),
+        VirtualKeyCode::M => Key::Char('m'),
+        VirtualKeyCode::N => Key::Char('n'),
+        VirtualKeyCode::O => Key::Char('o'),
+        VirtualKeyCode::P => Key::Char('p'),
+        VirtualKeyCode::Q => Key::Char('q'),
+        VirtualKeyCode::R => Key::Char('r'),
+        VirtualKeyCode::S => Key::Char('s'),
+        VirtualKeyCode::T => Key::Char('t'),
+        VirtualKeyCode::U => Key::Char('u'),
+        VirtualKeyCode::V => Key::Char('v'),
+        VirtualKeyCode::W => Key::Char('w'),
+        VirtualKeyCode::X => Key::Char('x'),
+        VirtualKeyCode::Y => Key::Char('y'),
+        VirtualKeyCode::Z => Key::Char('z'),
+        VirtualKeyCode::F1 => Key::Function(1),
+        VirtualKeyCode::F12 => Key::Function(12),
+        VirtualKeyCode::Escape => Key::Escape,
+        VirtualKeyCode::Tab => Key::Tab,
+        VirtualKeyCode::Up => Key::Up,
+        VirtualKeyCode::Down => Key::Down,
+        VirtualKeyCode::Left => Key::Left,
+        VirtualKeyCode::Right => Key::Right,
+        VirtualKeyCode::Home => Key::Home,
+        VirtualKeyCode::End => Key::End,
+        VirtualKeyCode::PageUp => Key::PageUp,
+        VirtualKeyCode::PageDown => Key::PageDown,
+        VirtualKeyCode::Pause => Key::Pause,
+        _ => return None,
+    };
+    let modifiers = scene.modifiers_pressed();
+    Some(Shortcut {
+        key,
+        control: modifiers.control,
+        shift: modifiers.shift,
+        alt: modifiers.alt,
+        command: modifiers.operating_system,
+    })
+}
+
+fn has_glyph(font: &Font, ch: char) -> bool {
+    // Glyph 0 is the placeholder for missing characters.
+    font.glyph(ch).id().0 != 0
+}
+
+fn font_for(config: &Config, ch: char) -> &Font {
+    if has_glyph(&config.font, ch) {
+        return &config.font;
+    }
+    config
+        .fallback_fonts
+        .iter()
+        .find(|font| has_glyph(font, ch))
+        .unwrap_or(&config.font)
+}
+
 pub(crate) fn run(console: ConsoleHandle) -> ! {
+    #[cfg(feature = "accessibility")]
+    let accessibility = console.config().accessibility.then(Accessibility::connect);
+    let high_contrast = console.config().high_contrast;
+    let hud = console.config().debug_hud.then(DebugHud::default);
     SingleWindowApplication::run(Gui {
         zoom: 1.0,
+        high_contrast,
         console,
         line_height: Figure::new(0.),
+        char_width: Figure::new(0.),
+        page_rows: 0,
+        scroll_remainder: 0.,
+        rows: Vec::new(),
+        mouse: None,
+        dragging: false,
+        last_click: None,
+        help: None,
+        search: None,
+        #[cfg(feature = "emoji")]
+        emoji: None,
+        #[cfg(feature = "spellcheck")]
+        corrections: None,
+        gutter_columns: 0,
+        pinned_bottom: None,
+        hud,
+        clipboard: Clipboard::default(),
+        chord: Chord::default(),
+        chorded: false,
+        #[cfg(feature = "accessibility")]
+        accessibility,
+        #[cfg(feature = "config-file")]
+        settings_generation: 0,
+        #[cfg(feature = "config-file")]
+        file_font_size: None,
+        #[cfg(feature = "config-file")]
+        file_high_contrast: None,
     })
 }
 
+/// How the mouse wheel and touchpad scroll the output, configured with
+/// [`Config::scrolling`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct ScrollOptions {
+    /// The number of lines scrolled by each notch of a mouse wheel. Defaults
+    /// to 3.
+    pub lines_per_notch: f32,
+    /// Multiplies the distance scrolled by a touchpad. Defaults to 1.
+    pub touchpad_sensitivity: f32,
+    /// Reverses the scroll direction, so that content follows the fingers
+    /// on a touchpad. Disabled by default.
+    pub natural: bool,
+}
+
+impl Default for ScrollOptions {
+    fn default() -> Self {
+        Self {
+            lines_per_notch: 3.,
+            touchpad_sensitivity: 1.,
+            natural: false,
+        }
+    }
+}
+
+impl ScrollOptions {
+    pub fn lines_per_notch(mut self, lines: f32) -> Self {
+        self.lines_per_notch = lines;
+        self
+    }
+
+    pub fn touchpad_sensitivity(mut self, sensitivity: f32) -> Self {
+        self.touchpad_sensitivity = sensitivity;
+        self
+    }
+
+    pub fn natural(mut self, natural: bool) -> Self {
+        self.natural = natural;
+        self
+    }
+}
+
 pub struct Gui {
     zoom: f32,
+    high_contrast: bool,
     console: ConsoleHandle,
     line_height: Figure<f32, Scaled>,
+    char_width: Figure<f32, Scaled>,
+    page_rows: usize,
+    // Touchpads scroll by fractions of a line, which are kept until they add
+    // up to a whole line.
+    scroll_remainder: f32,
+    rows: Vec<RenderedRow>,
+    mouse: Option<Point<f32, Scaled>>,
+    dragging: bool,
+    last_click: Option<Click>,
+    // The built-in help, shown above any overlay from the app.
+    help: Option<Overlay>,
+    // Shown above the input while open, and captures typing.
+    search: Option<SearchPrompt>,
+    // Shown in place of the app's popup while open, and captures typing.
+    #[cfg(feature = "emoji")]
+    emoji: Option<EmojiPicker>,
+    // Shown in place of the app's popup while open.
+    #[cfg(feature = "spellcheck")]
+    corrections: Option<Corrections>,
+    gutter_columns: usize,
+    // The bottom of the pinned view while the output is split.
+    pinned_bottom: Option<Figure<f32, Scaled>>,
+    hud: Option<DebugHud>,
+    clipboard: Clipboard,
+    // The keys pressed so far of a multi-key binding.
+    chord: Chord,
+    // Whether the last key pressed was part of a chord, so the character it
+    // types is ignored.
+    chorded: bool,
+    #[cfg(feature = "accessibility")]
+    accessibility: Option<Accessibility>,
+    // The config file's generation last applied to the window.
+    #[cfg(feature = "config-file")]
+    settings_generation: u64,
+    // The font size and high contrast last applied from the config file.
+    #[cfg(feature = "config-file")]
+    file_font_size: Option<f32>,
+    #[cfg(feature = "config-file")]
+    file_high_contrast: Option<bool>,
+}
+
+// Where a view of the output is drawn.
+struct ViewArea {
+    // The baseline of the bottom row.
+    baseline: Figure<f32, Scaled>,
+    // Rows above this aren't drawn.
+    top: Figure<f32, Scaled>,
+}
+
+#[derive(Default)]
+struct DrawnView {
+    // The rows of every visible line, including those outside the view.
+    rows: usize,
+    // The columns of the widest row.
+    widest: usize,
+    wrapping: Duration,
+}
+
+struct RenderedRow {
+    top: Figure<f32, Scaled>,
+    line: usize,
+    range: Range<usize>,
+    // The number of columns before the row's text.
+    indent: usize,
+}
+
+struct Click {
+    at: Instant,
+    position: TextPosition,
+    count: u8,
+}
+
+impl Gui {
+    // Converts a key press into the shortcut it acts as, adding it to any
+    // chord in progress. Returns None if the key isn't a shortcut or is part
+    // of an unfinished chord.
+    fn press_shortcut(
+        &mut self,
+        key: VirtualKeyCode,
+        scene: &Target,
+        status: &mut RedrawStatus,
+    ) -> Option<Shortcut> {
+        let Some(shortcut) = shortcut_for(key, scene) else {
+            self.chorded = false;
+            return None;
+        };
+        let chording = self.chord.pending(Instant::now()).is_some();
+        let pressed = self
+            .console
+            .config()
+            .press_shortcut(&mut self.chord, shortcut);
+        self.chorded = chording || pressed.is_none();
+        if self.chorded {
+            // Shows or hides the keys pressed so far.
+            status.set_needs_redraw();
+        }
+        pressed
+    }
+
+    // Returns true if `action` is handled in the window's current state.
+    // Otherwise, the next binding for the key press is tried.
+    fn applies(&self, action: Action) -> bool {
+        if !action.is_available() {
+            return false;
+        }
+        match action {
+            Action::CloseSearch => self.search.is_some(),
+            Action::MoveEmojiSelection | Action::ClosePicker => self.is_picking_emoji(),
+            Action::MoveCorrectionSelection | Action::CloseCorrections => self.is_correcting(),
+            Action::FocusFormField => self.console.has_form(),
+            Action::CopyModeMotion => self.console.is_copying(),
+            Action::MoveListSelection => self.console.is_choosing_from_list(),
+            // Escape only dismisses a list once nothing else is left for it
+            // to close.
+            Action::CancelList => {
+                let state = self.console.state();
+                self.console.has_list()
+                    && self.help.is_none()
+                    && !self.console.is_copying()
+                    && !self.console.has_form()
+                    && state.overlay.lock().is_none()
+                    && state.popup.lock().is_none()
+            }
+            // Without an input line, the arrow keys and Home are free to
+            // navigate the scrollback.
+            Action::ScrollSideways | Action::ScrollLine | Action::ScrollToTop => {
+                self.console.state().input.lock().hidden
+            }
+            _ => true,
+        }
+    }
+
+    #[cfg(feature = "emoji")]
+    fn is_picking_emoji(&self) -> bool {
+        self.emoji.is_some()
+    }
+
+    #[cfg(not(feature = "emoji"))]
+    fn is_picking_emoji(&self) -> bool {
+        false
+    }
+
+    #[cfg(feature = "spellcheck")]
+    fn is_correcting(&self) -> bool {
+        self.corrections.is_some()
+    }
+
+    #[cfg(not(feature = "spellcheck"))]
+    fn is_correcting(&self) -> bool {
+        false
+    }
+
+    fn perform(&mut self, action: Action, shortcut: Shortcut, status: &mut RedrawStatus) {
+        // Up and Left move backwards for the actions bound to arrows.
+        let backwards = matches!(shortcut.key, Key::Up | Key::Left);
+        match action {
+            Action::Help => {
+                self.help = Some(keymap::help_overlay(self.console.config()));
+                status.set_needs_redraw();
+            }
+            Action::Search => {
+                if self.help.is_none() && self.search.is_none() {
+                    self.search = Some(SearchPrompt::default());
+                    status.set_needs_redraw();
+                }
+            }
+            Action::ToggleIgnoreCase => {
+                if let Some(search) = &mut self.search {
+                    search.toggle_ignore_case(self.console.state());
+                    status.set_needs_redraw();
+                }
+            }
+            Action::ToggleRegex => {
+                if let Some(search) = &mut self.search {
+                    search.toggle_regex(self.console.state());
+                    status.set_needs_redraw();
+                }
+            }
+            Action::CloseSearch => {
+                self.search = None;
+                status.set_needs_redraw();
+            }
+            Action::PickEmoji => {
+                #[cfg(feature = "emoji")]
+                if self.help.is_none() && !self.console.state().input.lock().hidden {
+                    self.emoji = Some(EmojiPicker::default());
+                    status.set_needs_redraw();
+                }
+            }
+            Action::MoveEmojiSelection =>
+            {
+                #[cfg(feature = "emoji")]
+                if let Some(picker) = &mut self.emoji {
+                    picker.move_selection(if backwards { -1 } else { 1 });
+                    status.set_needs_redraw();
+                }
+            }
+            Action::ClosePicker => {
+                #[cfg(feature = "emoji")]
+                {
+                    self.emoji = None;
+                    status.set_needs_redraw();
+                }
+            }
+            Action::SuggestSpellings => {
+                #[cfg(feature = "spellcheck")]
+                {
+                    self.suggest_spellings();
+                    status.set_needs_redraw();
+                }
+            }
+            Action::MoveCorrectionSelection =>
+            {
+                #[cfg(feature = "spellcheck")]
+                if let Some(corrections) = &mut self.corrections {
+                    corrections.move_selection(if backwards { -1 } else { 1 });
+                    status.set_needs_redraw();
+                }
+            }
+            Action::CloseCorrections => {
+                #[cfg(feature = "spellcheck")]
+                {
+                    self.corrections = None;
+                    status.set_needs_redraw();
+                }
+            }
+            Action::ZoomIn | Action::ZoomOut => {
+                self.zoom_by(if action == Action::ZoomIn { 1. } else { -1. });
+                status.set_needs_redraw();
+            }
+            Action::ResetZoom => {
+                self.reset_zoom();
+                status.set_needs_redraw();
+            }
+            Action::ToggleHighContrast => {
+                self.high_contrast = !self.high_contrast;
+                status.set_needs_redraw();
+            }
+            Action::ToggleSplit => {
+                self.console.toggle_split();
+            }
+            Action::ToggleHud => {
+                self.hud = match self.hud.take() {
+                    Some(_) => None,
+                    None => Some(DebugHud::default()),
+                };
+                status.set_needs_redraw();
+            }
+            Action::Dismiss => {
+                if self.help.take().is_some()
+                    || self.console.exit_copy_mode()
+                    || self.console.dismiss_overlay()
+                    || self.console.dismiss_popup()
+                    || self.console.cancel_form()
+                {
+                    status.set_needs_redraw();
+                }
+            }
+            Action::NextTab => {
+                self.console.next_tab();
+                status.set_needs_redraw();
+            }
+            Action::PreviousTab => {
+                self.console.previous_tab();
+                status.set_needs_redraw();
+            }
+            Action::FocusFormField => {
+                self.console.focus_form_field(!shortcut.shift);
+                status.set_needs_redraw();
+            }
+            Action::NextPlaceholder => {
+                if !self.console.next_placeholder() {
+                    self.console.complete_suggestion();
+                }
+            }
+            Action::CopyMode => {
+                if !self.console.exit_copy_mode() {
+                    self.console.begin_copy_mode(self.is_over_pinned_view());
+                }
+                status.set_needs_redraw();
+            }
+            Action::CopyModeMotion => {
+                let motion = match shortcut.key {
+                    Key::Up => 'k',
+                    Key::Down => 'j',
+                    Key::Left => 'h',
+                    _ => 'l',
+                };
+                self.copy_mode_key(motion);
+                status.set_needs_redraw();
+            }
+            Action::ScrollSideways => {
+                let columns = if backwards {
+                    -HORIZONTAL_SCROLL_COLUMNS
+                } else {
+                    HORIZONTAL_SCROLL_COLUMNS
+                };
+                self.console.scroll_horizontally(columns);
+            }
+            Action::CompleteSuggestion => {
+                self.console.complete_suggestion();
+            }
+            Action::TogglePaused => {
+                self.console.toggle_output_paused();
+            }
+            Action::PreviousMark | Action::NextMark => {
+                self.console
+                    .jump_to_mark(action == Action::PreviousMark, self.page_rows);
+            }
+            Action::ToggleMark => {
+                // Rows are recorded starting from the bottom of the view.
+                if let Some(row) = self.rows.first() {
+                    self.console.toggle_mark(row.line);
+                }
+            }
+            Action::PageUp => {
+                self.console.scroll(self.page_rows as isize);
+            }
+            Action::PageDown => {
+                self.console.scroll(-(self.page_rows as isize));
+            }
+            Action::MoveListSelection => {
+                self.console
+                    .move_list_selection(if backwards { -1 } else { 1 });
+            }
+            Action::CancelList => {
+                self.console.cancel_list();
+            }
+            Action::ScrollLine => {
+                self.console.scroll(if backwards { 1 } else { -1 });
+            }
+            Action::ScrollToTop => {
+                self.console.scroll(isize::MAX);
+            }
+            Action::RecallHistory => {
+                self.console.recall_history(backwards);
+            }
+            // End always unlocks a scrolled view.
+            Action::ScrollToCurrent => {
+                self.console.scroll_to_current();
+            }
+        }
+    }
+
+    // Changes the zoom by `steps` of the configured step, keeping it within
+    // the configured limits.
+    fn zoom_by(&mut self, steps: f32) {
+        let config = self.console.config();
+        let (minimum, maximum) = config.zoom_limits;
+        self.zoom = (self.zoom + steps * config.zoom_step).clamp(minimum, maximum);
+    }
+
+    // Returns to the configured font size, keeping the zoom within the
+    // configured limits.
+    fn reset_zoom(&mut self) {
+        #[cfg(feature = "config-file")]
+        let size = self.file_font_size.unwrap_or(FONT_SIZE);
+        #[cfg(not(feature = "config-file"))]
+        let size = FONT_SIZE;
+        self.zoom = size / FONT_SIZE;
+        self.zoom_by(0.);
+    }
+
+    // Applies the font size and high contrast from the config file when it has
+    // changed, returning true if it had.
+    #[cfg(feature = "config-file")]
+    fn apply_config_file(&mut self) -> bool {
+        let config = self.console.config();
+        let Some(file) = &config.file else {
+            return false;
+        };
+        let generation = file.generation();
+        if generation == self.settings_generation {
+            return false;
+        }
+        self.settings_generation = generation;
+        let settings = file.settings();
+        let (font_size, high_contrast) = (settings.font_size, settings.high_contrast);
+        drop(settings);
+        let app_high_contrast = config.high_contrast;
+        // Each setting is only applied when it changes, so that zooming and
+        // toggling high contrast last until the file changes them. A removed
+        // setting restores the app's.
+        if font_size != self.file_font_size {
+            self.file_font_size = font_size;
+            self.reset_zoom();
+        }
+        if high_contrast != self.file_high_contrast {
+            self.file_high_contrast = high_contrast;
+            self.high_contrast = high_contrast.unwrap_or(app_high_contrast);
+        }
+        true
+    }
+
+    fn text_color(&self, color: crate::Color) -> Color {
+        let mut style = self.console.config().style(Style::new(color));
+        if self.high_contrast {
+            style = style.high_contrast();
+        }
+        Color::from(style.foreground)
+    }
+
+    // Passes `key` to copy mode, copying the selection once it is chosen.
+    fn copy_mode_key(&mut self, key: char) {
+        if let Some(text) = self.console.copy_mode_key(key, self.page_rows) {
+            if let Err(err) = self.clipboard.copy(text) {
+                self.console
+                    .state()
+                    .report_warning(format_args!("error copying to clipboard: {err}"));
+            }
+        }
+    }
+
+    fn is_over_pinned_view(&self) -> bool {
+        match (self.mouse, self.pinned_bottom) {
+            (Some(mouse), Some(bottom)) => mouse.y < bottom.get(),
+            _ => false,
+        }
+    }
+
+    fn position_at(&self, location: Point<f32, Scaled>) -> Option<TextPosition> {
+        let row = self.rows.iter().find(|row| {
+            location.y >= row.top.get() && location.y < (row.top + self.line_height).get()
+        })?;
+        let scrollback = self.console.state().scrollback.lock();
+        let line = scrollback.line(row.line)?;
+        let column = (location.x / self.char_width.get()).max(0.) as usize;
+        let column = column.saturating_sub(self.gutter_columns + row.indent);
+        // Rows are drawn reordered for display, so the column is mapped
+        // through the same reordering.
+        Some(TextPosition {
+            line: row.line,
+            offset: line.offset_at_column(row.range.clone(), column),
+        })
+    }
+
+    fn click(&mut self, location: Point<f32, Scaled>, extend: bool) {
+        let Some(position) = self.position_at(location) else {
+            self.console.state().scrollback.lock().clear_selection();
+            self.last_click = None;
+            return;
+        };
+
+        let now = Instant::now();
+        let count = match &self.last_click {
+            Some(last)
+                if last.position == position && now.duration_since(last.at) < MULTI_CLICK_DELAY =>
+            {
+                last.count % 3 + 1
+            }
+            _ => 1,
+        };
+        self.last_click = Some(Click {
+            at: now,
+            position,
+            count,
+        });
+
+        let mut scrollback = self.console.state().scrollback.lock();
+        if extend {
+            scrollback.extend_selection(position);
+        } else {
+            let granularity = match count {
+                1 => Granularity::Character,
+                2 => Granularity::Word,
+                _ => Granularity::Line,
+            };
+            scrollback.select(self.is_over_pinned_view(), position, granularity);
+        }
+        self.dragging = true;
+    }
+
+    // Renders `text`, switching to a fallback font for any characters missing
+    // from the primary font. Returns the width of the rendered text. Without
+    // ligatures, each character is rendered on its own at its column.
+    fn render_text(
+        &self,
+        scene: &Target,
+        text: &str,
+        color: Color,
+        mut left: Figure<f32, Scaled>,
+        baseline: Figure<f32, Scaled>,
+    ) -> kludgine::app::Result<Figure<f32, Scaled>> {
+        let config = self.console.config();
+        if !config.ligatures {
+            for (column, ch) in text.chars().enumerate() {
+                let mut buffer = [0; 4];
+                let prepared = Text::prepare(
+                    ch.encode_utf8(&mut buffer),
+                    font_for(config, ch),
+                    Figure::new(FONT_SIZE),
+                    color,
+                    scene,
+                );
+                prepared.render_baseline_at(
+                    scene,
+                    Point::from_figures(left + self.char_width * column as f32, baseline),
+                )?;
+            }
+            return Ok(self.char_width * text.chars().count() as f32);
+        }
+        let mut runs: Vec<(Range<usize>, &Font)> = Vec::new();
+        for (index, ch) in text.char_indices() {
+            let font = font_for(config, ch);
+            let end = index + ch.len_utf8();
+            match runs.last_mut() {
+                Some((run, run_font)) if std::ptr::eq(*run_font, font) => run.end = end,
+                _ => runs.push((index..end, font)),
+            }
+        }
+
+        let start = left;
+        for (run, font) in runs {
+            let prepared = Text::prepare(&text[run], font, Figure::new(FONT_SIZE), color, scene);
+            prepared.render_baseline_at(scene, Point::from_figures(left, baseline))?;
+            left += prepared.width.to_scaled(scene.scale());
+        }
+        Ok(left - start)
+    }
+
+    fn render_indicators(
+        &self,
+        scene: &Target,
+        indicators: &[String],
+        mut top: Figure<f32, Scaled>,
+        ascent: Figure<f32, Scaled>,
+    ) -> kludgine::app::Result<()> {
+        for indicator in indicators {
+            let prepared = Text::prepare(
+                indicator,
+                &self.console.config().font,
+                Figure::new(FONT_SIZE),
+                Color::BLACK,
+                scene,
+            );
+            let width = prepared.width.to_scaled(scene.scale());
+            let left = scene.size().width() - width;
+            Shape::rect(Rect::new(
+                Point::from_figures(left, top),
+                Size::from_figures(width, self.line_height),
+            ))
+            .fill(Fill::new(Color::YELLOW))
+            .render(scene);
+            prepared.render_baseline_at(scene, Point::from_figures(left, top + ascent))?;
+            top += self.line_height;
+        }
+        Ok(())
+    }
+
+    fn render_overlay(
+        &self,
+        scene: &Target,
+        rows: &[String],
+        ascent: Figure<f32, Scaled>,
+    ) -> kludgine::app::Result<()> {
+        let width = rows.first().map_or(0, |row| row.chars().count()) as f32;
+        let size = Size::from_figures(
+            self.char_width * width,
+            self.line_height * rows.len() as f32,
+        );
+        let mut top = (scene.size().height() - size.height()) / 2.;
+        let left = (scene.size().width() - size.width()) / 2.;
+        Shape::rect(Rect::new(Point::from_figures(left, top), size))
+            .fill(Fill::new(Color::BLACK))
+            .render(scene);
+        for row in rows {
+            let prepared = Text::prepare(
+                row,
+                &self.console.config().font,
+                Figure::new(FONT_SIZE),
+                Color::WHITE,
+                scene,
+            );
+            prepared.render_baseline_at(scene, Point::from_figures(left, top + ascent))?;
+            top += self.line_height;
+        }
+        Ok(())
+    }
+
+    // Renders the rows of `scrollback` shown by `view` within `area`, from
+    // the bottom up. Rows above the area are counted but not drawn.
+    fn render_view(
+        &mut self,
+        scene: &Target,
+        scrollback: &mut Scrollback,
+        view: &Viewport,
+        area: ViewArea,
+        ascent: Figure<f32, Scaled>,
+    ) -> kludgine::app::Result<DrawnView> {
+        let line_height = self.line_height;
+        let text_left = self.char_width * self.gutter_columns as f32;
+        let gutter = scrollback.gutter;
+        let mut y = area.baseline;
+        let mut drawn = DrawnView::default();
+        let line_count = scrollback.len();
+        let wrap_columns = scrollback.wrap_columns();
+        let horizontal_scroll = if scrollback.no_wrap {
+            view.horizontal_scroll
+        } else {
+            0
+        };
+        for (index, line) in scrollback.events.iter_mut().enumerate() {
+            let line_index = line_count - 1 - index;
+            if !scrollback.channels.is_visible(line) {
+                continue;
+            }
+            let color = scrollback
+                .channels
+                .color_of(line)
+                .unwrap_or(crate::Color::WHITE);
+            let background = scrollback.background_of(line_index, line);
+            let wrap_started = Instant::now();
+            line.rewrap(wrap_columns);
+            drawn.wrapping += wrap_started.elapsed();
+            let selected = view
+                .selection
+                .as_ref()
+                .and_then(|selection| selection.range_in_line(line_index, line.len()));
+            // Messages are aligned as a block, so each row starts at the
+            // block's left edge.
+            let message_width = layout::message_width(line);
+
+            // Matches are only found once a row of the line is visible.
+            let mut matches = None;
+            for (row_index, range) in line.line_ranges().iter().enumerate().rev() {
+                drawn.rows += 1;
+                if drawn.rows <= view.scroll {
+                    continue;
+                }
+                let top = y - ascent;
+                let placement =
+                    RowPlacement::new(line, range.clone(), message_width, scrollback.columns);
+                drawn.widest = drawn.widest.max(placement.indent + placement.width);
+                if top.get() < area.top.get() {
+                    // Above the view, but still counted.
+                    y -= line_height;
+                    continue;
+                }
+                // Columns scrolled past are removed from the start of the row.
+                let (indent, hidden, range) =
+                    placement.scrolled(line, range.clone(), horizontal_scroll);
+                if let Some(background) = background {
+                    // Backgrounds span the whole width, not just the text.
+                    Shape::rect(Rect::new(
+                        Point::from_figures(Figure::new(0.), top),
+                        Size::from_figures(scene.size().width(), line_height),
+                    ))
+                    .fill(Fill::new(Color::from(background)))
+                    .render(scene);
+                }
+                if let (Some(sender), Some(width)) = (&line.sender, message_width) {
+                    let width = width.saturating_sub(hidden);
+                    let fill = if sender.is_local() {
+                        Color::DARKSLATEBLUE
+                    } else {
+                        Color::DARKSLATEGRAY
+                    };
+                    Shape::rect(Rect::new(
+                        Point::from_figures(text_left + self.char_width * indent as f32, top),
+                        Size::from_figures(self.char_width * width as f32, line_height),
+                    ))
+                    .fill(Fill::new(fill))
+                    .render(scene);
+                }
+                let matches = matches.get_or_insert_with(|| {
+                    self.search
+                        .as_ref()
+                        .map_or_else(Vec::new, |search| search.highlights(line.as_str()))
+                });
+                for found in matches.iter() {
+                    let text = line.as_str();
+                    self.highlight(scene, text, &range, found, indent, top, SEARCH_MATCH_COLOR);
+                }
+                if let Some(selected) = &selected {
+                    let text = line.as_str();
+                    self.highlight(scene, text, &range, selected, indent, top, Color::STEELBLUE);
+                }
+                self.rows.push(RenderedRow {
+                    top,
+                    line: line_index,
+                    range: range.clone(),
+                    indent,
+                });
+                if line.mark.is_some() {
+                    Shape::rect(Rect::new(
+                        Point::from_figures(Figure::new(0.), top),
+                        Size::from_figures(Figure::new(2.), line_height),
+                    ))
+                    .fill(Fill::new(MARK_COLOR))
+                    .render(scene);
+                }
+                if row_index == 0 && self.gutter_columns > 0 {
+                    let label = gutter.label(line_index, line);
+                    let prepared = Text::prepare(
+                        &label,
+                        &self.console.config().font,
+                        Figure::new(FONT_SIZE),
+                        Color::GRAY,
+                        scene,
+                    );
+                    // Labels are right-aligned, leaving a column of spacing.
+                    let label_columns = label.chars().count() + 1;
+                    let column = self.gutter_columns.saturating_sub(label_columns);
+                    prepared.render_baseline_at(
+                        scene,
+                        Point::from_figures(self.char_width * column as f32, y),
+                    )?;
+                }
+                let mut column = indent;
+                for (run, run_rtl) in line.visual_runs(range.clone()) {
+                    let mut segments = line.segments(run);
+                    if run_rtl {
+                        segments.reverse();
+                    }
+                    for (segment, segment_color) in segments {
+                        let text = if run_rtl {
+                            Cow::Owned(line[segment].chars().rev().collect::<String>())
+                        } else {
+                            Cow::Borrowed(&line[segment])
+                        };
+                        self.render_text(
+                            scene,
+                            &text,
+                            self.text_color(segment_color.unwrap_or(color)),
+                            text_left + self.char_width * column as f32,
+                            y,
+                        )?;
+                        column += text.chars().count();
+                    }
+                }
+                if line.is_hyphenated(row_index) {
+                    self.render_text(
+                        scene,
+                        "-",
+                        self.text_color(color),
+                        text_left + self.char_width * column as f32,
+                        y,
+                    )?;
+                }
+                y -= line_height;
+            }
+        }
+
+        Ok(drawn)
+    }
+
+    // Fills the background behind the part of `row`, a byte range of `line`
+    // starting `indent` columns into the view, that is within `highlighted`.
+    #[allow(clippy::too_many_arguments)]
+    fn highlight(
+        &self,
+        scene: &Target,
+        line: &str,
+        row: &Range<usize>,
+        highlighted: &Range<usize>,
+        indent: usize,
+        top: Figure<f32, Scaled>,
+        color: Color,
+    ) {
+        let start = highlighted.start.max(row.start);
+        let end = highlighted.end.min(row.end);
+        if start >= end {
+            return;
+        }
+        let text_left = self.char_width * self.gutter_columns as f32;
+        let start_column = indent + line[row.start..start].chars().count();
+        let columns = line[start..end].chars().count();
+        Shape::rect(Rect::new(
+            Point::from_figures(text_left + self.char_width * start_column as f32, top),
+            Size::from_figures(self.char_width * columns as f32, self.line_height),
+        ))
+        .fill(Fill::new(color))
+        .render(scene);
+    }
+
+    // Flashes the border of the window.
+    fn render_bell(&self, scene: &Target) {
+        let size = scene.size();
+        let thickness = Figure::new(3.);
+        let edges = [
+            (Figure::new(0.), Figure::new(0.), size.width(), thickness),
+            (
+                Figure::new(0.),
+                size.height() - thickness,
+                size.width(),
+                thickness,
+            ),
+            (Figure::new(0.), Figure::new(0.), thickness, size.height()),
+            (
+                size.width() - thickness,
+                Figure::new(0.),
+                thickness,
+                size.height(),
+            ),
+        ];
+        for (left, top, width, height) in edges {
+            Shape::rect(Rect::new(
+                Point::from_figures(left, top),
+                Size::from_figures(width, height),
+            ))
+            .fill(Fill::new(BELL_COLOR))
+            .render(scene);
+        }
+    }
+
+    // Renders the search prompt in the row above `bottom`, with its hint
+    // above it. The keys that toggle the options are shown at the right.
+    fn render_search_bar(
+        &self,
+        scene: &Target,
+        search: &SearchPrompt,
+        bottom: Figure<f32, Scaled>,
+        columns: usize,
+        ascent: Figure<f32, Scaled>,
+    ) -> kludgine::app::Result<()> {
+        let hint = search.hint();
+        let rows = 1 + usize::from(hint.is_some());
+        let top = bottom - self.line_height * rows as f32;
+        Shape::rect(Rect::new(
+            Point::from_figures(Figure::new(0.), top),
+            Size::from_figures(scene.size().width(), self.line_height * rows as f32),
+        ))
+        .fill(Fill::new(Color::DIMGRAY))
+        .render(scene);
+
+        let mut baseline = top + ascent;
+        if let Some(hint) = &hint {
+            let color = if search.is_invalid() {
+                Color::LIGHTCORAL
+            } else {
+                Color::LIGHTGRAY
+            };
+            self.render_text(scene, hint, color, Figure::new(0.), baseline)?;
+            baseline += self.line_height;
+        }
+        self.render_text(
+            scene,
+            &search.prompt(),
+            Color::WHITE,
+            Figure::new(0.),
+            baseline,
+        )?;
+        let toggles = search.toggles();
+        let left = columns.saturating_sub(toggles.chars().count());
+        self.render_text(
+            scene,
+            &toggles,
+            Color::LIGHTGRAY,
+            self.char_width * left as f32,
+            baseline,
+        )?;
+        Ok(())
+    }
+
+    // Returns the popup to show and the rows it may use, which are at most
+    // `max_rows`. The GUI's own pickers are shown in place of the app's popup.
+    fn active_popup(&self, max_rows: usize) -> Option<(Popup, usize)> {
+        #[cfg(feature = "emoji")]
+        if let Some(picker) = &self.emoji {
+            return Some((picker.popup(), picker.max_rows().min(max_rows)));
+        }
+        #[cfg(feature = "spellcheck")]
+        if let Some(corrections) = &self.corrections {
+            return Some((corrections.popup(), max_rows));
+        }
+        let popup = self.console.state().popup.lock().clone();
+        popup.map(|popup| (popup, max_rows))
+    }
+
+    // Shows replacements for the last misspelled word in the input.
+    #[cfg(feature = "spellcheck")]
+    fn suggest_spellings(&mut self) {
+        let Some(range) = self.console.misspelled().pop() else {
+            self.corrections = None;
+            return;
+        };
+        let word = self.console.state().input.lock().buffer[range.clone()].to_string();
+        let suggestions = self.console.spelling_suggestions(&word);
+        self.corrections = Some(Corrections::new(range, suggestions));
+    }
+
+    // Draws a wavy line under `range`, a byte range of the input, whose first
+    // row's baseline is `baseline`.
+    #[cfg(feature = "spellcheck")]
+    fn render_squiggle(
+        &self,
+        scene: &Target,
+        input: &mut crate::Input,
+        range: Range<usize>,
+        baseline: Figure<f32, Scaled>,
+    ) {
+        let (first_row, first_column) = input.position_of(range.start);
+        let (last_row, last_column) = input.position_of(range.end);
+        let step = self.char_width / 4.;
+        for row in first_row..=last_row {
+            let start = if row == first_row { first_column } else { 0 };
+            let end = if row == last_row {
+                last_column
+            } else {
+                input.columns
+            };
+            let top = baseline + self.line_height * row as f32 + Figure::new(2.);
+            let segments = end.saturating_sub(start) * 4;
+            for segment in 0..segments {
+                let left = self.char_width * start as f32 + step * segment as f32;
+                let top = top + Figure::new((segment % 2) as f32);
+                Shape::rect(Rect::new(
+                    Point::from_figures(left, top),
+                    Size::from_figures(step, Figure::new(1.)),
+                ))
+                .fill(Fill::new(MISSPELLED_COLOR))
+                .render(scene);
+            }
+        }
+    }
+
+    // Renders `popup` next to `cursor`, the top-left corner of the text
+    // cursor.
+    fn render_popup(
+        &self,
+        scene: &Target,
+        popup: &Popup,
+        cursor: (Figure<f32, Scaled>, Figure<f32, Scaled>),
+        max_columns: usize,
+        max_rows: usize,
+        ascent: Figure<f32, Scaled>,
+    ) -> kludgine::app::Result<()> {
+        let rows = popup.boxed_rows(max_columns, max_rows);
+        let width = rows.first().map_or(0, |row| row.chars().count()) as f32;
+        let size = Size::from_figures(
+            self.char_width * width,
+            self.line_height * rows.len() as f32,
+        );
+        let (left, top) = popup::place(
+            (cursor.0.get(), cursor.1.get()),
+            self.line_height.get(),
+            (size.width().get(), size.height().get()),
+            (scene.size().width().get(), scene.size().height().get()),
+        );
+        let (left, mut top) = (Figure::new(left), Figure::new(top));
+        Shape::rect(Rect::new(Point::from_figures(left, top), size))
+            .fill(Fill::new(Color::BLACK))
+            .render(scene);
+        let selected = popup.selected_row(max_rows);
+        for (index, row) in rows.iter().enumerate() {
+            if Some(index) == selected {
+                // Highlight inside the border.
+                Shape::rect(Rect::new(
+                    Point::from_figures(left + self.char_width, top),
+                    Size::from_figures(self.char_width * (width - 2.), self.line_height),
+                ))
+                .fill(Fill::new(Color::STEELBLUE))
+                .render(scene);
+            }
+            let prepared = Text::prepare(
+                row,
+                &self.console.config().font,
+                Figure::new(FONT_SIZE),
+                Color::WHITE,
+                scene,
+            );
+            prepared.render_baseline_at(scene, Point::from_figures(left, top + ascent))?;
+            top += self.line_height;
+        }
+        Ok(())
+    }
+
+    fn render_tab_bar(
+        &self,
+        scene: &Target,
+        ascent: Figure<f32, Scaled>,
+    ) -> kludgine::app::Result<()> {
+        Shape::rect(Rect::new(
+            Point::from_figures(Figure::new(0.), Figure::new(0.)),
+            Size::from_figures(scene.size().width(), self.line_height),
+        ))
+        .fill(Fill::new(Color::DIMGRAY))
+        .render(scene);
+
+        let mut left = Figure::new(0.);
+        for (index, title) in self.console.tab_titles().iter().enumerate() {
+            let prepared = Text::prepare(
+                &format!(" {title} "),
+                &self.console.config().font,
+                Figure::new(FONT_SIZE),
+                Color::WHITE,
+                scene,
+            );
+            let width = prepared.width.to_scaled(scene.scale());
+            if index == self.console.active_tab() {
+                Shape::rect(Rect::new(
+                    Point::from_figures(left, Figure::new(0.)),
+                    Size::from_figures(width, self.line_height),
+                ))
+                .fill(Fill::new(Color::BLACK))
+                .render(scene);
+            }
+            prepared.render_baseline_at(scene, Point::from_figures(left, ascent))?;
+            left += width;
+        }
+        Ok(())
+    }
 }
 
 impl WindowCreator for Gui {
@@ -45,7 +1235,7 @@ impl Window for Gui {
         Self: Sized,
     {
         self.console
-            .state
+            .state()
             .set_redrawer(move || redrawer.request_redraw());
 
         Ok(())
@@ -66,39 +1256,113 @@ impl Window for Gui {
                 key: Some(key),
                 state: ElementState::Pressed,
                 ..
-            } => match key {
-                VirtualKeyCode::Plus | VirtualKeyCode::NumpadAdd
-                    if scene.modifiers_pressed().primary_modifier() =>
-                {
-                    self.zoom += 0.1;
-                    status.set_needs_redraw();
-                }
-                VirtualKeyCode::Minus | VirtualKeyCode::NumpadSubtract
-                    if scene.modifiers_pressed().primary_modifier() =>
-                {
-                    self.zoom -= 0.1;
-                    status.set_needs_redraw();
-                }
-                VirtualKeyCode::Numpad0 | VirtualKeyCode::Key0
-                    if scene.modifiers_pressed().primary_modifier() =>
-                {
-                    self.zoom = 1.0;
-                    status.set_needs_redraw();
-                }
-                VirtualKeyCode::Tab | VirtualKeyCode::Right => {
-                    self.console.complete_suggestion();
+            } => {
+                if let Some(shortcut) = self.press_shortcut(key, scene, status) {
+                    // Shortcuts bound by scripts replace the built-in ones.
+                    #[cfg(feature = "scripting")]
+                    if crate::script::press(self.console.state(), shortcut) {
+                        status.set_needs_redraw();
+                        return Ok(());
+                    }
+                    let action = keymap::SHORTCUTS
+                        .iter()
+                        .find(|&&(keys, action)| keys.matches(shortcut) && self.applies(action))
+                        .map(|&(_, action)| action);
+                    if let Some(action) = action {
+                        self.perform(action, shortcut, status);
+                    }
                 }
-                _ => {}
-            },
+            }
             Event::MouseWheel { delta, .. } => {
+                let options = self.console.config().scrolling;
                 let lines = match delta {
-                    MouseScrollDelta::LineDelta(_, y) => y,
+                    MouseScrollDelta::LineDelta(_, y) => y * options.lines_per_notch,
                     MouseScrollDelta::PixelDelta(pixels) => {
                         let line_height = self.line_height.to_pixels(scene.scale());
-                        pixels.y as f32 / line_height.get()
+                        pixels.y as f32 / line_height.get() * options.touchpad_sensitivity
                     }
                 };
-                self.console.scroll(lines as isize);
+                let lines = if options.natural { -lines } else { lines };
+                if scene.modifiers_pressed().control {
+                    // Touchpads report zero deltas, which signum treats as
+                    // positive.
+                    if lines != 0. {
+                        self.zoom_by(lines.signum());
+                        status.set_needs_redraw();
+                    }
+                } else {
+                    self.scroll_remainder += lines;
+                    let whole = self.scroll_remainder.trunc();
+                    self.scroll_remainder -= whole;
+                    if whole == 0. {
+                        // Less than a line has accumulated.
+                    } else if scene.modifiers_pressed().shift {
+                        // Scrolling down moves right, as in most editors.
+                        self.console.scroll_horizontally(-whole as isize);
+                    } else if self.is_over_pinned_view() {
+                        self.console.scroll_pinned(whole as isize);
+                    } else {
+                        self.console.scroll(whole as isize);
+                    }
+                }
+            }
+            Event::MouseButton {
+                button: MouseButton::Left,
+                state,
+            } => match state {
+                ElementState::Pressed => {
+                    if let Some(location) = self.mouse {
+                        self.click(location, scene.modifiers_pressed().shift);
+                        status.set_needs_redraw();
+                    }
+                }
+                ElementState::Released => {
+                    self.dragging = false;
+                    let selected = PRIMARY_SELECTION
+                        .then(|| self.console.state().scrollback.lock().selected_text())
+                        .flatten();
+                    if let Some(text) = selected {
+                        if let Err(err) = self.clipboard.set_primary(text) {
+                            self.console.state().report_warning(format_args!(
+                                "error setting primary selection: {err}"
+                            ));
+                        }
+                    }
+                }
+            },
+            Event::MouseButton {
+                button: MouseButton::Middle,
+                state: ElementState::Pressed,
+            } if PRIMARY_SELECTION => {
+                let input = self.console.state().input.lock();
+                let end = (!input.hidden).then(|| input.len());
+                drop(input);
+                match end.map(|end| (end, self.clipboard.primary())) {
+                    Some((end, Ok(Some(text)))) => {
+                        // Pasted text is inserted without being submitted.
+                        let text = text.replace(['\r', '\n'], " ");
+                        self.console.replace_input(end..end, &text);
+                        status.set_needs_redraw();
+                    }
+                    Some((_, Err(err))) => self
+                        .console
+                        .state()
+                        .report_warning(format_args!("error pasting the primary selection: {err}")),
+                    Some((_, Ok(None))) | None => {}
+                }
+            }
+            Event::MouseMoved { position } => {
+                self.mouse = position;
+                if self.dragging {
+                    if let Some(position) = position.and_then(|p| self.position_at(p)) {
+                        self.console
+                            .state()
+                            .scrollback
+                            .lock()
+                            .extend_selection(position);
+                        status.set_needs_redraw();
+                    }
+                }
             }
             _ => {}
         }
@@ -109,16 +1373,65 @@ impl Window for Gui {
     fn receive_character(
         &mut self,
         ch: char,
-        _status: &mut RedrawStatus,
+        status: &mut RedrawStatus,
         scene: &Target,
         _window: WindowHandle,
     ) -> kludgine::app::Result<()>
     where
         Self: Sized,
     {
-        if scene.modifiers_pressed().primary_modifier() {
+        if std::mem::take(&mut self.chorded) {
+            // The key was part of a chord.
+        } else if scene.modifiers_pressed().primary_modifier() {
             // This is a shortcut of some sort.
+        } else if self.help.is_some() {
+            // The help overlay is modal.
+        } else if self.console.is_copying() {
+            // Copy mode captures typing.
+            self.copy_mode_key(ch);
+            status.set_needs_redraw();
         } else {
+            if let Some(search) = &mut self.search {
+                match ch {
+                    '\r' | '\n' => self.search = None,
+                    '\u{8}' => search.pop(self.console.state()),
+                    ch if !ch.is_control() => search.push(ch, self.console.state()),
+                    _ => {}
+                }
+                status.set_needs_redraw();
+                return Ok(());
+            }
+            #[cfg(feature = "emoji")]
+            if let Some(picker) = &mut self.emoji {
+                match ch {
+                    '\r' | '\n' => {
+                        if let Some(emoji) = picker.chosen() {
+                            emoji.chars().for_each(|ch| self.console.input(ch));
+                        }
+                        self.emoji = None;
+                    }
+                    '\u{8}' => picker.pop(),
+                    ch if !ch.is_control() => picker.push(ch),
+                    _ => {}
+                }
+                status.set_needs_redraw();
+                return Ok(());
+            }
+            // Typing anything else dismisses the suggestions.
+            #[cfg(feature = "spellcheck")]
+            if let Some(corrections) = self.corrections.take() {
+                status.set_needs_redraw();
+                if ch == '\r' || ch == '\n' {
+                    if let Some(replacement) = corrections.chosen() {
+                        self.console.replace_input(corrections.range, replacement);
+                    }
+                    return Ok(());
+                }
+            }
+            if (ch == '\r' || ch == '\n') && self.console.select_list_item() {
+                status.set_needs_redraw();
+                return Ok(());
+            }
             self.console.input(ch);
         }
         Ok(())
@@ -130,117 +1443,319 @@ impl Window for Gui {
         status: &mut RedrawStatus,
         _window: WindowHandle,
     ) -> kludgine::app::Result<()> {
-        let mut input = self.console.state.input.lock();
-        let input = &mut *input;
-        let mut scrollback = self.console.state.scrollback.lock();
+        let started = Instant::now();
+        let mut wrapping = Duration::ZERO;
+        self.console.update_tabs();
+        let has_tabs = self.console.tab_count() > 1;
+        // Locking a clone of the tab leaves self free to be borrowed mutably
+        // while rendering.
+        let state = self.console.state().clone();
+        let mut input_guard = state.input.lock();
+        let input = &mut *input_guard;
+        let mut scrollback_guard = state.scrollback.lock();
+        let scrollback = &mut *scrollback_guard;
         let one_char = Text::prepare(
             "m",
-            &self.console.state.config.font,
-            Figure::new(14.0),
+            &self.console.config().font,
+            Figure::new(FONT_SIZE),
             Color::WHITE,
             scene,
         );
         let one_char_width = one_char.width;
-        let cols = (scene.size().width() / one_char_width.to_scaled(scene.scale())).get() as usize;
-        scrollback.columns = cols;
+        self.char_width = one_char_width.to_scaled(scene.scale());
         let ascent = Figure::<f32, Pixels>::new(one_char.metrics.ascent).to_scaled(scene.scale());
         let descent = Figure::<f32, Pixels>::new(one_char.metrics.descent).to_scaled(scene.scale());
         let line_height = ascent - descent;
-        let rows = (scene.size().height() / line_height).get() as usize;
+        self.line_height = line_height;
+        let gutter = scrollback.gutter;
+        // The tab bar is only shown once the app opens a second tab.
+        let layout = Layout::new(
+            (scene.size().width() / self.char_width).get() as usize,
+            (scene.size().height() / line_height).get() as usize,
+            gutter.columns(scrollback.len()),
+            has_tabs,
+        );
+        let cols = layout.columns;
+        let rows = layout.rows;
+        self.gutter_columns = layout.gutter_columns;
+        let text_left = self.char_width * self.gutter_columns as f32;
+        // The gutter is excluded from the width available for wrapping.
+        scrollback.columns = layout.text_columns;
+        scrollback.rows = rows;
+        let tab_bar_rows = usize::from(layout.tab_bar);
+        let content_top = line_height * tab_bar_rows as f32;
 
-        let mut input_source = match &mut input.mode {
-            InputMode::Text | InputMode::Suggesting(_) => {
+        let (input_top, input_lines_count) = if input.hidden {
+            (scene.size().height(), 0)
+        } else {
+            // Copied because the buffer is borrowed while wrapping.
+            let ghost = input.ghost_text().map(Cow::into_owned);
+            let decoration = input.decoration.clone();
+            // Leave room for the decoration and a column of spacing.
+            let decoration_columns = decoration
+                .as_ref()
+                .map_or(0, |decoration| decoration.chars().count() + 1);
+            let input_columns = cols.saturating_sub(decoration_columns).max(1);
+            input.columns = input_columns;
+            // One star per character, so that each row is as wide as the
+            // input's.
+            let mut input_source = if input.options.masked {
+                WrappedSource::Owned(Wrapped::from("*".repeat(input.buffer.chars().count())))
+            } else {
                 WrappedSource::Borrowed(&mut input.buffer)
+            };
+            #[cfg(feature = "spellcheck")]
+            let misspelled = match &self.console.config().spellcheck {
+                Some(checker) if !input.options.masked => checker.misspelled(&input.buffer),
+                _ => Vec::new(),
+            };
+            let wrap_started = Instant::now();
+            input_source.rewrap(input_columns);
+            wrapping += wrap_started.elapsed();
+            let input_lines = input_source.rows();
+            let input_lines_count = input_lines.len();
+
+            let input_top =
+                scene.size().height() + descent - line_height * input_lines_count as f32;
+            Shape::rect(Rect::new(
+                Point::from_figures(Figure::new(0.), input_top),
+                Size::from_figures(scene.size().width(), Figure::new(1.)),
+            ))
+            .fill(Fill::new(Color::WHITE))
+            .render(scene);
+
+            let mut baseline = input_top + ascent;
+            if let Some(decoration) = &decoration {
+                let columns = decoration.chars().count();
+                self.render_text(
+                    scene,
+                    decoration,
+                    Color::GRAY,
+                    scene.size().width() - self.char_width * columns as f32,
+                    baseline,
+                )?;
+            }
+            for (line_number, line) in input_lines.enumerate() {
+                let width =
+                    self.render_text(scene, line, Color::WHITE, Figure::new(0.), baseline)?;
+
+                if line_number == input_lines_count - 1 {
+                    if let Some(ghost) = &ghost {
+                        let suggestion = Text::prepare(
+                            &ghost,
+                            &self.console.config().font,
+                            Figure::new(FONT_SIZE),
+                            Color::GRAY,
+                            scene,
+                        );
+                        suggestion
+                            .render_baseline_at(scene, Point::from_figures(width, baseline))?;
+                    }
+                }
+                baseline += line_height;
             }
-            InputMode::Secure => {
-                WrappedSource::Owned(Wrapped::from("*".repeat(input.buffer.len())))
+            #[cfg(feature = "spellcheck")]
+            for word in misspelled {
+                self.render_squiggle(scene, input, word, input_top + ascent);
             }
+
+            (input_top, input_lines_count)
         };
-        input_source.rewrap(cols);
-        let input_lines = input_source.lines();
-        let input_lines_count = input_lines.len();
+        // The top-left corner of the text cursor.
+        let cursor = (!input.hidden).then(|| {
+            let (row, column) = input.cursor_position();
+            (
+                self.char_width * column as f32,
+                input_top + line_height * row as f32,
+            )
+        });
 
-        let input_top = scene.size().height() + descent - line_height * input_lines_count as f32;
-        Shape::rect(Rect::new(
-            Point::from_figures(Figure::new(0.), input_top),
-            Size::from_figures(scene.size().width(), Figure::new(1.)),
-        ))
-        .fill(Fill::new(Color::WHITE))
-        .render(scene);
+        let view_rows = rows.saturating_sub(input_lines_count + tab_bar_rows);
+        // While split, the pinned view takes the top half, above a separator.
+        let pinned_rows = match &scrollback.pinned {
+            Some(_) if view_rows >= MINIMUM_SPLIT_ROWS => view_rows / 2,
+            _ => 0,
+        };
+        let followed_rows = if pinned_rows > 0 {
+            view_rows - pinned_rows - 1
+        } else {
+            view_rows
+        };
+        self.page_rows = followed_rows.max(1);
+        let separator_top = content_top + line_height * pinned_rows as f32;
+        self.pinned_bottom = (pinned_rows > 0).then_some(separator_top);
 
-        let mut baseline = input_top + ascent;
-        for (line_number, line) in input_lines.enumerate() {
-            let prepared = Text::prepare(
-                line,
-                &self.console.state.config.font,
-                Figure::new(14.0),
-                Color::WHITE,
-                scene,
-            );
-            prepared.render_baseline_at(scene, Point::from_figures(Figure::new(0.), baseline))?;
-
-            if line_number == input_lines_count - 1 {
-                if let InputMode::Suggesting(suggestion) = &input.mode {
-                    let suggestion = Text::prepare(
-                        suggestion,
-                        &self.console.state.config.font,
-                        Figure::new(14.0),
-                        Color::GRAY,
-                        scene,
-                    );
-                    suggestion.render_baseline_at(
-                        scene,
-                        Point::from_figures(prepared.width.to_scaled(scene.scale()), baseline),
-                    )?;
-                }
+        self.rows.clear();
+        let view = scrollback.view.clone();
+        let followed_area = ViewArea {
+            baseline: input_top + descent,
+            top: if pinned_rows > 0 {
+                separator_top + line_height
+            } else {
+                content_top
+            },
+        };
+        let followed = self.render_view(scene, scrollback, &view, followed_area, ascent)?;
+        wrapping += followed.wrapping;
+        let maximum_horizontal_scroll = if scrollback.no_wrap {
+            followed.widest.saturating_sub(scrollback.columns)
+        } else {
+            0
+        };
+        if scrollback.view.set_limits(
+            followed.rows.saturating_sub(followed_rows),
+            maximum_horizontal_scroll,
+        ) {
+            status.set_needs_redraw();
+        }
+        // Spilled lines are paged back in once a view reaches the oldest line
+        // in memory.
+        #[cfg(feature = "spill")]
+        let mut spill_error = None;
+        #[cfg(feature = "spill")]
+        if scrollback
+            .views()
+            .any(|view| view.scroll >= view.maximum_scroll)
+        {
+            match scrollback.page_in() {
+                Ok(true) => status.set_needs_redraw(),
+                Ok(false) => {}
+                Err(err) => spill_error = Some(err),
             }
-            baseline += line_height;
         }
 
-        let mut y = input_top + descent;
-        let mut total_lines = 0;
-        let scroll = scrollback.scroll;
-        for line in &mut scrollback.events {
-            line.rewrap(cols);
-            let lines = line.lines();
-
-            for line in lines.rev() {
-                total_lines += 1;
-                if total_lines <= scroll {
-                    continue;
-                }
-                let prepared = Text::prepare(
-                    line,
-                    &self.console.state.config.font,
-                    Figure::new(14.0),
-                    Color::WHITE,
-                    scene,
-                );
-                prepared.render_baseline_at(scene, Point::from_figures(Figure::new(0.), y))?;
-                y -= line_height;
+        if let Some(pinned) = scrollback.pinned.clone().filter(|_| pinned_rows > 0) {
+            let pinned_area = ViewArea {
+                baseline: separator_top + descent,
+                top: content_top,
+            };
+            let drawn = self.render_view(scene, scrollback, &pinned, pinned_area, ascent)?;
+            wrapping += drawn.wrapping;
+            let limited = scrollback.pinned.as_mut().is_some_and(|pinned| {
+                pinned.set_limits(
+                    drawn.rows.saturating_sub(pinned_rows),
+                    maximum_horizontal_scroll,
+                )
+            });
+            if limited {
+                status.set_needs_redraw();
             }
+            let label = match pinned.unseen {
+                0 => String::from(" following output below "),
+                unseen => format!(" {unseen} new below "),
+            };
+            let rule = cols.saturating_sub(label.chars().count() + 2);
+            let divider = format!("──{label}{}", "─".repeat(rule));
+            self.render_text(
+                scene,
+                &divider,
+                Color::GRAY,
+                Figure::new(0.),
+                separator_top + ascent,
+            )?;
         }
 
-        scrollback.maximum_scroll =
-            total_lines.saturating_sub(rows.saturating_sub(input_lines_count));
-        if scrollback.scroll > scrollback.maximum_scroll {
-            // Oops, we were scrolled too far now that we've re-rendered.
-            scrollback.scroll = scrollback.maximum_scroll;
+        let mut indicators = Vec::new();
+        if scrollback.view.scroll > 0 {
+            indicators.push(match scrollback.view.unseen {
+                0 => String::from("scroll locked"),
+                unseen => format!("scroll locked, {unseen} new"),
+            });
+        }
+        if let Some(pending) = &scrollback.pending {
+            indicators.push(format!("paused, {} pending", pending.len()));
+        }
+        if scrollback.gagged > 0 {
+            indicators.push(format!("{} gagged", scrollback.gagged));
+        }
+        if let Some(keys) = self.chord.pending(Instant::now()) {
+            indicators.push(format!("{keys} …"));
+            // Redraw until the chord is finished or times out.
+            status.set_needs_redraw();
+        }
+        if let Some(hud) = &self.hud {
+            indicators.extend(hud.rows(scrollback));
+        }
+        if layout.tab_bar {
+            self.render_tab_bar(scene, ascent)?;
+        }
+        if layout.indicators {
+            self.render_indicators(scene, &indicators, content_top, ascent)?;
+        }
+        self.console.lines_displayed();
+        if let Some(search) = &self.search {
+            self.render_search_bar(scene, search, input_top, cols, ascent)?;
+        }
+        if let (Some((popup, max_rows)), Some(cursor)) = (self.active_popup(rows / 2), cursor) {
+            self.render_popup(scene, &popup, cursor, cols, max_rows, ascent)?;
+        }
+        // The box's border and padding are taken out of the width by
+        // boxed_rows.
+        let overlay_rows = match &mut self.help {
+            Some(help) => Some(help.boxed_rows(cols)),
+            None => self
+                .console
+                .state()
+                .overlay
+                .lock()
+                .as_mut()
+                .map(|overlay| overlay.boxed_rows(cols)),
+        };
+        if let Some(rows) = overlay_rows {
+            self.render_overlay(scene, &rows, ascent)?;
+        }
+        let bell = *self.console.state().bell.lock();
+        if bell.is_some_and(|rung| rung.elapsed() < BELL_FLASH) {
+            self.render_bell(scene);
+            // Redraw until the flash is over.
             status.set_needs_redraw();
         }
 
+        #[cfg(feature = "accessibility")]
+        if let Some(accessibility) = &mut self.accessibility {
+            let spilled = scrollback.len() - scrollback.events.len();
+            let lines = scrollback
+                .events
+                .iter()
+                .rev()
+                .enumerate()
+                .filter(|(_, line)| scrollback.channels.is_visible(line))
+                .map(|(index, line)| (spilled + index, line.as_str()));
+            let secure = input.options.masked;
+            accessibility.update(lines, &input.buffer, secure);
+        }
+
+        // Reporting metrics locks the scrollback.
+        drop(scrollback_guard);
+        drop(input_guard);
+        #[cfg(feature = "spill")]
+        if let Some(err) = spill_error {
+            state.report_error(format_args!("error paging in scrollback: {err}"));
+        }
+        self.console.frame_rendered(started, wrapping);
+        if let Some(hud) = &mut self.hud {
+            hud.frame_rendered(started);
+            status.estimate_next_frame(HUD_REFRESH);
+        }
         Ok(())
     }
 
     fn update(
         &mut self,
         _scene: &Target,
-        _status: &mut RedrawStatus,
+        status: &mut RedrawStatus,
         window: WindowHandle,
     ) -> kludgine::app::Result<()>
     where
         Self: Sized,
     {
+        if self.console.update_tabs() {
+            status.set_needs_redraw();
+        }
+        #[cfg(feature = "config-file")]
+        if self.apply_config_file() {
+            status.set_needs_redraw();
+        }
         if self.console.should_shutdown() {
             self.console
                 .shutdown()
@@ -255,6 +1770,17 @@ impl Window for Gui {
     }
 }
 
+impl From<crate::Color> for Color {
+    fn from(color: crate::Color) -> Self {
+        Color::new(
+            f32::from(color.red) / 255.,
+            f32::from(color.green) / 255.,
+            f32::from(color.blue) / 255.,
+            1.,
+        )
+    }
+}
+
 enum WrappedSource<'a> {
     Borrowed(&'a mut Wrapped),
     Owned(Wrapped),
@@ -0,0 +1,75 @@
+use std::time::UNIX_EPOCH;
+
+use crate::scrollback::Line;
+use crate::Level;
+
+/// Information displayed in a column to the left of each line.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum Gutter {
+    #[default]
+    None,
+    /// The line's number, starting at 1 for the oldest line.
+    LineNumbers,
+    /// The time the line was pushed, in UTC.
+    Timestamps,
+    /// The line's icon, or a glyph for its [`Level`] if it has no icon.
+    Icons,
+}
+
+impl Gutter {
+    /// Returns the number of columns needed for a scrollback containing
+    /// `line_count` lines, including a column of spacing.
+    pub fn columns(&self, line_count: usize) -> usize {
+        match self {
+            Gutter::None => 0,
+            Gutter::LineNumbers => line_count.max(1).ilog10() as usize + 2,
+            Gutter::Timestamps => 9,
+            Gutter::Icons => 2,
+        }
+    }
+
+    /// Returns the text displayed next to `line`, which is `number` lines
+    /// from the oldest line.
+    pub fn label(&self, number: usize, line: &Line) -> String {
+        match self {
+            Gutter::None => String::new(),
+            Gutter::LineNumbers => (number + 1).to_string(),
+            Gutter::Timestamps => {
+                let seconds = line
+                    .timestamp
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                format!(
+                    "{:02}:{:02}:{:02}",
+                    seconds / 3600 % 24,
+                    seconds / 60 % 60,
+                    seconds % 60
+                )
+            }
+            Gutter::Icons => match (line.icon, line.level) {
+                (Some(icon), _) => icon.to_string(),
+                (None, Level::Info) => String::new(),
+                (None, Level::Warning) => String::from("!"),
+                (None, Level::Error) => String::from("✖"),
+            },
+        }
+    }
+}
+
+#[test]
+fn gutter_labels() {
+    use std::time::Duration;
+
+    let mut line = Line::from(String::from("text"));
+    line.timestamp = UNIX_EPOCH + Duration::from_secs(86_400 + 3_723);
+    assert_eq!(Gutter::Timestamps.label(0, &line), "01:02:03");
+    assert_eq!(Gutter::LineNumbers.label(9, &line), "10");
+    assert_eq!(Gutter::LineNumbers.columns(9), 2);
+    assert_eq!(Gutter::LineNumbers.columns(10), 3);
+    assert_eq!(Gutter::Icons.label(0, &line), "");
+    line.level = Level::Error;
+    assert_eq!(Gutter::Icons.label(0, &line), "✖");
+    line.icon = Some('★');
+    assert_eq!(Gutter::Icons.label(0, &line), "★");
+}
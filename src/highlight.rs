@@ -0,0 +1,207 @@
+use std::fmt;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use regex::Regex;
+
+use crate::scrollback::Line;
+use crate::style::{overlay_span, Span};
+use crate::Style;
+
+/// Identifies a rule added with
+/// [`Console::add_highlight`](crate::Console::add_highlight).
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct HighlightId(u64);
+
+#[derive(Debug)]
+struct Rule {
+    id: HighlightId,
+    pattern: Regex,
+    style: Option<Style>,
+    bell: bool,
+    // Whether matching lines are reported to the app.
+    trigger: bool,
+    gag: Option<Gag>,
+}
+
+/// What happens to lines matching a gag rule.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub(crate) enum Gag {
+    /// The line is hidden, but kept for exporting.
+    Hide,
+    Discard,
+}
+
+/// What the rules matching a line call for.
+#[derive(Debug, Default)]
+pub(crate) struct Outcome {
+    pub ring: bool,
+    /// The strictest gag of the matching rules.
+    pub gag: Option<Gag>,
+}
+
+/// The rules that color parts of lines as they're pushed.
+#[derive(Debug, Default)]
+pub(crate) struct Highlights {
+    rules: Vec<Rule>,
+    next_id: u64,
+}
+
+/// A tab's rules, kept by the drawings pushed to it to color them again each
+/// time they're redrawn.
+#[derive(Clone)]
+pub(crate) struct Restyle(pub Arc<Mutex<Highlights>>);
+
+impl Restyle {
+    /// Colors `line` with the current rules. Triggers already fired when the
+    /// line was pushed, so they aren't reported again.
+    pub fn apply(&self, line: &mut Line) {
+        self.0.lock().apply(line, |_, _| {});
+    }
+}
+
+impl fmt::Debug for Restyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Restyle").finish_non_exhaustive()
+    }
+}
+
+impl Highlights {
+    pub fn add(
+        &mut self,
+        pattern: Regex,
+        style: Option<Style>,
+        bell: bool,
+        trigger: bool,
+    ) -> HighlightId {
+        let id = HighlightId(self.next_id);
+        self.next_id += 1;
+        self.rules.push(Rule {
+            id,
+            pattern,
+            style,
+            bell,
+            trigger,
+            gag: None,
+        });
+        id
+    }
+
+    pub fn add_gag(&mut self, pattern: Regex, gag: Gag) -> HighlightId {
+        let id = self.add(pattern, None, false, false);
+        if let Some(rule) = self.rules.last_mut() {
+            rule.gag = Some(gag);
+        }
+        id
+    }
+
+    pub fn remove(&mut self, id: HighlightId) -> bool {
+        let count = self.rules.len();
+        self.rules.retain(|rule| rule.id != id);
+        self.rules.len() < count
+    }
+
+    /// Colors the parts of `line` that match each rule. Rules added later
+    /// take precedence. `triggered` is called with each triggering rule that
+    /// matched and the capture groups of its first match.
+    pub fn apply(
+        &self,
+        line: &mut Line,
+        mut triggered: impl FnMut(HighlightId, Vec<Option<String>>),
+    ) -> Outcome {
+        let mut outcome = Outcome::default();
+        for rule in &self.rules {
+            if rule.trigger {
+                if let Some(captures) = rule.pattern.captures(line.as_str()) {
+                    let captures = captures
+                        .iter()
+                        .map(|group| group.map(|group| group.as_str().to_string()))
+                        .collect();
+                    triggered(rule.id, captures);
+                }
+            }
+            let found = rule
+                .pattern
+                .find_iter(line.as_str())
+                .map(|found| found.range())
+                .filter(|range| !range.is_empty())
+                .collect::<Vec<_>>();
+            if !found.is_empty() {
+                outcome.ring |= rule.bell;
+                outcome.gag = outcome.gag.max(rule.gag);
+            }
+            if let Some(style) = rule.style {
+                for range in found {
+                    overlay_span(&mut line.spans, Span::new(range, style.foreground));
+                }
+            }
+        }
+        line.gagged = outcome.gag.is_some();
+        outcome
+    }
+}
+
+#[test]
+fn highlight_rules() {
+    use crate::Color;
+
+    let mut highlights = Highlights::default();
+    let nickname = highlights.add(
+        Regex::new("ferris").unwrap(),
+        Some(Style::new(Color::rgb(0, 255, 0))),
+        false,
+        false,
+    );
+    highlights.add(
+        Regex::new("ERROR|ferris!").unwrap(),
+        Some(Style::new(Color::rgb(255, 0, 0))),
+        true,
+        false,
+    );
+    let greeting = highlights.add(
+        Regex::new(r"(hi|hello) (\w+)(\?)?").unwrap(),
+        None,
+        false,
+        true,
+    );
+
+    let mut triggered = Vec::new();
+    let mut line = Line::from(String::from("hi ferris! and ferris"));
+    line.spans = vec![Span::new(0..6, Color::WHITE)];
+    let outcome = highlights.apply(&mut line, |id, captures| triggered.push((id, captures)));
+    assert!(outcome.ring);
+    assert_eq!(outcome.gag, None);
+    let group = |text: &str| Some(String::from(text));
+    assert_eq!(
+        triggered,
+        [(
+            greeting,
+            vec![group("hi ferris"), group("hi"), group("ferris"), None]
+        )]
+    );
+    assert_eq!(
+        line.spans,
+        [
+            Span::new(0..3, Color::WHITE),
+            Span::new(3..10, Color::rgb(255, 0, 0)),
+            Span::new(15..21, Color::rgb(0, 255, 0)),
+        ]
+    );
+
+    assert!(highlights.remove(nickname));
+    assert!(!highlights.remove(nickname));
+    let mut line = Line::from(String::from("ferris"));
+    assert!(!highlights.apply(&mut line, |_, _| unreachable!()).ring);
+    assert!(line.spans.is_empty());
+
+    highlights.add_gag(Regex::new("^PING").unwrap(), Gag::Hide);
+    highlights.add_gag(Regex::new("PING :keepalive").unwrap(), Gag::Discard);
+    let mut line = Line::from(String::from("PING :server"));
+    assert_eq!(highlights.apply(&mut line, |_, _| ()).gag, Some(Gag::Hide));
+    assert!(line.gagged);
+    let mut line = Line::from(String::from("PING :keepalive"));
+    assert_eq!(
+        highlights.apply(&mut line, |_, _| ()).gag,
+        Some(Gag::Discard)
+    );
+}
@@ -0,0 +1,65 @@
+use std::collections::VecDeque;
+use std::mem::size_of;
+use std::time::{Duration, Instant};
+
+use crate::scrollback::{Line, Scrollback};
+use crate::style::Span;
+
+/// Frame timing and scrollback statistics, displayed when the debug HUD is
+/// enabled.
+#[derive(Debug, Default)]
+pub(crate) struct DebugHud {
+    // When each frame in the last second finished rendering.
+    frames: VecDeque<Instant>,
+    last_render: Duration,
+}
+
+impl DebugHud {
+    pub fn frame_rendered(&mut self, started: Instant) {
+        let now = Instant::now();
+        self.last_render = now - started;
+        self.frames.push_back(now);
+        while self
+            .frames
+            .front()
+            .is_some_and(|frame| now - *frame > Duration::from_secs(1))
+        {
+            self.frames.pop_front();
+        }
+    }
+
+    /// Returns the rows of text to display, describing the frames rendered
+    /// so far.
+    pub fn rows(&self, scrollback: &Scrollback) -> Vec<String> {
+        vec![
+            format!(
+                "{} fps, {:.1} ms",
+                self.frames.len(),
+                self.last_render.as_secs_f64() * 1_000.
+            ),
+            format!(
+                "{} lines, ~{} KiB",
+                scrollback.len(),
+                memory_estimate(scrollback) / 1_024
+            ),
+            format!(
+                "scroll {} of {}",
+                scrollback.view.scroll, scrollback.view.maximum_scroll
+            ),
+        ]
+    }
+}
+
+// Estimates the bytes used by the scrollback's lines.
+fn memory_estimate(scrollback: &Scrollback) -> usize {
+    scrollback
+        .events
+        .iter()
+        .map(|line| {
+            size_of::<Line>()
+                + line.capacity()
+                + line.spans.capacity() * size_of::<Span>()
+                + line.mark.as_ref().map_or(0, String::capacity)
+        })
+        .sum()
+}
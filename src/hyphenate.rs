@@ -0,0 +1,23 @@
+use hyphenation::{Hyphenator, Language, Load, Standard};
+use once_cell::sync::OnceCell;
+
+// Dictionaries are shared by every console in the process, because wrapping
+// happens wherever text is measured.
+static DICTIONARY: OnceCell<Standard> = OnceCell::new();
+
+/// Loads the embedded dictionary for `language`. Only the first dictionary
+/// loaded is used.
+pub(crate) fn load(language: Language) -> hyphenation::load::Result<()> {
+    if DICTIONARY.get().is_none() {
+        let _ = DICTIONARY.set(Standard::from_embedded(language)?);
+    }
+    Ok(())
+}
+
+/// Returns the byte offsets within `word` where it may be hyphenated, in
+/// ascending order.
+pub(crate) fn breaks(word: &str) -> Vec<usize> {
+    DICTIONARY
+        .get()
+        .map_or_else(Vec::new, |dictionary| dictionary.hyphenate(word).breaks)
+}
@@ -0,0 +1,62 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::producer::Producer;
+
+// The columns each nested scope indents lines by.
+pub(crate) const INDENT_COLUMNS: usize = 2;
+
+/// Indents the lines pushed by a console handle until it is dropped.
+/// Returned by [`Console::indent`](crate::Console::indent).
+#[must_use = "lines are only indented until the scope is dropped"]
+pub struct IndentScope {
+    pub(crate) producer: Arc<Producer>,
+}
+
+impl Drop for IndentScope {
+    fn drop(&mut self) {
+        self.producer
+            .indent
+            .fetch_sub(INDENT_COLUMNS, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn nested_scopes() {
+    use crate::scrollback::Line;
+    use crate::{events, Config, Console, Shared, State};
+
+    let (sender, app) = events::channel(&Config::default());
+    let state = Arc::new(State::new(
+        Arc::new(Shared::from(Config::default())),
+        String::from("main"),
+        false,
+        sender,
+    ));
+    let console = Console::new(state, app);
+    let indents = |console: &Console| {
+        let line = console.attribute(Line::from(String::from("x"))).remove(0);
+        line.indent
+    };
+    {
+        let _outer = console.indent();
+        assert_eq!(indents(&console), 2);
+        {
+            let _inner = console.clone().indent();
+            assert_eq!(indents(&console), 4);
+            // Other producers pushing meanwhile aren't indented.
+            let worker = console.labeled("worker");
+            assert_eq!(indents(&worker), 0);
+            let _worker = worker.indent();
+            assert_eq!(indents(&worker), 2);
+            assert_eq!(indents(&console), 4);
+        }
+        assert_eq!(indents(&console), 2);
+    }
+    assert_eq!(indents(&console), 0);
+
+    let mut line = Line::from(String::from("aaaa bbbb"));
+    line.indent = 4;
+    line.rewrap(9);
+    assert_eq!(line.line_ranges().len(), 2);
+}
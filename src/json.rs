@@ -0,0 +1,295 @@
+use std::fmt;
+
+use crate::scrollback::Line;
+use crate::style::Span;
+use crate::{Color, TreeNode};
+
+const KEY_COLOR: Color = Color::rgb(156, 220, 254);
+const STRING_COLOR: Color = Color::rgb(206, 145, 120);
+const NUMBER_COLOR: Color = Color::rgb(181, 206, 168);
+const LITERAL_COLOR: Color = Color::rgb(86, 156, 214);
+
+/// The text passed to [`Console::push_json`](crate::Console::push_json) or
+/// [`Console::push_json_tree`](crate::Console::push_json_tree) is not valid
+/// JSON.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct InvalidJson {
+    /// The byte offset where parsing failed.
+    pub offset: usize,
+}
+
+impl fmt::Display for InvalidJson {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid json at byte {}", self.offset)
+    }
+}
+
+impl std::error::Error for InvalidJson {}
+
+/// Pretty-prints `json` with two-space indentation, coloring keys, strings,
+/// numbers, and literals.
+pub(crate) fn pretty_print(json: &str) -> Result<Line, InvalidJson> {
+    let mut printer = Printer {
+        source: json,
+        position: 0,
+        text: String::with_capacity(json.len() * 2),
+        spans: Vec::new(),
+        indent: 0,
+    };
+    printer.value()?;
+    printer.skip_whitespace();
+    if printer.position < json.len() {
+        return Err(printer.error());
+    }
+
+    let mut line = Line::from(printer.text);
+    line.spans = printer.spans;
+    Ok(line)
+}
+
+/// Converts `json` into a tree with a node for each value, labeled with its
+/// key within objects. Objects and arrays nested `depth` or more levels deep
+/// are collapsed.
+pub(crate) fn to_tree(json: &str, depth: usize) -> Result<TreeNode, InvalidJson> {
+    let mut printer = Printer {
+        source: json,
+        position: 0,
+        text: String::new(),
+        spans: Vec::new(),
+        indent: 0,
+    };
+    let root = printer.node(String::new(), depth)?;
+    printer.skip_whitespace();
+    if printer.position < json.len() {
+        return Err(printer.error());
+    }
+    Ok(root)
+}
+
+struct Printer<'a> {
+    source: &'a str,
+    position: usize,
+    text: String,
+    spans: Vec<Span>,
+    indent: usize,
+}
+
+impl Printer<'_> {
+    fn error(&self) -> InvalidJson {
+        InvalidJson {
+            offset: self.position,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.source.as_bytes().get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), InvalidJson> {
+        self.skip_whitespace();
+        if self.peek() == Some(byte) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error())
+        }
+    }
+
+    // Copies `source[start..self.position]` to the output in `color`.
+    fn emit(&mut self, start: usize, color: Color) {
+        let output_start = self.text.len();
+        self.text.push_str(&self.source[start..self.position]);
+        self.spans
+            .push(Span::new(output_start..self.text.len(), color));
+    }
+
+    fn newline(&mut self) {
+        self.text.push('\n');
+        for _ in 0..self.indent {
+            self.text.push_str("  ");
+        }
+    }
+
+    fn value(&mut self) -> Result<(), InvalidJson> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.container(b'}', true),
+            Some(b'[') => self.container(b']', false),
+            Some(b'"') => self.string(STRING_COLOR),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(b't') => self.literal("true"),
+            Some(b'f') => self.literal("false"),
+            Some(b'n') => self.literal("null"),
+            _ => Err(self.error()),
+        }
+    }
+
+    // Parses a value into a node labeled `key` followed by the value, or by
+    // `{}` or `[]` for an object or array whose entries become its children.
+    // `depth` is the number of levels left before nodes are collapsed.
+    fn node(&mut self, mut key: String, depth: usize) -> Result<TreeNode, InvalidJson> {
+        self.skip_whitespace();
+        let (close, object) = match self.peek() {
+            Some(b'{') => (b'}', true),
+            Some(b'[') => (b']', false),
+            _ => {
+                let start = self.position;
+                self.value()?;
+                key.push_str(&self.source[start..self.position]);
+                return Ok(TreeNode::new(key));
+            }
+        };
+        self.position += 1;
+        key.push_str(if object { "{}" } else { "[]" });
+        let mut node = TreeNode::new(key);
+        self.skip_whitespace();
+        if self.peek() == Some(close) {
+            self.position += 1;
+            return Ok(node);
+        }
+        loop {
+            let mut key = String::new();
+            if object {
+                self.skip_whitespace();
+                if self.peek() != Some(b'"') {
+                    return Err(self.error());
+                }
+                let start = self.position;
+                self.string(KEY_COLOR)?;
+                key.push_str(&self.source[start..self.position]);
+                self.expect(b':')?;
+                key.push_str(": ");
+            }
+            node = node.with_child(self.node(key, depth.saturating_sub(1))?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(byte) if byte == close => {
+                    self.position += 1;
+                    break;
+                }
+                _ => return Err(self.error()),
+            }
+        }
+        Ok(if depth == 0 { node.collapsed() } else { node })
+    }
+
+    // Prints an object or an array, placing each entry on its own line.
+    fn container(&mut self, close: u8, object: bool) -> Result<(), InvalidJson> {
+        let open = self.source.as_bytes()[self.position];
+        self.position += 1;
+        self.text.push(char::from(open));
+        self.skip_whitespace();
+        if self.peek() == Some(close) {
+            self.position += 1;
+            self.text.push(char::from(close));
+            return Ok(());
+        }
+
+        self.indent += 1;
+        loop {
+            self.newline();
+            if object {
+                self.skip_whitespace();
+                if self.peek() != Some(b'"') {
+                    return Err(self.error());
+                }
+                self.string(KEY_COLOR)?;
+                self.expect(b':')?;
+                self.text.push_str(": ");
+            }
+            self.value()?;
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => {
+                    self.position += 1;
+                    self.text.push(',');
+                }
+                Some(byte) if byte == close => {
+                    self.position += 1;
+                    break;
+                }
+                _ => return Err(self.error()),
+            }
+        }
+        self.indent -= 1;
+        self.newline();
+        self.text.push(char::from(close));
+        Ok(())
+    }
+
+    fn string(&mut self, color: Color) -> Result<(), InvalidJson> {
+        let start = self.position;
+        self.position += 1;
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    self.position += 1;
+                    break;
+                }
+                Some(b'\\') => self.position += 2,
+                Some(byte) if byte < 0x20 => return Err(self.error()),
+                Some(_) => self.position += 1,
+                None => return Err(self.error()),
+            }
+        }
+        self.emit(start, color);
+        Ok(())
+    }
+
+    fn number(&mut self) -> Result<(), InvalidJson> {
+        let start = self.position;
+        while matches!(
+            self.peek(),
+            Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+        ) {
+            self.position += 1;
+        }
+        if self.source[start..self.position].parse::<f64>().is_err() {
+            return Err(InvalidJson { offset: start });
+        }
+        self.emit(start, NUMBER_COLOR);
+        Ok(())
+    }
+
+    fn literal(&mut self, literal: &str) -> Result<(), InvalidJson> {
+        if !self.source[self.position..].starts_with(literal) {
+            return Err(self.error());
+        }
+        let start = self.position;
+        self.position += literal.len();
+        self.emit(start, LITERAL_COLOR);
+        Ok(())
+    }
+}
+
+#[test]
+fn pretty_printing() {
+    let line = pretty_print(r#"{"name":"thingy","tags":[1, true],"empty":{}}"#).unwrap();
+    assert_eq!(
+        line.as_str(),
+        "{\n  \"name\": \"thingy\",\n  \"tags\": [\n    1,\n    true\n  ],\n  \"empty\": {}\n}"
+    );
+    assert_eq!(&line[line.spans[0].range.clone()], "\"name\"");
+    assert_eq!(line.spans[0].color, KEY_COLOR);
+    assert_eq!(line.spans[3].color, NUMBER_COLOR);
+    assert_eq!(pretty_print("[1,]").unwrap_err(), InvalidJson { offset: 3 });
+    assert_eq!(pretty_print("{} x").unwrap_err(), InvalidJson { offset: 3 });
+}
+
+#[test]
+fn json_trees() {
+    let json = r#"{"name":"thingy","tags":[1, {"nested":true}],"empty":{}}"#;
+    assert_eq!(
+        to_tree(json, 2).unwrap().draw(),
+        "{}\n├─ \"name\": \"thingy\"\n├─ \"tags\": []\n│  ├─ 1\n│  └─ {} (+1)\n└─ \"empty\": {}"
+    );
+    assert_eq!(to_tree(json, 0).unwrap().draw(), "{} (+6)");
+    assert_eq!(to_tree("[1,]", 1).unwrap_err(), InvalidJson { offset: 3 });
+}
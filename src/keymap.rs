@@ -0,0 +1,344 @@
+// The shortcuts handled by the GUI. The help overlay is generated from the
+// same table that key presses are dispatched through, so that it always
+// lists what the window does.
+#![cfg_attr(not(feature = "gui"), allow(dead_code))]
+
+use crate::shortcut::{Key, Shortcut};
+use crate::{Config, Overlay};
+
+/// Something the window does when a shortcut is pressed.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum Action {
+    Help,
+    Search,
+    ToggleIgnoreCase,
+    ToggleRegex,
+    CloseSearch,
+    PickEmoji,
+    MoveEmojiSelection,
+    ClosePicker,
+    SuggestSpellings,
+    MoveCorrectionSelection,
+    CloseCorrections,
+    ZoomIn,
+    ZoomOut,
+    ResetZoom,
+    ToggleHighContrast,
+    ToggleSplit,
+    ToggleHud,
+    Dismiss,
+    NextTab,
+    PreviousTab,
+    FocusFormField,
+    NextPlaceholder,
+    CopyMode,
+    CopyModeMotion,
+    ScrollSideways,
+    CompleteSuggestion,
+    TogglePaused,
+    PreviousMark,
+    NextMark,
+    ToggleMark,
+    PageUp,
+    PageDown,
+    MoveListSelection,
+    CancelList,
+    ScrollLine,
+    ScrollToTop,
+    RecallHistory,
+    ScrollToCurrent,
+}
+
+impl Action {
+    /// Describes the action in the help overlay. Actions with the same
+    /// description share a row.
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Help => "Show this help",
+            Action::Search => "Search the output",
+            Action::ToggleIgnoreCase => "Toggle ignoring case while searching",
+            Action::ToggleRegex => "Toggle regex while searching",
+            Action::CloseSearch
+            | Action::ClosePicker
+            | Action::CloseCorrections
+            | Action::Dismiss => "Close a prompt, picker, or overlay",
+            Action::PickEmoji => "Pick an emoji",
+            Action::MoveEmojiSelection | Action::MoveCorrectionSelection => "Choose from a picker",
+            Action::SuggestSpellings => "Suggest spellings",
+            Action::ZoomIn | Action::ZoomOut => "Zoom in or out",
+            Action::ResetZoom => "Reset the zoom",
+            Action::ToggleHighContrast => "Toggle high contrast",
+            Action::ToggleSplit => "Split to keep the view while following output",
+            Action::ToggleHud => "Toggle the debug HUD",
+            Action::NextTab | Action::PreviousTab => "Switch tabs",
+            Action::FocusFormField => "Move between the fields of a form",
+            Action::NextPlaceholder => "Move to the next placeholder or complete",
+            Action::CopyMode => "Copy from the output with the keyboard",
+            Action::CopyModeMotion => "Move the cursor in copy mode",
+            Action::ScrollSideways => "Scroll sideways without an input line",
+            Action::CompleteSuggestion => "Complete the suggestion",
+            Action::TogglePaused => "Pause or resume output",
+            Action::PreviousMark | Action::NextMark => "Jump to the previous or next mark",
+            Action::ToggleMark => "Mark or unmark the bottom line",
+            Action::PageUp | Action::PageDown => "Scroll by a page",
+            Action::MoveListSelection => "Move through the newest list while nothing is typed",
+            Action::CancelList => "Dismiss the newest list",
+            Action::ScrollLine | Action::ScrollToTop => "Scroll when there is no input line",
+            Action::RecallHistory => "Recall previous inputs",
+            Action::ScrollToCurrent => "Scroll to the most recent output",
+        }
+    }
+
+    /// Returns false if the feature the action depends on is disabled.
+    pub fn is_available(self) -> bool {
+        let emoji = matches!(
+            self,
+            Action::PickEmoji | Action::MoveEmojiSelection | Action::ClosePicker
+        );
+        let spellcheck = matches!(
+            self,
+            Action::SuggestSpellings | Action::MoveCorrectionSelection | Action::CloseCorrections
+        );
+        (!emoji || cfg!(feature = "emoji")) && (!spellcheck || cfg!(feature = "spellcheck"))
+    }
+}
+
+/// The key presses a binding matches.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum Keys {
+    /// The shortcut, with exactly its modifiers.
+    Exact(Shortcut),
+    /// The key with the platform's primary modifier, whichever other
+    /// modifiers are held.
+    Primary(Key),
+    /// The key with any modifiers.
+    Any(Key),
+}
+
+impl Keys {
+    pub fn matches(self, shortcut: Shortcut) -> bool {
+        match self {
+            Keys::Exact(exact) => exact == shortcut,
+            Keys::Primary(key) => shortcut.key == key && shortcut.is_primary(),
+            Keys::Any(key) => shortcut.key == key,
+        }
+    }
+
+    fn name(self) -> String {
+        match self {
+            Keys::Exact(shortcut) => shortcut.to_string(),
+            Keys::Primary(key) => Shortcut::new(key).primary().to_string(),
+            Keys::Any(key) => key.to_string(),
+        }
+    }
+}
+
+/// Every shortcut the window handles, in the order they are tried. The first
+/// binding that matches a key press and applies in the window's current
+/// state is performed.
+pub(crate) const SHORTCUTS: &[(Keys, Action)] = &[
+    (Keys::Any(Key::Function(1)), Action::Help),
+    (
+        Keys::Exact(Shortcut::new(Key::Char('/')).control().shift()),
+        Action::Help,
+    ),
+    (
+        Keys::Exact(Shortcut::new(Key::Char('f')).primary()),
+        Action::Search,
+    ),
+    (
+        Keys::Exact(Shortcut::new(Key::Char('i')).primary()),
+        Action::ToggleIgnoreCase,
+    ),
+    (
+        Keys::Exact(Shortcut::new(Key::Char('r')).primary()),
+        Action::ToggleRegex,
+    ),
+    (Keys::Any(Key::Escape), Action::CloseSearch),
+    (
+        Keys::Exact(Shortcut::new(Key::Char('e')).primary()),
+        Action::PickEmoji,
+    ),
+    (Keys::Any(Key::Up), Action::MoveEmojiSelection),
+    (Keys::Any(Key::Down), Action::MoveEmojiSelection),
+    (Keys::Any(Key::Escape), Action::ClosePicker),
+    (
+        Keys::Exact(Shortcut::new(Key::Char('.')).primary()),
+        Action::SuggestSpellings,
+    ),
+    (Keys::Any(Key::Up), Action::MoveCorrectionSelection),
+    (Keys::Any(Key::Down), Action::MoveCorrectionSelection),
+    (Keys::Any(Key::Escape), Action::CloseCorrections),
+    (Keys::Primary(Key::Char('+')), Action::ZoomIn),
+    (Keys::Primary(Key::Char('-')), Action::ZoomOut),
+    (Keys::Primary(Key::Char('0')), Action::ResetZoom),
+    (
+        Keys::Exact(Shortcut::new(Key::Char('h')).control().shift()),
+        Action::ToggleHighContrast,
+    ),
+    (
+        Keys::Exact(Shortcut::new(Key::Char('s')).control().shift()),
+        Action::ToggleSplit,
+    ),
+    (Keys::Any(Key::Function(12)), Action::ToggleHud),
+    (Keys::Any(Key::Escape), Action::CancelList),
+    (Keys::Any(Key::Escape), Action::Dismiss),
+    (
+        Keys::Exact(Shortcut::new(Key::Tab).control()),
+        Action::NextTab,
+    ),
+    (
+        Keys::Exact(Shortcut::new(Key::Tab).control().shift()),
+        Action::PreviousTab,
+    ),
+    (Keys::Any(Key::Tab), Action::FocusFormField),
+    (Keys::Any(Key::Tab), Action::NextPlaceholder),
+    (
+        Keys::Exact(Shortcut::new(Key::Char(' ')).control().shift()),
+        Action::CopyMode,
+    ),
+    (Keys::Any(Key::Up), Action::CopyModeMotion),
+    (Keys::Any(Key::Down), Action::CopyModeMotion),
+    (Keys::Any(Key::Left), Action::CopyModeMotion),
+    (Keys::Any(Key::Right), Action::CopyModeMotion),
+    (Keys::Any(Key::Left), Action::ScrollSideways),
+    (Keys::Any(Key::Right), Action::ScrollSideways),
+    (Keys::Any(Key::Right), Action::CompleteSuggestion),
+    (Keys::Any(Key::Pause), Action::TogglePaused),
+    (
+        Keys::Exact(Shortcut::new(Key::Up).control()),
+        Action::PreviousMark,
+    ),
+    (
+        Keys::Exact(Shortcut::new(Key::Down).control()),
+        Action::NextMark,
+    ),
+    (
+        Keys::Exact(Shortcut::new(Key::Char('m')).control()),
+        Action::ToggleMark,
+    ),
+    (Keys::Any(Key::PageUp), Action::PageUp),
+    (Keys::Any(Key::PageDown), Action::PageDown),
+    (Keys::Any(Key::Up), Action::MoveListSelection),
+    (Keys::Any(Key::Down), Action::MoveListSelection),
+    (Keys::Any(Key::Up), Action::ScrollLine),
+    (Keys::Any(Key::Down), Action::ScrollLine),
+    (Keys::Any(Key::Home), Action::ScrollToTop),
+    (Keys::Any(Key::Up), Action::RecallHistory),
+    (Keys::Any(Key::Down), Action::RecallHistory),
+    (Keys::Any(Key::End), Action::ScrollToCurrent),
+];
+
+/// Mouse actions, and keys typed rather than pressed as shortcuts, shown in
+/// the help after the shortcuts when the feature they depend on is enabled.
+const OTHER_BINDINGS: &[(bool, &str, &str)] = &[
+    (
+        true,
+        "Enter",
+        "Submit, or choose an item of the newest list while nothing is typed",
+    ),
+    (
+        true,
+        "h, j, k, l, w, b, 0, $, g, G",
+        "Move the cursor in copy mode",
+    ),
+    (
+        true,
+        "v, Space",
+        "Start or clear the selection in copy mode",
+    ),
+    (true, "y, Enter", "Copy the selection and leave copy mode"),
+    (true, "Mouse wheel", "Scroll"),
+    (
+        true,
+        "Shift+Mouse wheel",
+        "Scroll sideways when wrapping is off",
+    ),
+    (true, "Ctrl+Mouse wheel", "Zoom in or out"),
+    (true, "Click, drag", "Select text"),
+    (true, "Double/triple click", "Select a word or line"),
+    (true, "Shift+Click", "Extend the selection"),
+    (
+        crate::PRIMARY_SELECTION,
+        "Middle click",
+        "Paste the primary selection",
+    ),
+];
+
+/// Returns the rows of the help overlay: the keys bound to each action,
+/// including keys the config file remaps to them, and the action.
+pub(crate) fn help_rows(config: &Config) -> Vec<(String, &'static str)> {
+    let mut rows = Vec::<(Vec<String>, &'static str)>::new();
+    for &(keys, action) in SHORTCUTS {
+        if !action.is_available() {
+            continue;
+        }
+        let description = action.description();
+        let row = match rows
+            .iter()
+            .position(|(_, existing)| *existing == description)
+        {
+            Some(index) => &mut rows[index].0,
+            None => {
+                rows.push((Vec::new(), description));
+                &mut rows.last_mut().expect("just pushed").0
+            }
+        };
+        for name in std::iter::once(keys.name()).chain(config.remapped_to(keys)) {
+            if !row.contains(&name) {
+                row.push(name);
+            }
+        }
+    }
+    rows.into_iter()
+        .map(|(keys, description)| (keys.join(", "), description))
+        .chain(
+            OTHER_BINDINGS
+                .iter()
+                .filter(|(enabled, ..)| *enabled)
+                .map(|&(_, keys, description)| (keys.to_string(), description)),
+        )
+        .collect()
+}
+
+pub(crate) fn help_overlay(config: &Config) -> Overlay {
+    let rows = help_rows(config);
+    let key_width = rows
+        .iter()
+        .map(|(keys, _)| keys.chars().count())
+        .max()
+        .unwrap_or_default();
+    let text = rows
+        .iter()
+        .map(|(keys, action)| format!("{keys:key_width$}  {action}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Overlay::text(text).with_title("Keys")
+}
+
+#[test]
+fn help_lists_shortcuts() {
+    let rows = help_rows(&Config::default());
+    let keys_for = |description: &str| {
+        rows.iter()
+            .find(|(_, row)| *row == description)
+            .map(|(keys, _)| keys.clone())
+    };
+    assert_eq!(
+        keys_for("Show this help").as_deref(),
+        Some("F1, Ctrl+Shift+/")
+    );
+    assert_eq!(
+        keys_for("Copy from the output with the keyboard").as_deref(),
+        Some("Ctrl+Shift+Space")
+    );
+    assert_eq!(
+        keys_for("Switch tabs").as_deref(),
+        Some("Ctrl+Tab, Ctrl+Shift+Tab")
+    );
+    assert_eq!(keys_for("Pick an emoji").is_some(), cfg!(feature = "emoji"));
+    // Every action that is available is described.
+    for &(_, action) in SHORTCUTS {
+        assert!(!action.is_available() || keys_for(action.description()).is_some());
+    }
+}
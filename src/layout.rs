@@ -0,0 +1,154 @@
+use std::ops::Range;
+
+use crate::scrollback::Line;
+use crate::width::Alignment;
+
+// The narrowest text area that the gutter is shown beside.
+const MINIMUM_TEXT_COLUMNS_FOR_GUTTER: usize = 20;
+// The narrowest console that shows status indicators, which would otherwise
+// cover most of the text they're drawn over.
+const MINIMUM_COLUMNS_FOR_INDICATORS: usize = 30;
+// The fewest rows that the tab bar is shown with.
+const MINIMUM_ROWS_FOR_TAB_BAR: usize = 4;
+
+/// How a console's columns and rows are divided between its parts. As the
+/// console shrinks, the gutter is hidden first, followed by the status
+/// indicators and the tab bar, so that the text remains readable.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub(crate) struct Layout {
+    /// The total number of columns, which is never less than 1.
+    pub columns: usize,
+    /// The total number of rows, which is never less than 1.
+    pub rows: usize,
+    pub gutter_columns: usize,
+    /// The columns text is wrapped within, which is never less than 1.
+    pub text_columns: usize,
+    pub indicators: bool,
+    pub tab_bar: bool,
+}
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+impl Layout {
+    pub fn new(columns: usize, rows: usize, gutter_columns: usize, has_tabs: bool) -> Self {
+        let columns = columns.max(1);
+        let rows = rows.max(1);
+        let gutter_columns = if columns >= gutter_columns + MINIMUM_TEXT_COLUMNS_FOR_GUTTER {
+            gutter_columns
+        } else {
+            0
+        };
+        Self {
+            columns,
+            rows,
+            gutter_columns,
+            text_columns: columns - gutter_columns,
+            indicators: columns >= MINIMUM_COLUMNS_FOR_INDICATORS,
+            tab_bar: has_tabs && rows >= MINIMUM_ROWS_FOR_TAB_BAR,
+        }
+    }
+}
+
+/// Where a row of a line is placed within the text area. The window and
+/// screenshots both place rows with it, so that they agree.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) struct RowPlacement {
+    /// The columns the row takes, which for messages is the width of their
+    /// widest row, because they're aligned as a block.
+    pub width: usize,
+    /// The columns before the row, from the line's indent and alignment.
+    pub indent: usize,
+}
+
+impl RowPlacement {
+    /// Places the row of `line` at `range` within `columns`. `message_width`
+    /// is the value of [`message_width`] for the line.
+    pub fn new(
+        line: &Line,
+        range: Range<usize>,
+        message_width: Option<usize>,
+        columns: usize,
+    ) -> Self {
+        let width = message_width.unwrap_or_else(|| line[range].trim_end().chars().count());
+        let free = columns.saturating_sub(line.indent + width);
+        let indent = line.indent
+            + match line.alignment {
+                Some(Alignment::Left) => 0,
+                Some(Alignment::Center) => free / 2,
+                Some(Alignment::Right) => free,
+                // Right-to-left paragraphs are aligned to the right edge.
+                None if line.is_rtl() => free,
+                None => 0,
+            };
+        Self { width, indent }
+    }
+
+    /// Returns the columns before the row once the view is scrolled
+    /// `horizontal_scroll` columns sideways, the columns of the row that are
+    /// scrolled past, and the part of `range` that is still shown.
+    pub fn scrolled(
+        &self,
+        line: &Line,
+        range: Range<usize>,
+        horizontal_scroll: usize,
+    ) -> (usize, usize, Range<usize>) {
+        let hidden = horizontal_scroll.saturating_sub(self.indent);
+        let indent = self.indent.saturating_sub(horizontal_scroll);
+        let start = line[range.clone()]
+            .char_indices()
+            .nth(hidden)
+            .map_or(range.end, |(offset, _)| range.start + offset);
+        (indent, hidden, start..range.end)
+    }
+}
+
+/// Returns the width of the widest row of a message, which is placed as a
+/// block, or None if `line` isn't a message. The line must be wrapped.
+pub(crate) fn message_width(line: &Line) -> Option<usize> {
+    line.sender.as_ref().map(|_| {
+        line.rows()
+            .map(|row| row.trim_end().chars().count())
+            .max()
+            .unwrap_or_default()
+    })
+}
+
+#[test]
+fn layout_degrades() {
+    let full = Layout::new(80, 24, 3, true);
+    assert_eq!(full.gutter_columns, 3);
+    assert_eq!(full.text_columns, 77);
+    assert!(full.indicators && full.tab_bar);
+
+    let narrow = Layout::new(22, 3, 3, true);
+    assert_eq!(narrow.gutter_columns, 0);
+    assert_eq!(narrow.text_columns, 22);
+    assert!(!narrow.indicators && !narrow.tab_bar);
+
+    for columns in 0..=1 {
+        let single = Layout::new(columns, 0, 9, false);
+        assert_eq!((single.columns, single.rows), (1, 1));
+        assert_eq!(single.text_columns, 1);
+    }
+}
+
+#[test]
+fn row_placement() {
+    let mut line = Line::from(String::from("abcdef"));
+    line.rewrap(10);
+    line.indent = 2;
+    let placed = RowPlacement::new(&line, 0..6, message_width(&line), 10);
+    assert_eq!(
+        placed,
+        RowPlacement {
+            width: 6,
+            indent: 2
+        }
+    );
+    assert_eq!(placed.scrolled(&line, 0..6, 4), (0, 2, 2..6));
+
+    line.alignment = Some(Alignment::Right);
+    let placed = RowPlacement::new(&line, 0..6, None, 10);
+    assert_eq!(placed.indent, 4);
+    assert_eq!(placed.scrolled(&line, 0..6, 1), (3, 0, 0..6));
+}
@@ -1,73 +1,686 @@
-use parking_lot::Mutex;
-use std::ops::{Deref, DerefMut};
+use parking_lot::{Condvar, Mutex};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::ops::{ControlFlow, Deref, DerefMut, Range};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime};
 
-use crate::scrollback::Scrollback;
+use crate::backpressure::Limit;
+use crate::channel::ChannelSettings;
+use crate::copy_mode::{CopyAction, CopyMode};
+use crate::drawing::Drawing;
+use crate::events::{EventReceiver, EventSender};
+use crate::form::ActiveForm;
+use crate::highlight::{Gag, Highlights, Restyle};
+use crate::list::ActiveList;
+use crate::metrics::MetricsHook;
+use crate::plugin::Plugins;
+use crate::producer::Producer;
+use crate::scrollback::{Line, Scrollback};
+use crate::shortcut::Chord;
+#[cfg(feature = "spill")]
+use crate::spill::Spill;
+use crate::style::StyleTransform;
+use crate::template::Template;
+use crate::worker::Workers;
 use crate::wrap::Wrapped;
 
+pub use crate::backpressure::{Full, Overflow};
+pub use crate::channel::Channel;
+pub use crate::chat::Sender;
+#[cfg(feature = "clap")]
+pub use crate::cli::ParsedCommand;
+pub use crate::command::{Arguments, CommandError, CommandInfo, ConsoleCommand};
+pub use crate::drawing::{BoxGlyphs, BoxStyle};
+#[cfg(feature = "encrypted-transcript")]
+pub use crate::encrypted_transcript::{read_transcript, TranscriptOptions};
+pub use crate::export::{MarkdownOptions, MarkdownStyle};
+pub use crate::form::Form;
+#[cfg(feature = "gui")]
+pub use crate::gui::ScrollOptions;
+pub use crate::gutter::Gutter;
+pub use crate::highlight::HighlightId;
+pub use crate::indent::IndentScope;
+pub use crate::json::InvalidJson;
+pub use crate::metrics::{Metrics, Queue};
+pub use crate::newline::Newline;
+pub use crate::overlay::Overlay;
+pub use crate::plugin::Plugin;
+pub use crate::popup::Popup;
+#[cfg(feature = "process")]
+pub use crate::process::Process;
+#[cfg(feature = "pty")]
+pub use crate::pty::Pty;
+pub use crate::scrollback::Level;
+pub use crate::search::{Search, SearchMatch, SearchOptions};
+pub use crate::select::{
+    bounded_select_channel, select_channel, RecvError, SelectReceiver, SelectSender, Selector,
+    TryRecvError,
+};
+pub use crate::shortcut::{InvalidShortcut, Key, Shortcut};
+pub use crate::shutdown::ShutdownToken;
+#[cfg(feature = "serde")]
+pub use crate::snapshot::Snapshot;
+#[cfg(feature = "spellcheck")]
+pub use crate::spellcheck::{Dictionary, WordList};
+#[cfg(feature = "lz4")]
+pub use crate::spill::Compressed;
+#[cfg(feature = "spill")]
+pub use crate::spill::{ScrollbackStore, SegmentFiles};
+pub use crate::stream::Stream;
+pub use crate::style::{Color, Style, COLOR_BLIND_SAFE};
+pub use crate::suggest::InputGeneration;
+pub use crate::transcript::Rotation;
+pub use crate::tree::TreeNode;
+pub use crate::width::Alignment;
+pub use crate::writer::Writer;
+#[cfg(feature = "macros")]
+pub use console_thingy_macros::ConsoleCommand;
+#[cfg(feature = "hyphenation")]
+pub use hyphenation::Language;
+#[cfg(feature = "pty")]
+pub use portable_pty::CommandBuilder;
+pub use regex::{Error as RegexError, Regex};
+
+#[cfg(feature = "accessibility")]
+mod accessibility;
+mod ansi;
+mod backpressure;
+mod bidi;
+#[cfg(all(unix, feature = "capture-output"))]
+mod capture;
+mod channel;
+mod chat;
+#[cfg(feature = "clap")]
+mod cli;
+#[cfg(feature = "gui")]
+mod clipboard;
+mod command;
+#[cfg(feature = "config-file")]
+mod config_file;
+mod copy_mode;
+mod drawing;
+#[cfg(feature = "emoji")]
+mod emoji;
+#[cfg(feature = "encrypted-transcript")]
+mod encrypted_transcript;
+mod events;
+mod export;
+mod form;
 #[cfg(feature = "gui")]
 mod gui;
+mod gutter;
+mod highlight;
+#[cfg(feature = "gui")]
+mod hud;
+#[cfg(feature = "hyphenation")]
+mod hyphenate;
+mod indent;
+mod json;
+mod keymap;
+#[cfg(any(feature = "gui", feature = "screenshot"))]
+mod layout;
+mod list;
+mod metrics;
+mod newline;
+mod overlay;
+mod panics;
+mod plain;
+mod plugin;
+mod popup;
+#[cfg(feature = "process")]
+mod process;
+mod producer;
+#[cfg(feature = "pty")]
+mod pty;
+#[cfg(feature = "screenshot")]
+mod screenshot;
+#[cfg(feature = "scripting")]
+mod script;
 mod scrollback;
+mod search;
+mod select;
+mod selection;
+#[cfg(all(unix, feature = "session"))]
+mod session;
+mod shortcut;
+mod shutdown;
+#[cfg(feature = "serde")]
+mod snapshot;
+#[cfg(feature = "spellcheck")]
+mod spellcheck;
+#[cfg(feature = "spill")]
+mod spill;
+mod stream;
+mod style;
+mod suggest;
+mod template;
+mod transcript;
+mod tree;
 #[cfg(feature = "tui")]
 mod tui;
-mod wrap;
+pub mod width;
+mod worker;
+pub mod wrap;
+mod writer;
 
 #[derive(Debug)]
 pub struct Config {
     #[cfg(feature = "kludgine")]
     font: kludgine::core::text::Font,
+    #[cfg(feature = "kludgine")]
+    fallback_fonts: Vec<kludgine::core::text::Font>,
+    #[cfg(feature = "kludgine")]
+    ligatures: bool,
+    unlock_scroll_on_submit: bool,
+    auto_clear_on_submit: bool,
+    echo_input: bool,
+    echo_prefix: String,
+    completion_triggers: Vec<char>,
+    incoming_newline: Newline,
+    plain_when_piped: bool,
+    plain_json: bool,
+    style_transform: Option<StyleTransform>,
+    metrics: Option<MetricsHook>,
+    plugins: Plugins,
+    capture_panics: bool,
+    shutdown_on_panic: bool,
+    worker_timeout: Duration,
+    event_capacity: Option<usize>,
+    input_debounce: Option<Duration>,
+    #[cfg(all(unix, feature = "capture-output"))]
+    capture_output: bool,
+    plain_transcript: Option<(PathBuf, Rotation)>,
+    #[cfg(feature = "encrypted-transcript")]
+    transcript: Option<TranscriptOptions>,
+    #[cfg(feature = "accessibility")]
+    accessibility: bool,
+    #[cfg(feature = "gui")]
+    zoom_limits: (f32, f32),
+    #[cfg(feature = "gui")]
+    zoom_step: f32,
+    #[cfg(feature = "gui")]
+    high_contrast: bool,
+    #[cfg(feature = "gui")]
+    debug_hud: bool,
+    #[cfg(feature = "gui")]
+    scrolling: ScrollOptions,
+    #[cfg(feature = "hyphenation")]
+    hyphenation: Option<Language>,
+    #[cfg(feature = "spellcheck")]
+    spellcheck: Option<spellcheck::Spellchecker>,
+    #[cfg(feature = "config-file")]
+    file: Option<Arc<config_file::ConfigFile>>,
 }
 
-#[cfg(feature = "bundled-font")]
+// Without the bundled font, a font must be provided when using Kludgine.
+#[cfg(any(feature = "bundled-font", not(feature = "kludgine")))]
 impl Default for Config {
     fn default() -> Self {
         Self {
+            #[cfg(feature = "kludgine")]
             font: gui::bundled_font().clone(),
+            #[cfg(feature = "kludgine")]
+            fallback_fonts: Vec::new(),
+            #[cfg(feature = "kludgine")]
+            ligatures: true,
+            unlock_scroll_on_submit: true,
+            auto_clear_on_submit: false,
+            echo_input: false,
+            echo_prefix: String::from("> "),
+            completion_triggers: Vec::new(),
+            incoming_newline: Newline::default(),
+            plain_when_piped: true,
+            plain_json: false,
+            style_transform: None,
+            metrics: None,
+            plugins: Plugins::default(),
+            capture_panics: false,
+            shutdown_on_panic: false,
+            worker_timeout: Duration::from_secs(5),
+            event_capacity: None,
+            input_debounce: None,
+            #[cfg(all(unix, feature = "capture-output"))]
+            capture_output: false,
+            plain_transcript: None,
+            #[cfg(feature = "encrypted-transcript")]
+            transcript: None,
+            #[cfg(feature = "accessibility")]
+            accessibility: false,
+            #[cfg(feature = "gui")]
+            zoom_limits: (0.5, 4.0),
+            #[cfg(feature = "gui")]
+            zoom_step: 0.1,
+            #[cfg(feature = "gui")]
+            high_contrast: false,
+            #[cfg(feature = "gui")]
+            debug_hud: false,
+            #[cfg(feature = "gui")]
+            scrolling: ScrollOptions::default(),
+            #[cfg(feature = "hyphenation")]
+            hyphenation: None,
+            #[cfg(feature = "spellcheck")]
+            spellcheck: None,
+            #[cfg(feature = "config-file")]
+            file: None,
         }
     }
 }
 
-#[cfg(not(feature = "kludgine"))]
-impl Default for Config {
-    fn default() -> Self {
-        Self {}
+impl Config {
+    /// Loads the look and behavior of the console from the TOML file at
+    /// `path`, on top of the default configuration. Settings in the file take
+    /// precedence over the ones set by the app, which return when they are
+    /// removed from the file.
+    ///
+    /// ```toml
+    /// font_size = 16.0
+    /// high_contrast = false
+    /// leader = "Ctrl+Space"
+    ///
+    /// # Colors used by the app, and the colors displayed in their place.
+    /// [theme.colors]
+    /// "#ff0000" = "#d55e00"
+    ///
+    /// # Shortcuts, and the shortcuts they act as. Keys separated by spaces
+    /// # are pressed in turn, each within two seconds of the last, and
+    /// # `Leader` stands for the leader key.
+    /// [keys]
+    /// "Ctrl+K" = "Primary+F"
+    /// "Ctrl+X Ctrl+S" = "Primary+S"
+    /// "Leader F" = "Primary+F"
+    ///
+    /// [layout]
+    /// wrap = true
+    /// alternate_background = "#1a1a1a"
+    /// ```
+    ///
+    /// With the `hot-reload` feature, the file is watched while the console
+    /// runs, and changes are applied as it is saved. Errors in the reloaded
+    /// file are shown as a warning line, and the previous settings are kept.
+    #[cfg(all(
+        feature = "config-file",
+        any(feature = "bundled-font", not(feature = "kludgine"))
+    ))]
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let file = config_file::ConfigFile::load(path.as_ref())?;
+        Ok(Self {
+            file: Some(Arc::new(file)),
+            ..Self::default()
+        })
     }
-}
 
-impl Config {
-    #[cfg(all(feature = "gui", feature = "tui"))]
-    pub fn run<T>(self, app: T) -> !
+    /// Sets the fonts used for characters missing from the primary font,
+    /// such as emoji or CJK characters. Fonts are tried in order.
+    #[cfg(feature = "kludgine")]
+    pub fn fallback_fonts(
+        mut self,
+        fonts: impl IntoIterator<Item = kludgine::core::text::Font>,
+    ) -> Self {
+        self.fallback_fonts = fonts.into_iter().collect();
+        self
+    }
+
+    /// Controls whether the font may join characters into ligatures. When
+    /// disabled, each character is drawn on its own at its column, which
+    /// keeps alignment exact for fonts whose ligatures are narrower or wider
+    /// than the characters they replace. Enabled by default.
+    #[cfg(feature = "kludgine")]
+    pub fn ligatures(mut self, enabled: bool) -> Self {
+        self.ligatures = enabled;
+        self
+    }
+
+    /// Controls whether submitting input scrolls a locked view back to the
+    /// most recent output. Enabled by default.
+    pub fn unlock_scroll_on_submit(mut self, unlock: bool) -> Self {
+        self.unlock_scroll_on_submit = unlock;
+        self
+    }
+
+    /// Controls whether the input is cleared as it is submitted. The
+    /// submitted text is still delivered in [`ConsoleEvent::Input`].
+    /// Disabled by default.
+    pub fn auto_clear_on_submit(mut self, clear: bool) -> Self {
+        self.auto_clear_on_submit = clear;
+        self
+    }
+
+    /// Controls whether submitted input is pushed to the scrollback, after
+    /// the prefix set with [`Config::echo_prefix`]. Masked input is echoed
+    /// as asterisks. Disabled by default.
+    pub fn echo_input(mut self, echo: bool) -> Self {
+        self.echo_input = echo;
+        self
+    }
+
+    /// Sets the text placed before echoed input. Defaults to `"> "`.
+    pub fn echo_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.echo_prefix = prefix.into();
+        self
+    }
+
+    /// Sets characters, such as `@` or `#`, that begin a token completed by
+    /// the app. While the word being typed starts with one of these,
+    /// [`ConsoleEvent::TriggerCompletion`] is sent each time it changes.
+    pub fn completion_triggers(mut self, triggers: impl IntoIterator<Item = char>) -> Self {
+        self.completion_triggers = triggers.into_iter().collect();
+        self
+    }
+
+    /// Sets the line ending expected in pushed text and in data read by
+    /// [`Console::attach_reader`]. Defaults to [`Newline::Auto`].
+    pub fn incoming_newline(mut self, newline: Newline) -> Self {
+        self.incoming_newline = newline;
+        self
+    }
+
+    /// Controls whether the console falls back to plain text when stdin is
+    /// not a terminal and stdout is not a terminal. In this mode, each line
+    /// read from stdin is submitted as input and pushed lines are printed to
+    /// stdout, allowing apps to be scripted. Enabled by default.
+    pub fn plain_when_piped(mut self, plain: bool) -> Self {
+        self.plain_when_piped = plain;
+        self
+    }
+
+    /// Prints each pushed line as a JSON object when using plain text
+    /// output, so that other tools can consume the app's output. Each object
+    /// contains the line's `text`, `level`, `channel`, and `timestamp` in
+    /// seconds since the Unix epoch.
+    pub fn plain_json(mut self, json: bool) -> Self {
+        self.plain_json = json;
+        self
+    }
+
+    /// Sets a function that adjusts every style before it is displayed. This
+    /// allows remapping colors, such as with [`Style::color_blind_safe`],
+    /// without changing the app.
+    pub fn style_transform<F>(mut self, transform: F) -> Self
     where
-        T: App,
+        F: Fn(Style) -> Style + Send + Sync + 'static,
     {
-        let state = Arc::new(State::from(self));
-        let console = Console::spawn(app, state);
-        if tui::is_tty() {
-            tui::run(console)
-        } else {
-            gui::run(console)
+        self.style_transform = Some(StyleTransform(Box::new(transform)));
+        self
+    }
+
+    /// Reports rendering performance, such as redraw duration and
+    /// input-to-render latency, to `metrics`.
+    pub fn metrics<M>(mut self, metrics: M) -> Self
+    where
+        M: Metrics,
+    {
+        self.metrics = Some(MetricsHook(Arc::new(metrics)));
+        self
+    }
+
+    /// Controls whether panics in any thread are displayed in the scrollback
+    /// of the first tab, where they can be seen when stderr isn't visible,
+    /// such as when using the GUI. Panics are also reported as before.
+    /// Disabled by default.
+    pub fn capture_panics(mut self, capture: bool) -> Self {
+        self.capture_panics = capture;
+        self
+    }
+
+    /// Controls whether the console shuts down after displaying a panic
+    /// captured with [`Self::capture_panics`]. Disabled by default.
+    pub fn shutdown_on_panic(mut self, shutdown: bool) -> Self {
+        self.shutdown_on_panic = shutdown;
+        self
+    }
+
+    /// Limits the number of queued ticks, timers, triggers, and input buffer
+    /// changes waiting for each app to `capacity`. While the limit is
+    /// reached, more of those events are dropped. Other events, such as
+    /// submitted input, are never dropped and are always received first.
+    /// Unlimited by default.
+    pub fn event_capacity(mut self, capacity: usize) -> Self {
+        self.event_capacity = Some(capacity);
+        self
+    }
+
+    /// Delays [`ConsoleEvent::InputBufferChanged`] until the input hasn't
+    /// changed for `debounce`, so that rapid typing results in a single
+    /// event. This is useful when each change starts expensive work, such as
+    /// computing suggestions. Disabled by default.
+    pub fn input_change_debounce(mut self, debounce: Duration) -> Self {
+        self.input_debounce = Some(debounce);
+        self
+    }
+
+    /// Sets how long shutting down waits for the threads started with
+    /// [`Console::spawn`] to return. Threads still running afterwards are
+    /// reported and left behind. Defaults to 5 seconds.
+    pub fn worker_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.worker_timeout = timeout;
+        self
+    }
+
+    /// Controls whether this process's own stdout and stderr are displayed in
+    /// the scrollback of the first tab when using the GUI, so that output
+    /// printed by dependencies isn't lost. Disabled by default.
+    #[cfg(all(unix, feature = "capture-output"))]
+    pub fn capture_output(mut self, capture: bool) -> Self {
+        self.capture_output = capture;
+        self
+    }
+
+    /// Installs `plugin`, which is initialized with each app's console and
+    /// observes the events each app receives.
+    pub fn plugin(mut self, plugin: impl Plugin) -> Self {
+        self.plugins.push(plugin);
+        self
+    }
+
+    /// Appends every pushed line to the text file at `path` as it is pushed,
+    /// so that the session's record survives a crash. Each line is prefixed
+    /// with its timestamp, level, and channel.
+    pub fn transcript_plain(mut self, path: impl Into<PathBuf>, rotate: Rotation) -> Self {
+        self.plain_transcript = Some((path.into(), rotate));
+        self
+    }
+
+    /// Continuously appends every pushed line to an encrypted transcript,
+    /// such as for compliance records.
+    #[cfg(feature = "encrypted-transcript")]
+    pub fn transcript(mut self, options: TranscriptOptions) -> Self {
+        self.transcript = Some(options);
+        self
+    }
+
+    /// Controls whether the scrollback and input line are described to
+    /// assistive technologies, such as screen readers. Disabled by default.
+    #[cfg(feature = "accessibility")]
+    pub fn accessibility(mut self, enabled: bool) -> Self {
+        self.accessibility = enabled;
+        self
+    }
+
+    /// Sets the smallest and largest zoom the user can select. Defaults to
+    /// 0.5 and 4.0.
+    #[cfg(feature = "gui")]
+    pub fn zoom_limits(mut self, minimum: f32, maximum: f32) -> Self {
+        self.zoom_limits = (minimum, maximum.max(minimum));
+        self
+    }
+
+    /// Sets how much each zoom key press or scroll wheel step changes the
+    /// zoom. Defaults to 0.1.
+    #[cfg(feature = "gui")]
+    pub fn zoom_step(mut self, step: f32) -> Self {
+        self.zoom_step = step;
+        self
+    }
+
+    /// Controls whether the window starts with high-contrast colors. The user
+    /// can toggle this with Ctrl+Shift+H. Disabled by default.
+    #[cfg(feature = "gui")]
+    pub fn high_contrast(mut self, enabled: bool) -> Self {
+        self.high_contrast = enabled;
+        self
+    }
+
+    /// Controls whether the window starts with a debug HUD showing the frame
+    /// rate, render time, scrollback size, and scroll position. The user can
+    /// toggle this with F12. Disabled by default.
+    #[cfg(feature = "gui")]
+    pub fn debug_hud(mut self, enabled: bool) -> Self {
+        self.debug_hud = enabled;
+        self
+    }
+
+    /// Hyphenates words at syllable boundaries in `language` when they don't
+    /// fit on a row. Only English (US) is embedded by default; other
+    /// languages can be embedded by enabling the `hyphenation` crate's
+    /// `embed_*` features. Only the first language set in a process is used.
+    #[cfg(feature = "hyphenation")]
+    pub fn hyphenation(mut self, language: Language) -> Self {
+        self.hyphenation = Some(language);
+        self
+    }
+
+    /// Underlines words in the input that aren't in `dictionary`. Suggested
+    /// replacements are shown by pressing Primary+Period. Words can be added
+    /// while running with [`Console::add_to_dictionary`].
+    #[cfg(feature = "spellcheck")]
+    pub fn spellcheck(mut self, dictionary: impl Dictionary) -> Self {
+        self.spellcheck = Some(spellcheck::Spellchecker::new(dictionary));
+        self
+    }
+
+    /// Sets how far the mouse wheel and touchpad scroll the output.
+    #[cfg(feature = "gui")]
+    pub fn scrolling(mut self, options: ScrollOptions) -> Self {
+        self.scrolling = options;
+        self
+    }
+
+    pub(crate) fn style(&self, style: Style) -> Style {
+        #[cfg(feature = "config-file")]
+        let style = self.file.as_ref().map_or(style, |file| file.theme(style));
+        match &self.style_transform {
+            Some(transform) => (transform.0)(style),
+            None => style,
+        }
+    }
+
+    // Returns the keys the config file remaps to shortcuts matching `keys`,
+    // such as `Ctrl+X Ctrl+S`.
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub(crate) fn remapped_to(&self, keys: keymap::Keys) -> Vec<String> {
+        #[cfg(feature = "config-file")]
+        if let Some(file) = &self.file {
+            let mut remapped = file
+                .settings()
+                .keys
+                .iter()
+                .filter(|(_, to)| keys.matches(**to))
+                .map(|(from, _)| {
+                    let from = from.iter().map(Shortcut::to_string).collect::<Vec<_>>();
+                    from.join(" ")
+                })
+                .collect::<Vec<_>>();
+            // The keys are stored in a map, so sort them to list them the
+            // same way each time.
+            remapped.sort();
+            return remapped;
+        }
+        let _ = keys;
+        Vec::new()
+    }
+
+    // Adds a pressed shortcut to `chord`, returning the shortcut it acts as,
+    // as remapped by the config file. Returns None while the keys pressed
+    // begin a longer binding, or if they don't finish it.
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    pub(crate) fn press_shortcut(&self, chord: &mut Chord, shortcut: Shortcut) -> Option<Shortcut> {
+        let now = Instant::now();
+        #[cfg(feature = "config-file")]
+        if let Some(file) = &self.file {
+            return chord.press(shortcut, &file.settings().keys, now);
         }
+        chord.press(shortcut, &HashMap::new(), now)
     }
 
-    #[cfg(all(feature = "gui", not(feature = "tui")))]
     pub fn run<T>(self, app: T) -> !
     where
         T: App,
     {
-        let state = Arc::new(State::from(self));
-        let (console, sender, receiver) = Console::spawn(app, state.clone());
-        gui::run(state, thread, sender, receiver)
+        self.run_multi([app])
     }
 
-    #[cfg(all(feature = "tui", not(feature = "gui")))]
-    pub fn run<T>(self, app: T) -> !
+    /// Runs each app in its own tab, with its own thread. The console shuts
+    /// down once every app's console has been dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `apps` is empty.
+    pub fn run_multi<T>(self, apps: impl IntoIterator<Item = T>) -> !
+    where
+        T: App,
+    {
+        let apps = apps.into_iter().collect::<Vec<_>>();
+        assert!(!apps.is_empty(), "at least one app is required");
+        let plain = self.plain_when_piped && plain::is_piped();
+        let shared = Arc::new(Shared::from(self));
+        #[cfg(feature = "hot-reload")]
+        if shared.config.file.is_some() {
+            config_file::watch(Arc::downgrade(&shared));
+        }
+        if plain {
+            // Install the output before the apps can push any lines.
+            plain::install(&shared);
+            plain::run(Console::spawn_apps(apps, shared))
+        }
+
+        let console = Console::spawn_apps(apps, shared);
+        #[cfg(feature = "tui")]
+        if tui::is_tty() {
+            tui::run(console)
+        }
+
+        #[cfg(all(unix, feature = "gui", feature = "capture-output"))]
+        if console.shared.config.capture_output {
+            if let Err(err) = capture::install(&console.tabs[0]) {
+                // Stderr may already be redirected, so the error is shown in
+                // the console itself.
+                console.tabs[0].report_error(format_args!("error capturing output: {err}"));
+            }
+        }
+        #[cfg(feature = "gui")]
+        gui::run(console);
+        // Without a GUI, plain text is the only remaining option.
+        #[cfg(not(feature = "gui"))]
+        {
+            plain::install(&console.shared);
+            plain::run(console)
+        }
+    }
+
+    /// Runs `app` in the background as a session served on the Unix socket
+    /// at `path`, rather than displaying it. Frontends started with
+    /// [`Config::attach`] can attach and detach while the app keeps running,
+    /// and the scrollback is preserved between attachments.
+    #[cfg(all(unix, feature = "session"))]
+    pub fn serve_session<T>(self, path: impl AsRef<std::path::Path>, app: T) -> !
     where
         T: App,
     {
-        let (console, sender, receiver) = Console::new();
-        let thread = spawn_app(app, console);
-        tui::run(self, thread, sender, receiver)
+        let shared = Arc::new(Shared::from(self));
+        session::serve(path.as_ref(), Console::spawn_apps(vec![app], shared))
+    }
+
+    /// Attaches to a session served with [`Config::serve_session`],
+    /// displaying its scrollback and sending it each submitted input. The
+    /// session keeps running once this frontend exits.
+    #[cfg(all(unix, feature = "session"))]
+    pub fn attach(self, path: impl Into<std::path::PathBuf>) -> ! {
+        self.run(session::Attach { path: path.into() })
     }
 }
 
@@ -92,313 +705,2633 @@ fn spawn_app<T: App>(app: T, console: Console) -> JoinHandle<anyhow::Result<()>>
 }
 
 fn app_thread<T: App>(app: T, console: Console) -> anyhow::Result<()> {
-    app.run(console)
+    console.state.shared.plugins.init(&console);
+    let state = console.state.clone();
+    let result = app.run(console);
+    // The app's tab is closed once it returns, even if clones of its console
+    // are still held by other threads.
+    state.close();
+    state.redraw();
+    result
 }
 
-#[derive(Clone)]
 pub struct Console {
     state: Arc<State>,
-    app: flume::Receiver<ConsoleEvent>,
+    app: EventReceiver,
+    producer: Arc<Producer>,
+    // The number of handles to this tab. Other parts of the crate hold the
+    // state too, so its reference count can't be used instead.
+    handles: Arc<AtomicUsize>,
+}
+
+impl Clone for Console {
+    fn clone(&self) -> Self {
+        self.handles.fetch_add(1, Ordering::Relaxed);
+        Self {
+            state: self.state.clone(),
+            app: self.app.clone(),
+            producer: self.producer.clone(),
+            handles: self.handles.clone(),
+        }
+    }
 }
 
 impl Console {
-    fn spawn<T: App>(app: T, state: Arc<State>) -> ConsoleHandle {
-        let (app_sender, app_receiver) = flume::unbounded();
-        let thread = spawn_app(
-            app,
-            Self {
-                state: state.clone(),
-                app: app_receiver,
-            },
-        );
+    // Spawns each app with its own tab. A single app's tab is the primary
+    // tab, which shuts down the console when it is closed. Otherwise, the
+    // console shuts down once every tab has been closed.
+    fn spawn_apps<T: App>(apps: Vec<T>, shared: Arc<Shared>) -> ConsoleHandle {
+        let primary = apps.len() == 1;
+        let mut tabs = Vec::with_capacity(apps.len());
+        let mut threads = Vec::with_capacity(apps.len());
+        for (index, app) in apps.into_iter().enumerate() {
+            let title = if primary {
+                String::from("main")
+            } else {
+                format!("console {}", index + 1)
+            };
+            let (app_sender, app_receiver) = events::channel(&shared.config);
+            let state = Arc::new(State::new(shared.clone(), title, primary, app_sender));
+            if index == 0 && shared.config.capture_panics {
+                panics::install(&state);
+            }
+            #[cfg(feature = "hyphenation")]
+            if let (0, Some(language)) = (index, shared.config.hyphenation) {
+                if let Err(err) = hyphenate::load(language) {
+                    // Rows are still wrapped, just without hyphens.
+                    state.report_warning(format_args!(
+                        "error loading hyphenation dictionary: {err}"
+                    ));
+                }
+            }
+            threads.push(spawn_app(app, Self::new(state.clone(), app_receiver)));
+            tabs.push(state);
+        }
         ConsoleHandle {
+            shared,
+            tabs,
+            active: 0,
+            threads,
+            #[cfg(feature = "config-file")]
+            settings_generation: 0,
+        }
+    }
+
+    /// Opens a new tab in the same window with its own scrollback and input.
+    /// The tab is closed once every clone of the returned console is dropped.
+    pub fn open_tab(&self, title: impl Into<String>) -> Console {
+        let shared = self.state.shared.clone();
+        let (sender, receiver) = events::channel(&shared.config);
+        let state = Arc::new(State::new(shared.clone(), title.into(), false, sender));
+        shared.opened_tabs.lock().push(state.clone());
+        self.state.redraw();
+        Console::new(state, receiver)
+    }
+
+    fn new(state: Arc<State>, app: EventReceiver) -> Self {
+        Self {
             state,
-            thread: Some(thread),
-            events: Some(app_sender),
+            app,
+            producer: Arc::new(Producer::new(None, None)),
+            handles: Arc::new(AtomicUsize::new(1)),
+        }
+    }
+
+    /// Runs `worker` on a new thread named `name` with a clone of this
+    /// console. When the console shuts down, the worker is joined after the
+    /// apps have returned, waiting up to the timeout set with
+    /// [`Config::worker_shutdown_timeout`]. Workers should return once
+    /// [`Self::should_shutdown`] is true, such as by looping with
+    /// [`Self::sleep`].
+    pub fn spawn<F>(&self, name: impl Into<String>, worker: F)
+    where
+        F: FnOnce(Console) + Send + 'static,
+    {
+        let console = self.clone();
+        self.state
+            .shared
+            .workers
+            .spawn(name.into(), move || worker(console));
+    }
+
+    /// Closes this tab, even if other clones of this console are still
+    /// held. Closing the primary tab, or the last tab, shuts down the
+    /// console.
+    pub fn close(&self) {
+        self.state.close();
+        self.state.redraw();
+    }
+
+    /// Asks the console to shut down, as if its window had been closed.
+    ///
+    /// Shutting down happens in order: first, the token returned by
+    /// [`Self::shutdown_token`] is signaled, so background workers can stop.
+    /// Next, each tab's app receives [`ConsoleEvent::ShutdownRequested`],
+    /// after which waiting for another event fails. Once every app has
+    /// returned, the threads started with [`Self::spawn`] are joined, the
+    /// transcripts are closed, and the process exits.
+    pub fn request_shutdown(&self) {
+        self.state.shutdown();
+        self.state.redraw();
+    }
+
+    /// Returns a handle to this console whose pushed lines are prefixed with
+    /// `label`, such as "[worker-3] ...", which is useful when several threads
+    /// push to the same console. Clones of the returned handle share its
+    /// label and rate limit, which also apply to the writers, streams, and
+    /// processes it creates.
+    pub fn labeled(&self, label: impl Into<String>) -> Console {
+        let rate = self.producer.lines_per_second();
+        let mut console = self.clone();
+        console.producer = Arc::new(Producer::new(Some(Arc::from(label.into())), rate));
+        console
+    }
+
+    /// Returns a handle to this console that pushes at most
+    /// `lines_per_second` lines each second, keeping this handle's label.
+    /// Lines beyond the limit are dropped, and the number dropped is
+    /// reported before the next line that is pushed. Clones of the returned
+    /// handle share its limit, while other handles are unaffected.
+    pub fn with_rate_limit(&self, lines_per_second: u32) -> Console {
+        let label = self.producer.label().cloned();
+        let mut console = self.clone();
+        console.producer = Arc::new(Producer::new(label, Some(lines_per_second)));
+        console
+    }
+
+    /// Returns the label set with [`Self::labeled`].
+    pub fn label(&self) -> Option<&str> {
+        self.producer.label().map(|label| &**label)
+    }
+
+    // Pushes `line` on behalf of this handle's producer.
+    fn push(&self, line: Line) {
+        for line in self.attribute(line) {
+            self.state.push(line);
+        }
+    }
+
+    fn attribute(&self, line: Line) -> Vec<Line> {
+        self.state.attribute(Some(&self.producer), line)
+    }
+
+    /// Indents the lines pushed by this handle and its clones, including
+    /// their writers, streams, and processes, until the returned scope is
+    /// dropped. Other handles, such as those returned by [`Self::labeled`],
+    /// keep their own indentation. Scopes nest, each indenting lines further,
+    /// and indented lines are wrapped within the remaining width.
+    pub fn indent(&self) -> IndentScope {
+        self.producer
+            .indent
+            .fetch_add(indent::INDENT_COLUMNS, Ordering::Relaxed);
+        IndentScope {
+            producer: self.producer.clone(),
         }
     }
 
+    pub fn set_title(&self, title: impl Into<String>) {
+        *self.state.title.lock() = title.into();
+        self.state.redraw();
+    }
+
     pub fn push_line(&self, line: impl Into<String>) {
-        self.state.push(line.into());
+        self.push_line_with_level(Level::Info, line);
+    }
+
+    pub fn push_line_with_level(&self, level: Level, line: impl Into<String>) {
+        self.push(Line::from(line.into()).with_level(level));
         self.state.redraw();
     }
 
-    pub fn set_suggestion(&self, suggestion: impl Into<String>) {
-        self.state.set_suggestion(suggestion.into());
+    /// Pushes `error` in red, followed by the errors that caused it, each
+    /// indented beneath the last, and its backtrace if one was captured.
+    pub fn push_error(&self, error: &anyhow::Error) {
+        let line = Line::from(describe_error(error))
+            .with_level(Level::Error)
+            .with_color(Some(ERROR_COLOR));
+        self.push(line);
         self.state.redraw();
     }
 
-    pub fn clear_secure(&self) {
-        self.state.clear_secure();
+    /// Pushes a horizontal line spanning the console's width, with `title`
+    /// centered in it if given, such as `── build #42 ──`. The rule is
+    /// redrawn when the console is resized.
+    pub fn push_rule(&self, title: Option<&str>) {
+        let title = title.unwrap_or_default().to_string();
+        self.push(Drawing::Rule(title).into_line());
         self.state.redraw();
     }
 
-    pub fn set_secure(&self) {
-        self.state.set_secure();
+    /// Pushes `lines` surrounded by a border drawn with `style`, spanning
+    /// the console's width. The lines are wrapped within the border, and
+    /// are wrapped again when the console is resized.
+    pub fn push_boxed<L>(&self, lines: impl IntoIterator<Item = L>, style: BoxStyle)
+    where
+        L: Into<String>,
+    {
+        let lines = lines.into_iter().map(Into::into).collect();
+        self.push(Drawing::Boxed(lines, style).into_line());
         self.state.redraw();
     }
 
-    pub fn input(&self) -> Input {
-        let input = self.state.input.lock();
-        input.clone()
+    /// Saves the rows this tab currently displays to a PNG at `path`, such
+    /// as for documentation or to compare themes in tests. The rows are
+    /// drawn with the bundled font in the console's colors, so this also
+    /// works before the console is displayed and without a window.
+    #[cfg(feature = "screenshot")]
+    pub fn screenshot(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        screenshot::save(&self.state, path.as_ref())
     }
 
-    pub fn clear_input(&self) {
-        self.state.clear_input();
+    /// Pushes `items` as a list the user can choose from by moving a
+    /// highlight with the arrow keys and pressing Enter, which sends
+    /// [`ConsoleEvent::ListSelected`] with the returned id. Only the most
+    /// recently pushed list can be chosen from; pushing another list leaves
+    /// the previous one as it is. The arrow keys and Enter only move through
+    /// the list while nothing is typed, and Escape dismisses it, sending
+    /// [`ConsoleEvent::ListCancelled`].
+    pub fn push_list<I>(&self, items: impl IntoIterator<Item = I>) -> u64
+    where
+        I: Into<String>,
+    {
+        let id = stream::next_id();
+        let list = ActiveList::new(id, items.into_iter().map(Into::into).collect());
+        let mut line = Line::from(String::new());
+        list.draw(&mut line);
+        if *self.state.shared.plain.lock() {
+            // Without a window, the list can't be chosen from.
+            self.push(line);
+        } else {
+            self.state.seal_list();
+            line.stream = Some(id);
+            self.state.push_quietly(line);
+            *self.state.list.lock() = Some(list);
+        }
         self.state.redraw();
+        id
     }
 
-    pub fn clear_scrollback(&self) {
-        self.state.clear_scrollback();
+    /// Pushes the tree beneath `root`, connecting each node to its parent
+    /// with `├─` and `└─`, such as a dependency tree or a file listing.
+    /// The children of collapsed nodes are left out.
+    pub fn push_tree(&self, root: TreeNode) {
+        self.push(Line::from(root.draw()));
         self.state.redraw();
     }
 
-    pub fn reset_scroll(&self) {
-        self.state.scroll_to_current();
+    /// Pushes `line` with `background` filled behind it across the console's
+    /// whole width, such as to make fatal errors stand out.
+    pub fn push_line_with_background(&self, background: Color, line: impl Into<String>) {
+        let mut line = Line::from(line.into());
+        line.background = Some(background);
+        self.push(line);
         self.state.redraw();
     }
 
-    pub fn next_event(&self) -> Result<ConsoleEvent, flume::RecvError> {
-        self.app.recv()
+    /// Pushes `line` with `icon`, which is shown when using
+    /// [`Gutter::Icons`].
+    pub fn push_line_with_icon(&self, icon: char, line: impl Into<String>) {
+        let mut line = Line::from(line.into());
+        line.icon = Some(icon);
+        self.push(line);
+        self.state.redraw();
     }
 
-    pub fn should_shutdown(&self) -> bool {
-        self.state.should_shutdown()
+    /// Pushes `json` pretty-printed with syntax colors, such as an API
+    /// response. The whole document is pushed as a single line.
+    pub fn push_json(&self, json: &str) -> Result<(), InvalidJson> {
+        let line = json::pretty_print(json)?;
+        self.push(line);
+        self.state.redraw();
+        Ok(())
     }
 
-    pub fn read_input(&self) -> Option<String> {
-        loop {
-            let ConsoleEvent::Input = self.next_event().ok()? else { continue };
-            let input = self.input();
-            self.clear_input();
-            break Some(input.into());
+    /// Pushes `json` as a tree like [`Console::push_tree`], with a node for
+    /// each value. Objects and arrays nested `depth` or more levels deep are
+    /// collapsed, showing how many values they hide, which keeps large
+    /// responses readable.
+    pub fn push_json_tree(&self, json: &str, depth: usize) -> Result<(), InvalidJson> {
+        self.push_tree(json::to_tree(json, depth)?);
+        Ok(())
+    }
+
+    /// Pushes `line` placed according to `alignment`, such as right-aligning
+    /// the local user's messages in a chat. Lines are re-aligned when the
+    /// console is resized.
+    pub fn push_aligned(&self, alignment: Alignment, line: impl Into<String>) {
+        let mut line = Line::from(line.into());
+        line.alignment = Some(alignment);
+        self.push(line);
+        self.state.redraw();
+    }
+
+    /// Pushes a message from `sender`, displayed in a chat layout: the
+    /// sender's name is shown above the text, which wraps within 70% of the
+    /// console's width. Local messages are aligned to the right and remote
+    /// messages to the left.
+    pub fn push_message(&self, sender: Sender, text: &str) {
+        self.push(chat::message(sender, text));
+        self.state.redraw();
+    }
+
+    /// Controls whether lines wider than the view are wrapped. When disabled,
+    /// lines are only broken at newlines, and the view can be scrolled
+    /// horizontally instead. Enabled by default.
+    pub fn set_wrapping(&self, wrap: bool) {
+        let mut scrollback = self.state.scrollback.lock();
+        scrollback.no_wrap = !wrap;
+        #[cfg(feature = "config-file")]
+        {
+            scrollback.app_no_wrap = !wrap;
+        }
+        scrollback.view.horizontal_scroll = 0;
+        drop(scrollback);
+        self.state.redraw();
+    }
+
+    /// Splits the output into two views of the same lines. The top view stays
+    /// where the output was scrolled to, while the bottom view follows new
+    /// output, so that older output can be reviewed as new output arrives.
+    pub fn set_split(&self, split: bool) {
+        let mut scrollback = self.state.scrollback.lock();
+        if split {
+            scrollback.split();
+        } else {
+            scrollback.unsplit();
+        }
+        drop(scrollback);
+        self.state.redraw();
+    }
+
+    /// Sets the information shown to the left of each line in this tab.
+    pub fn set_gutter(&self, gutter: Gutter) {
+        self.state.scrollback.lock().gutter = gutter;
+        self.state.redraw();
+    }
+
+    /// Shades every other line in this tab with `background`, making rows
+    /// easier to follow across wide output. Lines pushed with
+    /// [`Self::push_line_with_background`] keep their own background.
+    pub fn set_alternate_background(&self, background: Option<Color>) {
+        let mut scrollback = self.state.scrollback.lock();
+        scrollback.alternate_background = background;
+        #[cfg(feature = "config-file")]
+        {
+            scrollback.app_alternate_background = background;
+        }
+        drop(scrollback);
+        self.state.redraw();
+    }
+
+    /// Pushes `line` unless the limit set with [`Self::limit_output`] has
+    /// been reached.
+    pub fn try_push_line(&self, line: impl Into<String>) -> Result<(), Full> {
+        for line in self.attribute(Line::from(line.into())) {
+            self.state.try_push(line)?;
+        }
+        self.state.redraw();
+        Ok(())
+    }
+
+    /// Limits the number of pushed lines that have not been displayed yet to
+    /// `capacity`. Once the limit is reached, pushing a line follows
+    /// `overflow`. This keeps memory bounded when lines are pushed faster
+    /// than they can be displayed.
+    pub fn limit_output(&self, capacity: usize, overflow: Overflow) {
+        *self.state.limit.lock() = Some(Limit::new(capacity, overflow));
+    }
+
+    /// Keeps only the `resident` most recent lines in memory, spilling older
+    /// lines to `store` as output arrives. Spilled lines are paged back in
+    /// when the user scrolls up to them. Lines that are already spilled when
+    /// this is called again are discarded.
+    #[cfg(feature = "spill")]
+    pub fn spill_scrollback(&self, resident: usize, store: impl ScrollbackStore) {
+        self.state.scrollback.lock().spill = Some(Spill::new(store, resident));
+    }
+
+    /// Keeps only the `resident` most recent lines uncompressed, compressing
+    /// older lines in memory as output arrives. This is
+    /// [`Self::spill_scrollback`] with a [`Compressed`] store.
+    #[cfg(feature = "lz4")]
+    pub fn compress_scrollback(&self, resident: usize) {
+        self.spill_scrollback(resident, Compressed::default());
+    }
+
+    /// Removes the limit set with [`Self::limit_output`].
+    pub fn unlimit_output(&self) {
+        *self.state.limit.lock() = None;
+        self.state.displayed.notify_all();
+    }
+
+    /// Begins a line whose text is written incrementally using the returned
+    /// stream.
+    pub fn begin_stream(&self) -> Stream {
+        Stream::begin(&self.state, Some(&self.producer))
+    }
+
+    /// Replaces the text of the most recently pushed line, returning false if
+    /// no lines have been pushed. This can be used to display progress.
+    ///
+    /// When output is plain text, the updated line is printed again.
+    pub fn replace_last_line(&self, text: impl Into<String>) -> bool {
+        let text = text.into();
+        let replaced = self.state.update_last_line(|line| {
+            ***line = text;
+            line.spans.clear();
+        });
+        self.state.redraw();
+        replaced
+    }
+
+    /// Appends `text` to the most recently pushed line, returning false if no
+    /// lines have been pushed. This can be used to display output as it is
+    /// streamed.
+    ///
+    /// When output is plain text, the updated line is printed again.
+    pub fn append_to_last_line(&self, text: &str) -> bool {
+        let appended = self.state.update_last_line(|line| line.push_str(text));
+        self.state.redraw();
+        appended
+    }
+
+    /// Inserts `line` immediately before the most recent line marked `name`.
+    /// Returns false if nothing was inserted, because no line is marked
+    /// `name` or the line was dropped by the rate limit or a gag rule.
+    pub fn insert_before(&self, mark: &str, line: impl Into<String>) -> bool {
+        if !self.state.scrollback.lock().has_mark(mark) {
+            return false;
+        }
+        let mut inserted = false;
+        // A notice of lines dropped by the rate limit is inserted too.
+        for line in self.attribute(Line::from(line.into())) {
+            let Some(line) = self.state.filter_incoming(line) else {
+                continue;
+            };
+            // The sinks are only notified once the line is in the scrollback.
+            inserted |= self.state.store_and_notify(|scrollback| {
+                scrollback
+                    .insert_before_mark(mark, line.clone())
+                    .then_some(line)
+            });
+        }
+        self.state.redraw();
+        inserted
+    }
+
+    /// Inserts `line` so that the scrollback remains in chronological order,
+    /// as if it had been pushed at `timestamp`. This allows displaying
+    /// messages that arrive out of order.
+    pub fn insert_at(&self, timestamp: SystemTime, line: impl Into<String>) {
+        let mut line = Line::from(line.into());
+        line.timestamp = timestamp;
+        for line in self.attribute(line) {
+            self.state
+                .incoming(line, Scrollback::insert_chronologically);
+        }
+        self.state.redraw();
+    }
+
+    /// Writes every line in this tab to `path` as Markdown, in a code block
+    /// wrapped within 80 columns, for pasting into issues and chats.
+    pub fn export_markdown(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.export_markdown_with(path, MarkdownOptions::default())
+    }
+
+    /// Writes every line in this tab to `path` as Markdown formatted
+    /// according to `options`.
+    pub fn export_markdown_with(
+        &self,
+        path: impl AsRef<Path>,
+        options: MarkdownOptions,
+    ) -> io::Result<()> {
+        let markdown = self
+            .state
+            .with_all_lines(|lines| export::markdown(lines, options));
+        std::fs::write(path, markdown)
+    }
+
+    /// Writes every line in this tab to `path` as an HTML page, with colors as
+    /// inline styles. Rows wrapped in the console are soft breaks that the
+    /// browser may rewrap.
+    pub fn export_html(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let config = &self.state.shared.config;
+        let (channels, columns) = {
+            let scrollback = self.state.scrollback.lock();
+            (scrollback.channels.clone(), scrollback.wrap_columns())
+        };
+        let html = self.state.with_all_lines(|lines| {
+            let lines = lines.map(|line| {
+                let color = channels.color_of(&line).unwrap_or(Color::WHITE);
+                (line, color)
+            });
+            export::html(lines, columns, |color| {
+                config.style(Style::new(color)).foreground
+            })
+        });
+        std::fs::write(path, html)
+    }
+
+    /// Captures the scrollback, scroll position, input, and input history so
+    /// that they can be saved and restored when the app is next launched.
+    #[cfg(feature = "serde")]
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot::capture(&self.state)
+    }
+
+    /// Replaces the scrollback, scroll position, input, and input history
+    /// with those captured in `snapshot`.
+    #[cfg(feature = "serde")]
+    pub fn restore(&self, snapshot: Snapshot) {
+        snapshot.restore(&self.state);
+        self.state.redraw();
+    }
+
+    /// Places a mark named `name` on the most recently pushed line. The user
+    /// can jump between marks to navigate long output.
+    pub fn mark(&self, name: impl Into<String>) {
+        self.state.scrollback.lock().mark(name.into());
+        self.state.redraw();
+    }
+
+    /// Spawns a thread that reads from `reader` until it is exhausted,
+    /// pushing each line it contains. This can be used to display a serial
+    /// port or another byte stream.
+    pub fn attach_reader<R>(&self, mut reader: R) -> JoinHandle<io::Result<()>>
+    where
+        R: Read + Send + 'static,
+    {
+        // The writer doesn't keep the console alive, which is important
+        // because the reader may block indefinitely.
+        let mut writer = self.writer();
+        std::thread::Builder::new()
+            .name(String::from("reader"))
+            .spawn(move || io::copy(&mut reader, &mut writer).map(|_| ()))
+            .expect("error spawning reader thread")
+    }
+
+    /// Spawns `command`, streaming its stdout and stderr into the scrollback.
+    /// Stderr is displayed in a distinct color.
+    #[cfg(feature = "process")]
+    pub fn spawn_process(&self, command: std::process::Command) -> io::Result<Process> {
+        Process::spawn(&self.state, Some(self.producer.clone()), command)
+    }
+
+    /// Spawns `command` attached to a pseudo-terminal, displaying its output
+    /// including any colors.
+    #[cfg(feature = "pty")]
+    pub fn spawn_pty(&self, command: CommandBuilder) -> anyhow::Result<Pty> {
+        Pty::spawn(&self.state, Some(self.producer.clone()), command)
+    }
+
+    /// Colors the parts of lines pushed from now on that match `pattern`
+    /// with `style`. If `bell` is true, the window flashes when a line
+    /// matches. Rules added later take precedence over earlier ones.
+    pub fn add_highlight(&self, pattern: Regex, style: Style, bell: bool) -> HighlightId {
+        self.state
+            .highlights
+            .lock()
+            .add(pattern, Some(style), bell, false)
+    }
+
+    /// Sends [`ConsoleEvent::Triggered`] for each line pushed from now on
+    /// that matches `pattern`, allowing the app to react to specific output.
+    /// If `style` is set, the matches are also colored as with
+    /// [`Self::add_highlight`]. The rule is removed with
+    /// [`Self::remove_highlight`].
+    pub fn add_trigger(&self, pattern: Regex, style: Option<Style>) -> HighlightId {
+        self.state
+            .highlights
+            .lock()
+            .add(pattern, style, false, true)
+    }
+
+    /// Removes a rule added with [`Self::add_highlight`], returning false if
+    /// it was already removed. Lines that were already colored keep their
+    /// colors.
+    pub fn remove_highlight(&self, id: HighlightId) -> bool {
+        self.state.highlights.lock().remove(id)
+    }
+
+    /// Hides lines pushed from now on that match `pattern`, such as spammy
+    /// protocol chatter. If `keep` is true, gagged lines are still written
+    /// to transcripts and exports; otherwise they're discarded. The number
+    /// of gagged lines is shown in the status bar. The rule is removed with
+    /// [`Self::remove_highlight`].
+    pub fn add_gag(&self, pattern: Regex, keep: bool) -> HighlightId {
+        let gag = if keep { Gag::Hide } else { Gag::Discard };
+        self.state.highlights.lock().add_gag(pattern, gag)
+    }
+
+    /// Runs the [rhai](https://rhai.rs) script at `path`, allowing users to
+    /// extend the app without recompiling it. Scripts can call `push_line`,
+    /// `input`, `clear_input`, `add_highlight(pattern, red, green, blue)`,
+    /// `add_gag(pattern, keep)`, `remove_highlight(rule)`, and
+    /// `bind_key(shortcut, function)`, which calls `function` when a
+    /// shortcut such as `"Ctrl+R"` is pressed.
+    ///
+    /// Users can also run scripts by submitting `/script load <path>`, which
+    /// isn't passed to the app.
+    #[cfg(feature = "scripting")]
+    pub fn run_script(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        script::load(&self.state, path.as_ref())?;
+        Ok(())
+    }
+
+    /// Searches every line in this tab for `query` on a background thread,
+    /// including lines spilled out of memory. Matches are found newest first.
+    /// Returns an error if `query` is too long to compile.
+    pub fn search(&self, query: &str) -> Result<Search, RegexError> {
+        self.search_with(query, SearchOptions::default())
+    }
+
+    /// Searches like [`Self::search`], matching `query` according to
+    /// `options`. Returns an error if `query` is an invalid regular
+    /// expression.
+    pub fn search_with(&self, query: &str, options: SearchOptions) -> Result<Search, RegexError> {
+        Ok(Search::start(&self.state, options.pattern(query)?))
+    }
+
+    /// Returns a writer that pushes each line written to it.
+    pub fn writer(&self) -> Writer {
+        Writer::new(&self.state, Some(self.producer.clone()))
+    }
+
+    pub fn channel(&self, name: impl Into<String>) -> Channel {
+        Channel::new(self.clone(), Arc::from(name.into()))
+    }
+
+    pub fn mute_channel(&self, name: &str) {
+        self.state
+            .update_channel(name, |channel| channel.muted = true);
+        self.state.redraw();
+    }
+
+    pub fn unmute_channel(&self, name: &str) {
+        self.state
+            .update_channel(name, |channel| channel.muted = false);
+        self.state.redraw();
+    }
+
+    pub fn set_channel_color(&self, name: &str, color: Option<Color>) {
+        self.state
+            .update_channel(name, |channel| channel.color = color);
+        self.state.redraw();
+    }
+
+    pub fn set_suggestion(&self, suggestion: impl Into<String>) {
+        self.state.set_suggestion(suggestion.into());
+        self.state.redraw();
+    }
+
+    /// Computes the suggestion for the input with `suggest` each time the
+    /// input changes, on a thread dedicated to it. While a suggestion is
+    /// being computed, only the newest input is queued, and suggestions for
+    /// input that has since changed are discarded. Masked input is never
+    /// passed to `suggest`.
+    pub fn set_suggester<F>(&self, suggest: F)
+    where
+        F: FnMut(&str) -> Option<String> + Send + 'static,
+    {
+        let suggester = suggest::spawn(Arc::downgrade(&self.state), suggest);
+        *self.state.suggester.lock() = Some(suggester);
+    }
+
+    /// Stops computing suggestions with the function set with
+    /// [`Self::set_suggester`].
+    pub fn clear_suggester(&self) {
+        *self.state.suggester.lock() = None;
+    }
+
+    /// Returns the input's current generation, for apps that compute
+    /// suggestions themselves, such as in an async task. Pass it to
+    /// [`Self::set_suggestion_for`] along with the suggestion computed for
+    /// the input read after calling this.
+    pub fn input_generation(&self) -> InputGeneration {
+        self.state.input_generation()
+    }
+
+    /// Sets the suggestion unless the input has changed since `generation`
+    /// was returned by [`Self::input_generation`], returning whether it was
+    /// set. This keeps stale suggestions from flickering in.
+    pub fn set_suggestion_for(
+        &self,
+        generation: InputGeneration,
+        suggestion: impl Into<String>,
+    ) -> bool {
+        let set = self.state.set_suggestion_for(generation, suggestion.into());
+        if set {
+            self.state.redraw();
+        }
+        set
+    }
+
+    /// Accepts `word` as correctly spelled, such as a name or a term specific
+    /// to the app. Does nothing unless [`Config::spellcheck`] was set.
+    #[cfg(feature = "spellcheck")]
+    pub fn add_to_dictionary(&self, word: &str) {
+        if let Some(checker) = &self.state.shared.config.spellcheck {
+            checker.add(word);
+            self.state.redraw();
+        }
+    }
+
+    /// Replaces the word being typed after a completion trigger with
+    /// `completion`, keeping the trigger, and starts a new word. Returns
+    /// false if a triggered word isn't being typed.
+    pub fn complete_trigger(&self, completion: &str) -> bool {
+        let triggers = &self.state.shared.config.completion_triggers;
+        let mut input = self.state.input.lock();
+        let Some((_, partial)) = input.trigger_token(triggers) else {
+            return false;
+        };
+        let start = input.buffer.len() - partial.len();
+        input.buffer.truncate(start);
+        input.buffer.push_str(completion);
+        input.buffer.push(' ');
+        input.suggestion.clear();
+        drop(input);
+        self.state.send(ConsoleEvent::InputBufferChanged);
+        self.state.redraw();
+        true
+    }
+
+    /// Replaces the input with `template`, where each `${name}` is a
+    /// placeholder. Tab moves between placeholders, inserting the
+    /// placeholder's name if nothing was typed for it, before completing
+    /// suggestions as usual.
+    pub fn insert_template(&self, template: &str) {
+        let (prefix, template) = Template::parse(template);
+        let mut input = self.state.input.lock();
+        *input.buffer = prefix;
+        input.template = template;
+        drop(input);
+        self.state.redraw();
+    }
+
+    pub fn clear_secure(&self) {
+        self.state.clear_secure();
+        self.state.redraw();
+    }
+
+    pub fn set_secure(&self) {
+        self.state.set_secure();
+        self.state.redraw();
+    }
+
+    /// Replaces the options controlling how the input line behaves.
+    pub fn set_input_options(&self, options: InputOptions) {
+        let mut input = self.state.input.lock();
+        if input.options.masked && !options.masked {
+            // Overwrite the masked input before it becomes visible.
+            input.clear();
+        }
+        input.options = options;
+        drop(input);
+        self.state.redraw();
+    }
+
+    pub fn input_options(&self) -> InputOptions {
+        self.state.input.lock().options
+    }
+
+    /// Saves the unsent input and its options under `key`, such as the name
+    /// of a channel being switched away from, and clears the input. Masked
+    /// input is cleared without being saved.
+    pub fn stash_input(&self, key: impl Into<String>) {
+        let draft = self.state.input.lock().take_draft();
+        self.state.drafts.lock().insert(key.into(), draft);
+        self.state.send(ConsoleEvent::InputBufferChanged);
+        self.state.redraw();
+    }
+
+    /// Replaces the input with the draft saved under `key` by `stash_input`.
+    /// Returns false, leaving the input unchanged, if nothing was saved.
+    pub fn restore_input(&self, key: &str) -> bool {
+        let Some(draft) = self.state.drafts.lock().remove(key) else {
+            return false;
+        };
+        self.state.input.lock().restore_draft(draft);
+        self.state.send(ConsoleEvent::InputBufferChanged);
+        self.state.redraw();
+        true
+    }
+
+    pub fn input(&self) -> Input {
+        let input = self.state.input.lock();
+        input.clone()
+    }
+
+    /// Sets dimmed text displayed while the input is empty, such as
+    /// "Type a message, / for commands…".
+    pub fn set_placeholder(&self, placeholder: impl Into<String>) {
+        self.state.input.lock().placeholder = Some(placeholder.into());
+        self.state.redraw();
+    }
+
+    pub fn clear_placeholder(&self) {
+        self.state.input.lock().placeholder = None;
+        self.state.redraw();
+    }
+
+    /// Sets short text displayed at the right edge of the input, such as the
+    /// number of characters remaining or a caps lock warning. Apps can update
+    /// it when receiving [`ConsoleEvent::InputBufferChanged`].
+    pub fn set_input_decoration(&self, decoration: impl Into<String>) {
+        self.state.input.lock().decoration = Some(decoration.into());
+        self.state.redraw();
+    }
+
+    pub fn clear_input_decoration(&self) {
+        self.state.input.lock().decoration = None;
+        self.state.redraw();
+    }
+
+    pub fn set_input_visible(&self, visible: bool) {
+        self.state.input.lock().hidden = !visible;
+        self.state.redraw();
+    }
+
+    pub fn clear_input(&self) {
+        self.state.clear_input();
+        self.state.redraw();
+    }
+
+    /// Fills in `form` using the input line, replacing any form already
+    /// being filled in. The input is set aside until the form is finished.
+    /// [`ConsoleEvent::FormSubmitted`] is sent with the returned id once the
+    /// user presses Enter, and [`ConsoleEvent::FormCancelled`] if they press
+    /// Escape instead. A form that is replaced is cancelled the same way.
+    ///
+    /// When output is plain text, Enter moves to the next field, submitting
+    /// the form after the last one.
+    pub fn show_form(&self, form: Form) -> u64 {
+        let id = stream::next_id();
+        self.state.cancel_form();
+        let mut active = self.state.form.lock();
+        let mut input = self.state.input.lock();
+        *active = Some(ActiveForm::begin(id, form, &mut input));
+        drop(input);
+        drop(active);
+        self.state.send(ConsoleEvent::InputBufferChanged);
+        self.state.redraw();
+        id
+    }
+
+    /// Shows `overlay` above the scrollback, replacing any overlay already
+    /// shown. [`ConsoleEvent::OverlayDismissed`] is sent when the user
+    /// dismisses it.
+    ///
+    /// When using plain text output, the overlay is printed immediately and
+    /// is dismissed right away.
+    pub fn show_overlay(&self, overlay: Overlay) {
+        self.state.show_overlay(overlay);
+        self.state.redraw();
+    }
+
+    /// Hides the current overlay without sending
+    /// [`ConsoleEvent::OverlayDismissed`].
+    pub fn hide_overlay(&self) {
+        *self.state.overlay.lock() = None;
+        self.state.redraw();
+    }
+
+    /// Shows `popup` next to the text cursor, replacing any popup already
+    /// shown. [`ConsoleEvent::PopupDismissed`] is sent when the user
+    /// dismisses it. Popups aren't shown when using plain text output.
+    pub fn show_popup(&self, popup: Popup) {
+        *self.state.popup.lock() = Some(popup);
+        self.state.redraw();
+    }
+
+    /// Hides the current popup without sending
+    /// [`ConsoleEvent::PopupDismissed`].
+    pub fn hide_popup(&self) {
+        *self.state.popup.lock() = None;
+        self.state.redraw();
+    }
+
+    pub fn clear_scrollback(&self) {
+        self.state.clear_scrollback();
+        self.state.redraw();
+    }
+
+    pub fn selected_text(&self) -> Option<String> {
+        self.state.scrollback.lock().selected_text()
+    }
+
+    pub fn pause_output(&self) {
+        self.state.pause_output();
+        self.state.redraw();
+    }
+
+    pub fn resume_output(&self) {
+        self.state.resume_output();
+        self.state.redraw();
+    }
+
+    pub fn is_output_paused(&self) -> bool {
+        self.state.scrollback.lock().pending.is_some()
+    }
+
+    pub fn reset_scroll(&self) {
+        self.state.scroll_to_current();
+        self.state.redraw();
+    }
+
+    /// Requests that the console be redrawn.
+    pub fn request_redraw(&self) {
+        self.state.redraw();
+    }
+
+    /// Sends [`ConsoleEvent::Tick`] every `interval`, replacing any interval
+    /// set previously. This allows animating content without spawning a
+    /// thread.
+    pub fn set_tick(&self, interval: Duration) {
+        let (sender, stopped) = flume::bounded(1);
+        *self.state.ticker.lock() = Some(sender);
+        let state = Arc::downgrade(&self.state);
+        std::thread::Builder::new()
+            .name(String::from("tick"))
+            .spawn(move || {
+                let mut next_tick = Instant::now() + interval;
+                while let Err(flume::RecvTimeoutError::Timeout) = stopped.recv_deadline(next_tick) {
+                    let Some(state) = state.upgrade() else {
+                        break;
+                    };
+                    state.send(ConsoleEvent::Tick);
+                    // Skip any ticks that were missed rather than sending a burst.
+                    next_tick = (next_tick + interval).max(Instant::now());
+                }
+            })
+            .expect("error spawning tick thread");
+    }
+
+    /// Stops sending [`ConsoleEvent::Tick`].
+    pub fn stop_tick(&self) {
+        *self.state.ticker.lock() = None;
+    }
+
+    /// Sends [`ConsoleEvent::Timer`] with `id` once `delay` has elapsed,
+    /// replacing any timer already using `id`.
+    pub fn after(&self, delay: Duration, id: u64) {
+        self.start_timer(id, delay, false);
+    }
+
+    /// Sends [`ConsoleEvent::Timer`] with `id` every `interval`, replacing
+    /// any timer already using `id`.
+    pub fn every(&self, interval: Duration, id: u64) {
+        self.start_timer(id, interval, true);
+    }
+
+    /// Stops the timer using `id`, returning false if no timer is using it.
+    pub fn cancel_timer(&self, id: u64) -> bool {
+        self.state.timers.lock().remove(&id).is_some()
+    }
+
+    fn start_timer(&self, id: u64, interval: Duration, repeat: bool) {
+        let (sender, stopped) = flume::bounded(1);
+        // Replacing a timer disconnects it while the timers are locked.
+        self.state.timers.lock().insert(id, sender);
+        let state = Arc::downgrade(&self.state);
+        std::thread::Builder::new()
+            .name(String::from("timer"))
+            .spawn(move || {
+                let mut next = Instant::now() + interval;
+                while let Err(flume::RecvTimeoutError::Timeout) = stopped.recv_deadline(next) {
+                    let Some(state) = state.upgrade() else {
+                        break;
+                    };
+                    if !repeat {
+                        let mut timers = state.timers.lock();
+                        if !stopped.is_disconnected() {
+                            timers.remove(&id);
+                        }
+                    }
+                    state.send(ConsoleEvent::Timer(id));
+                    if !repeat {
+                        break;
+                    }
+                    next = (next + interval).max(Instant::now());
+                }
+            })
+            .expect("error spawning timer thread");
+    }
+
+    pub fn next_event(&self) -> Result<ConsoleEvent, RecvError> {
+        let event = self.app.recv()?;
+        self.state.shared.plugins.observe(self, &event);
+        Ok(event)
+    }
+
+    /// Returns the next event if one has already been sent, without
+    /// blocking.
+    pub fn try_next_event(&self) -> Result<ConsoleEvent, TryRecvError> {
+        let event = self.app.try_recv()?;
+        self.state.shared.plugins.observe(self, &event);
+        Ok(event)
+    }
+
+    /// Returns an iterator that waits for each event, ending once the console
+    /// has shut down.
+    pub fn events(&self) -> impl Iterator<Item = ConsoleEvent> + '_ {
+        std::iter::from_fn(|| self.next_event().ok())
+    }
+
+    /// Calls `handle` with each event until it breaks, returning the value
+    /// it broke with, or None if the console shut down first.
+    pub fn for_each_event<B>(
+        &self,
+        mut handle: impl FnMut(ConsoleEvent) -> ControlFlow<B>,
+    ) -> Option<B> {
+        self.events().find_map(|event| match handle(event) {
+            ControlFlow::Break(value) => Some(value),
+            ControlFlow::Continue(()) => None,
+        })
+    }
+
+    /// Adds receiving this console's next event to `selector`, allowing an
+    /// app to wait for console events and its own channels in one loop:
+    ///
+    /// ```no_run
+    /// # fn example(console: console_thingy::Console, jobs: console_thingy::SelectReceiver<String>) {
+    /// use console_thingy::Selector;
+    ///
+    /// enum Next {
+    ///     Event(console_thingy::ConsoleEvent),
+    ///     Job(String),
+    ///     Stop,
+    /// }
+    ///
+    /// loop {
+    ///     let selector = Selector::new().recv(&jobs, |job| job.map_or(Next::Stop, Next::Job));
+    ///     let next = console
+    ///         .select_event(selector, |event| event.map_or(Next::Stop, Next::Event))
+    ///         .wait();
+    ///     match next {
+    ///         Next::Event(event) => { /* ... */ }
+    ///         Next::Job(job) => console.push_line(job),
+    ///         Next::Stop => break,
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// Events are passed to plugins as with [`Self::next_event`].
+    pub fn select_event<'a, T>(
+        &'a self,
+        selector: Selector<'a, T>,
+        mut mapper: impl FnMut(Result<ConsoleEvent, RecvError>) -> T + 'a,
+    ) -> Selector<'a, T> {
+        self.app.select(selector, move |event| {
+            if let Ok(event) = &event {
+                self.state.shared.plugins.observe(self, event);
+            }
+            mapper(event)
+        })
+    }
+
+    pub fn should_shutdown(&self) -> bool {
+        self.state.should_shutdown()
+    }
+
+    /// Returns a token that signals when the console is shutting down.
+    pub fn shutdown_token(&self) -> ShutdownToken {
+        self.state.shared.shutdown.clone()
+    }
+
+    /// Blocks for `duration`, returning early if the console starts shutting
+    /// down. Returns true if the whole duration elapsed, allowing background
+    /// workers to loop with `while console.sleep(interval)`.
+    pub fn sleep(&self, duration: Duration) -> bool {
+        !self.state.shared.shutdown.wait_timeout(duration)
+    }
+
+    pub fn read_input(&self) -> Option<String> {
+        loop {
+            let ConsoleEvent::Input(input) = self.next_event().ok()? else {
+                continue;
+            };
+            self.clear_input();
+            break Some(input);
+        }
+    }
+
+    pub fn read_secure(&self) -> Option<String> {
+        loop {
+            self.set_secure();
+            let ConsoleEvent::Input(input) = self.next_event().ok()? else {
+                continue;
+            };
+            self.clear_secure();
+            break Some(input);
+        }
+    }
+
+    /// Parses submitted lines starting with a slash with `P`, sending each
+    /// parsed command in [`ConsoleEvent::Command`] instead of
+    /// [`ConsoleEvent::Input`]. Help requested with `--help` or `help` and
+    /// parse errors are pushed to the scrollback as clap renders them.
+    /// Masked inputs aren't parsed.
+    #[cfg(feature = "clap")]
+    pub fn set_command_parser<P>(&self)
+    where
+        P: clap::Parser + Send + 'static,
+    {
+        *self.state.command_parser.lock() = Some(cli::parser::<P>());
+    }
+
+    /// Stops parsing submitted lines set with [`Self::set_command_parser`].
+    #[cfg(feature = "clap")]
+    pub fn clear_command_parser(&self) {
+        *self.state.command_parser.lock() = None;
+    }
+
+    /// Parses `input` as one of the slash commands in `C`, returning None if
+    /// it doesn't start with a slash. If it isn't a valid command, the
+    /// problem and the command's usage are pushed as an error and None is
+    /// returned. Unless `C` has its own `help` command, `/help` pushes the
+    /// list of commands.
+    pub fn parse_command<C: ConsoleCommand>(&self, input: &str) -> Option<C> {
+        let name = input.strip_prefix('/')?.split_whitespace().next();
+        let err = match C::parse(input) {
+            Ok(command) => return Some(command),
+            Err(err) => err,
+        };
+        let commands = C::commands();
+        if matches!(&err, CommandError::Unknown(unknown) if unknown == "help") {
+            let help = commands
+                .iter()
+                .map(|command| format!("{}  {}", command.usage, command.help))
+                .collect::<Vec<_>>();
+            self.push_line(help.join("\n"));
+        } else {
+            let mut message = err.to_string();
+            if let Some(command) = commands.iter().find(|command| Some(command.name) == name) {
+                message.push_str("\nusage: ");
+                message.push_str(command.usage);
+            }
+            self.push_line_with_level(Level::Error, message);
+        }
+        None
+    }
+}
+
+impl Drop for Console {
+    fn drop(&mut self) {
+        // If this is the last handle, close the tab.
+        if self.handles.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.close();
+        }
+    }
+}
+
+struct ConsoleHandle {
+    shared: Arc<Shared>,
+    tabs: Vec<Arc<State>>,
+    active: usize,
+    threads: Vec<JoinHandle<anyhow::Result<()>>>,
+    // The config file's generation last applied to the tabs.
+    #[cfg(feature = "config-file")]
+    settings_generation: u64,
+}
+
+impl ConsoleHandle {
+    pub fn state(&self) -> &Arc<State> {
+        &self.tabs[self.active]
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.shared.config
+    }
+
+    pub fn should_shutdown(&self) -> bool {
+        if self.shared.should_shutdown() {
+            true
+        } else {
+            self.threads.iter().all(JoinHandle::is_finished)
+        }
+    }
+
+    // Tears down the console in the order documented by
+    // Console::request_shutdown.
+    pub fn shutdown(&mut self) -> anyhow::Result<()> {
+        self.shared.shutdown();
+        // Tell each app, then disconnect it so that it stops waiting for
+        // events, allowing its thread to be joined.
+        for tab in &self.tabs {
+            tab.send(ConsoleEvent::ShutdownRequested);
+            tab.disconnect();
+        }
+        let mut result = Ok(());
+        for thread in self.threads.drain(..) {
+            let thread_result = thread.join().expect("console thread panicked");
+            if result.is_ok() {
+                result = thread_result;
+            }
+        }
+        for problem in self.shared.workers.join(self.shared.config.worker_timeout) {
+            self.state().report_warning(problem);
+        }
+        // The apps and workers may have pushed lines while shutting down, so
+        // the sinks are closed last.
+        self.shared.sinks.lock().clear();
+
+        result
+    }
+
+    pub fn send(&self, event: ConsoleEvent) {
+        self.state().send(event);
+    }
+
+    /// Adds newly opened tabs and removes closed ones. Returns true if the
+    /// set of tabs changed.
+    pub fn update_tabs(&mut self) -> bool {
+        let opened = std::mem::take(&mut *self.shared.opened_tabs.lock());
+        let mut changed = !opened.is_empty();
+        #[cfg(feature = "config-file")]
+        let opened_count = opened.len();
+        self.tabs.extend(opened);
+        #[cfg(feature = "config-file")]
+        if let Some(file) = &self.shared.config.file {
+            // Layout settings are applied to every tab when the file changes,
+            // and to each tab as it opens.
+            let generation = file.generation();
+            let tabs = if generation == self.settings_generation {
+                &self.tabs[self.tabs.len() - opened_count..]
+            } else {
+                &self.tabs[..]
+            };
+            let settings = file.settings();
+            for tab in tabs {
+                settings.apply_layout(&mut tab.scrollback.lock());
+            }
+            changed |= !tabs.is_empty();
+            self.settings_generation = generation;
+            drop(settings);
+            if let Some(err) = file.take_error() {
+                let warning = format!("{err:#}, keeping the previous settings");
+                self.state()
+                    .push_quietly(Line::from(warning).with_level(Level::Warning));
+                changed = true;
+            }
+        }
+
+        // Keep the last tabs displayed while the console shuts down.
+        if self.tabs.iter().all(|tab| tab.is_closed()) {
+            self.shared.shutdown();
+            return changed;
+        }
+
+        let active = self.state().clone();
+        let count = self.tabs.len();
+        self.tabs.retain(|tab| !tab.is_closed());
+        if self.tabs.len() != count {
+            changed = true;
+            self.active = self
+                .tabs
+                .iter()
+                .position(|tab| Arc::ptr_eq(tab, &active))
+                .unwrap_or_else(|| self.active.min(self.tabs.len() - 1));
+        }
+
+        changed
+    }
+
+    pub fn tab_titles(&self) -> Vec<String> {
+        self.tabs
+            .iter()
+            .map(|tab| tab.title.lock().clone())
+            .collect()
+    }
+
+    pub fn tab_count(&self) -> usize {
+        self.tabs.len()
+    }
+
+    pub fn active_tab(&self) -> usize {
+        self.active
+    }
+
+    pub fn next_tab(&mut self) {
+        self.active = (self.active + 1) % self.tabs.len();
+        self.state().redraw();
+    }
+
+    pub fn previous_tab(&mut self) {
+        self.active = self.active.checked_sub(1).unwrap_or(self.tabs.len() - 1);
+        self.state().redraw();
+    }
+
+    pub fn input(&self, ch: char) {
+        self.shared
+            .input_received
+            .lock()
+            .get_or_insert_with(Instant::now);
+        let mut input = self.state().input.lock();
+        // Overlays are modal, so input is ignored while one is shown.
+        if input.hidden || self.state().overlay.lock().is_some() {
+            return;
+        }
+        match ch {
+            '\u{8}' => {
+                input.buffer.pop();
+                input.suggestion.clear();
+                if input
+                    .template
+                    .as_ref()
+                    .is_some_and(|template| template.is_abandoned(&input.buffer))
+                {
+                    input.template = None;
+                }
+
+                self.send(ConsoleEvent::InputBufferChanged);
+                self.send_trigger_completion(&input);
+            }
+            '\r' | '\n' => {
+                drop(input);
+                self.state().submit_input();
+            }
+            '\t' => {}
+            _ => {
+                input.buffer.push(ch);
+                if input.suggestion.starts_with(ch) {
+                    input.suggestion.remove(0);
+                }
+                self.send(ConsoleEvent::InputBufferChanged);
+                self.send_trigger_completion(&input);
+            }
+        }
+        self.state().redraw();
+    }
+
+    // Sends TriggerCompletion if a triggered word is being typed.
+    fn send_trigger_completion(&self, input: &Input) {
+        let triggers = &self.shared.config.completion_triggers;
+        if let Some((trigger, partial)) = input.trigger_token(triggers) {
+            self.send(ConsoleEvent::TriggerCompletion {
+                trigger,
+                partial: partial.to_string(),
+            });
+        }
+    }
+
+    /// Replaces the input with an older or newer submitted input. Recalling
+    /// past the newest input clears the input. Returns false if there is
+    /// nothing to recall.
+    pub fn recall_history(&self, older: bool) -> bool {
+        let mut input = self.state().input.lock();
+        if input.hidden || input.options.masked || !input.options.history {
+            return false;
+        }
+        let recalled = match (input.recalled, older) {
+            (None, true) => Some(0),
+            (None, false) => return false,
+            (Some(index), true) if index + 1 < input.history.len() => Some(index + 1),
+            (Some(_), true) => return false,
+            (Some(0), false) => None,
+            (Some(index), false) => Some(index - 1),
+        };
+        let Some(entry) = recalled.map_or(Some(String::new()), |index| {
+            input.history.iter().rev().nth(index).cloned()
+        }) else {
+            return false;
+        };
+        input.recalled = recalled;
+        input.template = None;
+        *input.buffer = entry;
+        drop(input);
+        self.send(ConsoleEvent::InputBufferChanged);
+        self.state().redraw();
+        true
+    }
+
+    /// Dismisses the active tab's overlay, returning false if no overlay was
+    /// shown.
+    pub fn dismiss_overlay(&self) -> bool {
+        let dismissed = self.state().overlay.lock().take().is_some();
+        if dismissed {
+            self.send(ConsoleEvent::OverlayDismissed);
+            self.state().redraw();
+        }
+        dismissed
+    }
+
+    /// Starts copy mode in the active tab, placing a cursor in the
+    /// scrollback that is moved with vi-style motions. The cursor is placed
+    /// in the pinned view of a split if `pinned` is true. Returns false if
+    /// there is nothing to copy.
+    pub fn begin_copy_mode(&self, pinned: bool) -> bool {
+        let mut copy_mode = self.state().copy_mode.lock();
+        *copy_mode = CopyMode::begin(&mut self.state().scrollback.lock(), pinned);
+        self.state().redraw();
+        copy_mode.is_some()
+    }
+
+    pub fn is_copying(&self) -> bool {
+        self.state().copy_mode.lock().is_some()
+    }
+
+    /// Handles `key` in copy mode, keeping the cursor within the
+    /// `page_rows` rows shown. Returns the selected text once it is copied
+    /// with `y` or Enter, which ends copy mode.
+    pub fn copy_mode_key(&self, key: char, page_rows: usize) -> Option<String> {
+        let mut copy_mode = self.state().copy_mode.lock();
+        let action = copy_mode
+            .as_mut()?
+            .key(key, &mut self.state().scrollback.lock(), page_rows);
+        self.state().redraw();
+        match action {
+            CopyAction::Copied(text) => {
+                *copy_mode = None;
+                Some(text)
+            }
+            CopyAction::Exited => {
+                *copy_mode = None;
+                None
+            }
+            CopyAction::Moved | CopyAction::Ignored => None,
+        }
+    }
+
+    /// Ends copy mode without copying, returning false if it wasn't active.
+    pub fn exit_copy_mode(&self) -> bool {
+        let copying = self.is_copying();
+        self.copy_mode_key('q', 0);
+        copying
+    }
+
+    /// Returns true if the active tab has a form being filled in.
+    pub fn has_form(&self) -> bool {
+        self.state().form.lock().is_some()
+    }
+
+    /// Moves to the next field of the active tab's form, or the previous one
+    /// if `forward` is false.
+    pub fn focus_form_field(&self, forward: bool) {
+        if let Some(form) = &mut *self.state().form.lock() {
+            form.focus_next(forward, &mut self.state().input.lock());
+            self.send(ConsoleEvent::InputBufferChanged);
+            self.state().redraw();
+        }
+    }
+
+    /// Cancels the active tab's form, returning false if there is none.
+    pub fn cancel_form(&self) -> bool {
+        let cancelled = self.state().cancel_form();
+        if cancelled {
+            self.state().redraw();
+        }
+        cancelled
+    }
+
+    /// Returns true if the active tab has a list waiting for an item to be
+    /// chosen.
+    pub fn has_list(&self) -> bool {
+        self.state().list.lock().is_some()
+    }
+
+    /// Returns true if the active tab's list takes the arrow keys and Enter,
+    /// which it only does while nothing is typed.
+    pub fn is_choosing_from_list(&self) -> bool {
+        self.has_list() && self.state().input.lock().is_empty()
+    }
+
+    /// Moves the highlight of the active tab's list by `by` items.
+    pub fn move_list_selection(&self, by: isize) {
+        let mut list = self.state().list.lock();
+        let Some(list) = list.as_mut() else {
+            return;
+        };
+        if list.move_selection(by) {
+            self.state()
+                .scrollback
+                .lock()
+                .update_stream(list.id, |line| list.draw(line));
+            self.state().redraw();
+        }
+    }
+
+    /// Chooses the highlighted item of the active tab's list, returning false
+    /// if there is no list, it is empty, or something is typed.
+    pub fn select_list_item(&self) -> bool {
+        if !self.is_choosing_from_list() {
+            return false;
+        }
+        let Some(index) = self
+            .state()
+            .list
+            .lock()
+            .as_ref()
+            .and_then(ActiveList::selected)
+        else {
+            return false;
+        };
+        let Some(list) = self.state().seal_list() else {
+            return false;
+        };
+        self.send(ConsoleEvent::ListSelected { id: list.id, index });
+        self.state().redraw();
+        true
+    }
+
+    /// Dismisses the active tab's list without choosing an item, returning
+    /// false if there is none.
+    pub fn cancel_list(&self) -> bool {
+        let Some(list) = self.state().seal_list() else {
+            return false;
+        };
+        self.send(ConsoleEvent::ListCancelled { id: list.id });
+        self.state().redraw();
+        true
+    }
+
+    /// Dismisses the active tab's popup, returning false if no popup was
+    /// shown.
+    pub fn dismiss_popup(&self) -> bool {
+        let dismissed = self.state().popup.lock().take().is_some();
+        if dismissed {
+            self.send(ConsoleEvent::PopupDismissed);
+            self.state().redraw();
+        }
+        dismissed
+    }
+
+    /// Returns the byte ranges of the misspelled words in the input. Masked
+    /// input is never checked.
+    #[cfg(feature = "spellcheck")]
+    pub fn misspelled(&self) -> Vec<Range<usize>> {
+        let input = self.state().input.lock();
+        match &self.shared.config.spellcheck {
+            Some(checker) if !input.options.masked && !input.hidden => {
+                checker.misspelled(&input.buffer)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns replacements for the misspelled `word`.
+    #[cfg(feature = "spellcheck")]
+    pub fn spelling_suggestions(&self, word: &str) -> Vec<String> {
+        self.shared
+            .config
+            .spellcheck
+            .as_ref()
+            .map_or_else(Vec::new, |checker| checker.suggestions(word))
+    }
+
+    /// Replaces `range`, a byte range of the input, with `replacement`.
+    pub fn replace_input(&self, range: Range<usize>, replacement: &str) {
+        let mut input = self.state().input.lock();
+        if range.start > range.end
+            || !input.buffer.is_char_boundary(range.start)
+            || !input.buffer.is_char_boundary(range.end)
+        {
+            return;
+        }
+        input.buffer.replace_range(range, replacement);
+        input.suggestion.clear();
+        drop(input);
+        self.send(ConsoleEvent::InputBufferChanged);
+        self.state().redraw();
+    }
+
+    /// Moves to the next placeholder of a template inserted with
+    /// `Console::insert_template`, returning false if there is none.
+    pub fn next_placeholder(&self) -> bool {
+        let mut input = self.state().input.lock();
+        let input = &mut *input;
+        let Some(template) = &mut input.template else {
+            return false;
+        };
+        let Some(appended) = template.advance(&input.buffer) else {
+            return false;
+        };
+        input.buffer.push_str(&appended);
+        if template.is_finished() {
+            input.template = None;
+        }
+        self.state().redraw();
+        self.send(ConsoleEvent::InputBufferChanged);
+        true
+    }
+
+    pub fn complete_suggestion(&self) -> bool {
+        let mut input = self.state().input.lock();
+        let input = &mut *input;
+
+        if input.hidden || input.suggestion.is_empty() {
+            return false;
+        }
+
+        input.buffer.push_str(&input.suggestion);
+        input.suggestion.clear();
+        self.state().redraw();
+        self.send(ConsoleEvent::InputBufferChanged);
+        true
+    }
+
+    pub fn scroll(&self, lines: isize) {
+        self.state().scrollback.lock().view.scroll_by(lines);
+        self.state().redraw();
+    }
+
+    /// Scrolls the pinned view of a split by `lines`. Has no effect unless
+    /// split with `Console::set_split`.
+    pub fn scroll_pinned(&self, lines: isize) {
+        let mut scrollback = self.state().scrollback.lock();
+        if let Some(pinned) = &mut scrollback.pinned {
+            pinned.scroll_by(lines);
+        }
+        drop(scrollback);
+        self.state().redraw();
+    }
+
+    pub fn toggle_split(&self) {
+        let mut scrollback = self.state().scrollback.lock();
+        if scrollback.pinned.is_some() {
+            scrollback.unsplit();
+        } else {
+            scrollback.split();
+        }
+        drop(scrollback);
+        self.state().redraw();
+    }
+
+    /// Scrolls right by `columns`, or left if negative. Has no effect while
+    /// lines are wrapped.
+    pub fn scroll_horizontally(&self, columns: isize) {
+        let mut scrollback = self.state().scrollback.lock();
+        scrollback.view.scroll_horizontally(columns);
+        self.state().redraw();
+    }
+
+    /// Scrolls to show the nearest older or newer mark at the top of a view
+    /// of `page_rows` rows. Returns false if there is no mark to jump to.
+    pub fn jump_to_mark(&self, older: bool, page_rows: usize) -> bool {
+        let mut scrollback = self.state().scrollback.lock();
+        let Some(scroll) = scrollback.mark_scroll(older, page_rows) else {
+            return false;
+        };
+        scrollback.view.scroll_to(scroll);
+        self.state().redraw();
+        true
+    }
+
+    /// Marks the line at `line`, counting from the oldest line, or removes
+    /// its mark if it is already marked.
+    pub fn toggle_mark(&self, line: usize) {
+        let mut scrollback = self.state().scrollback.lock();
+        if let Some(line) = scrollback.line_mut(line) {
+            line.mark = match line.mark.take() {
+                Some(_) => None,
+                None => Some(String::new()),
+            };
+        }
+        self.state().redraw();
+    }
+
+    /// Notifies every tab that the lines pushed so far have been displayed.
+    pub fn lines_displayed(&self) {
+        for tab in &self.tabs {
+            tab.lines_displayed();
+        }
+    }
+
+    /// Reports metrics for a frame that began rendering at `started`, which
+    /// spent `wrapping` wrapping text.
+    pub fn frame_rendered(&self, started: Instant, wrapping: Duration) {
+        let Some(MetricsHook(metrics)) = &self.shared.config.metrics else {
+            return;
+        };
+        metrics.redraw(started.elapsed());
+        metrics.wrap(wrapping);
+        if let Some(received) = self.shared.input_received.lock().take() {
+            metrics.input_latency(received.elapsed());
+        }
+
+        let state = self.state();
+        let events = state.events.lock().as_ref().map_or(0, EventSender::len);
+        metrics.queue_depth(Queue::Events, events);
+        let paused = state.scrollback.lock().pending.as_ref().map_or(0, Vec::len);
+        metrics.queue_depth(Queue::Paused, paused);
+        if let Some(limit) = &*state.limit.lock() {
+            metrics.queue_depth(Queue::Undisplayed, limit.undisplayed);
+        }
+    }
+
+    pub fn scroll_to_current(&self) {
+        self.state().scroll_to_current();
+        self.state().redraw();
+    }
+
+    pub fn toggle_output_paused(&self) {
+        if self.state().scrollback.lock().pending.is_some() {
+            self.state().resume_output();
+        } else {
+            self.state().pause_output();
+        }
+        self.state().redraw();
+    }
+}
+
+#[non_exhaustive]
+pub enum ConsoleEvent {
+    InputBufferChanged,
+    /// The input was submitted. Contains the input as it was when submitted,
+    /// even if it has since changed.
+    Input(String),
+    /// A process spawned with `Console::spawn_process` has exited.
+    ProcessExited {
+        id: u32,
+        status: io::Result<std::process::ExitStatus>,
+    },
+    /// A process spawned with `Console::spawn_pty` has exited.
+    PtyExited {
+        id: Option<u32>,
+        exit_code: io::Result<u32>,
+    },
+    /// The overlay shown with `Console::show_overlay` was dismissed.
+    OverlayDismissed,
+    /// The popup shown with `Console::show_popup` was dismissed.
+    PopupDismissed,
+    /// The word being typed starts with a character registered with
+    /// [`Config::completion_triggers`], and has changed. `partial` is the
+    /// rest of the word, which is empty right after the trigger is typed.
+    TriggerCompletion {
+        trigger: char,
+        partial: String,
+    },
+    /// The interval set with `Console::set_tick` has elapsed.
+    Tick,
+    /// A timer started with `Console::after` or `Console::every` has
+    /// elapsed.
+    Timer(u64),
+    /// A line starting with a slash was submitted and parsed by the parser
+    /// set with `Console::set_command_parser`.
+    #[cfg(feature = "clap")]
+    Command(ParsedCommand),
+    /// A pushed line matched a rule added with `Console::add_trigger`.
+    /// `captures` holds the capture groups of the rule's first match in the
+    /// line, starting with the whole match. Groups that didn't participate
+    /// in the match are None.
+    Triggered {
+        rule_id: HighlightId,
+        line: String,
+        captures: Vec<Option<String>>,
+    },
+    /// An item of the list pushed with `Console::push_list` was chosen.
+    /// `id` is the id `push_list` returned, and `index` is the item's index.
+    ListSelected {
+        id: u64,
+        index: usize,
+    },
+    /// The list pushed with `Console::push_list` was dismissed by pressing
+    /// Escape, and can no longer be chosen from.
+    ListCancelled {
+        id: u64,
+    },
+    /// The form shown with `Console::show_form` was submitted. `id` is the
+    /// id `show_form` returned, and `values` holds each field's value by its
+    /// label.
+    FormSubmitted {
+        id: u64,
+        values: HashMap<String, String>,
+    },
+    /// The form shown with `Console::show_form` was cancelled by pressing
+    /// Escape, or by showing another form.
+    FormCancelled {
+        id: u64,
+    },
+    /// The console is shutting down, such as because its window was closed
+    /// or `Console::request_shutdown` was called. This is the last event
+    /// each app receives. See `Console::request_shutdown` for the order in
+    /// which the console is torn down.
+    ShutdownRequested,
+}
+
+// State shared by every tab in a window.
+struct Shared {
+    config: Config,
+    shutdown: ShutdownToken,
+    redrawer: Mutex<Option<Box<dyn Redrawer>>>,
+    sinks: Mutex<Vec<Box<dyn Sink>>>,
+    // Whether output is being printed as plain text.
+    plain: Mutex<bool>,
+    opened_tabs: Mutex<Vec<Arc<State>>>,
+    // When the oldest key press not yet rendered was received.
+    input_received: Mutex<Option<Instant>>,
+    plugins: Plugins,
+    workers: Workers,
+}
+
+impl From<Config> for Shared {
+    fn from(mut config: Config) -> Self {
+        let mut sinks = Vec::<Box<dyn Sink>>::new();
+        if let Some((path, rotation)) = config.plain_transcript.take() {
+            let mut transcript = transcript::PlainTranscript::new(path, rotation);
+            sinks.push(Box::new(report_once(move |line: &Line| {
+                transcript.push(line)
+            })));
+        }
+        #[cfg(feature = "encrypted-transcript")]
+        if let Some(options) = config.transcript.take() {
+            let mut transcript = encrypted_transcript::EncryptedTranscript::new(options);
+            sinks.push(Box::new(report_once(move |line: &Line| {
+                transcript.push(line)
+            })));
+        }
+        let plugins = std::mem::take(&mut config.plugins);
+        Self {
+            config,
+            shutdown: ShutdownToken::default(),
+            redrawer: Mutex::default(),
+            sinks: Mutex::new(sinks),
+            plain: Mutex::new(false),
+            opened_tabs: Mutex::default(),
+            input_received: Mutex::default(),
+            plugins,
+            workers: Workers::default(),
+        }
+    }
+}
+
+impl Shared {
+    pub fn should_shutdown(&self) -> bool {
+        self.shutdown.is_shutdown()
+    }
+
+    pub fn shutdown(&self) {
+        self.shutdown.shutdown();
+    }
+
+    pub fn set_redrawer<R>(&self, redrawer: R)
+    where
+        R: Redrawer,
+    {
+        let mut installed = self.redrawer.lock();
+        *installed = Some(Box::new(redrawer));
+    }
+
+    pub fn redraw(&self) {
+        let mut redrawer = self.redrawer.lock();
+        if let Some(redrawer) = &mut *redrawer {
+            redrawer.redraw();
+        }
+    }
+
+    pub fn add_sink<S>(&self, sink: S)
+    where
+        S: Sink,
+    {
+        self.sinks.lock().push(Box::new(sink));
+    }
+}
+
+struct State {
+    shared: Arc<Shared>,
+    primary: bool,
+    title: Mutex<String>,
+    closed: Mutex<bool>,
+    events: Mutex<Option<EventSender>>,
+    input: Mutex<Input>,
+    scrollback: Mutex<Scrollback>,
+    overlay: Mutex<Option<Overlay>>,
+    popup: Mutex<Option<Popup>>,
+    // Unsent inputs saved by Console::stash_input.
+    drafts: Mutex<HashMap<String, Draft>>,
+    // Dropping the sender stops the tick thread.
+    ticker: Mutex<Option<flume::Sender<()>>>,
+    // Timers started with Console::after and Console::every, by id. Dropping
+    // a sender stops its timer.
+    timers: Mutex<HashMap<u64, flume::Sender<()>>>,
+    limit: Mutex<Option<Limit>>,
+    // Notified when pushed lines have been displayed.
+    displayed: Condvar,
+    highlights: Arc<Mutex<Highlights>>,
+    // When a highlight rule last rang the bell.
+    bell: Mutex<Option<Instant>>,
+    #[cfg(feature = "clap")]
+    command_parser: Mutex<Option<cli::CommandParser>>,
+    #[cfg(feature = "scripting")]
+    scripts: Mutex<script::Scripts>,
+    // Incremented each time the input changes, so that suggestions computed
+    // for an older input can be discarded.
+    input_generation: AtomicU64,
+    // Requests a suggestion from the function set with
+    // Console::set_suggester.
+    suggester: Mutex<Option<flume::Sender<()>>>,
+    // The list pushed with Console::push_list, until an item is chosen.
+    list: Mutex<Option<ActiveList>>,
+    // The form shown with Console::show_form, until it is finished. Locked
+    // before the input.
+    form: Mutex<Option<ActiveForm>>,
+    // The scrollback cursor while copy mode is active. Locked before the
+    // scrollback.
+    copy_mode: Mutex<Option<CopyMode>>,
+}
+
+impl State {
+    fn new(shared: Arc<Shared>, title: String, primary: bool, events: EventSender) -> Self {
+        Self {
+            shared,
+            primary,
+            title: Mutex::new(title),
+            closed: Mutex::new(false),
+            events: Mutex::new(Some(events)),
+            input: Mutex::default(),
+            scrollback: Mutex::default(),
+            overlay: Mutex::default(),
+            popup: Mutex::default(),
+            drafts: Mutex::default(),
+            ticker: Mutex::default(),
+            timers: Mutex::default(),
+            limit: Mutex::default(),
+            displayed: Condvar::new(),
+            highlights: Arc::default(),
+            bell: Mutex::default(),
+            #[cfg(feature = "clap")]
+            command_parser: Mutex::default(),
+            #[cfg(feature = "scripting")]
+            scripts: Mutex::default(),
+            input_generation: AtomicU64::new(0),
+            list: Mutex::default(),
+            form: Mutex::default(),
+            copy_mode: Mutex::default(),
+            suggester: Mutex::default(),
+        }
+    }
+
+    pub fn should_shutdown(&self) -> bool {
+        self.shared.should_shutdown()
+    }
+
+    pub fn shutdown(&self) {
+        self.shared.shutdown();
+    }
+
+    pub fn close(&self) {
+        if self.primary {
+            self.shutdown();
+        } else {
+            *self.closed.lock() = true;
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        *self.closed.lock()
+    }
+
+    pub fn send(&self, event: ConsoleEvent) {
+        if matches!(event, ConsoleEvent::InputBufferChanged) {
+            self.input_generation.fetch_add(1, Ordering::AcqRel);
+            if let Some(suggester) = &*self.suggester.lock() {
+                let _ = suggester.send(());
+            }
+        }
+        if let Some(events) = &*self.events.lock() {
+            events.send(event);
+        }
+    }
+
+    pub fn input_generation(&self) -> InputGeneration {
+        InputGeneration(self.input_generation.load(Ordering::Acquire))
+    }
+
+    // Sets the suggestion unless the input has changed since `generation`.
+    pub fn set_suggestion_for(&self, generation: InputGeneration, suggestion: String) -> bool {
+        let mut input = self.input.lock();
+        if self.input_generation() != generation {
+            return false;
+        }
+        input.suggestion = suggestion;
+        true
+    }
+
+    // Drops the sender, allowing the app's event loop to exit.
+    pub fn disconnect(&self) {
+        *self.events.lock() = None;
+    }
+
+    pub fn set_redrawer<R>(&self, redrawer: R)
+    where
+        R: Redrawer,
+    {
+        self.shared.set_redrawer(redrawer);
+    }
+
+    pub fn redraw(&self) {
+        self.shared.redraw();
+    }
+
+    // Normalizes a line, then adds it to the scrollback with `store` and
+    // passes it to any sinks. Nothing is stored if a gag rule discarded the
+    // line.
+    fn incoming(&self, line: Line, store: impl FnOnce(&mut Scrollback, Line)) {
+        if let Some(line) = self.filter_incoming(line) {
+            self.store_and_notify(|scrollback| {
+                store(scrollback, line.clone());
+                Some(line)
+            });
+        }
+    }
+
+    // Attributes `line` to `producer`, if it was pushed by one. Returns the
+    // lines to push, which are none if the line was dropped by the
+    // producer's rate limit.
+    fn attribute(&self, producer: Option<&Producer>, line: Line) -> Vec<Line> {
+        match producer {
+            Some(producer) => producer.attribute(line, Instant::now()),
+            None => vec![line],
+        }
+    }
+
+    // Applies the incoming newline style and highlight rules to `line`,
+    // returning None if it was discarded by a gag rule.
+    fn filter_incoming(&self, mut line: Line) -> Option<Line> {
+        let mut triggered = Vec::new();
+        let gag = self.apply_rules(&mut line, &mut triggered);
+        if gag.is_some() {
+            self.scrollback.lock().gagged += 1;
         }
+        self.send_triggered(&line, triggered);
+        if gag == Some(Gag::Discard) {
+            return None;
+        }
+        Some(line)
     }
 
-    pub fn read_secure(&self) -> Option<String> {
-        loop {
-            self.set_secure();
-            let ConsoleEvent::Input = self.next_event().ok()? else { continue };
-            let input = self.input();
-            self.clear_secure();
-            break Some(input.into());
+    // Applies the incoming newline style and highlight rules to `line`,
+    // returning the gag of the rules it matched. The rules that were
+    // triggered are collected in `triggered`, to be sent once the scrollback
+    // is unlocked.
+    fn apply_rules(&self, line: &mut Line, triggered: &mut Vec<Triggered>) -> Option<Gag> {
+        if let Cow::Owned(normalized) = self.shared.config.incoming_newline.normalize(line) {
+            ***line = normalized;
+        }
+        if line.drawing.is_some() {
+            line.restyle = Some(Restyle(self.highlights.clone()));
         }
+        let outcome = self.highlights.lock().apply(line, |rule_id, captures| {
+            triggered.push((rule_id, captures));
+        });
+        if outcome.gag.is_none() && outcome.ring {
+            *self.bell.lock() = Some(Instant::now());
+        }
+        outcome.gag
     }
-}
 
-impl Drop for Console {
-    fn drop(&mut self) {
-        // If this is the last reference, mark the state as being shut down.
-        if Arc::strong_count(&self.state) == 2 {
-            self.state.shutdown();
-            self.state.redraw();
+    fn send_triggered(&self, line: &str, triggered: Vec<Triggered>) {
+        for (rule_id, captures) in triggered {
+            self.send(ConsoleEvent::Triggered {
+                rule_id,
+                line: line.to_string(),
+                captures,
+            });
         }
     }
-}
 
-struct ConsoleHandle {
-    state: Arc<State>,
-    thread: Option<JoinHandle<anyhow::Result<()>>>,
-    events: Option<flume::Sender<ConsoleEvent>>,
-}
+    // Changes the scrollback with `store`, then passes the line it returns to
+    // the sinks as a pushed line. Returns false if nothing was stored.
+    fn store_and_notify(&self, store: impl FnOnce(&mut Scrollback) -> Option<Line>) -> bool {
+        self.change_and_notify(|scrollback| store(scrollback).map(Change::Pushed))
+    }
 
-impl ConsoleHandle {
-    pub fn should_shutdown(&self) -> bool {
-        if self.state.should_shutdown() {
-            true
-        } else {
-            self.thread.as_ref().map_or(true, JoinHandle::is_finished)
+    // Changes the scrollback with `change`, then passes the change it returns
+    // to the sinks. The sinks stay locked while the scrollback changes, so
+    // that a session frontend attaching meanwhile receives each change either
+    // with the scrollback or from its sink, never both or neither. Returns
+    // false if nothing was changed.
+    fn change_and_notify(&self, change: impl FnOnce(&mut Scrollback) -> Option<Change>) -> bool {
+        let mut sinks = self.shared.sinks.lock();
+        let changed = change(&mut self.scrollback.lock());
+        let Some(change) = changed else {
+            return false;
+        };
+        let errors = sinks
+            .iter_mut()
+            .filter_map(|sink| sink.change(&change).err())
+            .collect::<Vec<_>>();
+        drop(sinks);
+        for err in errors {
+            self.report_error(format_args!("error writing transcript: {err}"));
         }
+        true
     }
 
-    pub fn shutdown(&mut self) -> anyhow::Result<()> {
-        // Disconnect the thread, so that we can join the handle.
-        self.state.shutdown();
-        self.events = None;
-        if let Some(thread) = self.thread.take() {
-            thread.join().expect("console thread panicked")?;
-        }
+    // Reports a failure of the console itself, such as writing a transcript,
+    // as an error line in this tab. The line isn't passed to the sinks, which
+    // may be what failed.
+    fn report_error(&self, error: impl std::fmt::Display) {
+        let line = Line::from(error.to_string())
+            .with_level(Level::Error)
+            .with_color(Some(ERROR_COLOR));
+        self.push_quietly(line);
+        self.redraw();
+    }
 
-        Ok(())
+    // Reports a problem the console recovered from, such as a dictionary
+    // that couldn't be loaded, as a warning line in this tab. Like errors,
+    // the line isn't passed to the sinks.
+    fn report_warning(&self, warning: impl std::fmt::Display) {
+        self.push_quietly(Line::from(warning.to_string()).with_level(Level::Warning));
+        self.redraw();
     }
 
-    pub fn send(&self, event: ConsoleEvent) {
-        if let Some(events) = &self.events {
-            let _ = events.send(event);
-        }
+    pub fn push(&self, line: Line) {
+        let _ = self.push_limited(line, true);
     }
 
-    pub fn input(&self, ch: char) {
-        let mut input = self.state.input.lock();
-        match ch {
-            '\u{8}' => {
-                input.buffer.pop();
-                if let InputMode::Suggesting(suggestion) = &mut input.mode {
-                    suggestion.clear();
-                }
+    pub fn try_push(&self, line: Line) -> Result<(), Full> {
+        self.push_limited(line, false)
+    }
 
-                self.send(ConsoleEvent::InputBufferChanged);
-            }
-            '\r' | '\n' => {
-                self.send(ConsoleEvent::Input);
+    // Pushes `line` if the output limit allows it. If `wait` is true, the
+    // limit's overflow behavior is followed rather than returning an error.
+    fn push_limited(&self, line: Line, wait: bool) -> Result<(), Full> {
+        let mut limit = self.limit.lock();
+        let notice = loop {
+            let Some(active) = limit.as_mut() else {
+                break None;
+            };
+            // Plain text is printed immediately, so it is never behind.
+            if !active.is_full() || *self.shared.plain.lock() {
+                break active.pushed();
             }
-            '\t' => {}
-            _ => {
-                input.buffer.push(ch);
-                if let InputMode::Suggesting(suggestion) = &mut input.mode {
-                    if suggestion.starts_with(ch) {
-                        suggestion.remove(0);
-                    }
+            match (wait, active.overflow) {
+                (true, Overflow::Block) if !self.should_shutdown() => {
+                    self.displayed
+                        .wait_for(&mut limit, Duration::from_millis(100));
                 }
-                self.send(ConsoleEvent::InputBufferChanged);
+                (true, Overflow::Block) => break active.pushed(),
+                (true, Overflow::Drop) => {
+                    active.dropped += 1;
+                    return Ok(());
+                }
+                (false, _) => return Err(Full),
             }
+        };
+        drop(limit);
+
+        if let Some(notice) = notice {
+            self.push_unlimited(Line::from(notice).with_level(Level::Warning));
         }
-        self.state.redraw();
+        self.push_unlimited(line);
+        Ok(())
     }
 
-    pub fn complete_suggestion(&self) -> bool {
-        let mut input = self.state.input.lock();
-        let input = &mut *input;
-
-        if let InputMode::Suggesting(suggestion) = &mut input.mode {
-            if suggestion.is_empty() {
-                false
-            } else {
-                input.buffer.push_str(suggestion);
-                suggestion.clear();
-                self.state.redraw();
-                self.send(ConsoleEvent::InputBufferChanged);
-                true
-            }
-        } else {
-            false
-        }
+    fn push_unlimited(&self, line: Line) {
+        self.incoming(line, Scrollback::append);
     }
 
-    pub fn scroll(&self, lines: isize) {
-        let mut scrollback = self.state.scrollback.lock();
-        if lines > 0 {
-            scrollback.scroll = scrollback
-                .scroll
-                .saturating_add(lines as usize)
-                .min(scrollback.maximum_scroll);
-        } else if lines == isize::MIN {
-            // Can't negate safely due to to being unrepresentable
-            scrollback.scroll = scrollback.scroll.saturating_sub(isize::MAX as usize + 1);
-        } else {
-            scrollback.scroll = scrollback.scroll.saturating_sub((-lines) as usize);
+    // Called once the lines pushed so far have been displayed.
+    pub fn lines_displayed(&self) {
+        if let Some(limit) = &mut *self.limit.lock() {
+            limit.undisplayed = 0;
         }
-        self.state.redraw();
+        self.displayed.notify_all();
     }
-}
-
-pub enum ConsoleEvent {
-    InputBufferChanged,
-    Input,
-}
 
-struct State {
-    config: Config,
-    shutdown: Mutex<bool>,
-    input: Mutex<Input>,
-    scrollback: Mutex<Scrollback>,
-    redrawer: Mutex<Option<Box<dyn Redrawer>>>,
-}
+    // Pushes a line without passing it to the sinks.
+    pub fn push_quietly(&self, line: Line) {
+        self.scrollback.lock().append(line);
+    }
 
-impl From<Config> for State {
-    fn from(config: Config) -> Self {
-        Self {
-            config,
-            shutdown: Mutex::new(false),
-            input: Mutex::default(),
-            scrollback: Mutex::default(),
-            redrawer: Mutex::default(),
+    // Submits the form being filled in, returning false if there is none.
+    fn submit_form(&self) -> bool {
+        let mut form = self.form.lock();
+        let Some(active) = form.as_mut() else {
+            return false;
+        };
+        let mut input = self.input.lock();
+        if *self.shared.plain.lock() && !active.is_last_field() {
+            active.focus_next(true, &mut input);
+            return true;
         }
+        let active = form.take().expect("checked above");
+        let id = active.id;
+        let values = active.finish(&mut input);
+        drop(input);
+        drop(form);
+        self.send(ConsoleEvent::FormSubmitted { id, values });
+        self.send(ConsoleEvent::InputBufferChanged);
+        true
     }
-}
 
-impl State {
-    pub fn should_shutdown(&self) -> bool {
-        *self.shutdown.lock()
+    // Cancels the form being filled in, restoring the input it set aside.
+    // Returns false if there is none.
+    fn cancel_form(&self) -> bool {
+        let Some(form) = self.form.lock().take() else {
+            return false;
+        };
+        let id = form.id;
+        form.cancel(&mut self.input.lock());
+        self.send(ConsoleEvent::FormCancelled { id });
+        self.send(ConsoleEvent::InputBufferChanged);
+        true
     }
 
-    pub fn shutdown(&self) {
-        *self.shutdown.lock() = true;
+    // Stops the active list from being chosen from, returning it.
+    fn seal_list(&self) -> Option<ActiveList> {
+        let list = self.list.lock().take()?;
+        self.change_and_notify(|scrollback| {
+            let line = scrollback
+                .update_stream(list.id, |line| line.stream = None)
+                .cloned()?;
+            Some(Change::Finished(list.id, line))
+        });
+        Some(list)
     }
 
-    pub fn set_redrawer<R>(&self, redrawer: R)
-    where
-        R: Redrawer,
-    {
-        let mut installed = self.redrawer.lock();
-        *installed = Some(Box::new(redrawer));
+    // Updates the most recently pushed line and applies the highlight rules
+    // to it again. The line has already been shown, so if a gag rule
+    // discards it, it is hidden and kept from the sinks instead. Returns false
+    // if no lines have been pushed.
+    pub fn update_last_line(&self, update: impl FnOnce(&mut Line)) -> bool {
+        let mut updated = None;
+        let mut triggered = Vec::new();
+        self.change_and_notify(|scrollback| {
+            let mut was_gagged = false;
+            let mut gag = None;
+            let line = scrollback
+                .update_last(|line| {
+                    was_gagged = line.gagged;
+                    update(line);
+                    gag = self.apply_rules(line, &mut triggered);
+                })
+                .cloned()?;
+            match (was_gagged, line.gagged) {
+                (false, true) => scrollback.gagged += 1,
+                (true, false) => scrollback.gagged = scrollback.gagged.saturating_sub(1),
+                _ => {}
+            }
+            updated = Some(line.to_string());
+            (gag != Some(Gag::Discard)).then_some(Change::UpdatedLast(line))
+        });
+        let Some(text) = updated else {
+            return false;
+        };
+        self.send_triggered(&text, triggered);
+        true
     }
 
-    pub fn redraw(&self) {
-        let mut redrawer = self.redrawer.lock();
-        if let Some(redrawer) = &mut *redrawer {
-            redrawer.redraw();
+    pub fn pause_output(&self) {
+        let mut scrollback = self.scrollback.lock();
+        if scrollback.pending.is_none() {
+            scrollback.pending = Some(Vec::new());
         }
     }
 
-    pub fn push(&self, line: String) {
+    pub fn resume_output(&self) {
         let mut scrollback = self.scrollback.lock();
-        let mut wrapped = Wrapped::from(line);
-        if scrollback.scroll != 0 {
-            // When the view port is scrolled, keep it at the same position
-            wrapped.rewrap(scrollback.columns);
-            let line_count = wrapped.lines().len();
-            scrollback.scroll += line_count;
+        if let Some(pending) = scrollback.pending.take() {
+            for line in pending {
+                scrollback.push(line);
+            }
         }
-        scrollback.events.push_front(wrapped);
+    }
+
+    pub fn update_channel(&self, name: &str, update: impl FnOnce(&mut ChannelSettings)) {
+        self.scrollback.lock().channels.update(name, update);
     }
 
     pub fn set_suggestion(&self, suggestion: String) {
         let mut input = self.input.lock();
-        input.mode = InputMode::Suggesting(suggestion);
+        input.suggestion = suggestion;
     }
 
     pub fn clear_secure(&self) {
         let mut input = self.input.lock();
-        // Input::clear will zero data if the input mode is secure.
+        // Input::clear will zero data if the input is masked.
         input.clear();
-        input.mode = InputMode::Text;
+        input.options.masked = false;
     }
 
     pub fn set_secure(&self) {
         let mut input = self.input.lock();
-        input.mode = InputMode::Secure;
+        input.options.masked = true;
+    }
+
+    pub fn show_overlay(&self, overlay: Overlay) {
+        if *self.shared.plain.lock() {
+            plain::print_overlay(&self.shared, overlay);
+            self.send(ConsoleEvent::OverlayDismissed);
+        } else {
+            *self.overlay.lock() = Some(overlay);
+        }
     }
 
     pub fn clear_input(&self) {
         let mut input = self.input.lock();
         input.buffer.clear();
-        if let InputMode::Suggesting(_) = &input.mode {
-            input.mode = InputMode::Text;
+        input.template = None;
+        input.suggestion.clear();
+        self.input_generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    // Sends the input to the app, echoing and clearing it if configured to.
+    pub fn submit_input(self: &Arc<Self>) {
+        if self.submit_form() {
+            return;
+        }
+        let config = &self.shared.config;
+        if config.unlock_scroll_on_submit {
+            self.scroll_to_current();
+        }
+
+        let mut input = self.input.lock();
+        input.remember();
+        let submitted = input.buffer.to_string();
+        let masked = input.options.masked;
+        let echoed = if masked {
+            "*".repeat(submitted.chars().count())
+        } else {
+            submitted.clone()
+        };
+        if config.auto_clear_on_submit {
+            // Input::clear will zero data if the input is masked.
+            input.clear();
+            input.suggestion.clear();
+        }
+        // Pushing may wait for the output limit, so the input can't be
+        // locked while echoing.
+        drop(input);
+
+        if config.echo_input {
+            self.push(Line::from(format!("{}{echoed}", config.echo_prefix)));
+        }
+        #[cfg(feature = "scripting")]
+        if !masked && script::run_command(self, &submitted) {
+            self.input.lock().clear();
+            return;
         }
+        #[cfg(feature = "clap")]
+        if let Some(command) = submitted.strip_prefix('/').filter(|_| !masked) {
+            let parsed = self
+                .command_parser
+                .lock()
+                .as_ref()
+                .map(|parse| parse(command));
+            if let Some(parsed) = parsed {
+                // Commands are consumed here rather than passed to the app,
+                // so the input is cleared whether or not they parse.
+                self.input.lock().clear();
+                match parsed {
+                    Ok(command) => self.send(ConsoleEvent::Command(command)),
+                    Err((level, output)) => self.push(Line::from(output).with_level(level)),
+                }
+                return;
+            }
+        }
+        self.send(ConsoleEvent::Input(submitted));
+    }
+
+    // Calls `f` with every line, oldest first, including lines spilled out of
+    // memory and lines pending while output is paused. Spilled lines are read
+    // one segment at a time after unlocking the scrollback. If a segment can't
+    // be read, the remaining spilled lines are left out and an error is
+    // reported.
+    fn with_all_lines<R>(&self, f: impl FnOnce(&mut dyn Iterator<Item = Line>) -> R) -> R {
+        let scrollback = self.scrollback.lock();
+        #[cfg(feature = "spill")]
+        let reader = scrollback.spill.as_ref().map(Spill::reader);
+        let resident = scrollback
+            .events
+            .iter()
+            .rev()
+            .chain(scrollback.pending.iter().flatten())
+            .cloned()
+            .collect::<Vec<_>>();
+        drop(scrollback);
+
+        #[cfg(feature = "spill")]
+        {
+            let mut error = None;
+            let result = {
+                let spilled = reader
+                    .iter()
+                    .flat_map(|reader| {
+                        (0..reader.segment_count()).map(move |index| reader.read(index))
+                    })
+                    .map_while(|segment| segment.map_err(|err| error = Some(err)).ok())
+                    .flatten();
+                f(&mut spilled.chain(resident))
+            };
+            if let Some(err) = error {
+                self.report_error(format_args!(
+                    "error reading spilled scrollback, spilled lines were left out: {err}"
+                ));
+            }
+            result
+        }
+        #[cfg(not(feature = "spill"))]
+        f(&mut resident.into_iter())
     }
 
     pub fn clear_scrollback(&self) {
         let mut scrollback = self.scrollback.lock();
-        scrollback.scroll = 0;
+        scrollback.view.reset_scroll();
         scrollback.events.clear();
+        scrollback.gagged = 0;
+        scrollback.clear_selection();
+        #[cfg(feature = "spill")]
+        if let Some(Err(err)) = scrollback.spill.as_mut().map(Spill::clear) {
+            drop(scrollback);
+            self.report_error(format_args!("error clearing spilled scrollback: {err}"));
+        }
     }
 
     pub fn scroll_to_current(&self) {
-        let mut scrollback = self.scrollback.lock();
-        scrollback.scroll = 0;
+        self.scrollback.lock().view.reset_scroll();
+    }
+}
+
+// A highlight rule that was triggered, with its captures.
+type Triggered = (HighlightId, Vec<Option<String>>);
+
+// The number of submitted inputs remembered for recall.
+const HISTORY_LIMIT: usize = 1_000;
+
+// The color of errors and panics.
+const ERROR_COLOR: Color = Color::rgb(255, 96, 96);
+
+// Whether selecting text sets the primary selection, which is pasted with
+// the middle mouse button. See build.rs.
+const PRIMARY_SELECTION: bool = cfg!(primary_selection);
+
+// Formats an error and its chain of causes for Console::push_error.
+fn describe_error(error: &anyhow::Error) -> String {
+    let mut description = error.to_string();
+    for (depth, cause) in error.chain().skip(1).enumerate() {
+        let indent = "  ".repeat(depth + 1);
+        let cause = cause.to_string().replace('\n', &format!("\n{indent}  "));
+        description.push_str(&format!("\n{indent}caused by: {cause}"));
+    }
+    let backtrace = error.backtrace();
+    if backtrace.status() == std::backtrace::BacktraceStatus::Captured {
+        description.push_str("\n\nbacktrace:\n");
+        description.push_str(backtrace.to_string().trim_end());
+    }
+    description
+}
+
+/// How the input line behaves. Each option can be combined with the others.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct InputOptions {
+    /// Displays each character as `*`, and overwrites the input in memory
+    /// when it is cleared. Masked inputs are never remembered in the history.
+    pub masked: bool,
+    /// Remembers submitted inputs, allowing them to be recalled with Up and
+    /// Down.
+    pub history: bool,
+}
+
+impl Default for InputOptions {
+    fn default() -> Self {
+        Self {
+            masked: false,
+            history: true,
+        }
+    }
+}
+
+impl InputOptions {
+    pub fn masked(mut self, masked: bool) -> Self {
+        self.masked = masked;
+        self
+    }
+
+    pub fn history(mut self, history: bool) -> Self {
+        self.history = history;
+        self
     }
 }
 
 #[derive(Default, Clone)]
 pub struct Input {
     buffer: Wrapped,
-    mode: InputMode,
+    options: InputOptions,
+    // Completes the input when accepted. Empty if there is no suggestion.
+    suggestion: String,
+    hidden: bool,
+    // Submitted inputs, oldest first.
+    history: Vec<String>,
+    // How many entries back from the newest the buffer was recalled from.
+    recalled: Option<usize>,
+    template: Option<Template>,
+    // Displayed while the input is empty.
+    placeholder: Option<String>,
+    // Displayed at the right edge of the input's first row.
+    decoration: Option<String>,
+    // The columns the input was last displayed within, or 0 if it hasn't
+    // been displayed.
+    columns: usize,
 }
 
 impl Input {
+    /// Returns the row and column that `offset`, a byte offset into the
+    /// input, is displayed at in the input line.
+    pub fn position_of(&mut self, offset: usize) -> (usize, usize) {
+        let columns = self.display_columns();
+        self.buffer.position_of(offset, columns)
+    }
+
+    /// Returns the byte offset into the input that is displayed at `row` and
+    /// `column` of the input line.
+    pub fn offset_at(&mut self, row: usize, column: usize) -> usize {
+        let columns = self.display_columns();
+        self.buffer.offset_at(row, column, columns)
+    }
+
+    /// Returns the row and column of the text cursor, which follows the last
+    /// character typed.
+    pub fn cursor_position(&mut self) -> (usize, usize) {
+        self.position_of(self.buffer.len())
+    }
+
+    fn display_columns(&self) -> usize {
+        // Until it has been displayed, the input is treated as one row.
+        if self.columns == 0 {
+            usize::MAX
+        } else {
+            self.columns
+        }
+    }
+
+    // Returns the trigger and the rest of the word being typed if it starts
+    // with one of `triggers`. Masked input is never completed.
+    fn trigger_token(&self, triggers: &[char]) -> Option<(char, &str)> {
+        if self.options.masked {
+            return None;
+        }
+        let mut word = self.buffer.rsplit(char::is_whitespace).next()?.chars();
+        let trigger = word.next().filter(|ch| triggers.contains(ch))?;
+        Some((trigger, word.as_str()))
+    }
+
+    // Returns the text displayed after the input: the rest of the template,
+    // the suggestion, or the placeholder if nothing has been typed.
+    fn ghost_text(&self) -> Option<Cow<'_, str>> {
+        if let Some(template) = &self.template {
+            Some(Cow::Owned(template.hint(&self.buffer)))
+        } else if !self.suggestion.is_empty() {
+            Some(Cow::Borrowed(&self.suggestion))
+        } else if self.buffer.is_empty() {
+            self.placeholder.as_deref().map(Cow::Borrowed)
+        } else {
+            None
+        }
+    }
+
+    fn remember(&mut self) {
+        self.recalled = None;
+        self.template = None;
+        if self.options.masked
+            || !self.options.history
+            || self.buffer.is_empty()
+            || self.history.last() == Some(&*self.buffer)
+        {
+            return;
+        }
+        if self.history.len() == HISTORY_LIMIT {
+            self.history.remove(0);
+        }
+        self.history.push(self.buffer.to_string());
+    }
+
+    pub fn options(&self) -> InputOptions {
+        self.options
+    }
+
+    // Clears the input and resets its options, returning what was cleared.
+    fn take_draft(&mut self) -> Draft {
+        let buffer = if self.options.masked {
+            self.clear();
+            String::new()
+        } else {
+            std::mem::take(&mut *self.buffer)
+        };
+        self.recalled = None;
+        Draft {
+            buffer,
+            options: std::mem::take(&mut self.options),
+            suggestion: std::mem::take(&mut self.suggestion),
+            template: self.template.take(),
+        }
+    }
+
+    fn restore_draft(&mut self, draft: Draft) {
+        self.clear();
+        *self.buffer = draft.buffer;
+        self.options = draft.options;
+        self.suggestion = draft.suggestion;
+        self.template = draft.template;
+        self.recalled = None;
+    }
+
     pub fn clear(&mut self) {
-        if matches!(self.mode, InputMode::Secure) {
+        if self.options.masked {
             let len = self.buffer.len();
             // Overwrite the input with null bytes
             self.buffer.clear();
@@ -408,6 +3341,14 @@ impl Input {
     }
 }
 
+// The parts of the input saved by Console::stash_input.
+struct Draft {
+    buffer: String,
+    options: InputOptions,
+    suggestion: String,
+    template: Option<Template>,
+}
+
 impl Deref for Input {
     type Target = String;
 
@@ -430,7 +3371,7 @@ impl From<Input> for String {
 
 impl Drop for Input {
     fn drop(&mut self) {
-        if matches!(self.mode, InputMode::Secure) {
+        if self.options.masked {
             self.clear();
         }
     }
@@ -449,10 +3390,487 @@ where
     }
 }
 
-#[derive(Default, Clone)]
-pub enum InputMode {
-    #[default]
-    Text,
-    Suggesting(String),
-    Secure,
+// Receives every line as it is pushed, regardless of whether it is displayed.
+// Errors are reported in the tab the line was pushed to.
+trait Sink: Send + 'static {
+    fn push(&mut self, line: &Line) -> io::Result<()>;
+
+    // Receives each change to the scrollback. By default, pushed, updated,
+    // and finished lines are pushed, and lines still being streamed are left
+    // out until they are finished.
+    fn change(&mut self, change: &Change) -> io::Result<()> {
+        match change {
+            Change::Pushed(line) | Change::UpdatedLast(line) | Change::Finished(_, line) => {
+                self.push(line)
+            }
+            Change::Streamed(..) | Change::Discarded(_) => Ok(()),
+        }
+    }
+}
+
+// A change to the scrollback passed to the sinks.
+enum Change {
+    // The line was added to the scrollback.
+    Pushed(Line),
+    // The line replaced the most recently pushed line.
+    UpdatedLast(Line),
+    // The line being streamed with the id was started or written to.
+    Streamed(u64, Line),
+    // The line being streamed with the id, or a list, was sealed into the
+    // scrollback as the line.
+    Finished(u64, Line),
+    // The line being streamed with the id was discarded by a gag rule.
+    Discarded(u64),
+}
+
+impl<T> Sink for T
+where
+    T: FnMut(&Line) -> io::Result<()> + Send + 'static,
+{
+    fn push(&mut self, line: &Line) -> io::Result<()> {
+        self(line)
+    }
+}
+
+// Returns only the first of consecutive errors from `sink`, so that a full
+// disk doesn't report an error for every line.
+fn report_once(
+    mut sink: impl FnMut(&Line) -> io::Result<()>,
+) -> impl FnMut(&Line) -> io::Result<()> {
+    let mut failing = false;
+    move |line| match sink(line) {
+        Ok(()) => {
+            failing = false;
+            Ok(())
+        }
+        Err(_) if failing => Ok(()),
+        Err(err) => {
+            failing = true;
+            Err(err)
+        }
+    }
+}
+
+#[test]
+fn submit_echoes_and_clears() {
+    let (sender, events) = events::channel(&Config::default());
+    let config = Config::default()
+        .auto_clear_on_submit(true)
+        .echo_input(true);
+    let state = Arc::new(State::new(
+        Arc::new(Shared::from(config)),
+        String::from("main"),
+        true,
+        sender,
+    ));
+    *state.input.lock().buffer = String::from("hello");
+    state.submit_input();
+
+    assert!(matches!(events.try_recv(), Ok(ConsoleEvent::Input(input)) if input == "hello"));
+    assert!(state.input.lock().is_empty());
+    assert_eq!(state.input.lock().history, ["hello"]);
+    assert_eq!(state.scrollback.lock().events[0].as_str(), "> hello");
+}
+
+#[test]
+fn replacing_forms() {
+    let (sender, app) = events::channel(&Config::default());
+    let state = Arc::new(State::new(
+        Arc::new(Shared::from(Config::default())),
+        String::from("main"),
+        true,
+        sender,
+    ));
+    let console = Console::new(state.clone(), app);
+    *state.input.lock().buffer = String::from("draft");
+    let first = console.show_form(Form::new().field("name"));
+    let second = console.show_form(Form::new().field("port"));
+    let events = std::iter::from_fn(|| console.try_next_event().ok()).collect::<Vec<_>>();
+    assert!(events
+        .iter()
+        .any(|event| matches!(event, ConsoleEvent::FormCancelled { id } if *id == first)));
+    assert!(!events
+        .iter()
+        .any(|event| matches!(event, ConsoleEvent::FormCancelled { id } if *id == second)));
+
+    // The draft set aside by the first form is restored by the second.
+    assert!(state.cancel_form());
+    assert_eq!(state.input.lock().as_str(), "draft");
+}
+
+#[test]
+fn choosing_from_lists() {
+    let (sender, app) = events::channel(&Config::default());
+    let shared = Arc::new(Shared::from(Config::default()));
+    let state = Arc::new(State::new(
+        shared.clone(),
+        String::from("main"),
+        true,
+        sender,
+    ));
+    let console = Console::new(state.clone(), app);
+    let handle = ConsoleHandle {
+        shared,
+        tabs: vec![state.clone()],
+        active: 0,
+        threads: Vec::new(),
+        #[cfg(feature = "config-file")]
+        settings_generation: 0,
+    };
+
+    let first = console.push_list(["a", "b"]);
+    // While something is typed, Enter submits it rather than choosing.
+    *state.input.lock().buffer = String::from("typed");
+    assert!(!handle.is_choosing_from_list());
+    assert!(!handle.select_list_item());
+    state.input.lock().buffer.clear();
+    assert!(handle.is_choosing_from_list());
+    assert!(handle.cancel_list());
+    assert!(!handle.cancel_list());
+    assert!(matches!(
+        console.try_next_event(),
+        Ok(ConsoleEvent::ListCancelled { id }) if id == first
+    ));
+
+    let second = console.push_list(["c"]);
+    assert!(handle.select_list_item());
+    assert!(matches!(
+        console.try_next_event(),
+        Ok(ConsoleEvent::ListSelected { id, index: 0 }) if id == second
+    ));
+    assert!(state
+        .scrollback
+        .lock()
+        .events
+        .iter()
+        .all(|line| line.stream.is_none()));
+}
+
+#[test]
+fn inserting_before_missing_marks() {
+    use std::io::Write;
+
+    let (sender, app) = events::channel(&Config::default());
+    let shared = Arc::new(Shared::from(Config::default()));
+    let sunk = Arc::new(AtomicUsize::new(0));
+    shared.add_sink({
+        let sunk = sunk.clone();
+        move |_: &Line| {
+            sunk.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+    });
+    let state = Arc::new(State::new(shared, String::from("main"), false, sender));
+    let console = Console::new(state, app);
+    console.push_line("marked");
+    console.mark("here");
+    assert!(!console.insert_before("missing", "lost"));
+    assert_eq!(sunk.load(Ordering::Relaxed), 1);
+    assert_eq!(console.state.scrollback.lock().events.len(), 1);
+
+    assert!(console.insert_before("here", "found"));
+    assert_eq!(sunk.load(Ordering::Relaxed), 2);
+    assert_eq!(console.state.scrollback.lock().events.len(), 2);
+
+    // Lines dropped by the rate limit aren't inserted.
+    let worker = console.labeled("worker").with_rate_limit(1);
+    assert!(worker.insert_before("here", "first"));
+    assert!(!worker.insert_before("here", "dropped"));
+    assert_eq!(
+        console.state.scrollback.lock().events[1].as_str(),
+        "[worker] first"
+    );
+
+    // Writers and streams attribute lines like pushing them does.
+    let mut writer = worker.writer();
+    writeln!(writer, "dropped").unwrap();
+    let mut writer = console.labeled("writer").writer();
+    writeln!(writer, "written").unwrap();
+    let stream = console.labeled("streamer").begin_stream();
+    stream.write("streamed");
+    stream.finish();
+    let scrollback = console.state.scrollback.lock();
+    assert_eq!(scrollback.events.len(), 5);
+    assert_eq!(scrollback.events[1].as_str(), "[writer] written");
+    assert_eq!(scrollback.events[0].as_str(), "[streamer] streamed");
+}
+
+#[test]
+fn finishing_streams() {
+    let (sender, app) = events::channel(&Config::default());
+    let shared = Arc::new(Shared::from(Config::default()));
+    let sunk = Arc::new(Mutex::new(Vec::new()));
+    shared.add_sink({
+        let sunk = sunk.clone();
+        move |line: &Line| {
+            sunk.lock().push(line.to_string());
+            Ok(())
+        }
+    });
+    let state = Arc::new(State::new(shared, String::from("main"), false, sender));
+    let console = Console::new(state, app);
+    console.add_gag(Regex::new("^PING").unwrap(), false);
+
+    // A `\r\n` split between writes is a single line ending.
+    let stream = console.begin_stream();
+    stream.write("one\r");
+    stream.write("\ntwo");
+    stream.finish();
+    let stream = console.begin_stream();
+    stream.write("PING");
+    stream.finish();
+
+    assert_eq!(*sunk.lock(), ["one\ntwo"]);
+    let scrollback = console.state.scrollback.lock();
+    assert_eq!(scrollback.events.len(), 1);
+    assert_eq!(scrollback.events[0].as_str(), "one\ntwo");
+    assert_eq!(scrollback.gagged, 1);
+}
+
+#[test]
+fn updated_lines_are_filtered() {
+    let (sender, app) = events::channel(&Config::default());
+    let shared = Arc::new(Shared::from(Config::default()));
+    let sunk = Arc::new(Mutex::new(Vec::new()));
+    shared.add_sink({
+        let sunk = sunk.clone();
+        move |line: &Line| {
+            sunk.lock().push(line.to_string());
+            Ok(())
+        }
+    });
+    let state = Arc::new(State::new(shared, String::from("main"), false, sender));
+    let console = Console::new(state, app);
+    let style = Style::new(Color::rgb(255, 0, 0));
+    console.add_highlight(Regex::new("done").unwrap(), style, false);
+    console.add_gag(Regex::new("^PING").unwrap(), false);
+
+    console.push_line("working\r\n");
+    assert!(console.append_to_last_line("... done"));
+    {
+        let scrollback = console.state.scrollback.lock();
+        let line = &scrollback.events[0];
+        assert_eq!(line.as_str(), "working\n... done");
+        assert_eq!(line.spans.len(), 1);
+        assert_eq!(&line[line.spans[0].range.clone()], "done");
+    }
+
+    // A line discarded once it's updated is hidden rather than removed.
+    assert!(console.replace_last_line("PING"));
+    assert_eq!(*sunk.lock(), ["working\n", "working\n... done"]);
+    let scrollback = console.state.scrollback.lock();
+    assert!(scrollback.events[0].gagged);
+    assert_eq!(scrollback.gagged, 1);
+}
+
+#[test]
+fn rewrapping_highlighted_drawings() {
+    let (sender, app) = events::channel(&Config::default());
+    let shared = Arc::new(Shared::from(Config::default()));
+    let state = Arc::new(State::new(shared, String::from("main"), false, sender));
+    let console = Console::new(state, app);
+    let style = Style::new(Color::rgb(255, 0, 0));
+    console.add_highlight(Regex::new("build").unwrap(), style, false);
+    console.push_rule(Some("build"));
+    console.labeled("ci").push_rule(Some("build"));
+
+    let mut scrollback = console.state.scrollback.lock();
+    for columns in [14, 20, 40, 15] {
+        for line in scrollback.events.iter_mut() {
+            line.rewrap(columns);
+            // The span moves with the title rather than staying on the
+            // bytes it covered before, which may split a dash.
+            assert_eq!(line.spans.len(), 1, "{line:?}");
+            assert_eq!(&line[line.spans[0].range.clone()], "build");
+            for row in line.line_ranges().to_vec() {
+                for (segment, _) in line.segments(row) {
+                    assert!(!line[segment].is_empty());
+                }
+            }
+        }
+    }
+    let labeled = &mut scrollback.events[0];
+    labeled.rewrap(17);
+    assert_eq!(labeled.as_str(), "[ci] ── build ───");
+}
+
+#[test]
+fn transcript_errors() {
+    // A file can't be created within a file.
+    let file = std::env::temp_dir().join(format!("console-thingy-file-{}", std::process::id()));
+    std::fs::write(&file, "").unwrap();
+    let config = Config::default().transcript_plain(file.join("transcript.log"), Rotation::Never);
+    let state = State::new(
+        Arc::new(Shared::from(config)),
+        String::from("main"),
+        true,
+        events::channel(&Config::default()).0,
+    );
+    state.push(Line::from(String::from("one")));
+    state.push(Line::from(String::from("two")));
+
+    // Only the first of consecutive failures is reported, after the line
+    // that failed to be written.
+    let scrollback = state.scrollback.lock();
+    let levels = scrollback
+        .events
+        .iter()
+        .rev()
+        .map(|line| line.level)
+        .collect::<Vec<_>>();
+    assert_eq!(levels, [Level::Info, Level::Error, Level::Info]);
+    std::fs::remove_file(file).unwrap();
+}
+
+#[test]
+fn completion_triggers() {
+    let mut input = Input::default();
+    *input.buffer = String::from("hi @al");
+    assert_eq!(input.trigger_token(&['@', '#']), Some(('@', "al")));
+    *input.buffer = String::from("#");
+    assert_eq!(input.trigger_token(&['@', '#']), Some(('#', "")));
+    *input.buffer = String::from("a@b @al ");
+    assert_eq!(input.trigger_token(&['@']), None);
+    *input.buffer = String::from("a@b");
+    assert_eq!(input.trigger_token(&['@']), None);
+}
+
+#[test]
+fn stashed_drafts() {
+    let mut input = Input::default();
+    *input.buffer = String::from("half a thought");
+    input.options = InputOptions::default().history(false);
+    let draft = input.take_draft();
+    assert!(input.is_empty());
+    assert!(input.options.history);
+
+    *input.buffer = String::from("elsewhere");
+    input.restore_draft(draft);
+    assert_eq!(input.as_str(), "half a thought");
+    assert!(!input.options.history);
+
+    input.options.masked = true;
+    assert!(input.take_draft().buffer.is_empty());
+}
+
+#[test]
+fn timers() {
+    let (sender, app) = events::channel(&Config::default());
+    let state = Arc::new(State::new(
+        Arc::new(Shared::from(Config::default())),
+        String::from("main"),
+        false,
+        sender,
+    ));
+    let console = Console::new(state, app);
+    console.after(Duration::from_millis(1), 7);
+    assert!(matches!(console.next_event(), Ok(ConsoleEvent::Timer(7))));
+    assert!(!console.cancel_timer(7));
+
+    console.every(Duration::from_millis(1), 8);
+    for _ in 0..2 {
+        assert!(matches!(console.next_event(), Ok(ConsoleEvent::Timer(8))));
+    }
+    assert!(console.cancel_timer(8));
+}
+
+#[test]
+fn selecting_events() {
+    let (sender, app) = events::channel(&Config::default());
+    let state = Arc::new(State::new(
+        Arc::new(Shared::from(Config::default())),
+        String::from("main"),
+        false,
+        sender,
+    ));
+    let console = Console::new(state, app);
+    let (jobs, job_receiver) = select_channel();
+    jobs.send(1).unwrap();
+    console.state.send(ConsoleEvent::Tick);
+
+    let mut received = (0..2)
+        .map(|_| {
+            let selector = Selector::new().recv(&job_receiver, Result::ok);
+            console
+                .select_event(selector, |event| {
+                    assert!(matches!(event, Ok(ConsoleEvent::Tick)));
+                    None
+                })
+                .wait()
+        })
+        .collect::<Vec<_>>();
+    received.sort();
+    assert_eq!(received, [None, Some(1)]);
+}
+
+#[test]
+fn event_iteration() {
+    let (sender, app) = events::channel(&Config::default());
+    let state = Arc::new(State::new(
+        Arc::new(Shared::from(Config::default())),
+        String::from("main"),
+        false,
+        sender,
+    ));
+    let console = Console::new(state, app);
+    assert!(console.try_next_event().is_err());
+    for input in ["a", "b", "quit", "c"] {
+        console.state.send(ConsoleEvent::Input(String::from(input)));
+    }
+    assert!(matches!(
+        console.try_next_event(),
+        Ok(ConsoleEvent::Input(_))
+    ));
+
+    let mut inputs = Vec::new();
+    let quit = console.for_each_event(|event| match event {
+        ConsoleEvent::Input(input) if input == "quit" => ControlFlow::Break(inputs.len()),
+        ConsoleEvent::Input(input) => {
+            inputs.push(input);
+            ControlFlow::Continue(())
+        }
+        _ => ControlFlow::Continue(()),
+    });
+    assert_eq!(quit, Some(1));
+    assert_eq!(inputs, ["b"]);
+
+    console.state.disconnect();
+    assert_eq!(console.events().count(), 1);
+}
+
+#[test]
+fn error_chains() {
+    let error = anyhow::anyhow!("permission denied\n(read only)")
+        .context("opening config")
+        .context("starting up");
+    let description = describe_error(&error);
+    assert_eq!(
+        description.split("\n\nbacktrace:").next(),
+        Some(
+            "starting up\n  caused by: opening config\n    caused by: permission denied\n      (read only)"
+        )
+    );
+}
+
+#[test]
+fn shutdown_requests() {
+    let (sender, received) = flume::unbounded();
+    let app = move |console: Console| {
+        // A clone held by a background worker doesn't keep the app alive.
+        let worker = console.labeled("worker");
+        std::thread::spawn(move || while worker.sleep(Duration::from_millis(10)) {});
+        console.request_shutdown();
+        let _ = sender.send(console.next_event().ok());
+        assert!(console.next_event().is_err());
+        Ok(())
+    };
+    let mut console = Console::spawn_apps(vec![app], Arc::new(Shared::from(Config::default())));
+    while !console.should_shutdown() {
+        std::thread::yield_now();
+    }
+    console.shutdown().unwrap();
+    assert!(matches!(
+        received.recv(),
+        Ok(Some(ConsoleEvent::ShutdownRequested))
+    ));
 }
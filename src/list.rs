@@ -0,0 +1,71 @@
+use crate::scrollback::Line;
+use crate::style::{Color, Span};
+
+const SELECTED_COLOR: Color = Color::rgb(120, 180, 255);
+
+/// A list pushed with [`Console::push_list`](crate::Console::push_list)
+/// that is waiting for the user to choose an item.
+#[derive(Debug)]
+pub(crate) struct ActiveList {
+    pub id: u64,
+    items: Vec<String>,
+    selected: usize,
+}
+
+impl ActiveList {
+    pub fn new(id: u64, items: Vec<String>) -> Self {
+        Self {
+            id,
+            items,
+            selected: 0,
+        }
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        (self.selected < self.items.len()).then_some(self.selected)
+    }
+
+    /// Moves the highlight by `by` items, stopping at either end. Returns
+    /// false if the highlight didn't move.
+    pub fn move_selection(&mut self, by: isize) -> bool {
+        let last = self.items.len().saturating_sub(1);
+        let selected = self.selected.saturating_add_signed(by).min(last);
+        let moved = selected != self.selected;
+        self.selected = selected;
+        moved
+    }
+
+    /// Draws the items into `line`, one row each, with the highlighted item
+    /// marked.
+    pub fn draw(&self, line: &mut Line) {
+        let mut text = String::new();
+        line.spans.clear();
+        for (index, item) in self.items.iter().enumerate() {
+            if index > 0 {
+                text.push('\n');
+            }
+            let start = text.len();
+            text.push_str(if index == self.selected { "› " } else { "  " });
+            text.push_str(item);
+            if index == self.selected {
+                line.spans
+                    .push(Span::new(start..text.len(), SELECTED_COLOR));
+            }
+        }
+        line.replace_range(.., &text);
+    }
+}
+
+#[test]
+fn moving_list_selections() {
+    let mut list = ActiveList::new(0, vec![String::from("yes"), String::from("no")]);
+    let mut line = Line::from(String::new());
+    list.draw(&mut line);
+    assert_eq!(line.as_str(), "› yes\n  no");
+    assert!(!list.move_selection(-1));
+    assert!(list.move_selection(5));
+    assert_eq!(list.selected(), Some(1));
+    list.draw(&mut line);
+    assert_eq!(line.as_str(), "  yes\n› no");
+    assert_eq!(line.spans, [Span::new(6..12, SELECTED_COLOR)]);
+}
@@ -0,0 +1,52 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Receives measurements of the console's performance, configured with
+/// [`Config::metrics`](crate::Config::metrics). Every method does nothing
+/// by default.
+///
+/// Methods are called from the thread rendering the console, so they should
+/// return quickly.
+pub trait Metrics: Send + Sync + 'static {
+    /// The time taken to render a frame.
+    fn redraw(&self, duration: Duration) {
+        let _ = duration;
+    }
+
+    /// The time spent wrapping text while rendering a frame.
+    fn wrap(&self, duration: Duration) {
+        let _ = duration;
+    }
+
+    /// The number of items waiting in `queue` after a frame was rendered.
+    fn queue_depth(&self, queue: Queue, depth: usize) {
+        let _ = (queue, depth);
+    }
+
+    /// The time between a key being pressed and the frame displaying it
+    /// finishing rendering.
+    fn input_latency(&self, latency: Duration) {
+        let _ = latency;
+    }
+}
+
+/// A queue whose depth is reported to [`Metrics::queue_depth`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Queue {
+    /// Events sent to the app that it hasn't received yet.
+    Events,
+    /// Lines pushed while output is paused.
+    Paused,
+    /// Lines pushed since the last frame, counted when an output limit is
+    /// set.
+    Undisplayed,
+}
+
+pub(crate) struct MetricsHook(pub Arc<dyn Metrics>);
+
+impl fmt::Debug for MetricsHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MetricsHook").finish()
+    }
+}
@@ -0,0 +1,114 @@
+use std::borrow::Cow;
+
+/// The line ending used by text pushed into the console.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum Newline {
+    /// `\n` ends a line. Stray `\r` characters are removed.
+    Lf,
+    /// `\r\n` ends a line. Lone `\r` or `\n` characters are removed.
+    CrLf,
+    /// `\r` ends a line. Stray `\n` characters are removed.
+    Cr,
+    /// Any of `\r\n`, `\r`, or `\n` ends a line.
+    #[default]
+    Auto,
+}
+
+impl Newline {
+    /// Returns `text` with every line ending converted to `\n`.
+    pub fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if !text.contains(['\r', '\n']) {
+            return Cow::Borrowed(text);
+        }
+
+        let mut normalized = String::with_capacity(text.len());
+        let mut chars = text.chars().peekable();
+        while let Some(ch) = chars.next() {
+            match (self, ch) {
+                (Newline::Lf, '\n') | (Newline::Cr, '\r') | (Newline::Auto, '\n') => {
+                    normalized.push('\n');
+                }
+                (Newline::CrLf | Newline::Auto, '\r') if chars.peek() == Some(&'\n') => {
+                    chars.next();
+                    normalized.push('\n');
+                }
+                (Newline::Auto, '\r') => normalized.push('\n'),
+                (_, '\r' | '\n') => {}
+                (_, ch) => normalized.push(ch),
+            }
+        }
+        Cow::Owned(normalized)
+    }
+}
+
+/// Splits a stream of bytes into lines as they arrive.
+#[derive(Debug)]
+pub(crate) struct LineSplitter {
+    newline: Newline,
+    buffer: Vec<u8>,
+    // Whether the previous byte was a `\r`. Line endings are emitted as soon
+    // as possible, so a `\r\n` spanning two reads is tracked here.
+    after_cr: bool,
+}
+
+impl LineSplitter {
+    pub fn new(newline: Newline) -> Self {
+        Self {
+            newline,
+            buffer: Vec::new(),
+            after_cr: false,
+        }
+    }
+
+    pub fn feed(&mut self, bytes: &[u8], mut line: impl FnMut(String)) {
+        for &byte in bytes {
+            let after_cr = std::mem::replace(&mut self.after_cr, byte == b'\r');
+            match (self.newline, byte) {
+                (Newline::Lf, b'\n') | (Newline::Cr | Newline::Auto, b'\r') => {
+                    line(self.take_line());
+                }
+                (Newline::Auto, b'\n') if !after_cr => {
+                    line(self.take_line());
+                }
+                (Newline::CrLf, b'\n') if after_cr => {
+                    line(self.take_line());
+                }
+                (_, b'\r' | b'\n') => {}
+                _ => self.buffer.push(byte),
+            }
+        }
+    }
+
+    /// Returns any partial line that has not been terminated yet.
+    pub fn finish(&mut self) -> Option<String> {
+        (!self.buffer.is_empty()).then(|| self.take_line())
+    }
+
+    fn take_line(&mut self) -> String {
+        let line = String::from_utf8_lossy(&self.buffer).into_owned();
+        self.buffer.clear();
+        line
+    }
+}
+
+#[test]
+fn newline_tests() {
+    assert_eq!(Newline::Auto.normalize("a\r\nb\rc\nd"), "a\nb\nc\nd");
+    assert_eq!(Newline::Lf.normalize("a\r\nb\rc"), "a\nbc");
+    assert_eq!(Newline::CrLf.normalize("a\r\nb\nc\rd"), "a\nbcd");
+    assert_eq!(Newline::Cr.normalize("a\r\nb\rc"), "a\nb\nc");
+
+    let mut lines = Vec::new();
+    let mut splitter = LineSplitter::new(Newline::Auto);
+    splitter.feed(b"one\r", |line| lines.push(line));
+    splitter.feed(b"\ntwo\nthr", |line| lines.push(line));
+    splitter.feed(b"ee\r\rfour", |line| lines.push(line));
+    lines.extend(splitter.finish());
+    assert_eq!(lines, ["one", "two", "three", "", "four"]);
+
+    let mut lines = Vec::new();
+    let mut splitter = LineSplitter::new(Newline::CrLf);
+    splitter.feed(b"one\r", |line| lines.push(line));
+    splitter.feed(b"\ntwo\r\n", |line| lines.push(line));
+    assert_eq!(lines, ["one", "two"]);
+}
@@ -0,0 +1,75 @@
+use crate::wrap::Wrapped;
+
+/// A box displayed centered above the scrollback, such as a help screen or
+/// error details. Shown with
+/// [`Console::show_overlay`](crate::Console::show_overlay) and dismissed by
+/// pressing Escape.
+#[derive(Debug, Clone)]
+pub struct Overlay {
+    title: Option<String>,
+    text: Wrapped,
+}
+
+impl Overlay {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            title: None,
+            text: Wrapped::from(text.into()),
+        }
+    }
+
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// Wraps the text to fit within `columns`, returning the rows of the box
+    /// including its border.
+    pub(crate) fn boxed_rows(&mut self, columns: usize) -> Vec<String> {
+        // Two columns for the border and two for padding.
+        let inner = columns.saturating_sub(4).max(1);
+        self.text.rewrap(inner);
+        let rows = self.text.rows().collect::<Vec<_>>();
+        let title = self.title.as_deref().unwrap_or_default();
+        let width = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .chain([title.chars().count()])
+            .max()
+            .unwrap_or_default()
+            .min(inner);
+
+        let mut boxed = Vec::with_capacity(rows.len() + 2);
+        let title = title.chars().take(width).collect::<String>();
+        let title_len = title.chars().count();
+        boxed.push(if title.is_empty() {
+            format!("┌{}┐", "─".repeat(width + 2))
+        } else {
+            format!("┌ {title} {}┐", "─".repeat(width - title_len))
+        });
+        for row in rows {
+            let padding = width.saturating_sub(row.chars().count());
+            boxed.push(format!("│ {row}{} │", " ".repeat(padding)));
+        }
+        boxed.push(format!("└{}┘", "─".repeat(width + 2)));
+        boxed
+    }
+}
+
+#[test]
+fn boxed_rows() {
+    let mut overlay = Overlay::text("Escape closes\nthis").with_title("Help");
+    assert_eq!(
+        overlay.boxed_rows(80),
+        [
+            "┌ Help ─────────┐",
+            "│ Escape closes │",
+            "│ this          │",
+            "└───────────────┘",
+        ]
+    );
+}
@@ -0,0 +1,69 @@
+use std::any::Any;
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::panic::Location;
+use std::sync::Arc;
+
+use crate::scrollback::Line;
+use crate::{Level, State, ERROR_COLOR};
+
+/// Pushes panics from every thread to `state`'s scrollback, in addition to
+/// reporting them as before.
+pub(crate) fn install(state: &Arc<State>) {
+    let state = Arc::downgrade(state);
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        previous(info);
+        let Some(state) = state.upgrade() else {
+            return;
+        };
+        let thread = std::thread::current();
+        let mut description = describe(thread.name(), info.payload(), info.location());
+        let backtrace = Backtrace::capture();
+        if backtrace.status() == BacktraceStatus::Captured {
+            description.push_str("\n\nbacktrace:\n");
+            description.push_str(backtrace.to_string().trim_end());
+        }
+        // The panicking thread may be holding the scrollback's lock, so the
+        // line is pushed once it has unwound.
+        let _ = std::thread::Builder::new()
+            .name(String::from("panic"))
+            .spawn(move || {
+                let line = Line::from(description)
+                    .with_level(Level::Error)
+                    .with_color(Some(ERROR_COLOR));
+                state.push(line);
+                if state.shared.config.shutdown_on_panic {
+                    state.shutdown();
+                }
+                state.redraw();
+            });
+    }));
+}
+
+fn describe(thread: Option<&str>, payload: &dyn Any, location: Option<&Location<'_>>) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>");
+    let mut description = format!("thread '{}' panicked", thread.unwrap_or("<unnamed>"));
+    if let Some(location) = location {
+        description.push_str(&format!(" at {location}"));
+    }
+    description.push_str(":\n");
+    description.push_str(message);
+    description
+}
+
+#[test]
+fn panic_descriptions() {
+    let location = Location::caller();
+    assert_eq!(
+        describe(Some("worker"), &String::from("oh no"), Some(location)),
+        format!("thread 'worker' panicked at {location}:\noh no")
+    );
+    assert_eq!(
+        describe(None, &42, None),
+        "thread '<unnamed>' panicked:\nBox<dyn Any>"
+    );
+}
@@ -0,0 +1,145 @@
+use std::fmt::Write as _;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::scrollback::Line;
+use crate::{ConsoleHandle, Overlay, Shared};
+
+// Overlays are wrapped to fit a standard terminal width.
+const OVERLAY_COLUMNS: usize = 80;
+
+pub fn is_piped() -> bool {
+    !io::stdin().is_terminal() && !io::stdout().is_terminal()
+}
+
+pub(crate) fn install(shared: &Shared) {
+    *shared.plain.lock() = true;
+    let json = shared.config.plain_json;
+    shared.add_sink(move |line: &Line| {
+        if !line.gagged {
+            print(line, json);
+        }
+        Ok(())
+    });
+}
+
+pub(crate) fn print_overlay(shared: &Shared, mut overlay: Overlay) {
+    for row in overlay.boxed_rows(OVERLAY_COLUMNS) {
+        print(&Line::from(row), shared.config.plain_json);
+    }
+}
+
+fn print(line: &Line, json: bool) {
+    let mut stdout = io::stdout().lock();
+    if json {
+        let _ = writeln!(stdout, "{}", to_json(line));
+    } else {
+        for row in line.as_str().split('\n') {
+            let _ = writeln!(stdout, "{:indent$}{row}", "", indent = line.indent);
+        }
+    }
+    let _ = stdout.flush();
+}
+
+fn to_json(line: &Line) -> String {
+    let timestamp = line
+        .timestamp
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let mut json = String::from("{\"text\":");
+    push_json_string(&mut json, line.as_str());
+    json.push_str(",\"level\":");
+    push_json_string(&mut json, line.level.as_str());
+    json.push_str(",\"channel\":");
+    match &line.channel {
+        Some(channel) => push_json_string(&mut json, channel),
+        None => json.push_str("null"),
+    }
+    let _ = write!(
+        json,
+        ",\"timestamp\":{}.{:03}}}",
+        timestamp.as_secs(),
+        timestamp.subsec_millis()
+    );
+    json
+}
+
+fn push_json_string(json: &mut String, value: &str) {
+    json.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            ch if ch.is_control() => {
+                let _ = write!(json, "\\u{:04x}", u32::from(ch));
+            }
+            ch => json.push(ch),
+        }
+    }
+    json.push('"');
+}
+
+pub(crate) fn run(console: ConsoleHandle) -> ! {
+    let (sender, lines) = flume::unbounded();
+    std::thread::Builder::new()
+        .name(String::from("stdin"))
+        .spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        })
+        .expect("error spawning stdin thread");
+
+    run_with_input(console, lines)
+}
+
+// Submits each line received from `lines` until the console shuts down or
+// every sender has been dropped.
+pub(crate) fn run_with_input(mut console: ConsoleHandle, lines: flume::Receiver<String>) -> ! {
+    while !console.should_shutdown() {
+        match lines.recv_timeout(Duration::from_millis(100)) {
+            Ok(line) => submit(&console, line),
+            Err(flume::RecvTimeoutError::Timeout) => {}
+            // The input has reached its end.
+            Err(flume::RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    match console.shutdown() {
+        Ok(()) => std::process::exit(0),
+        Err(err) => {
+            eprintln!("error: {err:#}");
+            std::process::exit(1)
+        }
+    }
+}
+
+fn submit(console: &ConsoleHandle, line: String) {
+    let state = console.state();
+    *state.input.lock().buffer = line;
+    state.submit_input();
+    // The app received the line in its event, so the input is left empty for
+    // the next line rather than waiting for the app to clear it.
+    state.input.lock().clear();
+}
+
+#[test]
+fn json_lines() {
+    use std::sync::Arc;
+
+    use crate::Level;
+
+    let mut line = Line::new(String::from("say \"hi\"\t\u{1}"), Some(Arc::from("chat")))
+        .with_level(Level::Warning);
+    line.timestamp = UNIX_EPOCH + Duration::from_millis(1_500);
+    assert_eq!(
+        to_json(&line),
+        r#"{"text":"say \"hi\"\t\u0001","level":"warning","channel":"chat","timestamp":1.500}"#
+    );
+}
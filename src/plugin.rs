@@ -0,0 +1,93 @@
+use std::fmt;
+
+use parking_lot::Mutex;
+
+use crate::{Console, ConsoleEvent};
+
+/// A reusable add-on to apps, such as a logging pane, timers, or
+/// notifications, installed with [`Config::plugin`](crate::Config::plugin).
+/// Every method does nothing by default.
+///
+/// Plugins use the console they're given like an app does, such as to add
+/// highlight rules and triggers or to open tabs. Each method is called while
+/// every plugin is locked, so plugins must not wait for events themselves.
+pub trait Plugin: Send + 'static {
+    /// Called with each app's console before the app starts running.
+    fn init(&mut self, console: &Console) {
+        let _ = console;
+    }
+
+    /// Called with each event an app receives, before the app receives it.
+    fn observe(&mut self, console: &Console, event: &ConsoleEvent) {
+        let _ = (console, event);
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Plugins(Mutex<Vec<Box<dyn Plugin>>>);
+
+impl fmt::Debug for Plugins {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Plugins")
+            .field(&self.0.lock().len())
+            .finish()
+    }
+}
+
+impl Plugins {
+    pub fn push(&mut self, plugin: impl Plugin) {
+        self.0.get_mut().push(Box::new(plugin));
+    }
+
+    pub fn init(&self, console: &Console) {
+        for plugin in &mut *self.0.lock() {
+            plugin.init(console);
+        }
+    }
+
+    pub fn observe(&self, console: &Console, event: &ConsoleEvent) {
+        for plugin in &mut *self.0.lock() {
+            plugin.observe(console, event);
+        }
+    }
+}
+
+#[test]
+fn plugins_observe_events() {
+    use std::sync::Arc;
+
+    use crate::{Config, Regex, Shared, State};
+
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Plugin for Recorder {
+        fn init(&mut self, console: &Console) {
+            console.add_trigger(Regex::new("ding").unwrap(), None);
+        }
+
+        fn observe(&mut self, _console: &Console, event: &ConsoleEvent) {
+            if let ConsoleEvent::Triggered { line, .. } = event {
+                self.0.lock().push(line.clone());
+            }
+        }
+    }
+
+    let observed = Arc::default();
+    let config = Config::default().plugin(Recorder(Arc::clone(&observed)));
+    let (sender, app) = crate::events::channel(&Config::default());
+    let state = Arc::new(State::new(
+        Arc::new(Shared::from(config)),
+        String::from("main"),
+        false,
+        sender,
+    ));
+    let console = Console::new(state, app);
+    console.state.shared.plugins.init(&console);
+
+    console.push_line("ding dong");
+    assert!(matches!(
+        console.next_event(),
+        Ok(ConsoleEvent::Triggered { .. })
+    ));
+    assert_eq!(*observed.lock(), ["ding dong"]);
+}
@@ -0,0 +1,144 @@
+/// A small box displayed next to the text cursor, such as a list of
+/// completions or mentions. Unlike an [`Overlay`](crate::Overlay), a popup
+/// doesn't capture input. Shown with
+/// [`Console::show_popup`](crate::Console::show_popup) and hidden with
+/// [`Console::hide_popup`](crate::Console::hide_popup) or by pressing
+/// Escape.
+#[derive(Debug, Clone, Default)]
+pub struct Popup {
+    items: Vec<String>,
+    selected: Option<usize>,
+    title: Option<String>,
+}
+
+impl Popup {
+    pub fn new<Item: Into<String>>(items: impl IntoIterator<Item = Item>) -> Self {
+        Self {
+            items: items.into_iter().map(Into::into).collect(),
+            selected: None,
+            title: None,
+        }
+    }
+
+    /// Highlights the item at `index`.
+    pub fn with_selected(mut self, index: usize) -> Self {
+        self.selected = Some(index);
+        self
+    }
+
+    /// Shows `title` in the box's top border.
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn items(&self) -> &[String] {
+        &self.items
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// Returns the rows of the box including its border, showing at most
+    /// `max_rows` items truncated to `max_columns`. Items are scrolled to
+    /// keep the selected item visible.
+    pub(crate) fn boxed_rows(&self, max_columns: usize, max_rows: usize) -> Vec<String> {
+        // Two columns for the border and two for padding.
+        let inner = max_columns.saturating_sub(4).max(1);
+        let visible = max_rows.saturating_sub(2).max(1);
+        let first = self
+            .selected
+            .map_or(0, |selected| (selected + 1).saturating_sub(visible));
+        let items = self
+            .items
+            .iter()
+            .skip(first)
+            .take(visible)
+            .map(|item| item.chars().take(inner).collect::<String>())
+            .collect::<Vec<_>>();
+        let title = self
+            .title
+            .as_ref()
+            .map(|title| title.chars().take(inner).collect::<String>());
+        let width = items
+            .iter()
+            .chain(&title)
+            .map(|item| item.chars().count())
+            .max()
+            .unwrap_or_default();
+
+        let mut boxed = Vec::with_capacity(items.len() + 2);
+        boxed.push(match title {
+            Some(title) => {
+                let rule = width - title.chars().count();
+                format!("┌ {title} {}┐", "─".repeat(rule))
+            }
+            None => format!("┌{}┐", "─".repeat(width + 2)),
+        });
+        for item in items {
+            let padding = width - item.chars().count();
+            boxed.push(format!("│ {item}{} │", " ".repeat(padding)));
+        }
+        boxed.push(format!("└{}┘", "─".repeat(width + 2)));
+        boxed
+    }
+
+    /// Returns the index of the row returned by `boxed_rows` that contains
+    /// the selected item.
+    pub(crate) fn selected_row(&self, max_rows: usize) -> Option<usize> {
+        let visible = max_rows.saturating_sub(2).max(1);
+        let selected = self.selected.filter(|&index| index < self.items.len())?;
+        Some(selected.min(visible - 1) + 1)
+    }
+}
+
+/// Returns the top-left corner of a popup of `size` anchored at `anchor`, the
+/// top-left corner of a cursor that is `anchor_height` tall, within
+/// `bounds`. The popup is placed above the cursor starting at its left edge,
+/// and is flipped below the cursor or to its left when it would not fit.
+pub(crate) fn place(
+    anchor: (f32, f32),
+    anchor_height: f32,
+    size: (f32, f32),
+    bounds: (f32, f32),
+) -> (f32, f32) {
+    let (x, y) = anchor;
+    let (width, height) = size;
+    let (max_x, max_y) = bounds;
+    let left = if x + width <= max_x {
+        x
+    } else {
+        (x - width).max(0.)
+    };
+    let top = if y - height >= 0. || y + anchor_height + height > max_y {
+        (y - height).max(0.)
+    } else {
+        y + anchor_height
+    };
+    (left, top)
+}
+
+#[test]
+fn popup_placement() {
+    let popup = Popup::new(["alpha", "beta", "gamma"]).with_selected(2);
+    assert_eq!(
+        popup.boxed_rows(80, 4),
+        ["┌───────┐", "│ beta  │", "│ gamma │", "└───────┘"]
+    );
+    assert_eq!(popup.selected_row(4), Some(2));
+    assert_eq!(
+        Popup::new(["a"]).with_title("ab").boxed_rows(80, 3),
+        ["┌ ab ┐", "│ a  │", "└────┘"]
+    );
+
+    // Above the cursor when there's room, and below when there isn't.
+    assert_eq!(place((10., 20.), 1., (5., 4.), (80., 24.)), (10., 16.));
+    assert_eq!(place((10., 2.), 1., (5., 4.), (80., 24.)), (10., 3.));
+    // Flipped to the left of the cursor near the right edge.
+    assert_eq!(place((78., 20.), 1., (5., 4.), (80., 24.)), (73., 16.));
+}
@@ -0,0 +1,83 @@
+use std::io::{self, Write};
+use std::process::{ChildStdin, Command, Stdio};
+use std::sync::Arc;
+
+use crate::producer::Producer;
+use crate::{Color, ConsoleEvent, Level, State, Writer};
+
+const STDERR_COLOR: Color = Color::rgb(255, 96, 96);
+
+/// A child process whose output is streamed into the console. Created by
+/// [`Console::spawn_process`](crate::Console::spawn_process).
+///
+/// When the process exits, [`ConsoleEvent::ProcessExited`] is sent after all
+/// of its output has been pushed.
+#[derive(Debug)]
+pub struct Process {
+    id: u32,
+    stdin: Option<ChildStdin>,
+}
+
+impl Process {
+    pub(crate) fn spawn(
+        state: &Arc<State>,
+        producer: Option<Arc<Producer>>,
+        mut command: Command,
+    ) -> io::Result<Self> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let id = child.id();
+        let stdin = child.stdin.take();
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+        let mut stdout_writer = Writer::new(state, producer.clone());
+        let mut stderr_writer = Writer::new(state, producer)
+            .with_color(STDERR_COLOR)
+            .with_level(Level::Error);
+        let state = Arc::downgrade(state);
+
+        std::thread::Builder::new()
+            .name(format!("process {id}"))
+            .spawn(move || {
+                let stderr_thread = std::thread::Builder::new()
+                    .name(format!("process {id} stderr"))
+                    .spawn(move || io::copy(&mut stderr, &mut stderr_writer))
+                    .expect("error spawning process thread");
+                let _ = io::copy(&mut stdout, &mut stdout_writer);
+                // Push any incomplete line before reporting the exit.
+                drop(stdout_writer);
+                let _ = stderr_thread.join();
+
+                let status = child.wait();
+                if let Some(state) = state.upgrade() {
+                    state.send(ConsoleEvent::ProcessExited { id, status });
+                }
+            })
+            .expect("error spawning process thread");
+
+        Ok(Self { id, stdin })
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Writes `line` followed by a newline to the process's stdin.
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let stdin = self
+            .stdin
+            .as_mut()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        stdin.write_all(line.as_bytes())?;
+        stdin.write_all(b"\n")?;
+        stdin.flush()
+    }
+
+    /// Closes the process's stdin, signaling the end of its input.
+    pub fn close_stdin(&mut self) {
+        self.stdin = None;
+    }
+}
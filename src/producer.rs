@@ -0,0 +1,138 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use crate::scrollback::Line;
+use crate::Level;
+
+/// The identity shared by a [`Console`](crate::Console) handle and its
+/// clones. Handles returned from [`Console::labeled`](crate::Console::labeled)
+/// and [`Console::with_rate_limit`](crate::Console::with_rate_limit) start
+/// their own.
+#[derive(Debug)]
+pub(crate) struct Producer {
+    label: Option<Arc<str>>,
+    rate: Option<Mutex<Rate>>,
+    // The columns pushed lines are indented by, from Console::indent.
+    pub indent: AtomicUsize,
+}
+
+impl Producer {
+    pub fn new(label: Option<Arc<str>>, lines_per_second: Option<u32>) -> Self {
+        Self {
+            label,
+            rate: lines_per_second.map(|limit| Mutex::new(Rate::new(limit))),
+            indent: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn label(&self) -> Option<&Arc<str>> {
+        self.label.as_ref()
+    }
+
+    pub fn lines_per_second(&self) -> Option<u32> {
+        self.rate.as_ref().map(|rate| rate.lock().limit)
+    }
+
+    /// Attributes `line` to this producer and indents it, returning the
+    /// lines to push: a notice if lines were dropped by the rate limit,
+    /// followed by `line`. Returns nothing if `line` is dropped.
+    pub fn attribute(&self, line: Line, now: Instant) -> Vec<Line> {
+        let dropped = match &self.rate {
+            Some(rate) => match rate.lock().admit(now) {
+                Some(dropped) => dropped,
+                None => return Vec::new(),
+            },
+            None => 0,
+        };
+        let mut lines = Vec::with_capacity(2);
+        if dropped > 0 {
+            let notice = match dropped {
+                1 => String::from("1 line dropped by rate limit"),
+                dropped => format!("{dropped} lines dropped by rate limit"),
+            };
+            lines.push(self.labeled(Line::from(notice).with_level(Level::Warning)));
+        }
+        lines.push(self.labeled(line));
+        lines
+    }
+
+    fn labeled(&self, mut line: Line) -> Line {
+        if let Some(label) = &self.label {
+            line.prefix(&format!("[{label}] "));
+            line.producer = Some(label.clone());
+        }
+        line.indent += self.indent.load(Ordering::Relaxed);
+        line
+    }
+}
+
+/// Counts the lines pushed within the current second.
+#[derive(Debug)]
+struct Rate {
+    limit: u32,
+    window: Option<Instant>,
+    pushed: u32,
+    dropped: usize,
+}
+
+impl Rate {
+    fn new(limit: u32) -> Self {
+        Self {
+            limit: limit.max(1),
+            window: None,
+            pushed: 0,
+            dropped: 0,
+        }
+    }
+
+    // Returns the number of lines dropped since the last admitted line, or
+    // None if this line must be dropped.
+    fn admit(&mut self, now: Instant) -> Option<usize> {
+        let expired = self.window.map_or(true, |window| {
+            now.saturating_duration_since(window) >= Duration::from_secs(1)
+        });
+        if expired {
+            self.window = Some(now);
+            self.pushed = 0;
+        }
+        if self.pushed >= self.limit {
+            self.dropped += 1;
+            return None;
+        }
+        self.pushed += 1;
+        Some(std::mem::take(&mut self.dropped))
+    }
+}
+
+#[test]
+fn rate_limited_producers() {
+    let producer = Producer::new(Some(Arc::from("worker-3")), Some(2));
+    let start = Instant::now();
+    let text = |lines: Vec<Line>| {
+        lines
+            .iter()
+            .map(|line| line.as_str().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        text(producer.attribute(Line::from(String::from("a")), start)),
+        ["[worker-3] a"]
+    );
+    producer.attribute(Line::from(String::from("b")), start);
+    assert!(producer
+        .attribute(Line::from(String::from("c")), start)
+        .is_empty());
+    assert!(producer
+        .attribute(Line::from(String::from("d")), start)
+        .is_empty());
+    assert_eq!(
+        text(producer.attribute(
+            Line::from(String::from("e")),
+            start + Duration::from_secs(1)
+        )),
+        ["[worker-3] 2 lines dropped by rate limit", "[worker-3] e"]
+    );
+}
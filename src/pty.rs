@@ -0,0 +1,86 @@
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use portable_pty::{native_pty_system, CommandBuilder, MasterPty, PtySize};
+
+use crate::producer::Producer;
+use crate::{ConsoleEvent, State, Writer};
+
+/// A child process attached to a pseudo-terminal, whose output is displayed
+/// in the console. Created by [`Console::spawn_pty`](crate::Console::spawn_pty).
+///
+/// Because the child sees a terminal, tools like `git` produce colored
+/// output. Colors are displayed, but cursor movement is not supported.
+pub struct Pty {
+    id: Option<u32>,
+    master: Box<dyn MasterPty + Send>,
+    writer: Box<dyn Write + Send>,
+}
+
+impl Pty {
+    pub(crate) fn spawn(
+        state: &Arc<State>,
+        producer: Option<Arc<Producer>>,
+        command: CommandBuilder,
+    ) -> anyhow::Result<Self> {
+        let columns = state.scrollback.lock().columns;
+        let pair = native_pty_system().openpty(PtySize {
+            cols: u16::try_from(columns).unwrap_or(u16::MAX).max(1),
+            ..PtySize::default()
+        })?;
+        let mut child = pair.slave.spawn_command(command)?;
+        // The slave must be closed in this process for reads to end when the
+        // child exits.
+        drop(pair.slave);
+
+        let id = child.process_id();
+        let mut reader = pair.master.try_clone_reader()?;
+        let writer = pair.master.take_writer()?;
+        let mut output = Writer::new(state, producer).interpret_ansi();
+        let state = Arc::downgrade(state);
+        std::thread::Builder::new()
+            .name(String::from("pty"))
+            .spawn(move || {
+                // Reading from the pty reports an error rather than EOF on
+                // some platforms once the child exits.
+                let _ = io::copy(&mut reader, &mut output);
+                drop(output);
+
+                let exit_code = child.wait().map(|status| status.exit_code());
+                if let Some(state) = state.upgrade() {
+                    state.send(ConsoleEvent::PtyExited { id, exit_code });
+                }
+            })
+            .expect("error spawning pty thread");
+
+        Ok(Self {
+            id,
+            master: pair.master,
+            writer,
+        })
+    }
+
+    pub fn id(&self) -> Option<u32> {
+        self.id
+    }
+
+    /// Writes `line` to the terminal as if it were typed and Enter pressed.
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.write_all(b"\r")?;
+        self.writer.flush()
+    }
+
+    pub fn write_all(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.writer.write_all(bytes)?;
+        self.writer.flush()
+    }
+
+    pub fn resize(&self, columns: u16, rows: u16) -> anyhow::Result<()> {
+        self.master.resize(PtySize {
+            rows,
+            cols: columns,
+            ..PtySize::default()
+        })
+    }
+}
@@ -0,0 +1,324 @@
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+use rusttype::{point, Font, Scale};
+
+use crate::layout::{self, RowPlacement};
+use crate::style::{Color, Style};
+use crate::wrap::Wrapped;
+use crate::State;
+
+const FONT_SIZE: f32 = 16.;
+const BACKGROUND: Color = Color::rgb(0, 0, 0);
+// The window's gray, which input decorations are drawn in.
+const DECORATION_COLOR: Color = Color::rgb(128, 128, 128);
+// The size used before the console has been displayed.
+const DEFAULT_COLUMNS: usize = 80;
+const DEFAULT_ROWS: usize = 24;
+
+// A row as displayed, with the text of each segment in its color.
+#[derive(Debug)]
+struct Row {
+    background: Option<Color>,
+    indent: usize,
+    segments: Vec<(String, Color)>,
+}
+
+/// Renders the rows that `state` displays to a PNG at `path`. The text is
+/// drawn with the bundled font, in the colors of the console's style, rather
+/// than read back from the window.
+///
+/// Rows are wrapped and placed like the window places them, using
+/// [`RowPlacement`], but only the followed view's text, line backgrounds,
+/// and input are drawn. The gutter, tab bar, status indicators, message
+/// bubbles, selection, search matches, and the pinned view of a split are
+/// left out, and each character takes one column, where the window uses
+/// the widths of its font's glyphs.
+pub(crate) fn save(state: &State, path: &Path) -> io::Result<()> {
+    let (columns, rows, displayed) = displayed_rows(state);
+    let image = Image::render(&displayed, columns, rows);
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, image.width as u32, image.height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&image.pixels))
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+}
+
+// Returns the size of the view in columns and rows, and the rows it
+// displays from top to bottom: the output followed by the input line.
+fn displayed_rows(state: &State) -> (usize, usize, Vec<Row>) {
+    let config = &state.shared.config;
+    let foreground = |color: Color| config.style(Style::new(color)).foreground;
+    let input = state.input.lock();
+    let mut scrollback = state.scrollback.lock();
+    let columns = match scrollback.columns {
+        0 => DEFAULT_COLUMNS,
+        columns => columns,
+    };
+    let rows = match scrollback.rows {
+        0 => DEFAULT_ROWS,
+        rows => rows,
+    };
+
+    // The input is wrapped like the window wraps it: leaving room for its
+    // decoration, which is right-aligned on the first row, and with one star
+    // per character when masked.
+    let mut input_rows = Vec::new();
+    if !input.hidden {
+        let decoration_columns = input
+            .decoration
+            .as_ref()
+            .map_or(0, |decoration| decoration.chars().count() + 1);
+        let mut text = Wrapped::from(if input.options.masked {
+            "*".repeat(input.buffer.chars().count())
+        } else {
+            input.buffer.to_string()
+        });
+        let input_columns = columns.saturating_sub(decoration_columns).max(1);
+        for (index, row) in text.lines(input_columns).enumerate() {
+            let mut segments = vec![(row.to_string(), Color::WHITE)];
+            if let (0, Some(decoration)) = (index, &input.decoration) {
+                let padding =
+                    columns.saturating_sub(row.chars().count() + decoration.chars().count());
+                segments.push((" ".repeat(padding), Color::WHITE));
+                segments.push((decoration.clone(), DECORATION_COLOR));
+            }
+            input_rows.push(Row {
+                background: None,
+                indent: 0,
+                segments,
+            });
+        }
+    }
+    let output_rows = rows.saturating_sub(input_rows.len());
+
+    let wrap_columns = scrollback.wrap_columns();
+    let horizontal_scroll = if scrollback.no_wrap {
+        scrollback.view.horizontal_scroll
+    } else {
+        0
+    };
+    let line_count = scrollback.len();
+    let mut skipped = 0;
+    let mut displayed = Vec::new();
+    'lines: for index in 0..scrollback.events.len() {
+        if !scrollback.channels.is_visible(&scrollback.events[index]) {
+            continue;
+        }
+        scrollback.events[index].rewrap(wrap_columns);
+        let line = &scrollback.events[index];
+        let color = scrollback.channels.color_of(line).unwrap_or(Color::WHITE);
+        let background = scrollback.background_of(line_count - 1 - index, line);
+        let message_width = layout::message_width(line);
+        for range in line.line_ranges().iter().rev() {
+            // Rows below the view are skipped.
+            if skipped < scrollback.view.scroll {
+                skipped += 1;
+                continue;
+            }
+            if displayed.len() == output_rows {
+                break 'lines;
+            }
+            let placement = RowPlacement::new(line, range.clone(), message_width, columns);
+            let (indent, _, range) = placement.scrolled(line, range.clone(), horizontal_scroll);
+            let segments = line
+                .segments(range)
+                .into_iter()
+                .map(|(segment, segment_color)| {
+                    let text = line[segment].trim_end_matches('\n').to_string();
+                    (text, foreground(segment_color.unwrap_or(color)))
+                })
+                .collect();
+            displayed.push(Row {
+                background,
+                indent,
+                segments,
+            });
+        }
+    }
+    displayed.reverse();
+    displayed.extend(input_rows);
+    (columns, rows, displayed)
+}
+
+struct Image {
+    width: usize,
+    height: usize,
+    // RGB, from the top-left corner.
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    fn render(displayed: &[Row], columns: usize, rows: usize) -> Self {
+        let font = Font::try_from_bytes(include_bytes!("../bundled-font/mononoki-Regular.ttf"))
+            .expect("the bundled font is valid");
+        let scale = Scale::uniform(FONT_SIZE);
+        let metrics = font.v_metrics(scale);
+        let cell_width = font
+            .glyph('m')
+            .scaled(scale)
+            .h_metrics()
+            .advance_width
+            .ceil() as usize;
+        let cell_height = (metrics.ascent - metrics.descent + metrics.line_gap).ceil() as usize;
+        let mut image = Self {
+            width: columns * cell_width,
+            height: rows * cell_height,
+            pixels: Vec::new(),
+        };
+        image.pixels =
+            [BACKGROUND.red, BACKGROUND.green, BACKGROUND.blue].repeat(image.width * image.height);
+
+        for (row_index, row) in displayed.iter().enumerate() {
+            let top = row_index * cell_height;
+            if let Some(background) = row.background {
+                // Backgrounds span the whole width, not just the text.
+                for y in top..top + cell_height {
+                    for x in 0..image.width {
+                        image.blend(x, y, background, 1.);
+                    }
+                }
+            }
+            let mut column = row.indent;
+            for (text, color) in &row.segments {
+                for ch in text.chars() {
+                    if column >= columns {
+                        break;
+                    }
+                    let glyph = font.glyph(ch).scaled(scale).positioned(point(
+                        (column * cell_width) as f32,
+                        top as f32 + metrics.ascent,
+                    ));
+                    if let Some(bounds) = glyph.pixel_bounding_box() {
+                        glyph.draw(|x, y, coverage| {
+                            let x = bounds.min.x + x as i32;
+                            let y = bounds.min.y + y as i32;
+                            if let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) {
+                                image.blend(x, y, *color, coverage);
+                            }
+                        });
+                    }
+                    column += 1;
+                }
+            }
+        }
+        image
+    }
+
+    // Blends `color` over the pixel at `x` and `y`, with `coverage` from 0
+    // to 1.
+    fn blend(&mut self, x: usize, y: usize, color: Color, coverage: f32) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let offset = (y * self.width + x) * 3;
+        for (channel, value) in
+            self.pixels[offset..offset + 3]
+                .iter_mut()
+                .zip([color.red, color.green, color.blue])
+        {
+            let blended = f32::from(*channel) * (1. - coverage) + f32::from(value) * coverage;
+            *channel = blended.round() as u8;
+        }
+    }
+}
+
+#[test]
+fn screenshot_rows() {
+    use std::sync::Arc;
+
+    use crate::scrollback::Line;
+    use crate::{Config, Shared};
+
+    let state = State::new(
+        Arc::new(Shared::from(Config::default())),
+        String::from("main"),
+        true,
+        crate::events::channel(&Config::default()).0,
+    );
+    for line in ["first", "second", "third"] {
+        state.push(Line::from(String::from(line)));
+    }
+    let mut scrollback = state.scrollback.lock();
+    scrollback.columns = 10;
+    scrollback.rows = 3;
+    drop(scrollback);
+    *state.input.lock().buffer = String::from("typed");
+
+    let (columns, rows, displayed) = displayed_rows(&state);
+    let text = displayed
+        .iter()
+        .map(|row| {
+            row.segments
+                .iter()
+                .map(|(text, _)| text.as_str())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(text, ["second", "third", "typed"]);
+
+    let image = Image::render(&displayed, columns, rows);
+    assert_eq!(image.pixels.len(), image.width * image.height * 3);
+    assert!(image.pixels.iter().any(|&channel| channel > 0));
+}
+
+#[test]
+fn screenshot_pixels() {
+    use std::sync::Arc;
+
+    use regex::Regex;
+
+    use crate::scrollback::Line;
+    use crate::{Config, Console, Shared};
+
+    let (sender, app) = crate::events::channel(&Config::default());
+    let shared = Arc::new(Shared::from(Config::default()));
+    let console = Console::new(
+        Arc::new(State::new(shared, String::from("main"), false, sender)),
+        app,
+    );
+    let red = Color::rgb(255, 0, 0);
+    let blue = Color::rgb(0, 0, 255);
+    console.add_highlight(Regex::new("build").unwrap(), Style::new(red), false);
+    // The rule is redrawn within the screenshot's columns, which restyles it.
+    console.push_rule(Some("build"));
+    let mut filled = Line::from(String::from(" "));
+    filled.background = Some(blue);
+    console.state.push(filled);
+    let mut scrollback = console.state.scrollback.lock();
+    scrollback.columns = 20;
+    scrollback.rows = 3;
+    drop(scrollback);
+
+    let (columns, rows, displayed) = displayed_rows(&console.state);
+    let image = Image::render(&displayed, columns, rows);
+    let cell_height = image.height / rows;
+    let row_pixels = |row: usize| {
+        image.pixels[row * cell_height * image.width * 3..(row + 1) * cell_height * image.width * 3]
+            .chunks(3)
+            .map(|pixel| Color::rgb(pixel[0], pixel[1], pixel[2]))
+            .collect::<Vec<_>>()
+    };
+
+    // The highlighted title is drawn in red among the white dashes. Glyphs
+    // are blended over the black background, so their edges, and the thin
+    // dashes, are darker shades of their colors.
+    let rule = row_pixels(0);
+    assert!(rule
+        .iter()
+        .any(|pixel| pixel.red > 200 && pixel.green == 0 && pixel.blue == 0));
+    assert!(rule
+        .iter()
+        .any(|pixel| pixel.green > 0 && pixel.red == pixel.green && pixel.blue == pixel.green));
+    assert!(rule
+        .iter()
+        .all(|pixel| pixel.green == pixel.blue && pixel.red >= pixel.green));
+    // Backgrounds fill their rows exactly.
+    assert!(row_pixels(1).iter().all(|&pixel| pixel == blue));
+    // The empty input leaves the background showing.
+    assert!(row_pixels(2).iter().all(|&pixel| pixel == BACKGROUND));
+}
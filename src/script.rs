@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Weak};
+
+use parking_lot::Mutex;
+use rhai::{Dynamic, Engine, EvalAltResult, FnPtr, AST, INT};
+
+use crate::command::{Arguments, CommandError, CommandInfo, ConsoleCommand};
+use crate::scrollback::Line;
+use crate::{Color, HighlightId, Level, Regex, Shortcut, State, Style};
+
+// The shortcuts bound by a script, and the functions they call.
+type Bound = Arc<Mutex<Vec<(Shortcut, FnPtr)>>>;
+
+/// The shortcuts bound by the scripts run in a tab.
+#[derive(Default)]
+pub(crate) struct Scripts {
+    bindings: HashMap<Shortcut, (Arc<Script>, FnPtr)>,
+}
+
+// A script that was run, kept to call the functions it bound to shortcuts.
+struct Script {
+    engine: Engine,
+    ast: AST,
+    // Bindings made since the script last finished running, which are added
+    // to the tab's bindings once it does.
+    bound: Bound,
+}
+
+/// Returns an engine whose scripts control `state`'s tab with these
+/// functions:
+///
+/// - `push_line(text)`
+/// - `input()`, returning the text being typed, and `clear_input()`
+/// - `add_highlight(pattern, red, green, blue)` and `add_gag(pattern, keep)`,
+///   returning a rule that can be passed to `remove_highlight(rule)`
+/// - `bind_key(shortcut, function)`, calling `function` when a shortcut such
+///   as `"Ctrl+R"` is pressed, instead of the action it's bound to otherwise
+///
+/// The functions fail once the tab is closed.
+fn engine(state: &Arc<State>, bound: &Bound) -> Engine {
+    let mut engine = Engine::new();
+    engine.register_type_with_name::<HighlightId>("Rule");
+
+    let target = Arc::downgrade(state);
+    engine.register_fn("push_line", move |text: &str| {
+        let state = upgrade(&target)?;
+        state.push(Line::from(text.to_string()));
+        state.redraw();
+        Ok::<_, Box<EvalAltResult>>(())
+    });
+    let target = Arc::downgrade(state);
+    engine.register_fn("input", move || {
+        Ok::<_, Box<EvalAltResult>>(upgrade(&target)?.input.lock().as_str().to_string())
+    });
+    let target = Arc::downgrade(state);
+    engine.register_fn("clear_input", move || {
+        let state = upgrade(&target)?;
+        state.clear_input();
+        state.redraw();
+        Ok::<_, Box<EvalAltResult>>(())
+    });
+
+    let target = Arc::downgrade(state);
+    engine.register_fn(
+        "add_highlight",
+        move |pattern: &str, red: INT, green: INT, blue: INT| {
+            let color = Color::rgb(channel(red)?, channel(green)?, channel(blue)?);
+            let pattern = regex(pattern)?;
+            Ok::<_, Box<EvalAltResult>>(upgrade(&target)?.highlights.lock().add(
+                pattern,
+                Some(Style::new(color)),
+                false,
+                false,
+            ))
+        },
+    );
+    let target = Arc::downgrade(state);
+    engine.register_fn("add_gag", move |pattern: &str, keep: bool| {
+        let gag = if keep {
+            crate::highlight::Gag::Hide
+        } else {
+            crate::highlight::Gag::Discard
+        };
+        let pattern = regex(pattern)?;
+        Ok::<_, Box<EvalAltResult>>(upgrade(&target)?.highlights.lock().add_gag(pattern, gag))
+    });
+    let target = Arc::downgrade(state);
+    engine.register_fn("remove_highlight", move |rule: HighlightId| {
+        Ok::<_, Box<EvalAltResult>>(upgrade(&target)?.highlights.lock().remove(rule))
+    });
+
+    let bound = bound.clone();
+    engine.register_fn("bind_key", move |shortcut: &str, function: FnPtr| {
+        let shortcut = shortcut
+            .parse::<Shortcut>()
+            .map_err(|err| Box::<EvalAltResult>::from(err.to_string()))?;
+        bound.lock().push((shortcut, function));
+        Ok::<_, Box<EvalAltResult>>(())
+    });
+    engine
+}
+
+/// Runs the script at `path` with the functions described by [`engine`],
+/// keeping it to call the functions it binds to shortcuts.
+pub(crate) fn load(state: &Arc<State>, path: &Path) -> Result<(), Box<EvalAltResult>> {
+    let bound = Bound::default();
+    let engine = engine(state, &bound);
+    let ast = engine.compile_file(path.to_path_buf())?;
+    let script = Arc::new(Script { engine, ast, bound });
+    let result = script.engine.run_ast(&script.ast);
+    // Bindings made before an error still apply.
+    add_bindings(state, &script);
+    result
+}
+
+/// Calls the function a script bound to `shortcut`, returning false if no
+/// script bound it. Errors are reported in the tab.
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub(crate) fn press(state: &State, shortcut: Shortcut) -> bool {
+    let binding = state.scripts.lock().bindings.get(&shortcut).cloned();
+    let Some((script, function)) = binding else {
+        return false;
+    };
+    if let Err(err) = function.call::<Dynamic>(&script.engine, &script.ast, ()) {
+        state.report_error(format_args!("error running script: {err}"));
+    }
+    add_bindings(state, &script);
+    true
+}
+
+fn add_bindings(state: &State, script: &Arc<Script>) {
+    let mut scripts = state.scripts.lock();
+    for (shortcut, function) in script.bound.lock().drain(..) {
+        scripts
+            .bindings
+            .insert(shortcut, (script.clone(), function));
+    }
+}
+
+/// The `/script` command, which the console runs itself rather than passing
+/// to the app.
+pub(crate) enum ScriptCommand {
+    Load { path: String },
+}
+
+impl ConsoleCommand for ScriptCommand {
+    fn commands() -> &'static [CommandInfo] {
+        &[CommandInfo {
+            name: "script",
+            usage: "/script load <path>",
+            help: "Runs the rhai script at <path>.",
+        }]
+    }
+
+    fn parse_command(name: &str, arguments: &mut Arguments) -> Result<Self, CommandError> {
+        if name != "script" {
+            return Err(CommandError::Unknown(name.to_string()));
+        }
+        let action = arguments.next::<String>("action")?;
+        match action.as_str() {
+            "load" => Ok(Self::Load {
+                path: arguments.next("path")?,
+            }),
+            _ => Err(CommandError::Invalid {
+                argument: "action",
+                value: action,
+                reason: String::from("expected load"),
+            }),
+        }
+    }
+}
+
+/// Runs `submitted` if it's a `/script` command, returning false if it isn't
+/// one. Problems are pushed as errors.
+pub(crate) fn run_command(state: &Arc<State>, submitted: &str) -> bool {
+    let command = match ScriptCommand::parse(submitted) {
+        Ok(command) => command,
+        Err(CommandError::NotACommand | CommandError::Unknown(_)) => return false,
+        Err(err) => {
+            let usage = ScriptCommand::commands()[0].usage;
+            state.push(Line::from(format!("{err}\nusage: {usage}")).with_level(Level::Error));
+            state.redraw();
+            return true;
+        }
+    };
+    let ScriptCommand::Load { path } = command;
+    if let Err(err) = load(state, Path::new(&path)) {
+        state.push(Line::from(format!("error running script: {err}")).with_level(Level::Error));
+        state.redraw();
+    }
+    true
+}
+
+fn upgrade(state: &Weak<State>) -> Result<Arc<State>, Box<EvalAltResult>> {
+    state
+        .upgrade()
+        .ok_or_else(|| "the console was closed".into())
+}
+
+fn regex(pattern: &str) -> Result<Regex, Box<EvalAltResult>> {
+    Regex::new(pattern).map_err(|err| err.to_string().into())
+}
+
+fn channel(value: INT) -> Result<u8, Box<EvalAltResult>> {
+    u8::try_from(value).map_err(|_| format!("color channel out of range: {value}").into())
+}
+
+#[cfg(test)]
+fn test_state() -> Arc<State> {
+    use crate::{Config, Shared};
+
+    let (sender, _events) = crate::events::channel(&Config::default());
+    Arc::new(State::new(
+        Arc::new(Shared::from(Config::default())),
+        String::from("main"),
+        false,
+        sender,
+    ))
+}
+
+#[test]
+fn script_bindings() {
+    let state = test_state();
+    let engine = engine(&state, &Bound::default());
+    engine
+        .run(
+            r#"
+            let rule = add_gag("^PING", false);
+            push_line("PING :server");
+            push_line("ERROR 42");
+            add_highlight("\\d+", 255, 0, 0);
+            push_line("ERROR 43");
+            if !remove_highlight(rule) { throw "not removed"; }
+            push_line("PING :again");
+            "#,
+        )
+        .unwrap();
+    let scrollback = state.scrollback.lock();
+    let lines = scrollback
+        .events
+        .iter()
+        .rev()
+        .map(|line| line.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(lines, ["ERROR 42", "ERROR 43", "PING :again"]);
+    assert_eq!(scrollback.events[1].spans.len(), 1);
+    drop(scrollback);
+
+    assert!(engine.run(r#"add_highlight("(", 0, 0, 0)"#).is_err());
+    assert!(engine.run(r#"add_highlight("a", 256, 0, 0)"#).is_err());
+    assert!(engine.run(r#"bind_key("Ctrl+Nope", || 1)"#).is_err());
+}
+
+#[test]
+fn loaded_scripts() {
+    let state = test_state();
+    let path = std::env::temp_dir().join(format!("script-{}.rhai", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"
+        bind_key("Ctrl+R", || push_line("reloaded"));
+        bind_key("Ctrl+B", || bind_key("Ctrl+R", || push_line("rebound")));
+        "#,
+    )
+    .unwrap();
+
+    assert!(!run_command(&state, "/connect example.com"));
+    assert!(run_command(&state, "/script reload"));
+    assert!(run_command(
+        &state,
+        &format!("/script load \"{}\"", path.display())
+    ));
+    std::fs::remove_file(&path).unwrap();
+    assert!(run_command(
+        &state,
+        &format!("/script load \"{}\"", path.display())
+    ));
+
+    let ctrl = |key: &str| format!("Ctrl+{key}").parse::<Shortcut>().unwrap();
+    assert!(press(&state, ctrl("R")));
+    assert!(press(&state, ctrl("B")));
+    assert!(press(&state, ctrl("R")));
+    assert!(!press(&state, ctrl("S")));
+
+    let scrollback = state.scrollback.lock();
+    let lines = scrollback
+        .events
+        .iter()
+        .rev()
+        .map(|line| (line.level, line.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0].0, Level::Error);
+    assert!(lines[0].1.starts_with("invalid <action> \"reload\""));
+    assert_eq!(lines[1].0, Level::Error);
+    assert!(lines[1].1.starts_with("error running script"));
+    assert_eq!(
+        &lines[2..],
+        [(Level::Info, "reloaded"), (Level::Info, "rebound")]
+    );
+}
@@ -1,11 +1,931 @@
 use std::collections::VecDeque;
+#[cfg(feature = "spill")]
+use std::io;
+use std::ops::{Deref, DerefMut, Range};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
 
-use crate::wrap::Wrapped;
+use crate::channel::Channels;
+use crate::chat::{self, Sender};
+use crate::drawing::Drawing;
+use crate::gutter::Gutter;
+use crate::highlight::Restyle;
+use crate::selection::{self, Granularity, Selection, TextPosition};
+#[cfg(feature = "spill")]
+use crate::spill::{Spill, SEGMENT_LINES};
+use crate::style::Span;
+use crate::width::{self, Alignment};
+use crate::wrap::{self, Wrapped};
+use crate::Color;
 
-#[derive(Default)]
-pub struct Scrollback {
-    pub events: VecDeque<Wrapped>,
+/// The severity of a pushed line.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Level {
+    #[default]
+    Info,
+    Warning,
+    Error,
+}
+
+impl Level {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Info => "info",
+            Level::Warning => "warning",
+            Level::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Line {
+    text: Wrapped,
+    pub channel: Option<Arc<str>>,
+    pub color: Option<Color>,
+    /// Filled behind the line across the console's whole width.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub background: Option<Color>,
+    pub spans: Vec<Span>,
+    pub level: Level,
+    pub timestamp: SystemTime,
+    /// The name of the mark placed on this line, if any.
+    pub mark: Option<String>,
+    /// Displayed in the gutter when using [`Gutter::Icons`](crate::Gutter::Icons).
+    pub icon: Option<char>,
+    /// How each row is placed within the console's width. If `None`,
+    /// right-to-left text is aligned to the right and other text to the left.
+    pub alignment: Option<Alignment>,
+    /// Set for messages pushed with
+    /// [`Console::push_message`](crate::Console::push_message).
+    pub sender: Option<Sender>,
+    /// Set while the line is being written by a [`Stream`](crate::Stream),
+    /// or while it is a list waiting for the user to choose an item.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub stream: Option<u64>,
+    /// Set when a gag rule matched the line. Gagged lines aren't displayed,
+    /// but are kept for exporting.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub gagged: bool,
+    /// The label of the [`Console::labeled`](crate::Console::labeled) handle
+    /// that pushed this line.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub producer: Option<Arc<str>>,
+    /// Set for rules and boxes, which are redrawn to span the console's
+    /// width.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub drawing: Option<Drawing>,
+    /// The columns before each of the line's rows, from
+    /// [`Console::indent`](crate::Console::indent).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub indent: usize,
+    // Colors the drawing again each time it's redrawn.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) restyle: Option<Restyle>,
+    // Identifies the line while lines are inserted and removed around it,
+    // which changes its position.
+    #[cfg_attr(feature = "serde", serde(skip, default = "next_line_id"))]
+    pub(crate) id: u64,
+}
+
+fn next_line_id() -> u64 {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+impl Line {
+    pub fn new(text: String, channel: Option<Arc<str>>) -> Self {
+        Self {
+            text: Wrapped::from(text),
+            channel,
+            color: None,
+            background: None,
+            spans: Vec::new(),
+            level: Level::Info,
+            timestamp: SystemTime::now(),
+            mark: None,
+            icon: None,
+            alignment: None,
+            sender: None,
+            stream: None,
+            gagged: false,
+            producer: None,
+            drawing: None,
+            indent: 0,
+            restyle: None,
+            id: next_line_id(),
+        }
+    }
+
+    pub fn with_level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    pub fn with_color(mut self, color: Option<Color>) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Inserts `prefix` before the line's text, keeping its spans on the
+    /// same text.
+    pub fn prefix(&mut self, prefix: &str) {
+        self.insert_str(0, prefix);
+        for span in &mut self.spans {
+            span.range.start += prefix.len();
+            span.range.end += prefix.len();
+        }
+    }
+
+    /// Wraps the line to fit within `columns`, less its indent. Messages are
+    /// wrapped within a narrower width.
+    pub fn rewrap(&mut self, columns: usize) {
+        let columns = if columns == usize::MAX {
+            columns
+        } else {
+            columns.saturating_sub(self.indent).max(1)
+        };
+        // Without wrapping, drawings keep the width they were last drawn at.
+        // Spans don't carry over to the redrawn text, so the rules that
+        // colored the drawing are applied again.
+        if let (Some(drawing), true) = (&self.drawing, columns != usize::MAX) {
+            let label = self
+                .producer
+                .as_ref()
+                .map(|label| format!("[{label}] "))
+                .unwrap_or_default();
+            let drawn = format!(
+                "{label}{}",
+                drawing.draw(columns.saturating_sub(width::width(&label)).max(1))
+            );
+            if self.text.as_str() != drawn {
+                self.text.replace_range(.., &drawn);
+                self.spans.clear();
+                if let Some(restyle) = self.restyle.clone() {
+                    restyle.apply(self);
+                }
+            }
+        }
+        let columns = if self.sender.is_some() {
+            chat::message_columns(columns)
+        } else {
+            columns
+        };
+        self.text.rewrap(columns);
+    }
+
+    /// Splits `row` into segments that share the same span color. Segments
+    /// without a span use the line's color.
+    pub fn segments(&self, row: Range<usize>) -> Vec<(Range<usize>, Option<Color>)> {
+        let spans = self
+            .spans
+            .iter()
+            .map(|span| (span.range.clone(), span.color));
+        wrap::fragments(row, spans)
+    }
+}
+
+impl From<String> for Line {
+    fn from(text: String) -> Self {
+        Self::new(text, None)
+    }
+}
+
+impl Deref for Line {
+    type Target = Wrapped;
+
+    fn deref(&self) -> &Self::Target {
+        &self.text
+    }
+}
+
+impl DerefMut for Line {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.text
+    }
+}
+
+/// The part of the lines that a view displays. Each view of the same lines
+/// keeps its own position and selection.
+#[derive(Debug, Default, Clone)]
+pub struct Viewport {
+    /// The number of rows between the bottom of the view and the most recent
+    /// output.
     pub scroll: usize,
     pub maximum_scroll: usize,
+    /// The number of lines pushed while the view has been scrolled away from
+    /// the most recent output.
+    pub unseen: usize,
+    pub selection: Option<Selection>,
+    /// The number of columns scrolled past on the left while lines aren't
+    /// wrapped.
+    pub horizontal_scroll: usize,
+    pub maximum_horizontal_scroll: usize,
+    /// When true, the view stays in place as output arrives, even while
+    /// showing the most recent output.
+    pub frozen: bool,
+}
+
+impl Viewport {
+    // Returns true if the view must be moved to stay in place as output
+    // arrives.
+    fn is_stationary(&self) -> bool {
+        self.scroll != 0 || self.frozen
+    }
+
+    /// Scrolls toward older output by `lines`, or toward newer output if
+    /// negative.
+    pub fn scroll_by(&mut self, lines: isize) {
+        let scroll = self.scroll.saturating_add_signed(lines);
+        if lines > 0 {
+            self.scroll_to(scroll.min(self.maximum_scroll));
+        } else {
+            self.scroll_to(scroll);
+        }
+    }
+
+    pub fn scroll_to(&mut self, scroll: usize) {
+        self.scroll = scroll;
+        if scroll == 0 {
+            self.unseen = 0;
+        }
+    }
+
+    /// Scrolls to the most recent output.
+    pub fn reset_scroll(&mut self) {
+        self.scroll_to(0);
+    }
+
+    /// Sets how far the view can scroll, moving it back within the new
+    /// limits. Returns true if the view moved.
+    pub fn set_limits(&mut self, maximum_scroll: usize, maximum_horizontal_scroll: usize) -> bool {
+        self.maximum_scroll = maximum_scroll;
+        self.maximum_horizontal_scroll = maximum_horizontal_scroll;
+        let mut moved = false;
+        if self.horizontal_scroll > maximum_horizontal_scroll {
+            self.horizontal_scroll = maximum_horizontal_scroll;
+            moved = true;
+        }
+        if self.scroll > maximum_scroll {
+            self.scroll = maximum_scroll;
+            moved = true;
+        }
+        moved
+    }
+
+    /// Scrolls right by `columns`, or left if negative.
+    pub fn scroll_horizontally(&mut self, columns: isize) {
+        self.horizontal_scroll = self
+            .horizontal_scroll
+            .saturating_add_signed(columns)
+            .min(self.maximum_horizontal_scroll);
+    }
+}
+
+/// The lines of a tab, shared by the tab's views.
+#[derive(Default)]
+pub struct Scrollback {
+    pub events: VecDeque<Line>,
     pub columns: usize,
+    /// The rows the console was last displayed within, including the input
+    /// line, or 0 if it hasn't been displayed.
+    pub rows: usize,
+    /// The view that follows new output unless scrolled.
+    pub view: Viewport,
+    /// A second view shown above `view` while split, which stays in place
+    /// as output arrives.
+    pub pinned: Option<Viewport>,
+    /// Lines pushed while output is paused.
+    pub pending: Option<Vec<Line>>,
+    pub channels: Channels,
+    pub gutter: Gutter,
+    /// Filled behind every other line without its own background.
+    pub alternate_background: Option<Color>,
+    /// When true, lines are only broken at newlines, and rows wider than the
+    /// view are scrolled horizontally.
+    pub no_wrap: bool,
+    /// The app's `no_wrap` and `alternate_background`, which are restored
+    /// when the config file stops setting them.
+    #[cfg(feature = "config-file")]
+    pub app_no_wrap: bool,
+    #[cfg(feature = "config-file")]
+    pub app_alternate_background: Option<Color>,
+    /// The number of lines hidden by gag rules since the scrollback was
+    /// cleared, including discarded lines.
+    pub gagged: usize,
+    /// Where the oldest lines are spilled out of memory, if anywhere.
+    #[cfg(feature = "spill")]
+    pub spill: Option<Spill>,
+}
+
+impl Scrollback {
+    /// Returns the width lines are wrapped within.
+    pub fn wrap_columns(&self) -> usize {
+        if self.no_wrap {
+            usize::MAX
+        } else {
+            self.columns
+        }
+    }
+
+    /// Returns the color to fill behind the line at `index`, counting from
+    /// the oldest line.
+    pub fn background_of(&self, index: usize, line: &Line) -> Option<Color> {
+        line.background
+            .or_else(|| self.alternate_background.filter(|_| index % 2 == 1))
+    }
+
+    /// Returns the number of lines, including those spilled out of memory.
+    pub fn len(&self) -> usize {
+        #[cfg(feature = "spill")]
+        if let Some(spill) = &self.spill {
+            return self.events.len() + spill.len();
+        }
+        self.events.len()
+    }
+
+    fn views_mut(&mut self) -> impl Iterator<Item = &mut Viewport> {
+        std::iter::once(&mut self.view).chain(self.pinned.as_mut())
+    }
+
+    // Returns true if any view stays in place as output arrives.
+    fn is_scrolled(&self) -> bool {
+        self.views().any(Viewport::is_stationary)
+    }
+
+    pub fn views(&self) -> impl Iterator<Item = &Viewport> {
+        std::iter::once(&self.view).chain(self.pinned.as_ref())
+    }
+
+    /// Splits the view, freezing a copy of it above a view that follows new
+    /// output. Does nothing if already split.
+    pub fn split(&mut self) {
+        if self.pinned.is_none() {
+            self.pinned = Some(Viewport {
+                selection: None,
+                unseen: 0,
+                frozen: true,
+                ..self.view.clone()
+            });
+            self.view.reset_scroll();
+        }
+    }
+
+    pub fn unsplit(&mut self) {
+        self.pinned = None;
+    }
+
+    pub fn push(&mut self, mut line: Line) {
+        if self.is_scrolled() && self.channels.is_visible(&line) {
+            // When a view is scrolled, keep it at the same position
+            line.rewrap(self.wrap_columns());
+            let line_count = line.line_ranges().len();
+            for view in self.views_mut().filter(|view| view.is_stationary()) {
+                view.scroll += line_count;
+                view.unseen += 1;
+            }
+        }
+        self.events.push_front(line);
+        #[cfg(feature = "spill")]
+        self.spill_oldest();
+    }
+
+    /// Pushes `line`, or holds it in `pending` while output is paused.
+    pub fn append(&mut self, line: Line) {
+        if let Some(pending) = &mut self.pending {
+            pending.push(line);
+        } else {
+            self.push(line);
+        }
+    }
+
+    // Spills the oldest lines once there are enough beyond those kept in
+    // memory. Lines a view is scrolled back to are kept in memory too.
+    #[cfg(feature = "spill")]
+    fn spill_oldest(&mut self) {
+        let Some(resident) = self
+            .spill
+            .as_ref()
+            .filter(|spill| !spill.failed)
+            .map(|spill| spill.resident)
+        else {
+            return;
+        };
+        if self.events.len() < resident + SEGMENT_LINES
+            || self.events.len() < self.shown_lines() + SEGMENT_LINES
+        {
+            return;
+        }
+        let Some(spill) = &mut self.spill else {
+            return;
+        };
+        let mut lines = self.events.split_off(self.events.len() - SEGMENT_LINES);
+        if let Err(err) = spill.push(lines.make_contiguous()) {
+            spill.failed = true;
+            self.events.append(&mut lines);
+            let error = format!("error spilling scrollback, keeping it in memory: {err}");
+            self.events.push_front(
+                Line::from(error)
+                    .with_level(Level::Error)
+                    .with_color(Some(crate::ERROR_COLOR)),
+            );
+        }
+    }
+
+    // Returns the number of the most recent lines needed to display every
+    // view at its scroll position.
+    #[cfg(feature = "spill")]
+    fn shown_lines(&mut self) -> usize {
+        let rows = self
+            .views()
+            .map(|view| view.scroll + self.rows)
+            .max()
+            .unwrap_or_default();
+        let columns = self.wrap_columns();
+        let mut covered = 0;
+        let mut lines = 0;
+        for line in &mut self.events {
+            if covered >= rows {
+                break;
+            }
+            lines += 1;
+            if self.channels.is_visible(line) {
+                line.rewrap(columns);
+                covered += line.line_ranges().len();
+            }
+        }
+        lines
+    }
+
+    /// Moves the newest spilled lines back into memory. Returns false if no
+    /// lines are spilled, or if the store has failed.
+    #[cfg(feature = "spill")]
+    pub fn page_in(&mut self) -> io::Result<bool> {
+        let Some(spill) = self.spill.as_mut().filter(|spill| !spill.failed) else {
+            return Ok(false);
+        };
+        match spill.pop() {
+            Ok(Some(lines)) => {
+                self.events.extend(lines);
+                Ok(true)
+            }
+            Ok(None) => Ok(false),
+            Err(err) => {
+                spill.failed = true;
+                Err(err)
+            }
+        }
+    }
+
+    /// Inserts `line` so that it is older than the `newer` most recent lines.
+    pub fn insert(&mut self, newer: usize, mut line: Line) {
+        if newer == 0 {
+            return self.push(line);
+        }
+
+        let newer = newer.min(self.events.len());
+        if self.is_scrolled() && self.channels.is_visible(&line) {
+            let columns = self.wrap_columns();
+            let mut newer_rows = 0;
+            for newer_line in self.events.iter_mut().take(newer) {
+                if self.channels.is_visible(newer_line) {
+                    newer_line.rewrap(columns);
+                    newer_rows += newer_line.line_ranges().len();
+                }
+            }
+            // Keep views at the same position when inserting below them.
+            line.rewrap(columns);
+            let rows = line.line_ranges().len();
+            for view in self
+                .views_mut()
+                .filter(|view| newer_rows < view.scroll || view.frozen)
+            {
+                view.scroll += rows;
+            }
+        }
+
+        // Positions count from the oldest line, so inserting shifts the
+        // selected lines.
+        let position = self.len() - newer;
+        self.clear_selection_if(|selection| selection.range().end.line >= position);
+        self.events.insert(newer, line);
+    }
+
+    /// Returns true if any line is marked `name`.
+    pub fn has_mark(&self, name: &str) -> bool {
+        self.events
+            .iter()
+            .any(|line| line.mark.as_deref() == Some(name))
+    }
+
+    /// Inserts `line` immediately before the most recent line marked `name`.
+    /// Returns false if no line is marked `name`.
+    pub fn insert_before_mark(&mut self, name: &str, line: Line) -> bool {
+        let Some(marked) = self
+            .events
+            .iter()
+            .position(|line| line.mark.as_deref() == Some(name))
+        else {
+            return false;
+        };
+        self.insert(marked + 1, line);
+        true
+    }
+
+    /// Inserts `line` after every line with an earlier or equal timestamp.
+    pub fn insert_chronologically(&mut self, line: Line) {
+        let newer = self
+            .events
+            .iter()
+            .take_while(|newer| newer.timestamp > line.timestamp)
+            .count();
+        self.insert(newer, line);
+    }
+
+    /// Calls `update` with the most recently pushed line, returning the
+    /// updated line. Returns None if there are no lines.
+    pub fn update_last(&mut self, update: impl FnOnce(&mut Line)) -> Option<&Line> {
+        if self
+            .pending
+            .as_ref()
+            .is_some_and(|pending| !pending.is_empty())
+        {
+            let line = self.pending.as_mut()?.last_mut()?;
+            update(line);
+            return Some(line);
+        }
+
+        self.update_at(0, update)
+    }
+
+    /// Calls `update` with the line being streamed with `id`, returning the
+    /// updated line. Returns None if no line is being streamed with `id`.
+    pub fn update_stream(&mut self, id: u64, update: impl FnOnce(&mut Line)) -> Option<&Line> {
+        let pending = self
+            .pending
+            .as_ref()
+            .and_then(|pending| pending.iter().position(|line| line.stream == Some(id)));
+        if let Some(index) = pending {
+            let line = &mut self.pending.as_mut()?[index];
+            update(line);
+            return Some(line);
+        }
+
+        let newer = self
+            .events
+            .iter()
+            .position(|line| line.stream == Some(id))?;
+        self.update_at(newer, update)
+    }
+
+    /// Removes the line being streamed with `id`, returning it. Returns None
+    /// if no line is being streamed with `id`.
+    pub fn remove_stream(&mut self, id: u64) -> Option<Line> {
+        if let Some(pending) = &mut self.pending {
+            if let Some(index) = pending.iter().position(|line| line.stream == Some(id)) {
+                return Some(pending.remove(index));
+            }
+        }
+
+        let newer = self
+            .events
+            .iter()
+            .position(|line| line.stream == Some(id))?;
+        if self.is_scrolled() && self.channels.is_visible(&self.events[newer]) {
+            let columns = self.wrap_columns();
+            let mut newer_rows = 0;
+            for newer_line in self.events.iter_mut().take(newer) {
+                if self.channels.is_visible(newer_line) {
+                    newer_line.rewrap(columns);
+                    newer_rows += newer_line.line_ranges().len();
+                }
+            }
+            // Keep views at the same position when removing below them.
+            let line = &mut self.events[newer];
+            line.rewrap(columns);
+            let rows = line.line_ranges().len();
+            for view in self
+                .views_mut()
+                .filter(|view| newer_rows < view.scroll || view.frozen)
+            {
+                view.scroll = view.scroll.saturating_sub(rows);
+            }
+        }
+
+        // Removing shifts the positions of newer lines.
+        let position = self.len() - newer - 1;
+        self.clear_selection_if(|selection| selection.range().end.line >= position);
+        self.events.remove(newer)
+    }
+
+    // Calls `update` with the line that is older than the `newer` most recent
+    // lines.
+    fn update_at(&mut self, newer: usize, update: impl FnOnce(&mut Line)) -> Option<&Line> {
+        // The selection may no longer fit within the updated line.
+        let position = self.len().checked_sub(newer + 1)?;
+        self.clear_selection_if(|selection| {
+            let range = selection.range();
+            range.start.line <= position && range.end.line >= position
+        });
+
+        let columns = self.wrap_columns();
+        let mut below = 0;
+        if self.is_scrolled() {
+            for newer_line in self.events.iter_mut().take(newer) {
+                if self.channels.is_visible(newer_line) {
+                    newer_line.rewrap(columns);
+                    below += newer_line.line_ranges().len();
+                }
+            }
+        }
+
+        let is_above = |view: &Viewport| below < view.scroll || view.frozen;
+        let below_views =
+            self.channels.is_visible(self.events.get(newer)?) && self.views().any(is_above);
+        if !below_views {
+            let line = self.events.get_mut(newer)?;
+            update(line);
+            return Some(line);
+        }
+
+        // The line is below a view, so keep the view in place as the line
+        // grows or shrinks.
+        let line = self.events.get_mut(newer)?;
+        line.rewrap(columns);
+        let rows = line.line_ranges().len();
+        update(line);
+        line.rewrap(columns);
+        let updated_rows = line.line_ranges().len();
+        for view in self.views_mut().filter(|view| is_above(view)) {
+            view.scroll = (view.scroll + updated_rows).saturating_sub(rows);
+        }
+        self.events.get(newer)
+    }
+
+    /// Marks the most recently pushed line.
+    pub fn mark(&mut self, name: String) {
+        let line = match &mut self.pending {
+            Some(pending) if !pending.is_empty() => pending.last_mut(),
+            _ => self.events.front_mut(),
+        };
+        if let Some(line) = line {
+            line.mark = Some(name);
+        }
+    }
+
+    /// Returns the scroll offset that shows the nearest marked line that is
+    /// older or newer than the current view at the top of `page_rows` rows.
+    pub fn mark_scroll(&mut self, older: bool, page_rows: usize) -> Option<usize> {
+        let columns = self.wrap_columns();
+        let mut newer_rows = 0;
+        let mut newer_mark = None;
+        for line in &mut self.events {
+            if !self.channels.is_visible(line) {
+                continue;
+            }
+            line.rewrap(columns);
+            let rows = line.line_ranges().len();
+            if line.mark.is_some() {
+                let scroll = (newer_rows + rows).saturating_sub(page_rows);
+                if older && scroll > self.view.scroll {
+                    return Some(scroll);
+                } else if !older && scroll < self.view.scroll {
+                    newer_mark = Some(scroll);
+                } else if !older {
+                    break;
+                }
+            }
+            newer_rows += rows;
+        }
+        newer_mark
+    }
+
+    /// Returns the line at `line`, counting from the oldest line.
+    pub fn line(&self, line: usize) -> Option<&Line> {
+        let index = self.len().checked_sub(line + 1)?;
+        self.events.get(index)
+    }
+
+    pub fn line_mut(&mut self, line: usize) -> Option<&mut Line> {
+        let index = self.len().checked_sub(line + 1)?;
+        self.events.get_mut(index)
+    }
+
+    pub fn unit_at(&self, position: TextPosition, granularity: Granularity) -> Range<TextPosition> {
+        let Some(line) = self.line(position.line) else {
+            return position..position;
+        };
+        let text_range = match granularity {
+            Granularity::Character => position.offset..position.offset,
+            Granularity::Word => selection::word_at(line, position.offset),
+            Granularity::Line => 0..line.len(),
+        };
+        TextPosition {
+            line: position.line,
+            offset: text_range.start,
+        }..TextPosition {
+            line: position.line,
+            offset: text_range.end,
+        }
+    }
+
+    /// Returns the pinned view if `pinned` is true and the output is split,
+    /// or otherwise the followed view.
+    pub fn view_mut(&mut self, pinned: bool) -> &mut Viewport {
+        match self.pinned.as_mut().filter(|_| pinned) {
+            Some(view) => view,
+            None => &mut self.view,
+        }
+    }
+
+    /// Returns the position of the line whose id is `id`, counting from the
+    /// oldest line, or None if it isn't in memory.
+    pub(crate) fn position_of(&self, id: u64) -> Option<usize> {
+        let newer = self.events.iter().position(|line| line.id == id)?;
+        Some(self.len() - newer - 1)
+    }
+
+    /// Starts a selection at `position` in the pinned view if `pinned` is
+    /// true and the output is split, or otherwise in the followed view. Only
+    /// one view has a selection at a time.
+    pub fn select(&mut self, pinned: bool, position: TextPosition, granularity: Granularity) {
+        let selection = Selection::new(self.unit_at(position, granularity), granularity);
+        self.clear_selection();
+        self.view_mut(pinned).selection = Some(selection);
+    }
+
+    /// Extends the selection in whichever view has one to `position`,
+    /// starting one in the followed view if neither does.
+    pub fn extend_selection(&mut self, position: TextPosition) {
+        let granularity = self
+            .selection()
+            .map_or(Granularity::Character, Selection::granularity);
+        let unit = self.unit_at(position, granularity);
+        let view = match &mut self.pinned {
+            Some(pinned) if pinned.selection.is_some() => pinned,
+            _ => &mut self.view,
+        };
+        match &mut view.selection {
+            Some(selection) => selection.extend_to(unit),
+            None => view.selection = Some(Selection::new(unit, granularity)),
+        }
+    }
+
+    /// Returns the selection of whichever view has one.
+    pub fn selection(&self) -> Option<&Selection> {
+        self.views().find_map(|view| view.selection.as_ref())
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.clear_selection_if(|_| true);
+    }
+
+    fn clear_selection_if(&mut self, clear: impl Fn(&Selection) -> bool) {
+        for view in self.views_mut() {
+            if view.selection.as_ref().is_some_and(&clear) {
+                view.selection = None;
+            }
+        }
+    }
+
+    pub fn selected_text(&self) -> Option<String> {
+        let selection = self.selection().filter(|s| !s.is_empty())?;
+        let range = selection.range();
+        let mut text = String::new();
+        for line_index in range.start.line..=range.end.line {
+            let Some(line) = self
+                .line(line_index)
+                .filter(|line| self.channels.is_visible(line))
+            else {
+                continue;
+            };
+            let Some(selected) = selection.range_in_line(line_index, line.len()) else {
+                continue;
+            };
+            if line_index != range.start.line {
+                text.push('\n');
+            }
+            text.push_str(&line[selected]);
+        }
+        Some(text)
+    }
+}
+
+#[test]
+fn mark_navigation() {
+    let mut scrollback = Scrollback {
+        columns: 80,
+        ..Scrollback::default()
+    };
+    for line in 0..10 {
+        scrollback.push(Line::from(line.to_string()));
+        if line % 4 == 0 {
+            scrollback.mark(format!("mark {line}"));
+        }
+    }
+
+    // Marks are on lines 0, 4, and 8, which have 9, 5, and 1 newer lines.
+    assert_eq!(scrollback.mark_scroll(true, 2), Some(4));
+    scrollback.view.scroll = 4;
+    assert_eq!(scrollback.mark_scroll(true, 2), Some(8));
+    assert_eq!(scrollback.mark_scroll(false, 2), Some(0));
+    scrollback.view.scroll = 8;
+    assert_eq!(scrollback.mark_scroll(true, 2), None);
+    assert_eq!(scrollback.mark_scroll(false, 2), Some(4));
+}
+
+#[test]
+fn viewport_scrolling() {
+    let mut view = Viewport {
+        maximum_scroll: 10,
+        unseen: 3,
+        ..Viewport::default()
+    };
+    view.scroll_by(4);
+    view.scroll_by(20);
+    assert_eq!((view.scroll, view.unseen), (10, 3));
+    view.scroll_by(isize::MIN);
+    assert_eq!((view.scroll, view.unseen), (0, 0));
+}
+
+#[test]
+fn split_views() {
+    let mut scrollback = Scrollback {
+        columns: 80,
+        ..Scrollback::default()
+    };
+    for line in 0..5 {
+        scrollback.push(Line::from(line.to_string()));
+    }
+    scrollback.view.scroll = 2;
+    scrollback.split();
+    assert_eq!(scrollback.view.scroll, 0);
+
+    // The pinned view stays in place while the other follows new output.
+    scrollback.push(Line::from(String::from("5")));
+    scrollback.update_last(|line| line.push_str("\nmore"));
+    assert_eq!(scrollback.pinned.as_ref().map(|view| view.scroll), Some(4));
+    assert_eq!(scrollback.view.scroll, 0);
+
+    // Even when split while showing the most recent output.
+    scrollback.unsplit();
+    scrollback.split();
+    scrollback.push(Line::from(String::from("6")));
+    let pinned = scrollback.pinned.as_ref().unwrap();
+    assert_eq!((pinned.scroll, pinned.unseen), (1, 1));
+
+    // Selections are made in the view they start in.
+    let position = |line, offset| TextPosition { line, offset };
+    scrollback.select(true, position(1, 0), Granularity::Line);
+    scrollback.extend_selection(position(2, 0));
+    assert!(scrollback.view.selection.is_none());
+    assert_eq!(scrollback.selected_text().as_deref(), Some("1\n2"));
+    scrollback.select(false, position(3, 0), Granularity::Word);
+    assert!(scrollback.pinned.as_ref().unwrap().selection.is_none());
+    assert_eq!(scrollback.selected_text().as_deref(), Some("3"));
+
+    scrollback.unsplit();
+    assert!(scrollback.pinned.is_none());
+}
+
+#[test]
+fn insertion() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let mut scrollback = Scrollback {
+        columns: 80,
+        ..Scrollback::default()
+    };
+    let at = |text: &str, seconds| {
+        let mut line = Line::from(String::from(text));
+        line.timestamp = UNIX_EPOCH + Duration::from_secs(seconds);
+        line
+    };
+    scrollback.push(at("one", 1));
+    scrollback.push(at("three", 3));
+    scrollback.mark(String::from("three"));
+    scrollback.push(at("four", 4));
+    scrollback.insert_chronologically(at("two", 2));
+    scrollback.insert_chronologically(at("five", 5));
+    assert!(scrollback.insert_before_mark("three", at("before three", 0)));
+    assert!(!scrollback.insert_before_mark("missing", at("", 0)));
+    let lines = scrollback
+        .events
+        .iter()
+        .rev()
+        .map(|line| line.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        ["one", "two", "before three", "three", "four", "five"]
+    );
+
+    // Inserting below a scrolled view keeps the view in place.
+    scrollback.view.scroll = 2;
+    scrollback.insert(1, Line::from(String::from("4.5")));
+    assert_eq!(scrollback.view.scroll, 3);
+    scrollback.insert(4, Line::from(String::from("2.5")));
+    assert_eq!(scrollback.view.scroll, 3);
 }
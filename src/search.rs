@@ -0,0 +1,375 @@
+use std::borrow::Cow;
+use std::io;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use regex::{Regex, RegexBuilder};
+
+use crate::scrollback::Line;
+use crate::{Shortcut, State};
+
+// The most lines cloned from memory each time the scrollback is locked.
+const CHUNK_LINES: usize = 1_000;
+
+/// How a search query is matched against lines.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct SearchOptions {
+    /// Matches the query as a regular expression rather than as plain text.
+    pub regex: bool,
+    pub ignore_case: bool,
+}
+
+impl SearchOptions {
+    pub fn regex(mut self, regex: bool) -> Self {
+        self.regex = regex;
+        self
+    }
+
+    pub fn ignore_case(mut self, ignore_case: bool) -> Self {
+        self.ignore_case = ignore_case;
+        self
+    }
+
+    /// Compiles `query` into the pattern that lines are matched against.
+    pub(crate) fn pattern(&self, query: &str) -> Result<Regex, regex::Error> {
+        let pattern = if self.regex {
+            Cow::Borrowed(query)
+        } else {
+            Cow::Owned(regex::escape(query))
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(self.ignore_case)
+            .build()
+    }
+}
+
+/// A line containing matches found by a [`Search`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SearchMatch {
+    /// The line's position, counting from the oldest line.
+    pub line: usize,
+    pub text: String,
+    /// The byte ranges within `text` that match.
+    pub ranges: Vec<Range<usize>>,
+}
+
+/// A search of every line in a tab, including lines spilled out of memory,
+/// running on a background thread. Created by
+/// [`Console::search`](crate::Console::search).
+///
+/// Matching lines are found newest first and can be received as soon as
+/// they're found. Lines pushed after the search started aren't searched.
+/// Dropping the search stops it.
+#[derive(Debug)]
+pub struct Search {
+    matches: flume::Receiver<SearchMatch>,
+    progress: Arc<Progress>,
+}
+
+#[derive(Debug, Default)]
+struct Progress {
+    found: AtomicUsize,
+    finished: AtomicBool,
+    cancelled: AtomicBool,
+    error: Mutex<Option<io::Error>>,
+}
+
+impl Search {
+    pub(crate) fn start(state: &Arc<State>, pattern: Regex) -> Self {
+        let (sender, matches) = flume::unbounded();
+        let progress = Arc::new(Progress::default());
+        let end = state.scrollback.lock().len();
+        let state = Arc::downgrade(state);
+        let thread_progress = progress.clone();
+        std::thread::Builder::new()
+            .name(String::from("search"))
+            .spawn(move || {
+                let progress = thread_progress;
+                let mut end = end;
+                while end > 0
+                    && !pattern.as_str().is_empty()
+                    && !progress.cancelled.load(Ordering::Relaxed)
+                {
+                    let Some(state) = state.upgrade() else {
+                        break;
+                    };
+                    let (positions, lines) = match older_lines(&state, end) {
+                        Ok(Some(older)) => older,
+                        Ok(None) => break,
+                        Err(err) => {
+                            *progress.error.lock() = Some(err);
+                            break;
+                        }
+                    };
+
+                    end = positions.start;
+                    let found = progress.found.load(Ordering::Relaxed);
+                    for (position, line) in positions.rev().zip(lines) {
+                        let ranges = find_all(&pattern, &line);
+                        if ranges.is_empty() {
+                            continue;
+                        }
+                        progress.found.fetch_add(1, Ordering::Relaxed);
+                        let found = SearchMatch {
+                            line: position,
+                            text: line.as_str().to_string(),
+                            ranges,
+                        };
+                        if sender.send(found).is_err() {
+                            return;
+                        }
+                    }
+                    // The number found may be displayed.
+                    if progress.found.load(Ordering::Relaxed) > found {
+                        state.redraw();
+                    }
+                }
+                progress.finished.store(true, Ordering::Relaxed);
+                if let Some(state) = state.upgrade() {
+                    state.redraw();
+                }
+            })
+            .expect("error spawning search thread");
+
+        Self { matches, progress }
+    }
+
+    /// Waits for the next match. Returns None once the search is finished and
+    /// every match has been received.
+    pub fn next_match(&self) -> Option<SearchMatch> {
+        self.matches.recv().ok()
+    }
+
+    /// Returns the next match if one has been found.
+    pub fn try_next_match(&self) -> Option<SearchMatch> {
+        self.matches.try_recv().ok()
+    }
+
+    /// Returns the number of matching lines found so far.
+    pub fn found(&self) -> usize {
+        self.progress.found.load(Ordering::Relaxed)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.progress.finished.load(Ordering::Relaxed)
+    }
+
+    /// Returns the error that finished the search early, such as failing to
+    /// read lines spilled out of memory. The older lines weren't searched.
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.progress.error.lock().take()
+    }
+}
+
+impl Drop for Search {
+    fn drop(&mut self) {
+        self.progress.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+// Returns the byte ranges of `text` matching `pattern`. Empty matches, such
+// as those of `a*`, aren't useful to highlight.
+fn find_all(pattern: &Regex, text: &str) -> Vec<Range<usize>> {
+    pattern
+        .find_iter(text)
+        .map(|found| found.range())
+        .filter(|range| !range.is_empty())
+        .collect()
+}
+
+/// The GUI's search prompt. Matches in the visible lines are highlighted as
+/// the query is typed, while matching lines in the whole history are counted
+/// in the background.
+#[derive(Debug, Default)]
+pub(crate) struct SearchPrompt {
+    query: String,
+    options: SearchOptions,
+    // The compiled query, or why it is invalid. None while the query is
+    // empty.
+    pattern: Option<Result<Regex, String>>,
+    search: Option<Search>,
+}
+
+impl SearchPrompt {
+    pub fn push(&mut self, ch: char, state: &Arc<State>) {
+        self.query.push(ch);
+        self.changed(state);
+    }
+
+    pub fn pop(&mut self, state: &Arc<State>) {
+        self.query.pop();
+        self.changed(state);
+    }
+
+    pub fn toggle_regex(&mut self, state: &Arc<State>) {
+        self.options.regex = !self.options.regex;
+        self.changed(state);
+    }
+
+    pub fn toggle_ignore_case(&mut self, state: &Arc<State>) {
+        self.options.ignore_case = !self.options.ignore_case;
+        self.changed(state);
+    }
+
+    // Recompiles the query and restarts counting the matching lines.
+    fn changed(&mut self, state: &Arc<State>) {
+        self.search = None;
+        if self.query.is_empty() {
+            self.pattern = None;
+            return;
+        }
+        let pattern = self.options.pattern(&self.query).map_err(|err| {
+            // Syntax errors point at the problem over several lines, ending
+            // with a description of it.
+            let message = err.to_string();
+            let description = message.lines().last().unwrap_or_default();
+            format!(
+                "invalid regex: {}",
+                description.trim_start_matches("error: ")
+            )
+        });
+        if let Ok(pattern) = &pattern {
+            self.search = Some(Search::start(state, pattern.clone()));
+        }
+        self.pattern = Some(pattern);
+    }
+
+    pub fn prompt(&self) -> String {
+        format!("Find: {}", self.query)
+    }
+
+    /// Returns the keys that toggle the options, and whether they're on.
+    pub fn toggles(&self) -> String {
+        let check = |on: bool| if on { "[x]" } else { "[ ]" };
+        format!(
+            "{} {primary}+I ignore case  {} {primary}+R regex",
+            check(self.options.ignore_case),
+            check(self.options.regex),
+            primary = Shortcut::PRIMARY
+        )
+    }
+
+    /// Returns why the query is invalid, or how many lines match it.
+    pub fn hint(&self) -> Option<String> {
+        match self.pattern.as_ref()? {
+            Err(err) => Some(err.clone()),
+            Ok(_) => {
+                let search = self.search.as_ref()?;
+                let found = search.found();
+                let lines = if found == 1 { "line" } else { "lines" };
+                Some(if search.progress.error.lock().is_some() {
+                    format!("{found} matching {lines}, older lines couldn't be read")
+                } else if search.is_finished() {
+                    format!("{found} matching {lines}")
+                } else {
+                    format!("{found} matching {lines} so far")
+                })
+            }
+        }
+    }
+
+    pub fn is_invalid(&self) -> bool {
+        matches!(self.pattern, Some(Err(_)))
+    }
+
+    /// Returns the byte ranges of `text` matching the query.
+    pub fn highlights(&self, text: &str) -> Vec<Range<usize>> {
+        match &self.pattern {
+            Some(Ok(pattern)) => find_all(pattern, text),
+            _ => Vec::new(),
+        }
+    }
+}
+
+// Returns the positions of the next lines older than `end`, and the lines
+// ordered newest first. Spilled lines are read and decoded after unlocking the
+// scrollback so that rendering isn't held up.
+fn older_lines(state: &State, end: usize) -> io::Result<Option<(Range<usize>, Vec<Line>)>> {
+    let scrollback = state.scrollback.lock();
+    // Lines may have been cleared since the search started.
+    let end = end.min(scrollback.len());
+    let resident_start = scrollback.len() - scrollback.events.len();
+    if end > resident_start {
+        let start = end.saturating_sub(CHUNK_LINES).max(resident_start);
+        let lines = (start..end)
+            .rev()
+            .filter_map(|position| scrollback.line(position).cloned())
+            .collect();
+        return Ok(Some((start..end, lines)));
+    }
+
+    #[cfg(feature = "spill")]
+    if let Some(reader) = scrollback.spill.as_ref().map(crate::spill::Spill::reader) {
+        drop(scrollback);
+        let Some(position) = end.checked_sub(1) else {
+            return Ok(None);
+        };
+        let (start, segment) = reader.read_containing(position)?;
+        // Lines may have been spilled since `end` was in memory, so the
+        // segment can include lines that were already searched.
+        let mut lines = crate::spill::decode(&segment)?;
+        let newer = (start + lines.len()).saturating_sub(end);
+        lines.drain(..newer.min(lines.len()));
+        return Ok(Some((start..end, lines)));
+    }
+
+    Ok(None)
+}
+
+#[test]
+fn background_search() {
+    use crate::{Config, Shared};
+
+    let (sender, _events) = crate::events::channel(&Config::default());
+    let state = Arc::new(State::new(
+        Arc::new(Shared::from(Config::default())),
+        String::from("main"),
+        true,
+        sender,
+    ));
+    for text in ["apple, apple", "banana", "apple pie and apple tart"] {
+        state.scrollback.lock().push(Line::from(String::from(text)));
+    }
+
+    let pattern = SearchOptions::default().pattern("apple").unwrap();
+    let search = Search::start(&state, pattern);
+    let found = std::iter::from_fn(|| search.next_match())
+        .map(|found| (found.line, found.ranges))
+        .collect::<Vec<_>>();
+    assert_eq!(found, [(2, vec![0..5, 14..19]), (0, vec![0..5, 7..12])]);
+    assert_eq!(search.found(), 2);
+    assert!(search.is_finished());
+}
+
+#[test]
+fn search_prompt() {
+    use crate::{Config, Shared};
+
+    let (sender, _events) = crate::events::channel(&Config::default());
+    let state = Arc::new(State::new(
+        Arc::new(Shared::from(Config::default())),
+        String::from("main"),
+        true,
+        sender,
+    ));
+    let mut prompt = SearchPrompt::default();
+    for ch in "a.(".chars() {
+        prompt.push(ch, &state);
+    }
+    assert_eq!(prompt.highlights("xa.(A.(a.("), [1..4, 7..10]);
+    prompt.toggle_ignore_case(&state);
+    assert_eq!(prompt.highlights("xa.(A.(a.("), [1..4, 4..7, 7..10]);
+
+    prompt.toggle_regex(&state);
+    assert!(prompt.is_invalid());
+    assert_eq!(
+        prompt.hint().as_deref(),
+        Some("invalid regex: unclosed group")
+    );
+    assert!(prompt.highlights("a.(").is_empty());
+    prompt.pop(&state);
+    assert_eq!(prompt.highlights("ab, AC"), [0..2, 4..6]);
+}
@@ -0,0 +1,122 @@
+use std::fmt;
+use std::time::Duration;
+
+pub use flume::{RecvError, TryRecvError};
+
+/// Creates a channel that holds any number of values, whose receiver can be
+/// waited on together with console events using [`Selector`].
+pub fn select_channel<T>() -> (SelectSender<T>, SelectReceiver<T>) {
+    let (sender, receiver) = flume::unbounded();
+    (SelectSender(sender), SelectReceiver(receiver))
+}
+
+/// Creates a channel that holds at most `capacity` values, sending blocking
+/// while it is full. The receiver can be waited on together with console
+/// events using [`Selector`].
+pub fn bounded_select_channel<T>(capacity: usize) -> (SelectSender<T>, SelectReceiver<T>) {
+    let (sender, receiver) = flume::bounded(capacity);
+    (SelectSender(sender), SelectReceiver(receiver))
+}
+
+/// Sends values to a [`SelectReceiver`].
+pub struct SelectSender<T>(flume::Sender<T>);
+
+impl<T> SelectSender<T> {
+    /// Sends `value`, returning it if every receiver has been dropped.
+    pub fn send(&self, value: T) -> Result<(), T> {
+        self.0.send(value).map_err(|err| err.into_inner())
+    }
+}
+
+impl<T> Clone for SelectSender<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> fmt::Debug for SelectSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SelectSender").finish_non_exhaustive()
+    }
+}
+
+/// Receives values sent by a [`SelectSender`].
+pub struct SelectReceiver<T>(flume::Receiver<T>);
+
+impl<T> SelectReceiver<T> {
+    /// Waits for the next value. Once every sender has been dropped, the
+    /// values already sent are received before receiving fails.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.0.recv()
+    }
+
+    /// Returns the next value if one has already been sent, without
+    /// blocking.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        self.0.try_recv()
+    }
+}
+
+impl<T> Clone for SelectReceiver<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> fmt::Debug for SelectReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SelectReceiver").finish_non_exhaustive()
+    }
+}
+
+/// Waits for whichever of several receivers is ready first, such as an app's
+/// own channels and [`Console::select_event`](crate::Console::select_event).
+pub struct Selector<'a, T>(flume::Selector<'a, T>);
+
+impl<'a, T> Selector<'a, T> {
+    pub fn new() -> Self {
+        Self(flume::Selector::new())
+    }
+
+    /// Adds receiving from `receiver`, passing the result to `mapper` when
+    /// it is the one that's ready.
+    pub fn recv<U>(
+        self,
+        receiver: &'a SelectReceiver<U>,
+        mapper: impl FnMut(Result<U, RecvError>) -> T + 'a,
+    ) -> Self {
+        Self(self.0.recv(&receiver.0, mapper))
+    }
+
+    pub(crate) fn recv_flume<U>(
+        self,
+        receiver: &'a flume::Receiver<U>,
+        mapper: impl FnMut(Result<U, RecvError>) -> T + 'a,
+    ) -> Self {
+        Self(self.0.recv(receiver, mapper))
+    }
+
+    /// Waits until one of the receivers is ready, returning what its mapper
+    /// returned.
+    pub fn wait(self) -> T {
+        self.0.wait()
+    }
+
+    /// Waits like [`Self::wait`], returning None if no receiver is ready
+    /// within `timeout`.
+    pub fn wait_timeout(self, timeout: Duration) -> Option<T> {
+        self.0.wait_timeout(timeout).ok()
+    }
+}
+
+impl<T> Default for Selector<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for Selector<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Selector").finish_non_exhaustive()
+    }
+}
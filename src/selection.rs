@@ -0,0 +1,126 @@
+use std::ops::Range;
+
+/// A location in the scrollback. `line` counts from the oldest line, which
+/// keeps positions stable as new lines are pushed.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub struct TextPosition {
+    pub line: usize,
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Granularity {
+    Character,
+    Word,
+    Line,
+}
+
+#[derive(Debug, Clone)]
+pub struct Selection {
+    // The unit that was originally clicked. Extending the selection always
+    // keeps this range selected.
+    anchor: Range<TextPosition>,
+    range: Range<TextPosition>,
+    granularity: Granularity,
+}
+
+impl Selection {
+    pub fn new(anchor: Range<TextPosition>, granularity: Granularity) -> Self {
+        Self {
+            range: anchor.clone(),
+            anchor,
+            granularity,
+        }
+    }
+
+    pub fn granularity(&self) -> Granularity {
+        self.granularity
+    }
+
+    pub fn range(&self) -> Range<TextPosition> {
+        self.range.clone()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.range.start == self.range.end
+    }
+
+    pub fn extend_to(&mut self, unit: Range<TextPosition>) {
+        self.range = self.anchor.start.min(unit.start)..self.anchor.end.max(unit.end);
+    }
+
+    /// Returns the byte range of `line` that is selected, if any.
+    pub fn range_in_line(&self, line: usize, line_len: usize) -> Option<Range<usize>> {
+        if line < self.range.start.line || line > self.range.end.line {
+            return None;
+        }
+
+        let start = if line == self.range.start.line {
+            self.range.start.offset
+        } else {
+            0
+        };
+        let end = if line == self.range.end.line {
+            self.range.end.offset
+        } else {
+            line_len
+        };
+
+        Some(start..end)
+    }
+}
+
+#[derive(Eq, PartialEq)]
+enum CharClass {
+    Word,
+    Whitespace,
+    Other,
+}
+
+impl From<char> for CharClass {
+    fn from(ch: char) -> Self {
+        if ch.is_alphanumeric() || ch == '_' {
+            Self::Word
+        } else if ch.is_whitespace() {
+            Self::Whitespace
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// Returns the byte range of the word containing `offset`. Runs of
+/// whitespace are treated as a single word, and any other character is
+/// selected on its own.
+pub fn word_at(text: &str, offset: usize) -> Range<usize> {
+    let Some(ch) = text[offset..].chars().next() else {
+        return offset..offset;
+    };
+    let class = CharClass::from(ch);
+    if class == CharClass::Other {
+        return offset..offset + ch.len_utf8();
+    }
+
+    let start = text[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|(_, ch)| CharClass::from(*ch) == class)
+        .last()
+        .map_or(offset, |(index, _)| index);
+    let end = text[offset..]
+        .char_indices()
+        .find(|(_, ch)| CharClass::from(*ch) != class)
+        .map_or(text.len(), |(index, _)| offset + index);
+
+    start..end
+}
+
+#[test]
+fn word_boundaries() {
+    let text = "hello, big_world  !";
+    assert_eq!(&text[word_at(text, 2)], "hello");
+    assert_eq!(&text[word_at(text, 5)], ",");
+    assert_eq!(&text[word_at(text, 9)], "big_world");
+    assert_eq!(&text[word_at(text, 17)], "  ");
+    assert_eq!(word_at(text, text.len()), text.len()..text.len());
+}
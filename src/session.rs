@@ -0,0 +1,476 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::Shutdown;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, UNIX_EPOCH};
+
+use parking_lot::Mutex;
+
+use crate::scrollback::Line;
+use crate::{plain, App, Change, Color, Console, ConsoleHandle, Level, Sink, State};
+
+// Lines queued for a client before it is considered stalled and dropped.
+const CLIENT_BACKLOG: usize = 4096;
+
+// The protocol is line based. The daemon sends one change to the scrollback
+// per protocol line, formatted as tab-separated fields:
+//
+//     <change> <seconds since the epoch>.<milliseconds> <level> <channel>
+//         <producer> <color> <background> <text>
+//
+// The change is `+` for a pushed line, `=` for a line replacing the most
+// recently pushed line, `~<id>` for a line still being streamed, and `!<id>`
+// once that line is finished. `-<id>` alone discards a streamed line. Colors
+// are written as `rrggbb`. Tabs, newlines, and backslashes within fields are
+// escaped, and empty fields are left unset. Frontends send each submitted
+// input as a single protocol line.
+
+/// Serves `console` on the Unix socket at `path`. Each frontend that attaches
+/// receives the scrollback so far, followed by every change made while it
+/// remains attached. If the socket can't be bound, the app is run in the
+/// foreground as plain text instead, starting with a warning line.
+pub(crate) fn serve(path: &Path, console: ConsoleHandle) -> ! {
+    let listener = match bind(path) {
+        Ok(listener) => listener,
+        Err(err) => {
+            plain::install(&console.shared);
+            console.state().push(
+                Line::from(format!(
+                    "error serving a session at {}: {err}",
+                    path.display()
+                ))
+                .with_level(Level::Warning),
+            );
+            plain::run(console)
+        }
+    };
+
+    // Nothing is displayed locally, so output is never waiting to be seen.
+    *console.shared.plain.lock() = true;
+    let clients = Arc::new(Mutex::new(Vec::<Client>::new()));
+    console.shared.add_sink(ClientSink(clients.clone()));
+
+    let (sender, lines) = flume::unbounded();
+    let state = Arc::downgrade(console.state());
+    std::thread::Builder::new()
+        .name(String::from("session"))
+        .spawn(move || {
+            for client in listener.incoming() {
+                let Ok(client) = client else { continue };
+                let Some(state) = state.upgrade() else { break };
+                if attach_client(&state, &clients, &client).is_ok() {
+                    spawn_client_reader(client, sender.clone());
+                }
+            }
+        })
+        .expect("error spawning session thread");
+
+    let path = path.to_path_buf();
+    std::thread::Builder::new()
+        .name(String::from("session cleanup"))
+        .spawn({
+            let state = Arc::downgrade(console.state());
+            move || remove_on_shutdown(&state, &path)
+        })
+        .expect("error spawning session cleanup thread");
+
+    plain::run_with_input(console, lines)
+}
+
+fn bind(path: &Path) -> io::Result<UnixListener> {
+    match UnixStream::connect(path) {
+        Ok(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                "a session is already served there",
+            ))
+        }
+        // Nothing is listening, so the socket was left behind by a previous
+        // daemon and would prevent binding.
+        Err(err) if err.kind() == io::ErrorKind::ConnectionRefused => {
+            let _ = std::fs::remove_file(path);
+        }
+        Err(_) => {}
+    }
+    UnixListener::bind(path)
+}
+
+// Queues each change to the scrollback for every attached client.
+struct ClientSink(Arc<Mutex<Vec<Client>>>);
+
+impl ClientSink {
+    fn send(&self, encoded: String) {
+        self.0.lock().retain(|client| {
+            let sent = client.lines.try_send(encoded.clone()).is_ok();
+            if !sent {
+                // The client has disconnected or stopped reading.
+                let _ = client.stream.shutdown(Shutdown::Both);
+            }
+            sent
+        });
+    }
+}
+
+impl Sink for ClientSink {
+    fn push(&mut self, line: &Line) -> io::Result<()> {
+        self.send(encode_line("+", line));
+        Ok(())
+    }
+
+    fn change(&mut self, change: &Change) -> io::Result<()> {
+        self.send(encode(change));
+        Ok(())
+    }
+}
+
+// A frontend attached to the session.
+struct Client {
+    // Encoded lines waiting to be written by the client's writer thread.
+    lines: flume::Sender<String>,
+    stream: UnixStream,
+}
+
+// Registers a newly attached client, which is sent the existing scrollback
+// before the lines pushed after it attached. Lines are written on a thread
+// per client, so that a slow client never holds up the console.
+fn attach_client(
+    state: &State,
+    clients: &Mutex<Vec<Client>>,
+    client: &UnixStream,
+) -> io::Result<()> {
+    let writer = client.try_clone()?;
+    let stream = client.try_clone()?;
+    let (sender, lines) = flume::bounded(CLIENT_BACKLOG);
+
+    // Lines are stored while the sinks are locked, so none can be pushed
+    // between taking the snapshot and registering the client.
+    let sinks = state.shared.sinks.lock();
+    let scrollback = state.scrollback.lock();
+    let snapshot = scrollback
+        .events
+        .iter()
+        .rev()
+        .chain(scrollback.pending.iter().flatten())
+        .map(|line| match line.stream {
+            Some(id) => encode_line(&format!("~{id}"), line),
+            None => encode_line("+", line),
+        })
+        .collect::<Vec<_>>();
+    drop(scrollback);
+    clients.lock().push(Client {
+        lines: sender,
+        stream,
+    });
+    drop(sinks);
+
+    spawn_client_writer(writer, snapshot, lines);
+    Ok(())
+}
+
+fn spawn_client_writer(
+    mut client: UnixStream,
+    snapshot: Vec<String>,
+    lines: flume::Receiver<String>,
+) {
+    std::thread::Builder::new()
+        .name(String::from("session client writer"))
+        .spawn(move || {
+            for line in snapshot.into_iter().chain(lines.iter()) {
+                if writeln!(client, "{line}").is_err() {
+                    // Also stops the client's reader.
+                    let _ = client.shutdown(Shutdown::Both);
+                    break;
+                }
+            }
+        })
+        .expect("error spawning session client writer thread");
+}
+
+fn spawn_client_reader(client: UnixStream, sender: flume::Sender<String>) {
+    std::thread::Builder::new()
+        .name(String::from("session client"))
+        .spawn(move || {
+            for line in BufReader::new(client).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(unescape(&line)).is_err() {
+                    break;
+                }
+            }
+        })
+        .expect("error spawning session client thread");
+}
+
+fn remove_on_shutdown(state: &Weak<State>, path: &Path) {
+    while state
+        .upgrade()
+        .is_some_and(|state| !state.should_shutdown())
+    {
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    let _ = std::fs::remove_file(path);
+}
+
+/// An app that displays a session served by another process, forwarding
+/// each submitted input to it. Detaching leaves the session running.
+pub(crate) struct Attach {
+    pub path: PathBuf,
+}
+
+impl App for Attach {
+    fn run(self, console: Console) -> anyhow::Result<()> {
+        let stream = UnixStream::connect(&self.path)?;
+        let reader = BufReader::new(stream.try_clone()?);
+        let state = Arc::downgrade(&console.state);
+        std::thread::Builder::new()
+            .name(String::from("session"))
+            .spawn(move || receive(reader, &state))?;
+
+        let mut stream = stream;
+        while let Some(input) = console.read_input() {
+            if writeln!(stream, "{}", escape(&input)).is_err() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn receive(reader: BufReader<UnixStream>, state: &Weak<State>) {
+    for line in reader.lines() {
+        let Ok(line) = line else { break };
+        let Some(change) = decode(&line) else {
+            continue;
+        };
+        let Some(state) = state.upgrade() else { return };
+        apply(&state, change);
+        state.redraw();
+    }
+
+    // The daemon has exited.
+    if let Some(state) = state.upgrade() {
+        state.shutdown();
+        state.redraw();
+    }
+}
+
+// Makes a change received from the daemon to this frontend's scrollback.
+fn apply(state: &State, change: Change) {
+    match change {
+        Change::Pushed(line) => state.push(line),
+        Change::UpdatedLast(line) => {
+            state.update_last_line(|last| *last = line);
+        }
+        Change::Streamed(id, mut line) => {
+            line.stream = Some(id);
+            state.change_and_notify(|scrollback| {
+                if scrollback
+                    .update_stream(id, |streamed| *streamed = line.clone())
+                    .is_none()
+                {
+                    scrollback.append(line.clone());
+                }
+                Some(Change::Streamed(id, line))
+            });
+        }
+        Change::Finished(id, line) => {
+            let finished = state.change_and_notify(|scrollback| {
+                let line = scrollback
+                    .update_stream(id, |streamed| *streamed = line.clone())
+                    .cloned()?;
+                Some(Change::Finished(id, line))
+            });
+            // Lists are only sent once they're finished.
+            if !finished {
+                state.push(line);
+            }
+        }
+        Change::Discarded(id) => {
+            state.change_and_notify(|scrollback| {
+                scrollback.remove_stream(id)?;
+                Some(Change::Discarded(id))
+            });
+        }
+    }
+}
+
+fn encode(change: &Change) -> String {
+    match change {
+        Change::Pushed(line) => encode_line("+", line),
+        Change::UpdatedLast(line) => encode_line("=", line),
+        Change::Streamed(id, line) => encode_line(&format!("~{id}"), line),
+        Change::Finished(id, line) => encode_line(&format!("!{id}"), line),
+        Change::Discarded(id) => format!("-{id}"),
+    }
+}
+
+fn encode_line(change: &str, line: &Line) -> String {
+    let timestamp = line
+        .timestamp
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    format!(
+        "{change}\t{}.{:03}\t{}\t{}\t{}\t{}\t{}\t{}",
+        timestamp.as_secs(),
+        timestamp.subsec_millis(),
+        line.level.as_str(),
+        escape(line.channel.as_deref().unwrap_or_default()),
+        escape(line.producer.as_deref().unwrap_or_default()),
+        encode_color(line.color),
+        encode_color(line.background),
+        escape(line.as_str()),
+    )
+}
+
+fn encode_color(color: Option<Color>) -> String {
+    color.map_or_else(String::new, |color| {
+        format!("{:02x}{:02x}{:02x}", color.red, color.green, color.blue)
+    })
+}
+
+fn decode(encoded: &str) -> Option<Change> {
+    let mut fields = encoded.splitn(8, '\t');
+    let change = fields.next()?;
+    let kind = change.chars().next()?;
+    let id = || change[kind.len_utf8()..].parse::<u64>().ok();
+    if kind == '-' {
+        return Some(Change::Discarded(id()?));
+    }
+    let (seconds, millis) = fields.next()?.split_once('.')?;
+    let level = match fields.next()? {
+        "info" => Level::Info,
+        "warning" => Level::Warning,
+        "error" => Level::Error,
+        _ => return None,
+    };
+    let mut optional = || {
+        let field = unescape(fields.next()?);
+        Some((!field.is_empty()).then_some(field))
+    };
+    let channel = optional()?.map(Arc::from);
+    let producer = optional()?.map(Arc::from);
+    let color = optional()?.map(|color| decode_color(&color));
+    let background = optional()?.map(|color| decode_color(&color));
+    let mut line = Line::new(unescape(fields.next()?), channel).with_level(level);
+    line.producer = producer;
+    line.color = color.flatten();
+    line.background = background.flatten();
+    line.timestamp = UNIX_EPOCH
+        + Duration::from_secs(seconds.parse().ok()?)
+        + Duration::from_millis(millis.parse().ok()?);
+    match kind {
+        '+' => Some(Change::Pushed(line)),
+        '=' => Some(Change::UpdatedLast(line)),
+        '~' => Some(Change::Streamed(id()?, line)),
+        '!' => Some(Change::Finished(id()?, line)),
+        _ => None,
+    }
+}
+
+fn decode_color(color: &str) -> Option<Color> {
+    let value = u32::from_str_radix(color, 16).ok()?;
+    let [_, red, green, blue] = value.to_be_bytes();
+    Some(Color::rgb(red, green, blue))
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            unescaped.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+#[test]
+fn encoding_round_trip() {
+    let mut line =
+        Line::new(String::from("a\tb\\c\nd"), Some(Arc::from("serial"))).with_level(Level::Warning);
+    line.timestamp = UNIX_EPOCH + Duration::from_millis(1_234_567);
+    line.producer = Some(Arc::from("worker"));
+    line.background = Some(Color::rgb(0x12, 0xab, 0xff));
+    let encoded = encode(&Change::UpdatedLast(line.clone()));
+    assert!(!encoded.contains('\n'));
+    let Some(Change::UpdatedLast(decoded)) = decode(&encoded) else {
+        unreachable!("decoded as another change")
+    };
+    assert_eq!(decoded.as_str(), line.as_str());
+    assert_eq!(decoded.channel, line.channel);
+    assert_eq!(decoded.level, line.level);
+    assert_eq!(decoded.timestamp, line.timestamp);
+    assert_eq!(decoded.producer, line.producer);
+    assert_eq!(decoded.background, line.background);
+    assert_eq!(decoded.color, None);
+
+    let Some(Change::Streamed(7, decoded)) =
+        decode(&encode(&Change::Streamed(7, Line::from(String::new()))))
+    else {
+        unreachable!("decoded as another change")
+    };
+    assert!(decoded.channel.is_none());
+    assert!(matches!(
+        decode(&encode(&Change::Discarded(7))),
+        Some(Change::Discarded(7))
+    ));
+}
+
+#[test]
+fn attaching_clients() {
+    use crate::{events, Config, Shared};
+
+    let shared = Arc::new(Shared::from(Config::default()));
+    let clients = Arc::new(Mutex::new(Vec::new()));
+    shared.add_sink(ClientSink(clients.clone()));
+    let state = State::new(
+        shared,
+        String::from("main"),
+        false,
+        events::channel(&Config::default()).0,
+    );
+    state.push(Line::from(String::from("before")));
+    let (client, frontend) = UnixStream::pair().unwrap();
+    attach_client(&state, &clients, &client).unwrap();
+    state.push(Line::from(String::from("after")));
+    state.update_last_line(|line| line.push_str(" update"));
+
+    let mut lines = BufReader::new(frontend).lines();
+    let mut next = || decode(&lines.next().unwrap().unwrap()).unwrap();
+    assert!(matches!(next(), Change::Pushed(line) if line.as_str() == "before"));
+    assert!(matches!(next(), Change::Pushed(line) if line.as_str() == "after"));
+    // Updates replace the line on the frontend rather than adding another.
+    assert!(matches!(
+        next(),
+        Change::UpdatedLast(line) if line.as_str() == "after update"
+    ));
+
+    // A client that stops reading is dropped rather than holding up pushes.
+    for _ in 0..CLIENT_BACKLOG * 10 {
+        state.push(Line::from(String::from("unread")));
+    }
+    assert!(clients.lock().is_empty());
+}
@@ -0,0 +1,381 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// How long a chord waits for its next key before it is abandoned.
+pub(crate) const CHORD_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A key that can be part of a [`Shortcut`], independent of the backend.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum Key {
+    /// A character key, such as `'m'`, `'0'`, or `'+'`. Letters are
+    /// lowercase regardless of Shift.
+    Char(char),
+    /// A function key, such as `Function(1)` for F1.
+    Function(u8),
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Tab,
+    Escape,
+    Enter,
+    Backspace,
+    Pause,
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::Char('+') => f.write_str("Plus"),
+            Key::Char('-') => f.write_str("Minus"),
+            Key::Char(' ') => f.write_str("Space"),
+            Key::Char('.') => f.write_str("Period"),
+            Key::Char(ch) => write!(f, "{}", ch.to_uppercase()),
+            Key::Function(number) => write!(f, "F{number}"),
+            Key::PageUp => f.write_str("PageUp"),
+            Key::PageDown => f.write_str("PageDown"),
+            other => write!(f, "{other:?}"),
+        }
+    }
+}
+
+/// A key combined with modifiers.
+///
+/// [`Shortcut::primary`] resolves to Command on macOS and Control elsewhere,
+/// so that shortcuts declared with it behave the same way on every
+/// platform.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Shortcut {
+    pub key: Key,
+    pub control: bool,
+    pub shift: bool,
+    pub alt: bool,
+    /// The Command key on macOS, and the Windows or Super key elsewhere.
+    pub command: bool,
+}
+
+impl Shortcut {
+    /// The name of the primary modifier on this platform.
+    pub const PRIMARY: &'static str = if cfg!(target_os = "macos") {
+        "Cmd"
+    } else {
+        "Ctrl"
+    };
+
+    pub const fn new(key: Key) -> Self {
+        Self {
+            key,
+            control: false,
+            shift: false,
+            alt: false,
+            command: false,
+        }
+    }
+
+    /// Adds the platform's primary modifier.
+    pub const fn primary(mut self) -> Self {
+        if cfg!(target_os = "macos") {
+            self.command = true;
+        } else {
+            self.control = true;
+        }
+        self
+    }
+
+    pub const fn control(mut self) -> Self {
+        self.control = true;
+        self
+    }
+
+    pub const fn shift(mut self) -> Self {
+        self.shift = true;
+        self
+    }
+
+    pub const fn alt(mut self) -> Self {
+        self.alt = true;
+        self
+    }
+
+    /// Returns true if the platform's primary modifier is held.
+    pub const fn is_primary(&self) -> bool {
+        if cfg!(target_os = "macos") {
+            self.command
+        } else {
+            self.control
+        }
+    }
+}
+
+impl From<Key> for Shortcut {
+    fn from(key: Key) -> Self {
+        Self::new(key)
+    }
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let macos = cfg!(target_os = "macos");
+        if self.control {
+            f.write_str("Ctrl+")?;
+        }
+        if self.alt {
+            f.write_str(if macos { "Option+" } else { "Alt+" })?;
+        }
+        if self.shift {
+            f.write_str("Shift+")?;
+        }
+        if self.command {
+            f.write_str(if macos { "Cmd+" } else { "Super+" })?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+impl FromStr for Key {
+    type Err = InvalidShortcut;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let key = match name.to_ascii_lowercase().as_str() {
+            "plus" => Key::Char('+'),
+            "minus" => Key::Char('-'),
+            "space" => Key::Char(' '),
+            "period" => Key::Char('.'),
+            "up" => Key::Up,
+            "down" => Key::Down,
+            "left" => Key::Left,
+            "right" => Key::Right,
+            "home" => Key::Home,
+            "end" => Key::End,
+            "pageup" => Key::PageUp,
+            "pagedown" => Key::PageDown,
+            "tab" => Key::Tab,
+            "escape" | "esc" => Key::Escape,
+            "enter" => Key::Enter,
+            "backspace" => Key::Backspace,
+            "pause" => Key::Pause,
+            lower => {
+                let mut chars = lower.chars();
+                match (chars.next(), chars.as_str()) {
+                    (Some(ch), "") => Key::Char(ch),
+                    (Some('f'), number) => number
+                        .parse()
+                        .map(Key::Function)
+                        .map_err(|_| InvalidShortcut(name.to_string()))?,
+                    _ => return Err(InvalidShortcut(name.to_string())),
+                }
+            }
+        };
+        Ok(key)
+    }
+}
+
+/// Parses shortcuts written the way they are displayed, such as
+/// `Ctrl+Shift+H`. `Primary` is also accepted for the platform's primary
+/// modifier.
+impl FromStr for Shortcut {
+    type Err = InvalidShortcut;
+
+    fn from_str(shortcut: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidShortcut(shortcut.to_string());
+        // `+` is both the separator and a key, as in `Ctrl++`.
+        let (modifiers, key) = match shortcut.strip_suffix("++") {
+            Some(modifiers) => (modifiers, "+"),
+            None => shortcut.rsplit_once('+').unwrap_or(("", shortcut)),
+        };
+        let mut parsed = Shortcut::new(key.parse().map_err(|_| invalid())?);
+        for modifier in modifiers.split('+').filter(|modifier| !modifier.is_empty()) {
+            parsed = match modifier.to_ascii_lowercase().as_str() {
+                "primary" => parsed.primary(),
+                "ctrl" | "control" => parsed.control(),
+                "shift" => parsed.shift(),
+                "alt" | "option" => parsed.alt(),
+                "cmd" | "command" | "super" => Shortcut {
+                    command: true,
+                    ..parsed
+                },
+                _ => return Err(invalid()),
+            };
+        }
+        Ok(parsed)
+    }
+}
+
+/// The text parsed as a [`Shortcut`] isn't a valid shortcut.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct InvalidShortcut(pub String);
+
+impl fmt::Display for InvalidShortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid shortcut {:?}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidShortcut {}
+
+/// The keys pressed so far of a multi-key binding, such as `Ctrl+X` of
+/// `Ctrl+X Ctrl+S`.
+#[derive(Debug, Default)]
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub(crate) struct Chord {
+    keys: Vec<Shortcut>,
+    last_pressed: Option<Instant>,
+}
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+impl Chord {
+    /// Adds `shortcut` to the chord and looks up the keys pressed in
+    /// `bindings`. Returns the shortcut a completed binding acts as, or
+    /// `shortcut` itself if it doesn't begin a binding. Returns None while a
+    /// binding is waiting for its next key, and for a key that doesn't
+    /// continue the chord, which abandons it.
+    pub fn press(
+        &mut self,
+        shortcut: Shortcut,
+        bindings: &HashMap<Vec<Shortcut>, Shortcut>,
+        now: Instant,
+    ) -> Option<Shortcut> {
+        if self.is_expired(now) {
+            self.keys.clear();
+        }
+        self.keys.push(shortcut);
+        if let Some(bound) = bindings.get(&self.keys) {
+            self.keys.clear();
+            return Some(*bound);
+        }
+        if bindings
+            .keys()
+            .any(|keys| keys.len() > self.keys.len() && keys.starts_with(&self.keys))
+        {
+            self.last_pressed = Some(now);
+            return None;
+        }
+        let abandoned = self.keys.len() > 1;
+        self.keys.clear();
+        (!abandoned).then_some(shortcut)
+    }
+
+    /// Returns the keys pressed so far, such as `Ctrl+X`, or None if no
+    /// chord is waiting for its next key.
+    pub fn pending(&self, now: Instant) -> Option<String> {
+        if self.keys.is_empty() || self.is_expired(now) {
+            return None;
+        }
+        let keys = self
+            .keys
+            .iter()
+            .map(Shortcut::to_string)
+            .collect::<Vec<_>>();
+        Some(keys.join(" "))
+    }
+
+    fn is_expired(&self, now: Instant) -> bool {
+        self.last_pressed
+            .is_some_and(|pressed| now.duration_since(pressed) >= CHORD_TIMEOUT)
+    }
+}
+
+/// Parses a sequence of shortcuts separated by spaces, such as
+/// `Ctrl+X Ctrl+S`. `Leader` stands for `leader`, if one is set.
+pub(crate) fn parse_chord(
+    chord: &str,
+    leader: Option<Shortcut>,
+) -> Result<Vec<Shortcut>, InvalidShortcut> {
+    let keys = chord
+        .split_whitespace()
+        .map(|key| match (key.eq_ignore_ascii_case("leader"), leader) {
+            (true, Some(leader)) => Ok(leader),
+            _ => key.parse(),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if keys.is_empty() {
+        return Err(InvalidShortcut(chord.to_string()));
+    }
+    Ok(keys)
+}
+
+#[test]
+fn parsing_shortcuts() {
+    assert_eq!(
+        "Ctrl+Shift+H".parse(),
+        Ok(Shortcut::new(Key::Char('h')).control().shift())
+    );
+    assert_eq!(
+        "primary+plus".parse(),
+        Ok(Shortcut::new(Key::Char('+')).primary())
+    );
+    assert_eq!(
+        "Ctrl++".parse(),
+        Ok(Shortcut::new(Key::Char('+')).control())
+    );
+    assert_eq!("F12".parse(), Ok(Shortcut::from(Key::Function(12))));
+    assert_eq!("PageUp".parse(), Ok(Shortcut::from(Key::PageUp)));
+    let period = Shortcut::new(Key::Char('.')).control();
+    assert_eq!(period.to_string().parse(), Ok(period));
+    assert!("Hyper+K".parse::<Shortcut>().is_err());
+    assert!("Ctrl+Nope".parse::<Shortcut>().is_err());
+}
+
+#[test]
+fn primary_shortcuts() {
+    let zoom = Shortcut::new(Key::Char('+')).primary();
+    assert!(zoom.is_primary());
+    assert_eq!(zoom.to_string(), format!("{}+Plus", Shortcut::PRIMARY));
+    assert_eq!(
+        Shortcut::new(Key::Char('h')).control().shift().to_string(),
+        "Ctrl+Shift+H"
+    );
+    assert_eq!(Shortcut::from(Key::Function(12)).to_string(), "F12");
+    assert_eq!(Shortcut::from(Key::PageUp).to_string(), "PageUp");
+}
+
+#[test]
+fn pressing_chords() {
+    let save = Shortcut::new(Key::Char('s')).primary();
+    let leader = Shortcut::new(Key::Char(' ')).control();
+    let bindings = HashMap::from([
+        (parse_chord("Ctrl+X Ctrl+S", None).unwrap(), save),
+        (parse_chord("Leader F", Some(leader)).unwrap(), save),
+        (parse_chord("Ctrl+K", None).unwrap(), save),
+    ]);
+    let ctrl_x = Shortcut::new(Key::Char('x')).control();
+    let ctrl_s = Shortcut::new(Key::Char('s')).control();
+    let now = Instant::now();
+
+    let mut chord = Chord::default();
+    assert_eq!(chord.press(ctrl_x, &bindings, now), None);
+    assert_eq!(chord.pending(now).as_deref(), Some("Ctrl+X"));
+    assert_eq!(chord.press(ctrl_s, &bindings, now), Some(save));
+    assert_eq!(chord.pending(now), None);
+    assert_eq!(
+        chord.press(Shortcut::new(Key::Char('k')).control(), &bindings, now),
+        Some(save)
+    );
+    assert_eq!(chord.press(leader, &bindings, now), None);
+    assert_eq!(
+        chord.press(Shortcut::new(Key::Char('f')), &bindings, now),
+        Some(save)
+    );
+
+    // Keys that aren't bound act as themselves, unless they break a chord.
+    assert_eq!(chord.press(ctrl_s, &bindings, now), Some(ctrl_s));
+    assert_eq!(chord.press(ctrl_x, &bindings, now), None);
+    assert_eq!(chord.press(ctrl_x, &bindings, now), None);
+    assert_eq!(chord.pending(now), None);
+
+    // Chords are abandoned once they time out.
+    assert_eq!(chord.press(ctrl_x, &bindings, now), None);
+    let later = now + CHORD_TIMEOUT;
+    assert_eq!(chord.pending(later), None);
+    assert_eq!(chord.press(ctrl_s, &bindings, later), Some(ctrl_s));
+
+    assert!(parse_chord("Leader F", None).is_err());
+    assert!(parse_chord(" ", None).is_err());
+}
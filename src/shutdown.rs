@@ -0,0 +1,114 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::{Condvar, Mutex};
+
+/// Signals when the console is shutting down, so that background workers can
+/// stop promptly. Returned by
+/// [`Console::shutdown_token`](crate::Console::shutdown_token).
+///
+/// Workers waiting on something else, such as a `std::sync::Condvar` or a
+/// `tokio::sync::Notify`, can be woken with [`Self::on_shutdown`].
+#[derive(Clone, Default)]
+pub struct ShutdownToken(Arc<Signal>);
+
+#[derive(Default)]
+struct Signal {
+    state: Mutex<SignalState>,
+    condvar: Condvar,
+}
+
+#[derive(Default)]
+struct SignalState {
+    shutdown: bool,
+    callbacks: Vec<Box<dyn FnOnce() + Send>>,
+}
+
+impl fmt::Debug for ShutdownToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShutdownToken")
+            .field("shutdown", &self.is_shutdown())
+            .finish()
+    }
+}
+
+impl ShutdownToken {
+    pub(crate) fn shutdown(&self) {
+        let mut state = self.0.state.lock();
+        state.shutdown = true;
+        let callbacks = std::mem::take(&mut state.callbacks);
+        drop(state);
+        self.0.condvar.notify_all();
+        for callback in callbacks {
+            callback();
+        }
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        self.0.state.lock().shutdown
+    }
+
+    /// Blocks until the console is shutting down.
+    pub fn wait(&self) {
+        let mut state = self.0.state.lock();
+        while !state.shutdown {
+            self.0.condvar.wait(&mut state);
+        }
+    }
+
+    /// Blocks until the console is shutting down or `timeout` has elapsed.
+    /// Returns true if the console is shutting down.
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.0.state.lock();
+        while !state.shutdown {
+            if self.0.condvar.wait_until(&mut state, deadline).timed_out() {
+                break;
+            }
+        }
+        state.shutdown
+    }
+
+    /// Calls `callback` once the console is shutting down, or immediately if
+    /// it already is. For example, `move || notify.notify_waiters()` wakes
+    /// tasks waiting on a `tokio::sync::Notify`.
+    pub fn on_shutdown(&self, callback: impl FnOnce() + Send + 'static) {
+        let mut state = self.0.state.lock();
+        if state.shutdown {
+            drop(state);
+            callback();
+        } else {
+            state.callbacks.push(Box::new(callback));
+        }
+    }
+}
+
+#[test]
+fn shutdown_wakes_waiters() {
+    let token = ShutdownToken::default();
+    assert!(!token.wait_timeout(Duration::from_millis(1)));
+
+    let woken = Arc::new((std::sync::Mutex::new(false), std::sync::Condvar::new()));
+    let notify = woken.clone();
+    token.on_shutdown(move || {
+        *notify.0.lock().unwrap() = true;
+        notify.1.notify_all();
+    });
+    let waiter = std::thread::spawn({
+        let token = token.clone();
+        move || token.wait()
+    });
+
+    token.shutdown();
+    waiter.join().unwrap();
+    assert!(*woken.0.lock().unwrap());
+    assert!(token.wait_timeout(Duration::from_secs(60)));
+
+    // Callbacks added after shutting down are called immediately.
+    let (sender, called) = flume::bounded(1);
+    token.on_shutdown(move || {
+        let _ = sender.send(());
+    });
+    assert!(called.try_recv().is_ok());
+}
@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+
+use crate::scrollback::{Line, Viewport};
+#[cfg(feature = "spill")]
+use crate::spill::Spill;
+use crate::State;
+
+/// The contents of a console, captured with
+/// [`Console::snapshot`](crate::Console::snapshot) so that they can be
+/// saved and later restored with
+/// [`Console::restore`](crate::Console::restore).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    // Oldest first.
+    lines: Vec<Line>,
+    scroll: usize,
+    input: String,
+    history: Vec<String>,
+}
+
+impl Snapshot {
+    pub(crate) fn capture(state: &State) -> Self {
+        let scrollback = state.scrollback.lock();
+        #[cfg(feature = "spill")]
+        let (mut lines, spill_error) = match scrollback.spill.as_ref().map(Spill::read_all) {
+            Some(Ok(spilled)) => (spilled, None),
+            Some(Err(err)) => (Vec::new(), Some(err)),
+            None => (Vec::new(), None),
+        };
+        #[cfg(not(feature = "spill"))]
+        let mut lines = Vec::new();
+        lines.extend(scrollback.events.iter().rev().cloned());
+        if let Some(pending) = &scrollback.pending {
+            lines.extend(pending.iter().cloned());
+        }
+        let scroll = scrollback.view.scroll;
+        drop(scrollback);
+        #[cfg(feature = "spill")]
+        if let Some(err) = spill_error {
+            state.report_error(format_args!(
+                "error reading spilled scrollback, only lines in memory were captured: {err}"
+            ));
+        }
+
+        let input = state.input.lock();
+        Self {
+            lines,
+            scroll,
+            // Secure input is never saved.
+            input: if input.options.masked {
+                String::new()
+            } else {
+                input.to_string()
+            },
+            history: input.history.clone(),
+        }
+    }
+
+    // Replaces the state's scrollback and input without passing the lines to
+    // any sinks. Every restored line is kept in memory until more are pushed.
+    pub(crate) fn restore(self, state: &State) {
+        let mut scrollback = state.scrollback.lock();
+        scrollback.events = self.lines.into_iter().rev().collect();
+        scrollback.view = Viewport {
+            scroll: self.scroll,
+            ..Viewport::default()
+        };
+        #[cfg(feature = "spill")]
+        let spill_error = scrollback.spill.as_mut().and_then(|spill| {
+            let cleared = spill.clear();
+            spill.failed = cleared.is_err();
+            cleared.err()
+        });
+        drop(scrollback);
+        #[cfg(feature = "spill")]
+        if let Some(err) = spill_error {
+            state.report_error(format_args!("error clearing spilled scrollback: {err}"));
+        }
+
+        let mut input = state.input.lock();
+        if !input.options.masked {
+            *input.buffer = self.input;
+        }
+        input.history = self.history;
+        input.recalled = None;
+    }
+
+    /// Returns the number of lines in the snapshot.
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+}
+
+#[test]
+fn snapshot_round_trip() {
+    use std::sync::Arc;
+
+    use crate::{Config, Level, Shared};
+
+    let state = State::new(
+        Arc::new(Shared::from(Config::default())),
+        String::from("main"),
+        true,
+        crate::events::channel(&Config::default()).0,
+    );
+    state.push(Line::from(String::from("first")));
+    state.push(Line::from(String::from("second")).with_level(Level::Error));
+    state.scrollback.lock().mark(String::from("second"));
+    state.scrollback.lock().view.scroll = 1;
+    *state.input.lock().buffer = String::from("typed");
+    state.input.lock().history.push(String::from("submitted"));
+
+    let saved = serde_json::to_string(&Snapshot::capture(&state)).unwrap();
+    let restored = State::new(
+        Arc::new(Shared::from(Config::default())),
+        String::from("main"),
+        true,
+        crate::events::channel(&Config::default()).0,
+    );
+    serde_json::from_str::<Snapshot>(&saved)
+        .unwrap()
+        .restore(&restored);
+
+    let scrollback = restored.scrollback.lock();
+    let lines = scrollback
+        .events
+        .iter()
+        .rev()
+        .map(|line| (line.as_str(), line.level, line.mark.as_deref()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        [
+            ("first", Level::Info, None),
+            ("second", Level::Error, Some("second"))
+        ]
+    );
+    assert_eq!(scrollback.view.scroll, 1);
+    let input = restored.input.lock();
+    assert_eq!(input.as_str(), "typed");
+    assert_eq!(input.history, ["submitted"]);
+}
+
+#[test]
+#[cfg(feature = "spill")]
+fn snapshots_include_spilled_lines() {
+    use std::sync::Arc;
+
+    use crate::spill::{SegmentFiles, SEGMENT_LINES};
+    use crate::{Config, Shared};
+
+    let directory = std::env::temp_dir().join(format!("snapshot-spill-{}", std::process::id()));
+    let spilled_state = |name: &str| {
+        let state = State::new(
+            Arc::new(Shared::from(Config::default())),
+            String::from("main"),
+            true,
+            crate::events::channel(&Config::default()).0,
+        );
+        let store = SegmentFiles::new(directory.join(name)).unwrap();
+        state.scrollback.lock().spill = Some(Spill::new(store, 10));
+        for line in 0..SEGMENT_LINES + 10 {
+            state.push(Line::from(line.to_string()));
+        }
+        assert_eq!(state.scrollback.lock().events.len(), 10);
+        state
+    };
+
+    let snapshot = Snapshot::capture(&spilled_state("captured"));
+    assert_eq!(snapshot.line_count(), SEGMENT_LINES + 10);
+    assert_eq!(snapshot.lines[0].as_str(), "0");
+
+    // Lines spilled before restoring are discarded.
+    let restored = spilled_state("restored");
+    Snapshot {
+        lines: snapshot.lines[SEGMENT_LINES..].to_vec(),
+        ..snapshot
+    }
+    .restore(&restored);
+    let mut scrollback = restored.scrollback.lock();
+    assert_eq!(scrollback.len(), 10);
+    assert!(!scrollback.page_in().unwrap());
+    assert_eq!(scrollback.line(0).map(|line| line.as_str()), Some("1000"));
+    drop(scrollback);
+    drop(restored);
+    std::fs::remove_dir_all(directory).unwrap();
+}
@@ -0,0 +1,214 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Range;
+
+use parking_lot::Mutex;
+
+use crate::Popup;
+
+/// Words that are spelled correctly, used to check the input when
+/// [`Config::spellcheck`](crate::Config::spellcheck) is set.
+pub trait Dictionary: Send + Sync + 'static {
+    fn contains(&self, word: &str) -> bool;
+
+    /// Returns replacements for the misspelled `word`, best first.
+    fn suggestions(&self, word: &str) -> Vec<String> {
+        let _ = word;
+        Vec::new()
+    }
+}
+
+/// A [`Dictionary`] of words, matched regardless of case. Suggestions are
+/// words one edit away from the misspelled word.
+#[derive(Debug, Clone, Default)]
+pub struct WordList {
+    words: HashSet<String>,
+}
+
+impl WordList {
+    pub fn new<Word: AsRef<str>>(words: impl IntoIterator<Item = Word>) -> Self {
+        let mut list = Self::default();
+        list.extend(words);
+        list
+    }
+
+    pub fn insert(&mut self, word: &str) {
+        self.words.insert(word.to_lowercase());
+    }
+}
+
+impl<Word: AsRef<str>> Extend<Word> for WordList {
+    fn extend<T: IntoIterator<Item = Word>>(&mut self, words: T) {
+        for word in words {
+            self.insert(word.as_ref());
+        }
+    }
+}
+
+impl Dictionary for WordList {
+    fn contains(&self, word: &str) -> bool {
+        self.words.contains(&word.to_lowercase())
+    }
+
+    fn suggestions(&self, word: &str) -> Vec<String> {
+        let word = word.to_lowercase().chars().collect::<Vec<_>>();
+        let mut suggestions = self
+            .words
+            .iter()
+            .filter(|candidate| is_one_edit_away(&word, candidate))
+            .cloned()
+            .collect::<Vec<_>>();
+        suggestions.sort();
+        suggestions
+    }
+}
+
+// Returns true if a single insertion, deletion, substitution, or swap of
+// adjacent characters turns `word` into `candidate`.
+fn is_one_edit_away(word: &[char], candidate: &str) -> bool {
+    let candidate = candidate.chars().collect::<Vec<_>>();
+    let (shorter, longer) = if word.len() <= candidate.len() {
+        (word, candidate.as_slice())
+    } else {
+        (candidate.as_slice(), word)
+    };
+    let prefix = shorter
+        .iter()
+        .zip(longer)
+        .take_while(|(a, b)| a == b)
+        .count();
+    if prefix == longer.len() {
+        // Identical words aren't suggestions.
+        return false;
+    }
+    match longer.len() - shorter.len() {
+        0 => {
+            shorter[prefix + 1..] == longer[prefix + 1..]
+                || (prefix + 1 < shorter.len()
+                    && shorter[prefix] == longer[prefix + 1]
+                    && shorter[prefix + 1] == longer[prefix]
+                    && shorter[prefix + 2..] == longer[prefix + 2..])
+        }
+        1 => shorter[prefix..] == longer[prefix + 1..],
+        _ => false,
+    }
+}
+
+// The app's dictionary and the words added to it while running.
+pub(crate) struct Spellchecker {
+    dictionary: Box<dyn Dictionary>,
+    added: Mutex<HashSet<String>>,
+}
+
+impl fmt::Debug for Spellchecker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Spellchecker")
+            .field("added", &self.added)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Spellchecker {
+    pub fn new(dictionary: impl Dictionary) -> Self {
+        Self {
+            dictionary: Box::new(dictionary),
+            added: Mutex::default(),
+        }
+    }
+
+    pub fn add(&self, word: &str) {
+        self.added.lock().insert(word.to_lowercase());
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        self.dictionary.contains(word) || self.added.lock().contains(&word.to_lowercase())
+    }
+
+    /// Returns the byte ranges of the misspelled words in `text`. The last
+    /// word isn't checked until it is followed by something else, so that
+    /// words aren't flagged while they're being typed. Words containing
+    /// digits are skipped.
+    pub fn misspelled(&self, text: &str) -> Vec<Range<usize>> {
+        let is_word_char = |ch: char| ch.is_alphanumeric() || ch == '\'';
+        let mut misspelled = Vec::new();
+        let mut offset = 0;
+        for token in text.split_inclusive(|ch: char| !is_word_char(ch)) {
+            let start = offset;
+            offset += token.len();
+            let Some(word_end) = token.find(|ch: char| !is_word_char(ch)) else {
+                // Still being typed.
+                continue;
+            };
+            let word = &token[..word_end];
+            let trimmed = word.trim_start_matches('\'');
+            let start = start + word.len() - trimmed.len();
+            let word = trimmed.trim_end_matches('\'');
+            if word.is_empty() || word.contains(|ch: char| ch.is_numeric()) {
+                continue;
+            }
+            if !self.contains(word) {
+                misspelled.push(start..start + word.len());
+            }
+        }
+        misspelled
+    }
+
+    pub fn suggestions(&self, word: &str) -> Vec<String> {
+        self.dictionary.suggestions(word)
+    }
+}
+
+/// Replacements offered for a misspelled word in the input.
+#[derive(Debug)]
+pub(crate) struct Corrections {
+    /// The byte range of the misspelled word in the input.
+    pub range: Range<usize>,
+    suggestions: Vec<String>,
+    selected: usize,
+}
+
+impl Corrections {
+    pub fn new(range: Range<usize>, suggestions: Vec<String>) -> Self {
+        Self {
+            range,
+            suggestions,
+            selected: 0,
+        }
+    }
+
+    /// Moves the selection by `delta` suggestions, stopping at either end.
+    pub fn move_selection(&mut self, delta: isize) {
+        let last = self.suggestions.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    pub fn chosen(&self) -> Option<&str> {
+        self.suggestions.get(self.selected).map(String::as_str)
+    }
+
+    pub fn popup(&self) -> Popup {
+        if self.suggestions.is_empty() {
+            Popup::new(["no suggestions"])
+        } else {
+            Popup::new(self.suggestions.iter().cloned()).with_selected(self.selected)
+        }
+    }
+}
+
+#[test]
+fn spelling() {
+    let checker = Spellchecker::new(WordList::new(["the", "cat", "sat", "on", "hat", "don't"]));
+    checker.add("Ferris");
+    let text = "The cta sat on ferris's 'hat' 42x, don't catt";
+    let misspelled = checker
+        .misspelled(text)
+        .into_iter()
+        .map(|range| &text[range])
+        .collect::<Vec<_>>();
+    assert_eq!(misspelled, ["cta", "ferris's"]);
+
+    assert_eq!(checker.suggestions("cta"), ["cat"]);
+    assert_eq!(checker.suggestions("Catt"), ["cat"]);
+    assert_eq!(checker.suggestions("at"), ["cat", "hat", "sat"]);
+    assert!(checker.suggestions("dog").is_empty());
+}
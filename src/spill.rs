@@ -0,0 +1,318 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::scrollback::Line;
+
+/// The number of lines spilled to a store at once.
+pub(crate) const SEGMENT_LINES: usize = 1_000;
+
+/// Where lines spilled out of memory by
+/// [`Console::spill_scrollback`](crate::Console::spill_scrollback) are kept
+/// until they're scrolled back into view. Segments are pushed and popped like
+/// a stack: the most recently pushed segment holds the newest spilled lines.
+pub trait ScrollbackStore: Send + 'static {
+    fn push(&mut self, segment: Vec<u8>) -> io::Result<()>;
+
+    /// Removes and returns the most recently pushed segment.
+    fn pop(&mut self) -> io::Result<Option<Vec<u8>>>;
+
+    /// Returns the segment at `index`, counting from the oldest segment,
+    /// without removing it.
+    fn read(&self, index: usize) -> io::Result<Vec<u8>>;
+
+    /// Removes every segment.
+    fn clear(&mut self) -> io::Result<()>;
+}
+
+/// A [`ScrollbackStore`] that writes each segment to its own file within a
+/// directory. The files are removed when the store is dropped.
+#[derive(Debug)]
+pub struct SegmentFiles {
+    directory: PathBuf,
+    segments: usize,
+}
+
+impl SegmentFiles {
+    /// Stores segments in `directory`, creating it if needed. Each tab
+    /// needs its own directory.
+    pub fn new(directory: impl Into<PathBuf>) -> io::Result<Self> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+        Ok(Self {
+            directory,
+            segments: 0,
+        })
+    }
+
+    fn path(&self, index: usize) -> PathBuf {
+        self.directory.join(format!("{index}.segment"))
+    }
+}
+
+impl ScrollbackStore for SegmentFiles {
+    fn push(&mut self, segment: Vec<u8>) -> io::Result<()> {
+        fs::write(self.path(self.segments), segment)?;
+        self.segments += 1;
+        Ok(())
+    }
+
+    fn pop(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let Some(index) = self.segments.checked_sub(1) else {
+            return Ok(None);
+        };
+        let path = self.path(index);
+        let segment = fs::read(&path)?;
+        fs::remove_file(path)?;
+        self.segments = index;
+        Ok(Some(segment))
+    }
+
+    fn read(&self, index: usize) -> io::Result<Vec<u8>> {
+        fs::read(self.path(index))
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        while let Some(index) = self.segments.checked_sub(1) {
+            fs::remove_file(self.path(index))?;
+            self.segments = index;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for SegmentFiles {
+    fn drop(&mut self) {
+        let _ = self.clear();
+    }
+}
+
+/// A [`ScrollbackStore`] that keeps segments in memory, compressed with LZ4.
+/// Log output typically compresses to a fraction of its size, at the cost of
+/// compressing lines as they're spilled and decompressing them when they're
+/// scrolled back into view.
+#[cfg(feature = "lz4")]
+#[derive(Debug, Default)]
+pub struct Compressed {
+    segments: Vec<Vec<u8>>,
+}
+
+#[cfg(feature = "lz4")]
+impl ScrollbackStore for Compressed {
+    fn push(&mut self, segment: Vec<u8>) -> io::Result<()> {
+        self.segments
+            .push(lz4_flex::compress_prepend_size(&segment));
+        Ok(())
+    }
+
+    fn pop(&mut self) -> io::Result<Option<Vec<u8>>> {
+        self.segments
+            .pop()
+            .map(|segment| decompress(&segment))
+            .transpose()
+    }
+
+    fn read(&self, index: usize) -> io::Result<Vec<u8>> {
+        let segment = self
+            .segments
+            .get(index)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        decompress(segment)
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.segments.clear();
+        Ok(())
+    }
+}
+
+#[cfg(feature = "lz4")]
+fn decompress(segment: &[u8]) -> io::Result<Vec<u8>> {
+    lz4_flex::decompress_size_prepended(segment)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+// The oldest lines of a tab, spilled out of memory.
+pub(crate) struct Spill {
+    // Shared with readers, which read segments without locking the
+    // scrollback.
+    store: Arc<Mutex<Box<dyn ScrollbackStore>>>,
+    /// The number of the most recent lines kept in memory.
+    pub resident: usize,
+    // The number of lines in each segment in the store, oldest first.
+    segments: Vec<usize>,
+    /// Set once the store fails. Lines are then kept in memory, and spilled
+    /// lines are no longer paged in.
+    pub failed: bool,
+}
+
+impl fmt::Debug for Spill {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Spill")
+            .field("resident", &self.resident)
+            .field("segments", &self.segments)
+            .field("failed", &self.failed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Spill {
+    pub fn new(store: impl ScrollbackStore, resident: usize) -> Self {
+        Self {
+            store: Arc::new(Mutex::new(Box::new(store))),
+            resident,
+            segments: Vec::new(),
+            failed: false,
+        }
+    }
+
+    /// Returns the number of lines in the store.
+    pub fn len(&self) -> usize {
+        self.segments.iter().sum()
+    }
+
+    /// Stores `lines`, ordered newest first, as the newest spilled lines.
+    pub fn push(&mut self, lines: &[Line]) -> io::Result<()> {
+        self.store.lock().push(serde_json::to_vec(lines)?)?;
+        self.segments.push(lines.len());
+        Ok(())
+    }
+
+    /// Removes and returns the newest spilled lines, ordered newest first.
+    pub fn pop(&mut self) -> io::Result<Option<Vec<Line>>> {
+        let Some(segment) = self.store.lock().pop()? else {
+            return Ok(None);
+        };
+        self.segments.pop();
+        decode(&segment).map(Some)
+    }
+
+    /// Returns every spilled line, oldest first, without removing them.
+    pub fn read_all(&self) -> io::Result<Vec<Line>> {
+        let reader = self.reader();
+        let mut lines = Vec::with_capacity(self.len());
+        for index in 0..reader.segment_count() {
+            lines.extend(reader.read(index)?);
+        }
+        Ok(lines)
+    }
+
+    /// Returns a reader of the lines spilled so far, which can be used once
+    /// the scrollback is unlocked.
+    pub fn reader(&self) -> SpillReader {
+        SpillReader {
+            store: self.store.clone(),
+            segments: self.segments.clone(),
+        }
+    }
+
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.segments.clear();
+        self.store.lock().clear()
+    }
+}
+
+/// Reads the segments that were spilled when it was created. Lines may be
+/// paged in or spilled meanwhile, so reading a segment that has since been
+/// paged back in fails.
+pub(crate) struct SpillReader {
+    store: Arc<Mutex<Box<dyn ScrollbackStore>>>,
+    // The number of lines in each segment, oldest first.
+    segments: Vec<usize>,
+}
+
+impl SpillReader {
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Returns the lines of the segment at `index`, counting from the oldest
+    /// segment, ordered oldest first.
+    pub fn read(&self, index: usize) -> io::Result<Vec<Line>> {
+        let segment = self.store.lock().read(index)?;
+        let mut lines = decode(&segment)?;
+        lines.reverse();
+        Ok(lines)
+    }
+
+    /// Returns the segment containing the line at `position`, counting from
+    /// the oldest line, along with the position of the segment's oldest line.
+    /// The segment is decoded with [`decode`].
+    pub fn read_containing(&self, position: usize) -> io::Result<(usize, Vec<u8>)> {
+        let mut start = 0;
+        for (index, &lines) in self.segments.iter().enumerate() {
+            if position < start + lines {
+                return Ok((start, self.store.lock().read(index)?));
+            }
+            start += lines;
+        }
+        Err(io::Error::from(io::ErrorKind::NotFound))
+    }
+}
+
+/// Returns the lines in a segment read from the store, ordered newest first.
+pub(crate) fn decode(segment: &[u8]) -> io::Result<Vec<Line>> {
+    Ok(serde_json::from_slice(segment)?)
+}
+
+#[test]
+fn spilled_scrollback() {
+    use crate::scrollback::Scrollback;
+
+    let directory = std::env::temp_dir().join(format!("spill-{}", std::process::id()));
+    let mut scrollback = Scrollback {
+        columns: 80,
+        spill: Some(Spill::new(SegmentFiles::new(&directory).unwrap(), 10)),
+        ..Scrollback::default()
+    };
+    for line in 0..SEGMENT_LINES + 10 {
+        scrollback.push(Line::from(line.to_string()));
+    }
+    // Positions still count from the oldest line, even once it's spilled.
+    assert_eq!(scrollback.events.len(), 10);
+    assert_eq!(scrollback.len(), SEGMENT_LINES + 10);
+    assert!(scrollback.line(0).is_none());
+    assert_eq!(
+        scrollback.line(SEGMENT_LINES).map(|line| line.as_str()),
+        Some("1000")
+    );
+
+    assert!(scrollback.page_in().unwrap());
+    assert!(!scrollback.page_in().unwrap());
+    assert_eq!(scrollback.line(0).map(|line| line.as_str()), Some("0"));
+    assert_eq!(scrollback.len(), SEGMENT_LINES + 10);
+
+    // Lines a scrolled view shows aren't spilled, but older lines still are.
+    scrollback.view.scroll = SEGMENT_LINES;
+    scrollback.push(Line::from(String::from("more")));
+    assert_eq!(scrollback.events.len(), SEGMENT_LINES + 11);
+    scrollback.view.scroll = 5;
+    scrollback.push(Line::from(String::from("more")));
+    assert_eq!(scrollback.events.len(), 12);
+    assert_eq!(scrollback.view.scroll, 6);
+
+    let reader = scrollback.spill.as_ref().unwrap().reader();
+    assert_eq!(reader.segment_count(), 1);
+    assert_eq!(reader.read(0).unwrap()[0].as_str(), "0");
+
+    drop((reader, scrollback));
+    assert_eq!(fs::read_dir(&directory).unwrap().count(), 0);
+    fs::remove_dir(directory).unwrap();
+}
+
+#[test]
+#[cfg(feature = "lz4")]
+fn compressed_segments() {
+    let mut store = Compressed::default();
+    let segment = "the same line again\n".repeat(100).into_bytes();
+    store.push(segment.clone()).unwrap();
+    store.push(b"newest".to_vec()).unwrap();
+    assert!(store.segments[0].len() < segment.len() / 10);
+    assert_eq!(store.pop().unwrap().as_deref(), Some(&b"newest"[..]));
+    assert_eq!(store.pop().unwrap(), Some(segment));
+    assert_eq!(store.pop().unwrap(), None);
+}
@@ -0,0 +1,136 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+
+use crate::newline::Newline;
+use crate::producer::Producer;
+use crate::scrollback::Line;
+use crate::{Change, State};
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Returns an id for a line that is updated after it is pushed, which is
+/// unique among streams and lists.
+pub(crate) fn next_id() -> u64 {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// A line whose text is written incrementally, such as a response that is
+/// generated one token at a time. Created by
+/// [`Console::begin_stream`](crate::Console::begin_stream).
+///
+/// The text is rewrapped and displayed as it is written. Once the stream is
+/// finished or dropped, the line becomes a normal scrollback line and is
+/// printed when output is plain text. Highlight and gag rules are applied
+/// to the line once it is finished.
+#[derive(Debug)]
+pub struct Stream {
+    state: Weak<State>,
+    id: u64,
+    // Whether the last write ended with a `\r`, which is held back until the
+    // next write shows whether a `\n` follows it.
+    pending_cr: AtomicBool,
+}
+
+impl Stream {
+    pub(crate) fn begin(state: &Arc<State>, producer: Option<&Producer>) -> Self {
+        let id = next_id();
+        let mut lines = state.attribute(producer, Line::from(String::new()));
+        // If the line was dropped by the rate limit, writing to the stream
+        // does nothing, since there's no line to update.
+        if let Some(mut line) = lines.pop() {
+            for notice in lines {
+                state.push(notice);
+            }
+            line.stream = Some(id);
+            state.change_and_notify(|scrollback| {
+                scrollback.append(line.clone());
+                Some(Change::Streamed(id, line))
+            });
+        }
+        state.redraw();
+        Self {
+            state: Arc::downgrade(state),
+            id,
+            pending_cr: AtomicBool::new(false),
+        }
+    }
+
+    /// Appends `text` to the line. Line endings start new rows within the
+    /// same scrollback line.
+    pub fn write(&self, text: &str) {
+        let Some(state) = self.state.upgrade() else {
+            return;
+        };
+        let newline = state.shared.config.incoming_newline;
+        let mut text = if self.pending_cr.swap(false, Ordering::Relaxed) {
+            format!("\r{text}")
+        } else {
+            text.to_string()
+        };
+        if matches!(newline, Newline::Auto | Newline::CrLf) && text.ends_with('\r') {
+            text.pop();
+            self.pending_cr.store(true, Ordering::Relaxed);
+        }
+        let text = newline.normalize(&text);
+        state.change_and_notify(|scrollback| {
+            let line = scrollback
+                .update_stream(self.id, |line| line.push_str(&text))
+                .cloned()?;
+            Some(Change::Streamed(self.id, line))
+        });
+        state.redraw();
+    }
+
+    /// Seals the line into the scrollback.
+    pub fn finish(self) {
+        // Dropping the stream finishes it.
+    }
+}
+
+impl Drop for Stream {
+    fn drop(&mut self) {
+        let Some(state) = self.state.upgrade() else {
+            return;
+        };
+        // A `\r` ending the last write is a line ending by itself.
+        let pending = if *self.pending_cr.get_mut() {
+            state
+                .shared
+                .config
+                .incoming_newline
+                .normalize("\r")
+                .into_owned()
+        } else {
+            String::new()
+        };
+        let line = state
+            .scrollback
+            .lock()
+            .update_stream(self.id, |line| line.push_str(&pending))
+            .cloned();
+        let Some(line) = line else {
+            state.redraw();
+            return;
+        };
+
+        // The finished line is filtered like any other incoming line.
+        match state.filter_incoming(line) {
+            Some(mut line) => {
+                line.stream = None;
+                state.change_and_notify(|scrollback| {
+                    let line = scrollback
+                        .update_stream(self.id, |streamed| *streamed = line.clone())
+                        .cloned()?;
+                    Some(Change::Finished(self.id, line))
+                });
+            }
+            None => {
+                state.change_and_notify(|scrollback| {
+                    scrollback.remove_stream(self.id)?;
+                    Some(Change::Discarded(self.id))
+                });
+            }
+        }
+        state.redraw();
+    }
+}
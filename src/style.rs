@@ -0,0 +1,172 @@
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Color {
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+
+    pub const fn rgb(red: u8, green: u8, blue: u8) -> Self {
+        Self { red, green, blue }
+    }
+
+    // Returns the hue in degrees and the saturation from 0 to 1.
+    fn hue_and_saturation(&self) -> (f32, f32) {
+        let red = f32::from(self.red) / 255.;
+        let green = f32::from(self.green) / 255.;
+        let blue = f32::from(self.blue) / 255.;
+        let max = red.max(green).max(blue);
+        let min = red.min(green).min(blue);
+        let chroma = max - min;
+        if chroma == 0. {
+            return (0., 0.);
+        }
+        let hue = if max == red {
+            60. * ((green - blue) / chroma).rem_euclid(6.)
+        } else if max == green {
+            60. * ((blue - red) / chroma + 2.)
+        } else {
+            60. * ((red - green) / chroma + 4.)
+        };
+        (hue, chroma / max)
+    }
+}
+
+/// The Okabe-Ito palette, whose colors remain distinguishable with the
+/// common forms of color blindness.
+pub const COLOR_BLIND_SAFE: [Color; 7] = [
+    Color::rgb(230, 159, 0),
+    Color::rgb(86, 180, 233),
+    Color::rgb(0, 158, 115),
+    Color::rgb(240, 228, 66),
+    Color::rgb(0, 114, 178),
+    Color::rgb(213, 94, 0),
+    Color::rgb(204, 121, 167),
+];
+
+/// How a range of text is displayed.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Style {
+    pub foreground: Color,
+}
+
+impl Style {
+    pub const fn new(foreground: Color) -> Self {
+        Self { foreground }
+    }
+
+    /// Replaces saturated colors with the closest color in
+    /// [`COLOR_BLIND_SAFE`]. Grays are kept as they are. This can be passed
+    /// to [`Config::style_transform`](crate::Config::style_transform).
+    pub fn color_blind_safe(self) -> Self {
+        let (hue, saturation) = self.foreground.hue_and_saturation();
+        if saturation < 0.25 {
+            return self;
+        }
+        let foreground = match hue {
+            // Red
+            hue if !(15. ..345.).contains(&hue) => COLOR_BLIND_SAFE[5],
+            // Orange
+            hue if hue < 45. => COLOR_BLIND_SAFE[0],
+            // Yellow
+            hue if hue < 75. => COLOR_BLIND_SAFE[3],
+            // Green
+            hue if hue < 165. => COLOR_BLIND_SAFE[2],
+            // Cyan
+            hue if hue < 210. => COLOR_BLIND_SAFE[1],
+            // Blue
+            hue if hue < 270. => COLOR_BLIND_SAFE[4],
+            // Magenta
+            _ => COLOR_BLIND_SAFE[6],
+        };
+        Self { foreground }
+    }
+
+    /// Brightens the foreground as much as possible while keeping its hue,
+    /// maximizing the contrast against a black background.
+    pub fn high_contrast(self) -> Self {
+        let Color { red, green, blue } = self.foreground;
+        let max = u16::from(red.max(green).max(blue));
+        if max == 0 {
+            return Self::new(Color::WHITE);
+        }
+        // Scale the brightest channel to 255, and then mix in white.
+        let channel = |value: u8| {
+            let scaled = u16::from(value) * 255 / max;
+            (scaled + (255 - scaled) / 3) as u8
+        };
+        Self {
+            foreground: Color::rgb(channel(red), channel(green), channel(blue)),
+        }
+    }
+}
+
+/// A function that adjusts every style before it is displayed.
+pub(crate) struct StyleTransform(pub Box<dyn Fn(Style) -> Style + Send + Sync>);
+
+impl fmt::Debug for StyleTransform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("StyleTransform").finish()
+    }
+}
+
+/// A range of a line's text displayed in a specific color.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub range: Range<usize>,
+    pub color: Color,
+}
+
+impl Span {
+    pub fn new(range: Range<usize>, color: Color) -> Self {
+        Self { range, color }
+    }
+}
+
+/// Adds `span` to `spans`, which are sorted and don't overlap. Any part of
+/// another span that `span` covers is replaced.
+pub(crate) fn overlay_span(spans: &mut Vec<Span>, span: Span) {
+    let mut overlaid = Vec::with_capacity(spans.len() + 2);
+    for existing in spans.drain(..) {
+        if existing.range.end <= span.range.start || existing.range.start >= span.range.end {
+            overlaid.push(existing);
+            continue;
+        }
+        if existing.range.start < span.range.start {
+            overlaid.push(Span::new(
+                existing.range.start..span.range.start,
+                existing.color,
+            ));
+        }
+        if existing.range.end > span.range.end {
+            overlaid.push(Span::new(
+                span.range.end..existing.range.end,
+                existing.color,
+            ));
+        }
+    }
+    let index = overlaid.partition_point(|existing| existing.range.start < span.range.start);
+    overlaid.insert(index, span);
+    *spans = overlaid;
+}
+
+#[test]
+fn color_blind_safe() {
+    let safe = |color| Style::new(color).color_blind_safe().foreground;
+    assert_eq!(safe(Color::rgb(205, 49, 49)), COLOR_BLIND_SAFE[5]);
+    assert_eq!(safe(Color::rgb(13, 188, 121)), COLOR_BLIND_SAFE[2]);
+    assert_eq!(safe(Color::rgb(36, 114, 200)), COLOR_BLIND_SAFE[4]);
+    assert_eq!(safe(Color::rgb(102, 102, 102)), Color::rgb(102, 102, 102));
+
+    let contrast = |color| Style::new(color).high_contrast().foreground;
+    assert_eq!(contrast(Color::rgb(102, 102, 102)), Color::WHITE);
+    assert_eq!(contrast(Color::rgb(0, 0, 0)), Color::WHITE);
+    assert_eq!(contrast(Color::rgb(0, 0, 128)), Color::rgb(85, 85, 255));
+}
@@ -0,0 +1,67 @@
+use std::sync::Weak;
+
+use crate::State;
+
+/// Identifies a version of the input, so that suggestions computed for an
+/// older version can be discarded. Returned by
+/// [`Console::input_generation`](crate::Console::input_generation).
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct InputGeneration(pub(crate) u64);
+
+/// Computes suggestions on a new thread, returning the sender that requests
+/// a suggestion for the current input. The thread stops once the sender is
+/// dropped.
+pub(crate) fn spawn<F>(state: Weak<State>, mut suggest: F) -> flume::Sender<()>
+where
+    F: FnMut(&str) -> Option<String> + Send + 'static,
+{
+    let (sender, requests) = flume::unbounded();
+    std::thread::Builder::new()
+        .name(String::from("suggestions"))
+        .spawn(move || {
+            while requests.recv().is_ok() {
+                // Only the newest input matters.
+                requests.drain();
+                let Some(strong) = state.upgrade() else { break };
+                let generation = strong.input_generation();
+                let input = strong.input.lock();
+                // Masked input, such as a password, is never passed along.
+                if input.options.masked {
+                    continue;
+                }
+                let buffer = input.buffer.to_string();
+                drop(input);
+                // The console may shut down while the suggestion is computed.
+                drop(strong);
+
+                let suggestion = suggest(&buffer).unwrap_or_default();
+                let Some(state) = state.upgrade() else { break };
+                if state.set_suggestion_for(generation, suggestion) {
+                    state.redraw();
+                }
+            }
+        })
+        .expect("error spawning suggestions thread");
+    sender
+}
+
+#[test]
+fn stale_suggestions() {
+    use std::sync::Arc;
+
+    use crate::{events, Config, Console, ConsoleEvent, Shared};
+
+    let (sender, app) = events::channel(&Config::default());
+    let state = Arc::new(State::new(
+        Arc::new(Shared::from(Config::default())),
+        String::from("main"),
+        false,
+        sender,
+    ));
+    let console = Console::new(state, app);
+    let generation = console.input_generation();
+    console.state.send(ConsoleEvent::InputBufferChanged);
+    assert!(!console.set_suggestion_for(generation, "stale"));
+    assert!(console.set_suggestion_for(console.input_generation(), "fresh"));
+    assert_eq!(console.state.input.lock().suggestion, "fresh");
+}
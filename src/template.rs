@@ -0,0 +1,109 @@
+use std::collections::VecDeque;
+
+/// Placeholders remaining in a template inserted into the input line with
+/// [`Console::insert_template`](crate::Console::insert_template).
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Template {
+    // Each placeholder's name, followed by the literal text after it.
+    stops: VecDeque<(String, String)>,
+    // The length of the input when the current placeholder was reached.
+    stop_start: usize,
+}
+
+impl Template {
+    /// Splits `template` into the text before its first placeholder and the
+    /// remaining placeholders. Placeholders are written as `${name}`.
+    pub fn parse(template: &str) -> (String, Option<Self>) {
+        let mut stops = VecDeque::new();
+        let (prefix, mut rest) = split_placeholder(template);
+        while let Some((name, after)) = rest {
+            let (literal, next) = split_placeholder(after);
+            stops.push_back((name.to_string(), literal));
+            rest = next;
+        }
+
+        let template = (!stops.is_empty()).then_some(Self {
+            stops,
+            stop_start: prefix.len(),
+        });
+        (prefix, template)
+    }
+
+    /// Moves to the next placeholder, returning the text to append to
+    /// `input`. If nothing was typed for the current placeholder, its name is
+    /// used. Returns `None` once the last placeholder has been left.
+    pub fn advance(&mut self, input: &str) -> Option<String> {
+        let (name, literal) = self.stops.pop_front()?;
+        let mut appended = String::new();
+        if input.len() <= self.stop_start {
+            appended.push_str(&name);
+        }
+        appended.push_str(&literal);
+        self.stop_start = input.len() + appended.len();
+        Some(appended)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.stops.is_empty()
+    }
+
+    /// Returns true if `input` no longer reaches the current placeholder,
+    /// such as after erasing the text before it.
+    pub fn is_abandoned(&self, input: &str) -> bool {
+        input.len() < self.stop_start
+    }
+
+    /// Returns the remainder of the template, displayed after the input.
+    pub fn hint(&self, input: &str) -> String {
+        let mut hint = String::new();
+        for (index, (name, literal)) in self.stops.iter().enumerate() {
+            if index > 0 || input.len() <= self.stop_start {
+                hint.push('<');
+                hint.push_str(name);
+                hint.push('>');
+            }
+            hint.push_str(literal);
+        }
+        hint
+    }
+}
+
+// Returns the text before the first placeholder in `text`, and the
+// placeholder's name and the text after it.
+fn split_placeholder(text: &str) -> (String, Option<(&str, &str)>) {
+    let Some(start) = text.find("${") else {
+        return (text.to_string(), None);
+    };
+    let Some(length) = text[start + 2..].find('}') else {
+        return (text.to_string(), None);
+    };
+    let name = &text[start + 2..start + 2 + length];
+    (
+        text[..start].to_string(),
+        Some((name, &text[start + 3 + length..])),
+    )
+}
+
+#[test]
+fn template_stops() {
+    let (mut input, template) = Template::parse("/msg ${nick} ${text}");
+    let mut template = template.unwrap();
+    assert_eq!(input, "/msg ");
+    assert_eq!(template.hint(&input), "<nick> <text>");
+
+    input.push_str("ecton");
+    assert_eq!(template.hint(&input), " <text>");
+    input += &template.advance(&input).unwrap();
+    assert_eq!(input, "/msg ecton ");
+    assert!(!template.is_finished());
+
+    // Nothing was typed, so the placeholder's name is used.
+    input += &template.advance(&input).unwrap();
+    assert_eq!(input, "/msg ecton text");
+    assert!(template.is_finished());
+    assert!(template.advance(&input).is_none());
+
+    let (input, template) = Template::parse("no placeholders");
+    assert_eq!(input, "no placeholders");
+    assert!(template.is_none());
+}
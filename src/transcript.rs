@@ -0,0 +1,232 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::scrollback::Line;
+
+/// When a transcript moves on to a new file.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum Rotation {
+    /// Every line is appended to the same file.
+    #[default]
+    Never,
+    /// A new file is started each day, in UTC. The date is added to the
+    /// file name, such as `session.2023-01-31.log`.
+    Daily,
+    /// A new file is started once the current file reaches this many bytes.
+    /// A number is added to the file name, such as `session.1.log`.
+    Size(u64),
+}
+
+/// Mirrors every pushed line to a text file as it is pushed, configured
+/// with [`Config::transcript_plain`](crate::Config::transcript_plain).
+pub(crate) struct PlainTranscript {
+    file: RotatingFile,
+}
+
+impl PlainTranscript {
+    pub fn new(path: PathBuf, rotation: Rotation) -> Self {
+        Self {
+            file: RotatingFile::new(path, rotation),
+        }
+    }
+
+    pub fn push(&mut self, line: &Line) -> io::Result<()> {
+        let mut formatted = format_line(line);
+        formatted.push('\n');
+        self.file.append(&[], formatted.as_bytes())
+    }
+}
+
+// Formats a line with its timestamp, level, and channel, such as
+// `2023-01-31T12:34:56.789Z warning [serial] text`.
+pub(crate) fn format_line(line: &Line) -> String {
+    let mut formatted = format!(
+        "{} {}",
+        format_timestamp(line.timestamp),
+        line.level.as_str()
+    );
+    if let Some(channel) = &line.channel {
+        formatted.push_str(" [");
+        formatted.push_str(channel);
+        formatted.push(']');
+    }
+    formatted.push(' ');
+    formatted.push_str(line.as_str());
+    formatted
+}
+
+// Formats `time` in UTC, such as `2023-01-31T12:34:56.789Z`.
+pub(crate) fn format_timestamp(time: SystemTime) -> String {
+    let timestamp = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = timestamp.as_secs();
+    let (year, month, day) = civil_date(seconds / 86_400);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        seconds / 3600 % 24,
+        seconds / 60 % 60,
+        seconds % 60,
+        timestamp.subsec_millis(),
+    )
+}
+
+// Converts a number of days since the Unix epoch into a year, month, and day.
+fn civil_date(days: u64) -> (u64, u64, u64) {
+    // Howard Hinnant's days_from_civil algorithm, in reverse.
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    (year, month, day)
+}
+
+// An append-only file that moves on to a new path according to a
+// `Rotation`.
+pub(crate) struct RotatingFile {
+    base: PathBuf,
+    rotation: Rotation,
+    open: Option<OpenFile>,
+}
+
+struct OpenFile {
+    file: File,
+    day: u64,
+    index: u32,
+    length: u64,
+}
+
+impl RotatingFile {
+    pub fn new(base: PathBuf, rotation: Rotation) -> Self {
+        Self {
+            base,
+            rotation,
+            open: None,
+        }
+    }
+
+    // Appends `record`, first writing `header` if the file is new. Daily
+    // rotation follows the current time rather than the line's timestamp,
+    // because lines may be inserted out of order.
+    pub fn append(&mut self, header: &[u8], record: &[u8]) -> io::Result<()> {
+        let day = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+            / 86_400;
+        let rotate = match (&self.open, self.rotation) {
+            (None, _) => true,
+            (Some(open), Rotation::Daily) => open.day != day,
+            (Some(open), Rotation::Size(limit)) => {
+                open.length > header.len() as u64 && open.length + record.len() as u64 > limit
+            }
+            (Some(_), Rotation::Never) => false,
+        };
+        if rotate {
+            let index = match (&self.open, self.rotation) {
+                (Some(open), Rotation::Size(_)) => open.index + 1,
+                _ => 0,
+            };
+            self.open = Some(self.open_file(day, index, header, record.len() as u64)?);
+        }
+
+        let open = self.open.as_mut().expect("opened above");
+        open.file.write_all(record)?;
+        open.length += record.len() as u64;
+        Ok(())
+    }
+
+    fn open_file(
+        &self,
+        day: u64,
+        mut index: u32,
+        header: &[u8],
+        record_len: u64,
+    ) -> io::Result<OpenFile> {
+        if let Some(parent) = self.base.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        loop {
+            let path = self.path(day, index);
+            let length = fs::metadata(&path).map_or(0, |metadata| metadata.len());
+            // Skip files left full by a previous run.
+            if let Rotation::Size(limit) = self.rotation {
+                if length > header.len() as u64 && length + record_len > limit {
+                    index += 1;
+                    continue;
+                }
+            }
+
+            let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+            let mut length = length;
+            if length == 0 {
+                file.write_all(header)?;
+                length = header.len() as u64;
+            }
+            return Ok(OpenFile {
+                file,
+                day,
+                index,
+                length,
+            });
+        }
+    }
+
+    fn path(&self, day: u64, index: u32) -> PathBuf {
+        let mut name = self
+            .base
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if self.rotation == Rotation::Daily {
+            let (year, month, day) = civil_date(day);
+            name = format!("{name}.{year:04}-{month:02}-{day:02}");
+        }
+        if index > 0 {
+            name = format!("{name}.{index}");
+        }
+        if let Some(extension) = self.base.extension() {
+            name.push('.');
+            name.push_str(&extension.to_string_lossy());
+        }
+        self.base.with_file_name(name)
+    }
+}
+
+#[test]
+fn plain_rotation() {
+    use std::time::Duration;
+
+    let directory =
+        std::env::temp_dir().join(format!("console-thingy-plain-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    let mut transcript = PlainTranscript::new(directory.join("session.log"), Rotation::Size(100));
+    for number in 0..3 {
+        let mut line = Line::new(format!("line {number}"), Some("serial".into()));
+        line.timestamp = UNIX_EPOCH + Duration::from_millis(1_675_168_496_789);
+        transcript.push(&line).unwrap();
+    }
+
+    assert_eq!(
+        fs::read_to_string(directory.join("session.log")).unwrap(),
+        "2023-01-31T12:34:56.789Z info [serial] line 0\n\
+         2023-01-31T12:34:56.789Z info [serial] line 1\n"
+    );
+    assert_eq!(
+        fs::read_to_string(directory.join("session.1.log")).unwrap(),
+        "2023-01-31T12:34:56.789Z info [serial] line 2\n"
+    );
+    fs::remove_dir_all(&directory).unwrap();
+}
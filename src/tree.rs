@@ -0,0 +1,106 @@
+/// A node of a tree pushed with
+/// [`Console::push_tree`](crate::Console::push_tree), such as a dependency or
+/// a file.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TreeNode {
+    label: String,
+    children: Vec<TreeNode>,
+    expanded: bool,
+}
+
+impl TreeNode {
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            children: Vec::new(),
+            expanded: true,
+        }
+    }
+
+    pub fn with_child(mut self, child: TreeNode) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    pub fn with_children(mut self, children: impl IntoIterator<Item = TreeNode>) -> Self {
+        self.children.extend(children);
+        self
+    }
+
+    /// Hides this node's children, showing how many nodes are hidden
+    /// instead.
+    pub fn collapsed(mut self) -> Self {
+        self.expanded = false;
+        self
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn children(&self) -> &[TreeNode] {
+        &self.children
+    }
+
+    pub fn is_expanded(&self) -> bool {
+        self.expanded
+    }
+
+    // The number of nodes beneath this one.
+    fn descendants(&self) -> usize {
+        self.children
+            .iter()
+            .map(|child| 1 + child.descendants())
+            .sum()
+    }
+
+    /// Draws the tree, one row per visible node.
+    pub(crate) fn draw(&self) -> String {
+        let mut drawn = String::new();
+        self.draw_node(&mut drawn);
+        self.draw_children("", &mut drawn);
+        drawn
+    }
+
+    fn draw_node(&self, drawn: &mut String) {
+        drawn.push_str(&self.label);
+        if !self.expanded && !self.children.is_empty() {
+            drawn.push_str(&format!(" (+{})", self.descendants()));
+        }
+    }
+
+    // Draws the children beneath `prefix`, the connectors continuing from
+    // this node's ancestors.
+    fn draw_children(&self, prefix: &str, drawn: &mut String) {
+        if !self.expanded {
+            return;
+        }
+        for (index, child) in self.children.iter().enumerate() {
+            let last = index + 1 == self.children.len();
+            drawn.push('\n');
+            drawn.push_str(prefix);
+            drawn.push_str(if last { "└─ " } else { "├─ " });
+            child.draw_node(drawn);
+            let prefix = format!("{prefix}{}", if last { "   " } else { "│  " });
+            child.draw_children(&prefix, drawn);
+        }
+    }
+}
+
+#[test]
+fn drawing_trees() {
+    let tree = TreeNode::new("app")
+        .with_child(
+            TreeNode::new("serde")
+                .with_child(TreeNode::new("serde_derive").with_child(TreeNode::new("syn"))),
+        )
+        .with_child(
+            TreeNode::new("regex")
+                .with_child(TreeNode::new("memchr"))
+                .collapsed(),
+        );
+    assert_eq!(
+        tree.draw(),
+        "app\n├─ serde\n│  └─ serde_derive\n│     └─ syn\n└─ regex (+1)"
+    );
+}
@@ -0,0 +1,151 @@
+//! Utilities for laying out text in terminal columns, such as right-aligned
+//! status text or table cells.
+//!
+//! Widths are measured in columns using Unicode's East Asian Width property,
+//! so wide characters such as CJK ideographs count as two columns. ANSI
+//! escape sequences are preserved and count as zero columns.
+
+use std::borrow::Cow;
+
+use unicode_width::UnicodeWidthChar;
+
+/// Where text is placed within a wider space.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Alignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// Returns the number of columns `text` occupies.
+pub fn width(text: &str) -> usize {
+    segments(text).map(|(_, width)| width).sum()
+}
+
+/// Returns the longest prefix of `text` that fits within `width` columns.
+/// Escape sequences after the cut are kept so that styles are still reset.
+pub fn truncate_to_width(text: &str, width: usize) -> Cow<'_, str> {
+    let mut used = 0;
+    let mut truncated = None::<String>;
+    for (segment, segment_width) in segments(text) {
+        if segment_width > 0 && used + segment_width > width {
+            truncated.get_or_insert_with(|| {
+                let end = segment.as_ptr() as usize - text.as_ptr() as usize;
+                text[..end].to_string()
+            });
+            continue;
+        }
+        used += segment_width;
+        if let Some(truncated) = &mut truncated {
+            truncated.push_str(segment);
+        }
+    }
+    truncated.map_or(Cow::Borrowed(text), Cow::Owned)
+}
+
+/// Truncates `text` to `width` columns, ending it with `…` if anything was
+/// removed.
+pub fn truncate_with_ellipsis(text: &str, width: usize) -> Cow<'_, str> {
+    if self::width(text) <= width {
+        return Cow::Borrowed(text);
+    }
+    let mut truncated = truncate_to_width(text, width.saturating_sub(1)).into_owned();
+    if width > 0 {
+        truncated.push('…');
+    }
+    Cow::Owned(truncated)
+}
+
+/// Pads `text` with spaces to occupy `width` columns, placing it according
+/// to `alignment`. Text wider than `width` is returned unchanged.
+pub fn pad_to_width(text: &str, width: usize, alignment: Alignment) -> Cow<'_, str> {
+    let padding = width.saturating_sub(self::width(text));
+    if padding == 0 {
+        return Cow::Borrowed(text);
+    }
+    let (before, after) = match alignment {
+        Alignment::Left => (0, padding),
+        Alignment::Center => (padding / 2, padding - padding / 2),
+        Alignment::Right => (padding, 0),
+    };
+    Cow::Owned(format!("{}{text}{}", " ".repeat(before), " ".repeat(after)))
+}
+
+/// Truncates or pads `text` to occupy exactly `width` columns.
+pub fn fit_to_width(text: &str, width: usize, alignment: Alignment) -> String {
+    let truncated = truncate_to_width(text, width);
+    // A wide character that doesn't fit leaves a column to fill.
+    pad_to_width(&truncated, width, alignment).into_owned()
+}
+
+// Splits `text` into characters and escape sequences, along with the columns
+// each occupies. Zero-width characters, such as combining marks, stay with
+// the character before them, as does a character joined to it with a
+// zero-width joiner, so that they are never separated from it.
+fn segments(text: &str) -> impl Iterator<Item = (&str, usize)> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        let mut chars = rest.chars();
+        let ch = chars.next()?;
+        let length = if ch == '\u{1b}' {
+            escape_length(rest)
+        } else {
+            let mut length = ch.len_utf8();
+            let mut joined = ch == '\u{200d}';
+            for next in chars {
+                if !joined && next.width() != Some(0) {
+                    break;
+                }
+                joined = next == '\u{200d}';
+                length += next.len_utf8();
+            }
+            length
+        };
+        let (segment, remaining) = rest.split_at(length);
+        rest = remaining;
+        let width = if ch == '\u{1b}' {
+            0
+        } else {
+            ch.width().unwrap_or(0)
+        };
+        Some((segment, width))
+    })
+}
+
+// Returns the length of the escape sequence at the start of `text`.
+fn escape_length(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    match bytes.get(1) {
+        // Control sequences end with a byte from `@` to `~`.
+        Some(b'[') => bytes[2..]
+            .iter()
+            .position(|byte| (0x40..=0x7e).contains(byte))
+            .map_or(text.len(), |end| end + 3),
+        Some(byte) if byte.is_ascii() => 2,
+        _ => 1,
+    }
+}
+
+#[test]
+fn width_utilities() {
+    assert_eq!(width("abc"), 3);
+    assert_eq!(width("日本"), 4);
+    assert_eq!(width("\u{1b}[31mred\u{1b}[0m"), 3);
+    assert_eq!(truncate_to_width("日本語", 5), "日本");
+    assert_eq!(
+        truncate_to_width("\u{1b}[31mredder\u{1b}[0m", 3),
+        "\u{1b}[31mred\u{1b}[0m"
+    );
+    assert_eq!(truncate_with_ellipsis("status", 4), "sta…");
+    assert_eq!(truncate_with_ellipsis("ok", 4), "ok");
+    assert_eq!(pad_to_width("ok", 6, Alignment::Right), "    ok");
+    assert_eq!(pad_to_width("ok", 5, Alignment::Center), " ok  ");
+    assert_eq!(fit_to_width("日本語", 5, Alignment::Left), "日本 ");
+    // Combining marks stay with the character they're combined with.
+    assert_eq!(width("e\u{301}te\u{301}"), 3);
+    assert_eq!(truncate_to_width("e\u{301}te\u{301}", 2), "e\u{301}t");
+    assert_eq!(truncate_to_width("日\u{301}本", 1), "");
+    assert_eq!(truncate_with_ellipsis("e\u{301}te\u{301}", 2), "e\u{301}…");
+}
@@ -0,0 +1,73 @@
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+/// Background threads started with
+/// [`Console::spawn`](crate::Console::spawn), which are joined when the
+/// console shuts down.
+#[derive(Default)]
+pub(crate) struct Workers(Mutex<Vec<Worker>>);
+
+struct Worker {
+    name: String,
+    thread: JoinHandle<()>,
+    // Disconnected once the worker returns or panics.
+    done: flume::Receiver<()>,
+}
+
+impl Workers {
+    pub fn spawn(&self, name: String, worker: impl FnOnce() + Send + 'static) {
+        let (sender, done) = flume::bounded(0);
+        let thread = std::thread::Builder::new()
+            .name(name.clone())
+            .spawn(move || {
+                let _sender = sender;
+                worker();
+            })
+            .expect("error spawning worker thread");
+        let mut workers = self.0.lock();
+        // Forget workers that have already finished.
+        workers.retain(|worker| !worker.thread.is_finished());
+        workers.push(Worker { name, thread, done });
+    }
+
+    /// Waits up to `timeout` in total for every worker to return, returning
+    /// a description of each worker that panicked or is still running.
+    /// Workers that are still running are left running in the background.
+    pub fn join(&self, timeout: Duration) -> Vec<String> {
+        let deadline = Instant::now() + timeout;
+        let mut problems = Vec::new();
+        for worker in std::mem::take(&mut *self.0.lock()) {
+            let result = worker.done.recv_deadline(deadline);
+            if matches!(result, Err(flume::RecvTimeoutError::Timeout)) {
+                problems.push(format!(
+                    "worker thread {:?} didn't stop before shutting down",
+                    worker.name
+                ));
+            } else if worker.thread.join().is_err() {
+                problems.push(format!("worker thread {:?} panicked", worker.name));
+            }
+        }
+        problems
+    }
+}
+
+#[test]
+fn joining_workers() {
+    let workers = Workers::default();
+    workers.spawn(String::from("quick"), || {});
+    let (stop, stopped) = flume::bounded::<()>(0);
+    workers.spawn(String::from("slow"), move || {
+        let _ = stopped.recv();
+    });
+    workers.spawn(String::from("panicky"), || panic!("expected"));
+    assert_eq!(
+        workers.join(Duration::from_millis(50)),
+        [
+            "worker thread \"slow\" didn't stop before shutting down",
+            "worker thread \"panicky\" panicked"
+        ]
+    );
+    drop(stop);
+}
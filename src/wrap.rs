@@ -1,21 +1,116 @@
+//! Wraps text into rows using the same rules as the console, so that text
+//! formatted elsewhere lines up with what is displayed.
+//!
+//! Each character occupies one column. Rows end after whitespace or
+//! punctuation when possible, and long words are broken where they reach
+//! the edge.
+
 use std::ops::{Deref, DerefMut, Range};
 
+use crate::bidi;
+
+/// Text that remembers how it was last wrapped, so that wrapping it again
+/// at the same width is free until the text changes.
 #[derive(Debug, Default, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "String", into = "String")
+)]
 pub struct Wrapped {
     string: String,
     wrapped_width: usize,
     offsets: Vec<Range<usize>>,
+    // The indexes of rows that end partway through a hyphenated word.
+    hyphens: Vec<usize>,
+    // Ranges of the text paired with the index of their style, sorted and
+    // not overlapping.
+    styles: Vec<(Range<usize>, usize)>,
     dirty: bool,
 }
 
 impl Wrapped {
-    pub fn rewrap(&mut self, width: usize) {
+    /// Returns the rows of the text wrapped within `width` columns.
+    pub fn lines(&mut self, width: usize) -> Lines<'_> {
+        self.rewrap(width);
+        self.rows()
+    }
+
+    /// Returns the number of rows the text occupies when wrapped within
+    /// `width` columns.
+    pub fn line_count(&mut self, width: usize) -> usize {
+        self.rewrap(width);
+        self.offsets.len()
+    }
+
+    /// Returns the row and column that `offset`, a byte offset into the
+    /// text, is displayed at when wrapped within `width` columns. An offset
+    /// where a row wraps is at the start of the following row. Offsets past
+    /// the end are treated as the end of the text.
+    pub fn position_of(&mut self, offset: usize, width: usize) -> (usize, usize) {
+        self.rewrap(width);
+        let offset = offset.min(self.string.len());
+        let row = self
+            .offsets
+            .iter()
+            .rposition(|range| range.start <= offset)
+            .unwrap_or_default();
+        let start = self.offsets[row].start;
+        let column = self.string[start..]
+            .char_indices()
+            .take_while(|(index, _)| start + index < offset)
+            .count();
+        (row, column)
+    }
+
+    /// Returns the byte offset of the character displayed at `row` and
+    /// `column` when wrapped within `width` columns. Positions past the end
+    /// of a row are treated as the end of the row, and rows past the end as
+    /// the end of the text.
+    pub fn offset_at(&mut self, row: usize, column: usize, width: usize) -> usize {
+        self.rewrap(width);
+        let Some(range) = self.offsets.get(row) else {
+            return self.string.len();
+        };
+        self.string[range.clone()]
+            .char_indices()
+            .nth(column)
+            .map_or(range.end, |(index, _)| range.start + index)
+    }
+
+    /// Displays each range of the text with the style at its index, in a
+    /// table of styles kept by the caller, replacing any previous styles.
+    /// Ranges are byte offsets that must not overlap, and aren't adjusted
+    /// when the text is edited.
+    pub fn set_styles(&mut self, styles: impl IntoIterator<Item = (Range<usize>, usize)>) {
+        self.styles = styles.into_iter().collect();
+        self.styles.sort_by_key(|(range, _)| range.start);
+    }
+
+    pub fn styles(&self) -> &[(Range<usize>, usize)] {
+        &self.styles
+    }
+
+    /// Returns the rows of the text wrapped within `width` columns, each
+    /// split into fragments paired with their style's index. A style that
+    /// spans a row break continues on the following row.
+    pub fn styled_lines(&mut self, width: usize) -> StyledLines<'_> {
+        self.rewrap(width);
+        StyledLines {
+            source: &self.string,
+            wrapped: &self.offsets,
+            styles: &self.styles,
+        }
+    }
+
+    pub(crate) fn rewrap(&mut self, width: usize) {
         if self.dirty || self.wrapped_width != width {
             self.wrap(width);
         }
     }
 
-    pub fn lines(&self) -> Lines<'_> {
+    // Returns the rows from the last time the text was wrapped.
+    pub(crate) fn rows(&self) -> Lines<'_> {
         debug_assert!(!self.dirty);
 
         Lines {
@@ -24,44 +119,114 @@ impl Wrapped {
         }
     }
 
+    pub(crate) fn line_ranges(&self) -> &[Range<usize>] {
+        debug_assert!(!self.dirty);
+
+        &self.offsets
+    }
+
+    /// Returns true if `row` ends partway through a word, and should be
+    /// displayed followed by a hyphen.
+    pub fn is_hyphenated(&self, row: usize) -> bool {
+        self.hyphens.binary_search(&row).is_ok()
+    }
+
+    /// Returns true if the text's first paragraph is right-to-left.
+    pub(crate) fn is_rtl(&self) -> bool {
+        bidi::is_rtl(&self.string)
+    }
+
+    /// Returns the runs of `row` in display order. Rows are wrapped in
+    /// logical order, and then each row is reordered for display.
+    pub(crate) fn visual_runs(&self, row: Range<usize>) -> Vec<(Range<usize>, bool)> {
+        bidi::visual_runs(&self.string, row)
+    }
+
+    /// Returns the offset of the character displayed `column` characters
+    /// into `row`, matching the order [`Wrapped::visual_runs`] draws in.
+    pub(crate) fn offset_at_column(&self, row: Range<usize>, column: usize) -> usize {
+        bidi::offset_at_column(&self.string, row, column)
+    }
+
     fn wrap(&mut self, chars_wide: usize) {
         self.offsets.clear();
+        self.hyphens.clear();
         self.dirty = false;
         self.wrapped_width = chars_wide;
+        let chars_wide = chars_wide.max(1);
 
         let mut line_start = 0;
-        let mut is_after_breakable = true;
-        let mut last_word_start = 0;
-        let mut word_char_length = 0;
         let mut line_length = 0;
+        // The offset after the last breakable character in the current line,
+        // and the number of characters since it.
+        let mut last_break = None;
+        let mut chars_since_break = 0;
+        // The start of the current word, which may be on an earlier row.
+        let mut word_start = 0;
+        let breaks = break_opportunities(&self.string);
+        let mut next_break = 0;
         let mut chars = self.string.char_indices().peekable();
         while let Some((index, ch)) = chars.next() {
             if ch == '\n' || ch == '\r' {
                 // TODO handle CRLF
                 self.offsets.push(line_start..index);
                 line_start = index + 1;
-                last_word_start = 0;
-                word_char_length = 0;
                 line_length = 0;
-                is_after_breakable = true;
+                last_break = None;
+                chars_since_break = 0;
+                word_start = line_start;
+                continue;
+            }
+
+            line_length += 1;
+            let end = index + ch.len_utf8();
+            while breaks.get(next_break).is_some_and(|&offset| offset < end) {
+                next_break += 1;
+            }
+            if breaks.get(next_break) == Some(&end) {
+                last_break = Some(end);
+                word_start = end;
+                chars_since_break = 0;
             } else {
-                line_length += 1;
-                if is_break(ch) {
-                    is_after_breakable = true;
-                    word_char_length = 0;
-                } else if is_after_breakable {
-                    is_after_breakable = false;
-                    last_word_start = index;
-                    word_char_length = 1;
-                } else {
-                    word_char_length += 1;
-                }
+                chars_since_break += 1;
             }
 
-            if line_length == chars_wide && chars.peek().is_some() {
-                self.offsets.push(line_start..last_word_start);
-                line_start = last_word_start;
-                line_length = word_char_length;
+            // A whitespace character may extend a column past the edge, so
+            // that the space a row breaks at never starts the next row.
+            // Longer runs of whitespace are split at the edge.
+            let next_fits = chars.peek().map_or(true, |(_, next)| {
+                next.is_whitespace() && line_length <= chars_wide
+            });
+            if line_length >= chars_wide && !next_fits {
+                let word_end = breaks.get(next_break).copied().unwrap_or(self.string.len());
+                if let Some(split) =
+                    self.hyphenation_point(line_start, word_start..word_end, chars_wide)
+                {
+                    self.offsets.push(line_start..split);
+                    self.hyphens.push(self.offsets.len() - 1);
+                    line_start = split;
+                    line_length = self.string[split..end].chars().count();
+                    chars_since_break = line_length;
+                    last_break = None;
+                    continue;
+                }
+
+                match last_break {
+                    Some(break_at) if break_at > line_start => {
+                        self.offsets.push(line_start..break_at);
+                        line_start = break_at;
+                        line_length = chars_since_break;
+                    }
+                    _ => {
+                        // The word is wider than a row, so it is broken
+                        // after the last character that fits.
+                        self.offsets.push(line_start..end);
+                        line_start = end;
+                        line_length = 0;
+                        chars_since_break = 0;
+                    }
+                }
+                last_break = None;
             }
         }
 
@@ -73,12 +238,42 @@ impl Wrapped {
     }
 }
 
+impl Wrapped {
+    // Returns the offset to end a row starting at `line_start` with a hyphen,
+    // splitting the word in `word`.
+    #[cfg(feature = "hyphenation")]
+    fn hyphenation_point(
+        &self,
+        line_start: usize,
+        word: Range<usize>,
+        chars_wide: usize,
+    ) -> Option<usize> {
+        // Trailing spaces and punctuation aren't part of the word.
+        let text = self.string[word.clone()].trim_end_matches(|ch: char| !ch.is_alphabetic());
+        crate::hyphenate::breaks(text)
+            .into_iter()
+            .rev()
+            .map(|offset| word.start + offset)
+            // The hyphen needs a column too.
+            .find(|&split| {
+                split > line_start && self.string[line_start..split].chars().count() < chars_wide
+            })
+    }
+
+    #[cfg(not(feature = "hyphenation"))]
+    fn hyphenation_point(&self, _: usize, _: Range<usize>, _: usize) -> Option<usize> {
+        None
+    }
+}
+
 impl From<String> for Wrapped {
     fn from(string: String) -> Self {
         Self {
             string,
             wrapped_width: 0,
             offsets: Vec::new(),
+            hyphens: Vec::new(),
+            styles: Vec::new(),
             dirty: true,
         }
     }
@@ -111,10 +306,31 @@ impl DerefMut for Wrapped {
     }
 }
 
-fn is_break(ch: char) -> bool {
-    ch.is_ascii_punctuation() || ch == ' ' || ch == '\t' || ch.is_ascii_control()
+// Returns the offsets, in ascending order, where a row may end other than
+// at a newline. Rows may end after any whitespace or ASCII punctuation.
+#[cfg(not(feature = "line-breaking"))]
+fn break_opportunities(text: &str) -> Vec<usize> {
+    text.char_indices()
+        .filter(|(_, ch)| {
+            ch.is_ascii_punctuation() || *ch == ' ' || *ch == '\t' || ch.is_ascii_control()
+        })
+        .map(|(index, ch)| index + ch.len_utf8())
+        .collect()
 }
 
+// Returns the offsets, in ascending order, where a row may end other than
+// at a newline, following the Unicode line breaking algorithm (UAX #14).
+#[cfg(feature = "line-breaking")]
+fn break_opportunities(text: &str) -> Vec<usize> {
+    use unicode_linebreak::{linebreaks, BreakOpportunity};
+
+    linebreaks(text)
+        .filter(|(_, opportunity)| *opportunity == BreakOpportunity::Allowed)
+        .map(|(offset, _)| offset)
+        .collect()
+}
+
+/// The rows of a [`Wrapped`] text, returned by [`Wrapped::lines`].
 #[derive(Debug)]
 pub struct Lines<'a> {
     source: &'a str,
@@ -145,16 +361,175 @@ impl<'a> DoubleEndedIterator for Lines<'a> {
     }
 }
 
+/// The rows of a [`Wrapped`] text split by style, returned by
+/// [`Wrapped::styled_lines`]. Each row is its fragments in order, with the
+/// index of their style, or None if they aren't styled.
+#[derive(Debug)]
+pub struct StyledLines<'a> {
+    source: &'a str,
+    wrapped: &'a [Range<usize>],
+    styles: &'a [(Range<usize>, usize)],
+}
+
+impl<'a> Iterator for StyledLines<'a> {
+    type Item = Vec<(&'a str, Option<usize>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let range = self.wrapped.first()?.clone();
+        self.wrapped = &self.wrapped[1..];
+        let fragments = fragments(range, self.styles.iter().cloned());
+        Some(
+            fragments
+                .into_iter()
+                .map(|(range, style)| (&self.source[range], style))
+                .collect(),
+        )
+    }
+}
+
+impl<'a> ExactSizeIterator for StyledLines<'a> {
+    fn len(&self) -> usize {
+        self.wrapped.len()
+    }
+}
+
+/// Splits `row` at the edges of `styles`, which are sorted and don't
+/// overlap, pairing each part with its style. Parts without a style are
+/// paired with None.
+pub(crate) fn fragments<T>(
+    row: Range<usize>,
+    styles: impl IntoIterator<Item = (Range<usize>, T)>,
+) -> Vec<(Range<usize>, Option<T>)> {
+    let mut fragments = Vec::new();
+    let mut offset = row.start;
+    for (range, style) in styles {
+        let start = range.start.max(row.start);
+        let end = range.end.min(row.end);
+        if start >= end {
+            continue;
+        }
+        if offset < start {
+            fragments.push((offset..start, None));
+        }
+        fragments.push((start..end, Some(style)));
+        offset = end;
+    }
+    if offset < row.end || fragments.is_empty() {
+        fragments.push((offset..row.end, None));
+    }
+    fragments
+}
+
 #[test]
 fn wrap_tests() {
     let mut wrapped = Wrapped::from("hello world");
     wrapped.rewrap(10);
-    assert_eq!(wrapped.lines().collect::<Vec<_>>(), ["hello ", "world"]);
+    assert_eq!(wrapped.rows().collect::<Vec<_>>(), ["hello ", "world"]);
     wrapped.rewrap(11);
-    assert_eq!(wrapped.lines().collect::<Vec<_>>(), ["hello world"]);
+    assert_eq!(wrapped.rows().collect::<Vec<_>>(), ["hello world"]);
     wrapped.rewrap(10);
     assert_eq!(
-        wrapped.lines().rev().collect::<Vec<_>>(),
+        wrapped.rows().rev().collect::<Vec<_>>(),
         ["world", "hello "]
     );
 }
+
+#[test]
+fn narrow_wrap_tests() {
+    let mut wrapped = Wrapped::from("abc de");
+    wrapped.rewrap(1);
+    assert_eq!(
+        wrapped.rows().collect::<Vec<_>>(),
+        ["a", "b", "c ", "d", "e"]
+    );
+    wrapped.rewrap(2);
+    assert_eq!(wrapped.rows().collect::<Vec<_>>(), ["ab", "c ", "de"]);
+    wrapped.rewrap(3);
+    assert_eq!(wrapped.rows().collect::<Vec<_>>(), ["abc ", "de"]);
+
+    let mut url = Wrapped::from("see https://example.com/a/very/long/path");
+    url.rewrap(10);
+    let rows = url.rows().collect::<Vec<_>>();
+    assert!(rows.iter().all(|row| !row.is_empty()));
+    #[cfg(not(feature = "line-breaking"))]
+    assert_eq!(
+        rows,
+        ["see https:", "//example.", "com/a/", "very/long/", "path"]
+    );
+
+    let mut word = Wrapped::from("abcdefghij");
+    word.rewrap(4);
+    assert_eq!(word.rows().collect::<Vec<_>>(), ["abcd", "efgh", "ij"]);
+}
+
+#[test]
+fn whitespace_runs_split() {
+    let mut spaced = Wrapped::from("a          b");
+    for width in 1..=4 {
+        spaced.rewrap(width);
+        let rows = spaced.rows().collect::<Vec<_>>();
+        assert!(rows.len() > 1);
+        assert!(rows.iter().all(|row| row.chars().count() <= width + 1));
+        assert_eq!(rows.concat(), "a          b");
+    }
+    spaced.rewrap(3);
+    #[cfg(not(feature = "line-breaking"))]
+    assert_eq!(
+        spaced.rows().collect::<Vec<_>>(),
+        ["a   ", "    ", "   ", "b"]
+    );
+}
+
+#[test]
+fn apostrophe_breaks() {
+    let mut wrapped = Wrapped::from("a don't");
+    wrapped.rewrap(6);
+    let rows = wrapped.rows().collect::<Vec<_>>();
+    // The Unicode algorithm keeps contractions together.
+    if cfg!(feature = "line-breaking") {
+        assert_eq!(rows, ["a ", "don't"]);
+    } else {
+        assert_eq!(rows, ["a don'", "t"]);
+    }
+}
+
+#[test]
+fn public_wrapping() {
+    let mut wrapped = Wrapped::from("hello world\nbye");
+    assert_eq!(wrapped.line_count(8), 3);
+    assert_eq!(
+        wrapped.lines(8).collect::<Vec<_>>(),
+        ["hello ", "world", "bye"]
+    );
+    assert_eq!(wrapped.position_of(0, 8), (0, 0));
+    // The offset where a row wraps is at the start of the next row.
+    assert_eq!(wrapped.position_of(6, 8), (1, 0));
+    assert_eq!(wrapped.position_of(11, 8), (1, 5));
+    assert_eq!(wrapped.position_of(100, 8), (2, 3));
+    assert_eq!(wrapped.position_of(6, 20), (0, 6));
+
+    assert_eq!(wrapped.offset_at(1, 2, 8), 8);
+    assert_eq!(wrapped.offset_at(1, 50, 8), 11);
+    assert_eq!(wrapped.offset_at(9, 0, 8), 15);
+    for offset in 0..=wrapped.len() {
+        let (row, column) = wrapped.position_of(offset, 8);
+        assert_eq!(wrapped.offset_at(row, column, 8), offset);
+    }
+}
+
+#[test]
+fn styles_across_rows() {
+    let mut wrapped = Wrapped::from("hello world");
+    wrapped.set_styles([(3..8, 0)]);
+    assert_eq!(
+        wrapped.styled_lines(6).collect::<Vec<_>>(),
+        [
+            vec![("hel", None), ("lo ", Some(0))],
+            vec![("wo", Some(0)), ("rld", None)]
+        ]
+    );
+    assert_eq!(
+        wrapped.styled_lines(20).collect::<Vec<_>>(),
+        [vec![("hel", None), ("lo wo", Some(0)), ("rld", None)]]
+    );
+}
@@ -0,0 +1,103 @@
+use std::io::{self, Write};
+use std::sync::{Arc, Weak};
+
+use crate::ansi::AnsiParser;
+use crate::newline::LineSplitter;
+use crate::producer::Producer;
+use crate::scrollback::Line;
+use crate::{Color, Level, State};
+
+/// An [`io::Write`] implementation that pushes each complete line written to
+/// it into the console. Created by [`Console::writer`](crate::Console::writer).
+///
+/// Any incomplete line is pushed when the writer is dropped.
+#[derive(Debug)]
+pub struct Writer {
+    state: Weak<State>,
+    // The producer lines are attributed to, from the console the writer was
+    // created with.
+    producer: Option<Arc<Producer>>,
+    splitter: LineSplitter,
+    color: Option<Color>,
+    level: Level,
+    ansi: Option<AnsiParser>,
+}
+
+impl Writer {
+    pub(crate) fn new(state: &Arc<State>, producer: Option<Arc<Producer>>) -> Self {
+        Self {
+            state: Arc::downgrade(state),
+            producer,
+            splitter: LineSplitter::new(state.shared.config.incoming_newline),
+            color: None,
+            level: Level::Info,
+            ansi: None,
+        }
+    }
+
+    /// Pushes lines using `color`.
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Pushes lines with `level`.
+    pub fn with_level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Interprets ANSI escape sequences in the written text. Colors are
+    /// displayed, and other escape sequences are removed.
+    pub fn interpret_ansi(mut self) -> Self {
+        self.ansi = Some(AnsiParser::default());
+        self
+    }
+
+    fn line(&mut self, text: String) -> Line {
+        let line = match &mut self.ansi {
+            Some(ansi) => ansi.parse_line(&text),
+            None => Line::from(text),
+        };
+        line.with_color(self.color).with_level(self.level)
+    }
+
+    fn push(&self, state: &State, line: Line) {
+        for line in state.attribute(self.producer.as_deref(), line) {
+            state.push(line);
+        }
+    }
+}
+
+impl Write for Writer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let state = self
+            .state
+            .upgrade()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        let mut lines = Vec::new();
+        self.splitter.feed(buf, |line| lines.push(line));
+        if !lines.is_empty() {
+            for line in lines {
+                let line = self.line(line);
+                self.push(&state, line);
+            }
+            state.redraw();
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        if let (Some(line), Some(state)) = (self.splitter.finish(), self.state.upgrade()) {
+            let line = self.line(line);
+            self.push(&state, line);
+            state.redraw();
+        }
+    }
+}
@@ -0,0 +1,79 @@
+#![cfg(feature = "macros")]
+
+use console_thingy::{CommandError, ConsoleCommand};
+
+#[derive(ConsoleCommand, Debug, Eq, PartialEq)]
+enum Cmd {
+    /// Connects to a server.
+    Connect {
+        host: String,
+        port: Option<u16>,
+        dry_run: bool,
+    },
+    /// Says something.
+    Say(String, bool),
+    JoinChannel,
+}
+
+#[test]
+fn derived_commands() {
+    let usages = Cmd::commands()
+        .iter()
+        .map(|command| (command.name, command.usage, command.help))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        usages,
+        [
+            (
+                "connect",
+                "/connect <host> [port] [--dry-run]",
+                "Connects to a server."
+            ),
+            ("say", "/say <arg1> [--arg2]", "Says something."),
+            ("join-channel", "/join-channel", ""),
+        ]
+    );
+
+    assert_eq!(
+        Cmd::parse("/connect example.com"),
+        Ok(Cmd::Connect {
+            host: String::from("example.com"),
+            port: None,
+            dry_run: false,
+        })
+    );
+    assert_eq!(
+        Cmd::parse("/connect --dry-run example.com 6667"),
+        Ok(Cmd::Connect {
+            host: String::from("example.com"),
+            port: Some(6667),
+            dry_run: true,
+        })
+    );
+    assert_eq!(
+        Cmd::parse("/say \"hi there\" --arg2"),
+        Ok(Cmd::Say(String::from("hi there"), true))
+    );
+    assert_eq!(Cmd::parse("/join-channel"), Ok(Cmd::JoinChannel));
+
+    assert_eq!(
+        Cmd::parse("/connect"),
+        Err(CommandError::Missing { argument: "host" })
+    );
+    assert!(matches!(
+        Cmd::parse("/connect example.com soon"),
+        Err(CommandError::Invalid {
+            argument: "port",
+            ..
+        })
+    ));
+    assert_eq!(
+        Cmd::parse("/connect example.com 6667 extra"),
+        Err(CommandError::Unexpected(String::from("extra")))
+    );
+    assert_eq!(
+        Cmd::parse("/quit"),
+        Err(CommandError::Unknown(String::from("quit")))
+    );
+    assert_eq!(Cmd::completions("j"), ["join-channel"]);
+}